@@ -0,0 +1,13 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+fn main() {
+    println!("cargo:rerun-if-changed=compute.c");
+    println!("cargo:rerun-if-changed=../wgpu-core/ffi/webgpu.h");
+    cc::Build::new()
+        .file("compute.c")
+        .include("../wgpu-core/ffi")
+        .warnings_into_errors(true)
+        .compile("ffi_test");
+}
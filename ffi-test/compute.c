@@ -0,0 +1,174 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/* Doubles the contents of a storage buffer in a compute shader, and reads them back. */
+
+#include "webgpu.h"
+
+#include <stdio.h>
+
+#define ELEMENT_COUNT 4
+
+static const char *SHADER =
+    "[[block]]\n"
+    "struct Data {\n"
+    "    values: [[stride(4)]] array<u32>;\n"
+    "};\n"
+    "\n"
+    "[[group(0), binding(0)]]\n"
+    "var<storage> data: [[access(read_write)]] Data;\n"
+    "\n"
+    "[[stage(compute), workgroup_size(1)]]\n"
+    "fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {\n"
+    "    data.values[global_id.x] = data.values[global_id.x] * 2u;\n"
+    "}\n";
+
+static void request_adapter_callback(WGPURequestAdapterStatus status,
+                                     WGPUAdapter adapter,
+                                     const char *message,
+                                     void *userdata) {
+    if (status == WGPURequestAdapterStatus_Success) {
+        *(WGPUAdapter *)userdata = adapter;
+    } else {
+        printf("No adapter: %s\n", message);
+    }
+}
+
+static void request_device_callback(WGPURequestDeviceStatus status,
+                                    WGPUDevice device,
+                                    const char *message,
+                                    void *userdata) {
+    if (status == WGPURequestDeviceStatus_Success) {
+        *(WGPUDevice *)userdata = device;
+    } else {
+        printf("No device: %s\n", message);
+    }
+}
+
+static void error_callback(WGPUErrorType error_type, const char *message, void *userdata) {
+    printf("Error %u: %s\n", error_type, message);
+    *(int *)userdata += 1;
+}
+
+static void map_callback(WGPUBufferMapAsyncStatus status, void *userdata) {
+    *(WGPUBufferMapAsyncStatus *)userdata = status;
+}
+
+int wgpu_ffi_test_compute(void) {
+    int result = 0;
+    int error_count = 0;
+    uint32_t input[ELEMENT_COUNT] = {1, 2, 3, 4};
+    uint64_t size = sizeof(input);
+
+    WGPUInstance instance = wgpuCreateInstance(NULL);
+    WGPUAdapter adapter = NULL;
+    wgpuInstanceRequestAdapter(instance, NULL, request_adapter_callback, &adapter);
+    if (adapter == NULL) {
+        printf("Skipping the test\n");
+        wgpuInstanceRelease(instance);
+        return 0;
+    }
+    WGPUDevice device = NULL;
+    wgpuAdapterRequestDevice(adapter, NULL, request_device_callback, &device);
+    if (device == NULL) {
+        wgpuAdapterRelease(adapter);
+        wgpuInstanceRelease(instance);
+        return 1;
+    }
+    wgpuDeviceSetUncapturedErrorCallback(device, error_callback, &error_count);
+    WGPUQueue queue = wgpuDeviceGetQueue(device);
+
+    WGPUShaderModuleWGSLDescriptor wgsl_descriptor = {
+        .chain = {.next = NULL, .sType = WGPUSType_ShaderModuleWGSLDescriptor},
+        .source = SHADER,
+    };
+    WGPUShaderModuleDescriptor shader_descriptor = {
+        .nextInChain = &wgsl_descriptor.chain,
+        .label = "double",
+    };
+    WGPUShaderModule shader = wgpuDeviceCreateShaderModule(device, &shader_descriptor);
+
+    WGPUBufferDescriptor storage_descriptor = {
+        .label = "storage",
+        .usage = WGPUBufferUsage_Storage | WGPUBufferUsage_CopySrc | WGPUBufferUsage_CopyDst,
+        .size = size,
+        .mappedAtCreation = false,
+    };
+    WGPUBuffer storage = wgpuDeviceCreateBuffer(device, &storage_descriptor);
+    WGPUBufferDescriptor staging_descriptor = {
+        .label = "staging",
+        .usage = WGPUBufferUsage_MapRead | WGPUBufferUsage_CopyDst,
+        .size = size,
+        .mappedAtCreation = false,
+    };
+    WGPUBuffer staging = wgpuDeviceCreateBuffer(device, &staging_descriptor);
+    wgpuQueueWriteBuffer(queue, storage, 0, input, sizeof(input));
+
+    /* The layout is derived from the shader. */
+    WGPUComputePipelineDescriptor pipeline_descriptor = {
+        .label = "double",
+        .layout = NULL,
+        .compute = {.module = shader, .entryPoint = "main"},
+    };
+    WGPUComputePipeline pipeline = wgpuDeviceCreateComputePipeline(device, &pipeline_descriptor);
+    WGPUBindGroupLayout bind_group_layout = wgpuComputePipelineGetBindGroupLayout(pipeline, 0);
+    WGPUBindGroupEntry entry = {
+        .binding = 0,
+        .buffer = storage,
+        .offset = 0,
+        .size = WGPU_WHOLE_SIZE,
+    };
+    WGPUBindGroupDescriptor bind_group_descriptor = {
+        .layout = bind_group_layout,
+        .entryCount = 1,
+        .entries = &entry,
+    };
+    WGPUBindGroup bind_group = wgpuDeviceCreateBindGroup(device, &bind_group_descriptor);
+
+    WGPUCommandEncoder encoder = wgpuDeviceCreateCommandEncoder(device, NULL);
+    WGPUComputePassEncoder pass = wgpuCommandEncoderBeginComputePass(encoder, NULL);
+    wgpuComputePassEncoderSetPipeline(pass, pipeline);
+    wgpuComputePassEncoderSetBindGroup(pass, 0, bind_group, 0, NULL);
+    wgpuComputePassEncoderDispatch(pass, ELEMENT_COUNT, 1, 1);
+    wgpuComputePassEncoderEndPass(pass);
+    wgpuComputePassEncoderRelease(pass);
+    wgpuCommandEncoderCopyBufferToBuffer(encoder, storage, 0, staging, 0, size);
+    WGPUCommandBuffer command_buffer = wgpuCommandEncoderFinish(encoder, NULL);
+    wgpuCommandEncoderRelease(encoder);
+    wgpuQueueSubmit(queue, 1, &command_buffer);
+    wgpuCommandBufferRelease(command_buffer);
+
+    WGPUBufferMapAsyncStatus map_status = WGPUBufferMapAsyncStatus_Unknown;
+    wgpuBufferMapAsync(staging, WGPUMapMode_Read, 0, (size_t)size, map_callback, &map_status);
+    wgpuDevicePoll(device, true);
+    if (map_status != WGPUBufferMapAsyncStatus_Success) {
+        printf("Mapping failed with status %u\n", map_status);
+        result = 1;
+    } else {
+        const uint32_t *output = wgpuBufferGetConstMappedRange(staging, 0, (size_t)size);
+        for (int i = 0; i < ELEMENT_COUNT; ++i) {
+            if (output == NULL || output[i] != input[i] * 2) {
+                printf("Unexpected value at %d\n", i);
+                result = 1;
+                break;
+            }
+        }
+        wgpuBufferUnmap(staging);
+    }
+    if (error_count != 0) {
+        result = 1;
+    }
+
+    wgpuBindGroupRelease(bind_group);
+    wgpuBindGroupLayoutRelease(bind_group_layout);
+    wgpuComputePipelineRelease(pipeline);
+    wgpuShaderModuleRelease(shader);
+    wgpuBufferRelease(staging);
+    wgpuBufferRelease(storage);
+    wgpuQueueRelease(queue);
+    wgpuDeviceRelease(device);
+    wgpuAdapterRelease(adapter);
+    wgpuInstanceRelease(instance);
+    return result;
+}
@@ -0,0 +1,20 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/*! Tests of the `webgpu.h` C API, written in C.
+ *
+ * The C functions resolve to the entry points exported by `wgpu-core` with the `ffi` feature.
+!*/
+
+// Link the C API exported by `wgpu-core`.
+use wgc as _;
+
+extern "C" {
+    fn wgpu_ffi_test_compute() -> i32;
+}
+
+#[test]
+fn compute() {
+    assert_eq!(unsafe { wgpu_ffi_test_compute() }, 0);
+}
@@ -0,0 +1,451 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/*! This is a minimizer for WebGPU traces that reproduce a failure.
+ *
+ * Usage: `minimize <trace-dir> <output-dir> <failure-pattern>`
+ *
+ * The trace is replayed (with swap chains replaced by offscreen textures) over and over,
+ * and considered failing if the replay panics with a message containing `failure-pattern`.
+ * Since `GlobalPlay::process` panics with the debug representation of any error,
+ * the pattern can be either a part of the message or an error name, e.g. `MissingBufferUsage`.
+ * Crashes that abort the process can't be detected.
+ *
+ * Delta debugging is applied to the actions of the trace, then to the commands
+ * of each `Submit`, then to the commands of each recorded pass. Removing an action
+ * that creates a resource also removes all the actions referring to it. Note that ids
+ * are matched by value regardless of the resource type, so this is conservative.
+ * The minimal trace is written into `output-dir`, together with the data files it uses.
+!*/
+
+use player::{IdentityPassThroughFactory, OffscreenSwapChains};
+use wgc::{
+    command::{BasePass, ComputeCommand, RenderCommand},
+    device::trace,
+    gfx_select,
+};
+
+use std::{
+    collections::HashSet,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+/// A command of a recorded pass, which may consume a part of the pass payload.
+trait PassCommand: Copy {
+    /// Returns the number of dynamic offsets and string bytes used by this command.
+    fn payload(&self) -> (usize, usize);
+}
+
+impl PassCommand for ComputeCommand {
+    fn payload(&self) -> (usize, usize) {
+        match *self {
+            ComputeCommand::SetBindGroup {
+                num_dynamic_offsets,
+                ..
+            } => (num_dynamic_offsets as usize, 0),
+            ComputeCommand::PushDebugGroup { len, .. }
+            | ComputeCommand::InsertDebugMarker { len, .. } => (0, len),
+            _ => (0, 0),
+        }
+    }
+}
+
+impl PassCommand for RenderCommand {
+    fn payload(&self) -> (usize, usize) {
+        match *self {
+            RenderCommand::SetBindGroup {
+                num_dynamic_offsets,
+                ..
+            } => (num_dynamic_offsets as usize, 0),
+            RenderCommand::PushDebugGroup { len, .. }
+            | RenderCommand::InsertDebugMarker { len, .. } => (0, len),
+            _ => (0, 0),
+        }
+    }
+}
+
+/// Builds a copy of `base` with only the commands at `kept` indices,
+/// keeping their dynamic offsets and string data.
+fn filter_pass<C: PassCommand>(base: &BasePass<C>, kept: &[usize]) -> BasePass<C> {
+    let mut result = BasePass {
+        label: base.label.clone(),
+        commands: Vec::with_capacity(kept.len()),
+        dynamic_offsets: Vec::new(),
+        string_data: Vec::new(),
+        push_constant_data: base.push_constant_data.clone(),
+    };
+    let mut kept = kept.iter().peekable();
+    let (mut offset_cursor, mut string_cursor) = (0, 0);
+    for (index, command) in base.commands.iter().enumerate() {
+        let (num_offsets, string_len) = command.payload();
+        if kept.peek() == Some(&&index) {
+            kept.next();
+            result.commands.push(*command);
+            result
+                .dynamic_offsets
+                .extend_from_slice(&base.dynamic_offsets[offset_cursor..][..num_offsets]);
+            result
+                .string_data
+                .extend_from_slice(&base.string_data[string_cursor..][..string_len]);
+        }
+        offset_cursor += num_offsets;
+        string_cursor += string_len;
+    }
+    result
+}
+
+/// Reduces `items` to a smaller subset for which `test` still returns true,
+/// following the "ddmin" algorithm by removing complements of growing granularity.
+fn ddmin<T: Clone>(mut items: Vec<T>, test: &mut dyn FnMut(&[T]) -> bool) -> Vec<T> {
+    if items.is_empty() || test(&[]) {
+        return Vec::new();
+    }
+    let mut granularity = 2;
+    while items.len() >= 2 {
+        let chunk_size = (items.len() + granularity - 1) / granularity;
+        let mut reduced = false;
+        for start in (0..items.len()).step_by(chunk_size) {
+            let end = (start + chunk_size).min(items.len());
+            let complement = items[..start]
+                .iter()
+                .chain(&items[end..])
+                .cloned()
+                .collect::<Vec<_>>();
+            if test(&complement) {
+                items = complement;
+                granularity = (granularity - 1).max(2);
+                reduced = true;
+                break;
+            }
+        }
+        if !reduced {
+            if granularity >= items.len() {
+                break;
+            }
+            granularity = (granularity * 2).min(items.len());
+        }
+    }
+    items
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else {
+        String::new()
+    }
+}
+
+fn to_ron<T: serde::Serialize>(value: &T) -> String {
+    ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).unwrap()
+}
+
+/// Extracts all the ids serialized in a RON string, in order of appearance.
+fn serialized_ids(ron: &str) -> Vec<&str> {
+    let mut ids = Vec::new();
+    let mut rest = ron;
+    while let Some(start) = rest.find("Id(") {
+        let end = start + rest[start..].find(')').unwrap() + 1;
+        ids.push(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    ids
+}
+
+/// Id dependencies of a trace action.
+struct Dependencies {
+    created: Option<String>,
+    used: Vec<String>,
+}
+
+impl Dependencies {
+    fn new(action: &str) -> Self {
+        let name = &action[..action.find('(').unwrap_or(action.len())];
+        let mut ids = serialized_ids(action).into_iter().map(str::to_string);
+        let created = if name.starts_with("Create") || name == "GetSwapChainTexture" {
+            ids.next()
+        } else {
+            None
+        };
+        Self {
+            created,
+            used: ids.collect(),
+        }
+    }
+}
+
+struct Minimizer {
+    dir: PathBuf,
+    pattern: String,
+    run_count: usize,
+}
+
+impl Minimizer {
+    /// Replays the actions, returning the panic message if it failed.
+    fn replay(&self, actions: &[String]) -> Result<(), String> {
+        let global = wgc::hub::Global::new(
+            "minimizer",
+            IdentityPassThroughFactory,
+            wgt::BackendBit::PRIMARY,
+        );
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let actions = actions
+                .iter()
+                .map(|string| ron::de::from_str::<trace::Action>(string).unwrap())
+                .collect::<Vec<_>>();
+            let mut offscreen = OffscreenSwapChains::new(&actions, None);
+            let mut command_buffer_id_manager = wgc::hub::IdentityManager::default();
+            let mut actions = actions.into_iter();
+
+            let device = match actions.next() {
+                Some(trace::Action::Init { desc, backend }) => {
+                    let adapter = global
+                        .request_adapter(
+                            &wgc::instance::RequestAdapterOptions {
+                                power_preference: wgt::PowerPreference::LowPower,
+                                compatible_surface: None,
+                            },
+                            wgc::instance::AdapterInputs::IdSet(
+                                &[wgc::id::TypedId::zip(0, 0, backend)],
+                                |id| id.backend(),
+                            ),
+                        )
+                        .expect("Unable to find an adapter for selected backend");
+                    let id = wgc::id::TypedId::zip(1, 0, backend);
+                    let (_, error) = gfx_select!(adapter => global.adapter_request_device(
+                        adapter,
+                        &desc,
+                        None,
+                        id
+                    ));
+                    if let Some(e) = error {
+                        panic!("{:?}", e);
+                    }
+                    id
+                }
+                _ => panic!("Expected Action::Init"),
+            };
+
+            for action in actions {
+                gfx_select!(device => offscreen.process(&global, device, action, &self.dir, &mut command_buffer_id_manager));
+            }
+            gfx_select!(device => global.device_poll(device, true)).unwrap();
+        }));
+        // The global may be broken after a panic, so its destruction can panic too.
+        let _ = panic::catch_unwind(AssertUnwindSafe(move || drop(global)));
+        result.map_err(panic_message)
+    }
+
+    fn reproduces(&mut self, actions: &[String]) -> bool {
+        self.run_count += 1;
+        match self.replay(actions) {
+            Ok(()) => false,
+            Err(message) => message.contains(&self.pattern),
+        }
+    }
+
+    /// Removes top-level actions, together with the actions depending on them.
+    fn minimize_actions(&mut self, actions: Vec<String>) -> Vec<String> {
+        let dependencies = actions
+            .iter()
+            .map(|action| Dependencies::new(action))
+            .collect::<Vec<_>>();
+        // Closes the set of kept action indices over the id dependencies.
+        let close = |kept: &[usize]| {
+            let kept_set = kept.iter().cloned().collect::<HashSet<_>>();
+            let mut removed_ids = dependencies
+                .iter()
+                .enumerate()
+                .filter(|&(index, _)| !kept_set.contains(&index))
+                .filter_map(|(_, deps)| deps.created.as_ref())
+                .collect::<HashSet<_>>();
+            let mut closed = Vec::with_capacity(kept.len());
+            for &index in kept {
+                let deps = &dependencies[index];
+                if deps.used.iter().any(|id| removed_ids.contains(id)) {
+                    removed_ids.extend(deps.created.as_ref());
+                } else {
+                    closed.push(index);
+                }
+            }
+            closed
+        };
+        let select = |indices: &[usize]| {
+            // `Init` is always kept in front
+            Some(actions[0].clone())
+                .into_iter()
+                .chain(indices.iter().map(|&index| actions[index].clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let kept = ddmin(
+            (1..actions.len()).collect(),
+            &mut |candidate: &[usize]| self.reproduces(&select(&close(candidate))),
+        );
+        select(&close(&kept))
+    }
+
+    /// Removes commands inside each `Submit`.
+    fn minimize_submits(&mut self, mut actions: Vec<String>) -> Vec<String> {
+        for index in 0..actions.len() {
+            let (submit_index, commands) = match ron::de::from_str(&actions[index]) {
+                Ok(trace::Action::Submit(submit_index, commands)) => (
+                    submit_index,
+                    commands.iter().map(to_ron).collect::<Vec<_>>(),
+                ),
+                _ => continue,
+            };
+            let make_action = |commands: &[String]| {
+                let commands = commands
+                    .iter()
+                    .map(|string| ron::de::from_str::<trace::Command>(string).unwrap())
+                    .collect();
+                to_ron(&trace::Action::Submit(submit_index, commands))
+            };
+            let kept = ddmin(commands, &mut |candidate: &[String]| {
+                let mut actions = actions.clone();
+                actions[index] = make_action(candidate);
+                self.reproduces(&actions)
+            });
+            actions[index] = make_action(&kept);
+        }
+        actions
+    }
+
+    fn minimize_pass<C: PassCommand>(
+        &mut self,
+        base: &BasePass<C>,
+        make_actions: &dyn Fn(BasePass<C>) -> Vec<String>,
+    ) -> Vec<String> {
+        let kept = ddmin(
+            (0..base.commands.len()).collect(),
+            &mut |candidate: &[usize]| self.reproduces(&make_actions(filter_pass(base, candidate))),
+        );
+        make_actions(filter_pass(base, &kept))
+    }
+
+    /// Removes commands inside each recorded pass.
+    fn minimize_passes(&mut self, mut actions: Vec<String>) -> Vec<String> {
+        for index in 0..actions.len() {
+            let num_commands = match ron::de::from_str(&actions[index]) {
+                Ok(trace::Action::Submit(_, commands)) => commands.len(),
+                _ => continue,
+            };
+            for cmd_index in 0..num_commands {
+                // Re-parse every time, since the submission gets updated.
+                let (submit_index, mut commands) = match ron::de::from_str(&actions[index]) {
+                    Ok(trace::Action::Submit(submit_index, commands)) => (submit_index, commands),
+                    _ => unreachable!(),
+                };
+                let command = commands.remove(cmd_index);
+                let commands = commands.iter().map(to_ron).collect::<Vec<_>>();
+                let make_actions = |command: trace::Command| {
+                    let mut commands = commands
+                        .iter()
+                        .map(|string| ron::de::from_str::<trace::Command>(string).unwrap())
+                        .collect::<Vec<_>>();
+                    commands.insert(cmd_index, command);
+                    let mut result = actions.clone();
+                    result[index] = to_ron(&trace::Action::Submit(submit_index, commands));
+                    result
+                };
+                actions = match command {
+                    trace::Command::RunComputePass { base } => self.minimize_pass(&base, &|base| {
+                        make_actions(trace::Command::RunComputePass { base })
+                    }),
+                    trace::Command::RunRenderPass {
+                        base,
+                        target_colors,
+                        target_depth_stencil,
+                    } => {
+                        let target_colors = to_ron(&target_colors);
+                        let target_depth_stencil = to_ron(&target_depth_stencil);
+                        self.minimize_pass(&base, &|base| {
+                            make_actions(trace::Command::RunRenderPass {
+                                base,
+                                target_colors: ron::de::from_str(&target_colors).unwrap(),
+                                target_depth_stencil: ron::de::from_str(&target_depth_stencil)
+                                    .unwrap(),
+                            })
+                        })
+                    }
+                    _ => continue,
+                };
+            }
+        }
+        actions
+    }
+}
+
+fn write_trace(dir: &Path, out_dir: &Path, actions: &[String]) {
+    fs::create_dir_all(out_dir).unwrap();
+    let mut contents = String::from("[\n");
+    for action in actions {
+        contents.push_str(action);
+        contents.push_str(",\n");
+    }
+    contents.push(']');
+
+    // copy only the data files that are still referenced
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name != trace::FILE_NAME => name,
+            _ => continue,
+        };
+        if path.is_file() && contents.contains(&format!("\"{}\"", name)) {
+            fs::copy(&path, out_dir.join(name)).unwrap();
+        }
+    }
+    fs::write(out_dir.join(trace::FILE_NAME), contents).unwrap();
+}
+
+fn main() {
+    env_logger::init();
+
+    let mut args = std::env::args().skip(1);
+    let (dir, out_dir, pattern) = match (args.next(), args.next(), args.next()) {
+        (Some(dir), Some(out_dir), Some(pattern)) if Path::new(&dir).is_dir() => {
+            (PathBuf::from(dir), PathBuf::from(out_dir), pattern)
+        }
+        _ => panic!("Usage: minimize <trace-dir> <output-dir> <failure-pattern>"),
+    };
+
+    log::info!("Loading trace '{:?}'", dir);
+    let file = fs::File::open(dir.join(trace::FILE_NAME)).unwrap();
+    let actions: Vec<trace::Action> = ron::de::from_reader(file).unwrap();
+    let actions = actions.iter().map(to_ron).collect::<Vec<_>>();
+    log::info!("Found {} actions", actions.len());
+
+    // The replays are expected to panic a lot, so only log the messages.
+    panic::set_hook(Box::new(|info| log::debug!("Replay failed: {}", info)));
+
+    let mut minimizer = Minimizer {
+        dir: dir.clone(),
+        pattern,
+        run_count: 0,
+    };
+    match minimizer.replay(&actions) {
+        Err(ref message) if message.contains(&minimizer.pattern) => {
+            log::info!("Original failure: {}", message);
+        }
+        _ => {
+            let _ = panic::take_hook();
+            panic!("The trace doesn't fail with '{}'", minimizer.pattern);
+        }
+    }
+
+    let actions = minimizer.minimize_actions(actions);
+    log::info!("Reduced to {} actions", actions.len());
+    let actions = minimizer.minimize_submits(actions);
+    let actions = minimizer.minimize_passes(actions);
+    log::info!("Finished after {} replays", minimizer.run_count);
+
+    write_trace(&dir, &out_dir, &actions);
+    log::info!("Minimal trace is written into '{:?}'", out_dir);
+}
@@ -3,6 +3,11 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 /*! This is a player for WebGPU traces.
+ *
+ * Usage: `play <trace-dir> [frame-dump-dir]`
+ *
+ * Without the `winit` feature, swap chains are replaced by offscreen textures,
+ * and the presented frames are written as PNG files into `frame-dump-dir` if it's given.
 !*/
 
 use player::{GlobalPlay as _, IdentityPassThroughFactory};
@@ -36,6 +41,8 @@ fn main() {
         Some(arg) if Path::new(&arg).is_dir() => PathBuf::from(arg),
         _ => panic!("Provide the dir path as the parameter"),
     };
+    #[cfg(not(feature = "winit"))]
+    let dump_dir = std::env::args().nth(2).map(PathBuf::from);
 
     log::info!("Loading trace '{:?}'", dir);
     let file = fs::File::open(dir.join(trace::FILE_NAME)).unwrap();
@@ -105,11 +112,16 @@ fn main() {
     log::info!("Executing actions");
     #[cfg(not(feature = "winit"))]
     {
+        if let Some(ref dump_dir) = dump_dir {
+            fs::create_dir_all(dump_dir).unwrap();
+        }
+        let mut offscreen = player::OffscreenSwapChains::new(&actions, dump_dir);
+
         #[cfg(feature = "renderdoc")]
         rd.start_frame_capture(std::ptr::null(), std::ptr::null());
 
         while let Some(action) = actions.pop() {
-            gfx_select!(device => global.process(device, action, &dir, &mut command_buffer_id_manager));
+            gfx_select!(device => offscreen.process(&global, device, action, &dir, &mut command_buffer_id_manager));
         }
 
         #[cfg(feature = "renderdoc")]
@@ -12,7 +12,15 @@
 
 use wgc::device::trace;
 
-use std::{borrow::Cow, fmt::Debug, fs, marker::PhantomData, path::Path};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::Debug,
+    fs, io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    ptr, slice,
+};
 
 #[derive(Debug)]
 pub struct IdentityPassThrough<I>(PhantomData<I>);
@@ -38,6 +46,13 @@ impl<I: Clone + Debug + wgc::id::TypedId> wgc::hub::IdentityHandlerFactory<I>
 }
 impl wgc::hub::GlobalIdentityHandlerFactory for IdentityPassThroughFactory {}
 
+extern "C" fn map_callback(status: wgc::resource::BufferMapAsyncStatus, _user_data: *mut u8) {
+    match status {
+        wgc::resource::BufferMapAsyncStatus::Success => (),
+        _ => panic!("Unable to map"),
+    }
+}
+
 pub trait GlobalPlay {
     fn encode_commands<B: wgc::hub::GfxBackend>(
         &self,
@@ -51,6 +66,17 @@ pub trait GlobalPlay {
         dir: &Path,
         comb_manager: &mut wgc::hub::IdentityManager,
     );
+    /// Copies a region of a texture into a new `staging_buffer`, waits for it,
+    /// and returns the texels with rows tightly packed.
+    fn read_texture<B: wgc::hub::GfxBackend>(
+        &self,
+        device: wgc::id::DeviceId,
+        src: &wgc::command::TextureCopyView,
+        size: &wgt::Extent3d,
+        format: wgt::TextureFormat,
+        staging_buffer: wgc::id::BufferId,
+        comb_manager: &mut wgc::hub::IdentityManager,
+    ) -> Vec<u8>;
 }
 
 impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
@@ -142,8 +168,26 @@ impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
         //TODO: find a way to force ID perishing without excessive `maintain()` calls.
         match action {
             A::Init { .. } => panic!("Unexpected Action::Init: has to be the first action only"),
-            A::CreateSwapChain { .. } | A::PresentSwapChain(_) => {
-                panic!("Unexpected SwapChain action: winit feature is not enabled")
+            A::CreateSwapChain(id, desc) => {
+                // The surface has to exist already, like the one of a window.
+                // `OffscreenSwapChains` replays these without a surface.
+                self.device_create_swap_chain::<B>(device, id.to_surface_id(), &desc)
+                    .unwrap();
+            }
+            A::PresentSwapChain(id) => {
+                self.swap_chain_present::<B>(id).unwrap();
+            }
+            A::CreateBufferFromHal(id, _) => {
+                panic!(
+                    "Buffer {:?} was created from a hal buffer, which can't be replayed",
+                    id
+                )
+            }
+            A::CreateTextureFromHal(id, _) => {
+                panic!(
+                    "Texture {:?} was created from a hal texture, which can't be replayed",
+                    id
+                )
             }
             A::CreateBuffer(id, desc) => {
                 self.device_maintain_ids::<B>(device).unwrap();
@@ -231,10 +275,26 @@ impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
             A::DestroyBindGroup(id) => {
                 self.bind_group_drop::<B>(id);
             }
-            A::CreateShaderModule { id, desc, data } => {
+            A::CreateShaderModule {
+                id,
+                desc,
+                data,
+                defines,
+            } => {
+                let glsl_stage = wgc::device::trace::GLSL_EXTENSIONS
+                    .iter()
+                    .find(|&&(extension, _)| data.ends_with(&format!(".{}", extension)))
+                    .map(|&(_, stage)| stage);
                 let source = if data.ends_with(".wgsl") {
                     let code = fs::read_to_string(dir.join(data)).unwrap();
                     wgc::pipeline::ShaderModuleSource::Wgsl(Cow::Owned(code))
+                } else if let Some(stage) = glsl_stage {
+                    let code = fs::read_to_string(dir.join(data)).unwrap();
+                    wgc::pipeline::ShaderModuleSource::Glsl {
+                        code: Cow::Owned(code),
+                        stage,
+                        defines: defines.into_iter().collect(),
+                    }
                 } else {
                     let byte_vec = fs::read(dir.join(&data))
                         .unwrap_or_else(|e| panic!("Unable to open '{}': {:?}", data, e));
@@ -318,6 +378,9 @@ impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
                     panic!("{:?}", e);
                 }
             }
+            A::FreeQuerySet(id) => {
+                self.query_set_destroy::<B>(id).unwrap();
+            }
             A::DestroyQuerySet(id) => {
                 self.query_set_drop::<B>(id);
             }
@@ -365,4 +428,270 @@ impl GlobalPlay for wgc::hub::Global<IdentityPassThroughFactory> {
             }
         }
     }
+
+    fn read_texture<B: wgc::hub::GfxBackend>(
+        &self,
+        device: wgc::id::DeviceId,
+        src: &wgc::command::TextureCopyView,
+        size: &wgt::Extent3d,
+        format: wgt::TextureFormat,
+        staging_buffer: wgc::id::BufferId,
+        comb_manager: &mut wgc::hub::IdentityManager,
+    ) -> Vec<u8> {
+        let bytes_per_row = size.width * format.describe().block_size as u32;
+        let padded_bytes_per_row = {
+            let align = wgt::COPY_BYTES_PER_ROW_ALIGNMENT;
+            (bytes_per_row + align - 1) / align * align
+        };
+        let row_count = size.height * size.depth_or_array_layers;
+        let buffer_size = padded_bytes_per_row as wgt::BufferAddress * row_count as u64;
+
+        self.device_maintain_ids::<B>(device).unwrap();
+        let (_, error) = self.device_create_buffer::<B>(
+            device,
+            &wgc::resource::BufferDescriptor {
+                label: Some(Cow::Borrowed("player staging")),
+                size: buffer_size,
+                usage: wgt::BufferUsage::MAP_READ | wgt::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            },
+            staging_buffer,
+        );
+        if let Some(e) = error {
+            panic!("{:?}", e);
+        }
+
+        let (encoder, error) = self.device_create_command_encoder::<B>(
+            device,
+            &wgt::CommandEncoderDescriptor { label: None },
+            comb_manager.alloc(device.backend()),
+        );
+        if let Some(e) = error {
+            panic!("{:?}", e);
+        }
+        let cmdbuf = self.encode_commands::<B>(
+            encoder,
+            vec![trace::Command::CopyTextureToBuffer {
+                src: src.clone(),
+                dst: wgc::command::BufferCopyView {
+                    buffer: staging_buffer,
+                    layout: wgt::TextureDataLayout {
+                        offset: 0,
+                        bytes_per_row: padded_bytes_per_row,
+                        rows_per_image: size.height,
+                    },
+                },
+                size: *size,
+            }],
+        );
+        self.queue_submit::<B>(device, &[cmdbuf]).unwrap();
+
+        self.buffer_map_async::<B>(
+            staging_buffer,
+            0..buffer_size,
+            wgc::resource::BufferMapOperation {
+                host: wgc::device::HostMap::Read,
+                callback: map_callback,
+                user_data: ptr::null_mut(),
+            },
+        )
+        .unwrap();
+        self.device_poll::<B>(device, true).unwrap();
+
+        let ptr = self
+            .buffer_get_mapped_range::<B>(staging_buffer, 0, None)
+            .unwrap();
+        let mapped = unsafe { slice::from_raw_parts(ptr, buffer_size as usize) };
+        let mut data = Vec::with_capacity((bytes_per_row * row_count) as usize);
+        for row in mapped.chunks(padded_bytes_per_row as usize) {
+            data.extend_from_slice(&row[..bytes_per_row as usize]);
+        }
+
+        self.buffer_unmap::<B>(staging_buffer).unwrap();
+        self.buffer_drop::<B>(staging_buffer, false);
+        data
+    }
+}
+
+struct OffscreenSwapChain {
+    desc: wgt::SwapChainDescriptor,
+    texture: wgc::id::TextureId,
+    view: Option<wgc::id::TextureViewId>,
+}
+
+/// Replaces the swap chains of a trace with offscreen textures,
+/// so that it can be replayed without a window.
+///
+/// Each swap chain is backed by a texture matching the traced descriptor.
+/// If `dump_dir` is provided, every presented frame is read back and
+/// written there as a PNG file.
+pub struct OffscreenSwapChains {
+    swap_chains: HashMap<wgc::id::SwapChainId, OffscreenSwapChain>,
+    next_texture_index: u32,
+    next_buffer_index: u32,
+    frame_count: u32,
+    dump_dir: Option<PathBuf>,
+}
+
+impl OffscreenSwapChains {
+    /// Creates the offscreen state for replaying `actions`.
+    ///
+    /// The offscreen resources use the IDs past the ones found in `actions`.
+    pub fn new(actions: &[trace::Action], dump_dir: Option<PathBuf>) -> Self {
+        use wgc::id::TypedId as _;
+
+        let mut next_texture_index = 0;
+        let mut next_buffer_index = 0;
+        for action in actions {
+            match *action {
+                trace::Action::CreateTexture(id, _) => {
+                    next_texture_index = next_texture_index.max(id.unzip().0 + 1);
+                }
+                trace::Action::CreateBuffer(id, _) => {
+                    next_buffer_index = next_buffer_index.max(id.unzip().0 + 1);
+                }
+                _ => {}
+            }
+        }
+        Self {
+            swap_chains: HashMap::new(),
+            next_texture_index,
+            next_buffer_index,
+            frame_count: 0,
+            dump_dir,
+        }
+    }
+
+    /// Processes a trace action, servicing the swap chain ones offscreen.
+    pub fn process<B: wgc::hub::GfxBackend>(
+        &mut self,
+        global: &wgc::hub::Global<IdentityPassThroughFactory>,
+        device: wgc::id::DeviceId,
+        action: trace::Action,
+        dir: &Path,
+        comb_manager: &mut wgc::hub::IdentityManager,
+    ) {
+        use wgc::id::TypedId as _;
+
+        match action {
+            trace::Action::CreateSwapChain(id, desc) => {
+                log::info!("Creating an offscreen swap chain {:?}", id);
+                if let Some(old) = self.swap_chains.remove(&id) {
+                    global.texture_drop::<B>(old.texture, false);
+                }
+                let texture = wgc::id::TextureId::zip(self.next_texture_index, 1, B::VARIANT);
+                self.next_texture_index += 1;
+
+                global.device_maintain_ids::<B>(device).unwrap();
+                let (_, error) = global.device_create_texture::<B>(
+                    device,
+                    &wgc::resource::TextureDescriptor {
+                        label: Some(Cow::Borrowed("offscreen swap chain")),
+                        size: wgt::Extent3d {
+                            width: desc.width,
+                            height: desc.height,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgt::TextureDimension::D2,
+                        format: desc.format,
+                        usage: desc.usage | wgt::TextureUsage::COPY_SRC,
+                    },
+                    texture,
+                );
+                if let Some(e) = error {
+                    panic!("{:?}", e);
+                }
+                self.swap_chains.insert(
+                    id,
+                    OffscreenSwapChain {
+                        desc,
+                        texture,
+                        view: None,
+                    },
+                );
+            }
+            trace::Action::GetSwapChainTexture { id, parent_id } => {
+                let swap_chain = self
+                    .swap_chains
+                    .get_mut(&parent_id)
+                    .expect("Swap chain is not created");
+                assert!(swap_chain.view.is_none(), "Previous frame is not presented");
+                global.device_maintain_ids::<B>(device).unwrap();
+                let (_, error) = global.texture_create_view::<B>(
+                    swap_chain.texture,
+                    &wgc::resource::TextureViewDescriptor::default(),
+                    id,
+                );
+                if let Some(e) = error {
+                    panic!("{:?}", e);
+                }
+                swap_chain.view = Some(id);
+            }
+            trace::Action::PresentSwapChain(id) => {
+                self.frame_count += 1;
+                log::debug!("Presenting frame {}", self.frame_count);
+                let swap_chain = self
+                    .swap_chains
+                    .get_mut(&id)
+                    .expect("Swap chain is not created");
+                let view = swap_chain.view.take().expect("No frame to present");
+
+                if let Some(ref dump_dir) = self.dump_dir {
+                    let staging_buffer =
+                        wgc::id::BufferId::zip(self.next_buffer_index, 1, B::VARIANT);
+                    self.next_buffer_index += 1;
+                    let data = global.read_texture::<B>(
+                        device,
+                        &wgc::command::TextureCopyView {
+                            texture: swap_chain.texture,
+                            mip_level: 0,
+                            origin: wgt::Origin3d::ZERO,
+                            aspect: wgt::TextureAspect::All,
+                        },
+                        &wgt::Extent3d {
+                            width: swap_chain.desc.width,
+                            height: swap_chain.desc.height,
+                            depth_or_array_layers: 1,
+                        },
+                        swap_chain.desc.format,
+                        staging_buffer,
+                        comb_manager,
+                    );
+                    let path = dump_dir.join(format!("frame-{:04}.png", self.frame_count));
+                    write_png(&path, &swap_chain.desc, data);
+                }
+
+                global.texture_view_drop::<B>(view, false).unwrap();
+            }
+            action => global.process::<B>(device, action, dir, comb_manager),
+        }
+    }
+}
+
+fn write_png(path: &Path, desc: &wgt::SwapChainDescriptor, mut data: Vec<u8>) {
+    match desc.format {
+        wgt::TextureFormat::Rgba8Unorm | wgt::TextureFormat::Rgba8UnormSrgb => {}
+        wgt::TextureFormat::Bgra8Unorm | wgt::TextureFormat::Bgra8UnormSrgb => {
+            for texel in data.chunks_mut(4) {
+                texel.swap(0, 2);
+            }
+        }
+        other => {
+            log::warn!("Unable to write a frame of format {:?} as PNG", other);
+            return;
+        }
+    }
+
+    log::info!("Writing frame to {:?}", path);
+    let file = io::BufWriter::new(fs::File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, desc.width, desc.height);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(&data)
+        .unwrap();
 }
@@ -0,0 +1,9 @@
+[[group(0), binding(0)]]
+var image: [[access(read_write)]] texture_storage_2d<r32uint>;
+
+[[stage(compute), workgroup_size(1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+    const coords: vec2<i32> = vec2<i32>(i32(global_id.x), 0);
+    const value: vec4<u32> = textureLoad(image, coords);
+    textureStore(image, coords, vec4<u32>(value.x + global_id.x + 1u, 0u, 0u, 1u));
+}
@@ -9,14 +9,16 @@
  *  Test requirements:
  *    - all IDs have the backend `Empty`
  *    - all expected buffers have `MAP_READ` usage
+ *    - all expected textures have `COPY_SRC` usage
  *    - last action is `Submit`
  *    - no swapchain use
 !*/
 
 use player::{GlobalPlay, IdentityPassThroughFactory};
 use std::{
-    fs::{read_to_string, File},
-    io::{Read, Seek, SeekFrom},
+    collections::HashMap,
+    fs::{read, read_to_string, File},
+    io::{BufWriter, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
     ptr, slice,
 };
@@ -50,10 +52,45 @@ struct Expectation {
     data: ExpectedData,
 }
 
+#[derive(serde::Deserialize)]
+enum ExpectedImage {
+    /// Reference PNG file, compared against 8-bit RGBA or BGRA textures.
+    Png(String),
+    /// Reference file with tightly packed texels of the texture format.
+    Raw(String),
+}
+
+#[derive(Clone, serde::Deserialize)]
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+#[derive(serde::Deserialize)]
+struct TextureExpectation {
+    name: String,
+    texture: RawId,
+    #[serde(default)]
+    mip_level: u32,
+    #[serde(default)]
+    array_layer: u32,
+    /// Region of the mip level to check, all of it by default.
+    #[serde(default)]
+    region: Option<Region>,
+    data: ExpectedImage,
+    /// Maximum allowed difference of each 8-bit channel (or byte, for raw data).
+    #[serde(default)]
+    tolerance: u8,
+}
+
 #[derive(serde::Deserialize)]
 struct Test<'a> {
     features: wgt::Features,
     expectations: Vec<Expectation>,
+    #[serde(default)]
+    texture_expectations: Vec<TextureExpectation>,
     actions: Vec<wgc::device::trace::Action<'a>>,
 }
 
@@ -101,6 +138,30 @@ impl Test<'_> {
             panic!("{:?}", e);
         }
 
+        // Staging buffers for the texture expectations go past the trace buffers.
+        let mut staging_buffer_index = self
+            .actions
+            .iter()
+            .filter_map(|action| match *action {
+                wgc::device::trace::Action::CreateBuffer(id, _) => Some(id.unzip().0 + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        // Texture expectations are checked against the traced descriptors.
+        let texture_descs = self
+            .actions
+            .iter()
+            .filter_map(|action| match *action {
+                wgc::device::trace::Action::CreateTexture(id, ref desc) => {
+                    let (index, epoch, _) = id.unzip();
+                    Some(((index, epoch), (desc.format, desc.size)))
+                }
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
         let mut command_buffer_id_manager = wgc::hub::IdentityManager::default();
         println!("\t\t\tRunning...");
         for action in self.actions {
@@ -151,10 +212,153 @@ impl Test<'_> {
             }
         }
 
+        for expect in self.texture_expectations {
+            println!("\t\t\tChecking {}", expect.name);
+            let &(format, size) = texture_descs
+                .get(&(expect.texture.index, expect.texture.epoch))
+                .expect("Expected texture is not created by the test");
+            let region = expect.region.clone().unwrap_or(Region {
+                x: 0,
+                y: 0,
+                width: (size.width >> expect.mip_level).max(1),
+                height: (size.height >> expect.mip_level).max(1),
+            });
+            let texture =
+                wgc::id::TypedId::zip(expect.texture.index, expect.texture.epoch, backend);
+            let staging_buffer = wgc::id::TypedId::zip(staging_buffer_index, 1, backend);
+            staging_buffer_index += 1;
+            let contents = wgc::gfx_select!(device => global.read_texture(
+                device,
+                &wgc::command::TextureCopyView {
+                    texture,
+                    mip_level: expect.mip_level,
+                    origin: wgt::Origin3d {
+                        x: region.x,
+                        y: region.y,
+                        z: expect.array_layer,
+                    },
+                    aspect: wgt::TextureAspect::All,
+                },
+                &wgt::Extent3d {
+                    width: region.width,
+                    height: region.height,
+                    depth_or_array_layers: 1,
+                },
+                format,
+                staging_buffer,
+                &mut command_buffer_id_manager
+            ));
+            expect.check(dir, format, &region, contents);
+        }
+
         wgc::gfx_select!(device => global.clear_backend(()));
     }
 }
 
+fn is_rgba8(format: wgt::TextureFormat) -> bool {
+    match format {
+        wgt::TextureFormat::Rgba8Unorm
+        | wgt::TextureFormat::Rgba8UnormSrgb
+        | wgt::TextureFormat::Bgra8Unorm
+        | wgt::TextureFormat::Bgra8UnormSrgb => true,
+        _ => false,
+    }
+}
+
+impl TextureExpectation {
+    fn check(
+        &self,
+        dir: &Path,
+        format: wgt::TextureFormat,
+        region: &Region,
+        mut contents: Vec<u8>,
+    ) {
+        match format {
+            wgt::TextureFormat::Bgra8Unorm | wgt::TextureFormat::Bgra8UnormSrgb => {
+                for texel in contents.chunks_mut(4) {
+                    texel.swap(0, 2);
+                }
+            }
+            _ => {}
+        }
+
+        let expected_data = match self.data {
+            ExpectedImage::Png(ref name) => {
+                assert!(
+                    is_rgba8(format),
+                    "PNG references are only supported for 8-bit RGBA and BGRA formats"
+                );
+                let decoder = png::Decoder::new(File::open(dir.join(name)).unwrap());
+                let (info, mut reader) = decoder.read_info().unwrap();
+                assert_eq!(info.bit_depth, png::BitDepth::Eight);
+                assert_eq!(
+                    (info.width, info.height),
+                    (region.width, region.height),
+                    "Reference image size doesn't match the region"
+                );
+                let mut data = vec![0; info.buffer_size()];
+                reader.next_frame(&mut data).unwrap();
+                match info.color_type {
+                    png::ColorType::RGBA => data,
+                    png::ColorType::RGB => data
+                        .chunks(3)
+                        .flat_map(|rgb| vec![rgb[0], rgb[1], rgb[2], 0xFF])
+                        .collect(),
+                    other => panic!("Unsupported reference color type {:?}", other),
+                }
+            }
+            ExpectedImage::Raw(ref name) => read(dir.join(name)).unwrap(),
+        };
+        assert_eq!(
+            expected_data.len(),
+            contents.len(),
+            "Reference data size doesn't match the region"
+        );
+
+        let mismatch_count = contents
+            .iter()
+            .zip(&expected_data)
+            .filter(|&(&actual, &expected)| {
+                (actual as i16 - expected as i16).abs() > self.tolerance as i16
+            })
+            .count();
+        if mismatch_count == 0 {
+            return;
+        }
+
+        let diff_message = if is_rgba8(format) {
+            let path = std::env::temp_dir().join(format!("{}-diff.png", self.name));
+            let diff = contents
+                .chunks(4)
+                .zip(expected_data.chunks(4))
+                .flat_map(|(actual, expected)| {
+                    let mut texel = [0xFF; 4];
+                    for i in 0..3 {
+                        texel[i] = (actual[i] as i16 - expected[i] as i16).abs() as u8;
+                    }
+                    texel
+                })
+                .collect::<Vec<_>>();
+            let file = BufWriter::new(File::create(&path).unwrap());
+            let mut encoder = png::Encoder::new(file, region.width, region.height);
+            encoder.set_color(png::ColorType::RGBA);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder
+                .write_header()
+                .unwrap()
+                .write_image_data(&diff)
+                .unwrap();
+            format!(", see the difference in {:?}", path)
+        } else {
+            String::new()
+        };
+        panic!(
+            "Test expectation is not met!\n{} channels are different by more than {}{}",
+            mismatch_count, self.tolerance, diff_message
+        );
+    }
+}
+
 #[derive(serde::Deserialize)]
 struct Corpus {
     backends: wgt::BackendBit,
@@ -226,3 +430,238 @@ fn test_api() {
 
     Corpus::run_from(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data/all.ron"))
 }
+
+#[test]
+fn test_live_trace() {
+    use wgc::{device::trace, gfx_select};
+
+    const DATA: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+    let dir = std::env::temp_dir().join("wgpu-player-live-trace");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let global =
+        wgc::hub::Global::new("test", IdentityPassThroughFactory, wgt::BackendBit::PRIMARY);
+    let adapter = match global.request_adapter(
+        &wgc::instance::RequestAdapterOptions::default(),
+        wgc::instance::AdapterInputs::IdSet(
+            &BACKENDS
+                .iter()
+                .map(|&backend| wgc::id::TypedId::zip(0, 0, backend))
+                .collect::<Vec<_>>(),
+            |id| id.backend(),
+        ),
+    ) {
+        Ok(adapter) => adapter,
+        Err(_) => return,
+    };
+    let backend = adapter.backend();
+    let device = wgc::id::TypedId::zip(0, 0, backend);
+    let (_, error) = gfx_select!(adapter => global.adapter_request_traceable_device(
+        adapter,
+        &wgt::DeviceDescriptor::default(),
+        device
+    ));
+    assert!(error.is_none());
+
+    // The source is filled before the trace starts.
+    let source = wgc::id::TypedId::zip(0, 1, backend);
+    let (_, error) = gfx_select!(device => global.device_create_buffer(
+        device,
+        &wgt::BufferDescriptor {
+            label: None,
+            size: DATA.len() as wgt::BufferAddress,
+            usage: wgt::BufferUsage::COPY_SRC | wgt::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        },
+        source
+    ));
+    assert!(error.is_none());
+    gfx_select!(device => global.queue_write_buffer(device, source, 0, &DATA)).unwrap();
+    gfx_select!(device => global.queue_submit(device, &[])).unwrap();
+
+    gfx_select!(device => global.device_start_trace(device, &dir)).unwrap();
+    let destination = wgc::id::TypedId::zip(1, 1, backend);
+    let (_, error) = gfx_select!(device => global.device_create_buffer(
+        device,
+        &wgt::BufferDescriptor {
+            label: None,
+            size: DATA.len() as wgt::BufferAddress,
+            usage: wgt::BufferUsage::MAP_READ | wgt::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        },
+        destination
+    ));
+    assert!(error.is_none());
+    let encoder = wgc::id::TypedId::zip(0, 1, backend);
+    let (_, error) = gfx_select!(device => global.device_create_command_encoder(
+        device,
+        &wgt::CommandEncoderDescriptor { label: None },
+        encoder
+    ));
+    assert!(error.is_none());
+    gfx_select!(device => global.command_encoder_copy_buffer_to_buffer(
+        encoder,
+        source,
+        0,
+        destination,
+        0,
+        DATA.len() as wgt::BufferAddress
+    ))
+    .unwrap();
+    let (command_buffer, error) = gfx_select!(device => global.command_encoder_finish(
+        encoder,
+        &wgt::CommandBufferDescriptor { label: None }
+    ));
+    assert!(error.is_none());
+    gfx_select!(device => global.queue_submit(device, &[command_buffer])).unwrap();
+    gfx_select!(device => global.device_stop_trace(device)).unwrap();
+    gfx_select!(device => global.device_poll(device, true)).unwrap();
+    gfx_select!(device => global.clear_backend(()));
+
+    // The replay has to restore the source contents from the snapshot.
+    let file = File::open(dir.join(trace::FILE_NAME)).unwrap();
+    let mut actions: Vec<trace::Action> = ron::de::from_reader(file).unwrap();
+    actions.reverse();
+    let global = wgc::hub::Global::new("replay", IdentityPassThroughFactory, backend.into());
+    let adapter = global
+        .request_adapter(
+            &wgc::instance::RequestAdapterOptions::default(),
+            wgc::instance::AdapterInputs::IdSet(&[wgc::id::TypedId::zip(0, 0, backend)], |id| {
+                id.backend()
+            }),
+        )
+        .unwrap();
+    match actions.pop() {
+        Some(trace::Action::Init { desc, .. }) => {
+            let (_, error) = gfx_select!(adapter => global.adapter_request_device(
+                adapter,
+                &desc,
+                None,
+                device
+            ));
+            assert!(error.is_none());
+        }
+        _ => panic!("Expected Action::Init"),
+    }
+    let mut command_buffer_id_manager = wgc::hub::IdentityManager::default();
+    while let Some(action) = actions.pop() {
+        gfx_select!(device => global.process(device, action, &dir, &mut command_buffer_id_manager));
+    }
+    gfx_select!(device => global.buffer_map_async(
+        destination,
+        0..DATA.len() as wgt::BufferAddress,
+        wgc::resource::BufferMapOperation {
+            host: wgc::device::HostMap::Read,
+            callback: map_callback,
+            user_data: ptr::null_mut(),
+        }
+    ))
+    .unwrap();
+    gfx_select!(device => global.device_poll(device, true)).unwrap();
+    let ptr = gfx_select!(device => global.buffer_get_mapped_range(destination, 0, None)).unwrap();
+    assert_eq!(unsafe { slice::from_raw_parts(ptr, DATA.len()) }, &DATA[..]);
+    gfx_select!(device => global.clear_backend(()));
+}
+
+#[test]
+fn test_glsl_defines_trace() {
+    use wgc::{device::trace, gfx_select};
+
+    const CODE: &str = "#version 450
+layout(local_size_x = 1) in;
+void main() {
+    float value = VALUE;
+}
+";
+    let dir = std::env::temp_dir().join("wgpu-player-glsl-defines");
+    std::fs::create_dir_all(&dir).unwrap();
+    let &(_, stage) = trace::GLSL_EXTENSIONS
+        .iter()
+        .find(|&&(extension, _)| extension == "comp")
+        .unwrap();
+
+    let global =
+        wgc::hub::Global::new("test", IdentityPassThroughFactory, wgt::BackendBit::PRIMARY);
+    let adapter = match global.request_adapter(
+        &wgc::instance::RequestAdapterOptions::default(),
+        wgc::instance::AdapterInputs::IdSet(
+            &BACKENDS
+                .iter()
+                .map(|&backend| wgc::id::TypedId::zip(0, 0, backend))
+                .collect::<Vec<_>>(),
+            |id| id.backend(),
+        ),
+    ) {
+        Ok(adapter) => adapter,
+        Err(_) => return,
+    };
+    let backend = adapter.backend();
+    let device = wgc::id::TypedId::zip(0, 0, backend);
+    let (_, error) = gfx_select!(adapter => global.adapter_request_traceable_device(
+        adapter,
+        &wgt::DeviceDescriptor::default(),
+        device
+    ));
+    assert!(error.is_none());
+
+    gfx_select!(device => global.device_start_trace(device, &dir)).unwrap();
+    let shader_module = wgc::id::TypedId::zip(0, 1, backend);
+    let (_, error) = gfx_select!(device => global.device_create_shader_module(
+        device,
+        &wgc::pipeline::ShaderModuleDescriptor {
+            label: None,
+            flags: wgt::ShaderFlags::VALIDATION,
+            interface: None,
+        },
+        wgc::pipeline::ShaderModuleSource::Glsl {
+            code: std::borrow::Cow::Borrowed(CODE),
+            stage,
+            defines: std::iter::once(("VALUE".to_string(), "1.0".to_string())).collect(),
+        },
+        shader_module
+    ));
+    assert!(error.is_none());
+    gfx_select!(device => global.device_stop_trace(device)).unwrap();
+    gfx_select!(device => global.clear_backend(()));
+
+    // The source is written with the extension of its stage, and the
+    // defines are kept in the action, since the source doesn't parse without them.
+    let file = File::open(dir.join(trace::FILE_NAME)).unwrap();
+    let mut actions: Vec<trace::Action> = ron::de::from_reader(file).unwrap();
+    assert!(actions.iter().any(|action| match *action {
+        trace::Action::CreateShaderModule {
+            ref data,
+            ref defines,
+            ..
+        } => data.ends_with(".comp") && defines == &[("VALUE".to_string(), "1.0".to_string())],
+        _ => false,
+    }));
+    actions.reverse();
+    let global = wgc::hub::Global::new("replay", IdentityPassThroughFactory, backend.into());
+    let adapter = global
+        .request_adapter(
+            &wgc::instance::RequestAdapterOptions::default(),
+            wgc::instance::AdapterInputs::IdSet(&[wgc::id::TypedId::zip(0, 0, backend)], |id| {
+                id.backend()
+            }),
+        )
+        .unwrap();
+    match actions.pop() {
+        Some(trace::Action::Init { desc, .. }) => {
+            let (_, error) = gfx_select!(adapter => global.adapter_request_device(
+                adapter,
+                &desc,
+                None,
+                device
+            ));
+            assert!(error.is_none());
+        }
+        _ => panic!("Expected Action::Init"),
+    }
+    // Replaying panics if the shader module fails to be created.
+    let mut command_buffer_id_manager = wgc::hub::IdentityManager::default();
+    while let Some(action) = actions.pop() {
+        gfx_select!(device => global.process(device, action, &dir, &mut command_buffer_id_manager));
+    }
+    gfx_select!(device => global.clear_backend(()));
+}
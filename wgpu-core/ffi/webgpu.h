@@ -0,0 +1,1315 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/* Generated with cbindgen from `wgpu-core/src/ffi` using `wgpu-core/cbindgen.toml`.
+ * DO NOT MODIFY THIS MANUALLY! */
+
+#ifndef WGPU_H
+#define WGPU_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define WGPUBufferUsage_MapRead 0x1
+
+#define WGPUBufferUsage_MapWrite 0x2
+
+#define WGPUBufferUsage_CopySrc 0x4
+
+#define WGPUBufferUsage_CopyDst 0x8
+
+#define WGPUBufferUsage_Index 0x10
+
+#define WGPUBufferUsage_Vertex 0x20
+
+#define WGPUBufferUsage_Uniform 0x40
+
+#define WGPUBufferUsage_Storage 0x80
+
+#define WGPUBufferUsage_Indirect 0x100
+
+#define WGPUTextureUsage_CopySrc 0x1
+
+#define WGPUTextureUsage_CopyDst 0x2
+
+#define WGPUTextureUsage_Sampled 0x4
+
+#define WGPUTextureUsage_Storage 0x8
+
+#define WGPUTextureUsage_RenderAttachment 0x10
+
+#define WGPUShaderStage_Vertex 0x1
+
+#define WGPUShaderStage_Fragment 0x2
+
+#define WGPUShaderStage_Compute 0x4
+
+#define WGPUColorWriteMask_Red 0x1
+
+#define WGPUColorWriteMask_Green 0x2
+
+#define WGPUColorWriteMask_Blue 0x4
+
+#define WGPUColorWriteMask_Alpha 0x8
+
+#define WGPUColorWriteMask_All 0xF
+
+#define WGPU_WHOLE_SIZE UINT64_MAX
+
+#define WGPUSType_Invalid 0
+
+#define WGPUSType_ShaderModuleSPIRVDescriptor 5
+
+#define WGPUSType_ShaderModuleWGSLDescriptor 6
+
+#define WGPUAdapterType_DiscreteGPU 0
+
+#define WGPUAdapterType_IntegratedGPU 1
+
+#define WGPUAdapterType_CPU 2
+
+#define WGPUAdapterType_Unknown 3
+
+#define WGPUBackendType_Null 0
+
+#define WGPUBackendType_WebGPU 1
+
+#define WGPUBackendType_D3D11 2
+
+#define WGPUBackendType_D3D12 3
+
+#define WGPUBackendType_Metal 4
+
+#define WGPUBackendType_Vulkan 5
+
+#define WGPUBackendType_OpenGL 6
+
+#define WGPUPowerPreference_LowPower 0
+
+#define WGPUPowerPreference_HighPerformance 1
+
+#define WGPURequestAdapterStatus_Success 0
+
+#define WGPURequestAdapterStatus_Unavailable 1
+
+#define WGPURequestAdapterStatus_Error 2
+
+#define WGPURequestDeviceStatus_Success 0
+
+#define WGPURequestDeviceStatus_Error 1
+
+#define WGPUErrorType_NoError 0
+
+#define WGPUErrorType_Validation 1
+
+#define WGPUErrorType_OutOfMemory 2
+
+#define WGPUErrorType_Unknown 3
+
+#define WGPUErrorType_DeviceLost 4
+
+#define WGPUBufferMapAsyncStatus_Success 0
+
+#define WGPUBufferMapAsyncStatus_Error 1
+
+#define WGPUBufferMapAsyncStatus_Unknown 2
+
+#define WGPUBufferMapAsyncStatus_DeviceLost 3
+
+#define WGPUBufferMapAsyncStatus_DestroyedBeforeCallback 4
+
+#define WGPUBufferMapAsyncStatus_UnmappedBeforeCallback 5
+
+#define WGPUMapMode_Read 1
+
+#define WGPUMapMode_Write 2
+
+#define WGPUTextureFormat_Undefined 0
+
+#define WGPUTextureFormat_R8Unorm 1
+
+#define WGPUTextureFormat_R8Snorm 2
+
+#define WGPUTextureFormat_R8Uint 3
+
+#define WGPUTextureFormat_R8Sint 4
+
+#define WGPUTextureFormat_R16Uint 5
+
+#define WGPUTextureFormat_R16Sint 6
+
+#define WGPUTextureFormat_R16Float 7
+
+#define WGPUTextureFormat_RG8Unorm 8
+
+#define WGPUTextureFormat_RG8Snorm 9
+
+#define WGPUTextureFormat_RG8Uint 10
+
+#define WGPUTextureFormat_RG8Sint 11
+
+#define WGPUTextureFormat_R32Float 12
+
+#define WGPUTextureFormat_R32Uint 13
+
+#define WGPUTextureFormat_R32Sint 14
+
+#define WGPUTextureFormat_RG16Uint 15
+
+#define WGPUTextureFormat_RG16Sint 16
+
+#define WGPUTextureFormat_RG16Float 17
+
+#define WGPUTextureFormat_RGBA8Unorm 18
+
+#define WGPUTextureFormat_RGBA8UnormSrgb 19
+
+#define WGPUTextureFormat_RGBA8Snorm 20
+
+#define WGPUTextureFormat_RGBA8Uint 21
+
+#define WGPUTextureFormat_RGBA8Sint 22
+
+#define WGPUTextureFormat_BGRA8Unorm 23
+
+#define WGPUTextureFormat_BGRA8UnormSrgb 24
+
+#define WGPUTextureFormat_RGB10A2Unorm 25
+
+#define WGPUTextureFormat_RG11B10Ufloat 26
+
+#define WGPUTextureFormat_RGB9E5Ufloat 27
+
+#define WGPUTextureFormat_RG32Float 28
+
+#define WGPUTextureFormat_RG32Uint 29
+
+#define WGPUTextureFormat_RG32Sint 30
+
+#define WGPUTextureFormat_RGBA16Uint 31
+
+#define WGPUTextureFormat_RGBA16Sint 32
+
+#define WGPUTextureFormat_RGBA16Float 33
+
+#define WGPUTextureFormat_RGBA32Float 34
+
+#define WGPUTextureFormat_RGBA32Uint 35
+
+#define WGPUTextureFormat_RGBA32Sint 36
+
+#define WGPUTextureFormat_Stencil8 37
+
+#define WGPUTextureFormat_Depth16Unorm 38
+
+#define WGPUTextureFormat_Depth24Plus 39
+
+#define WGPUTextureFormat_Depth24PlusStencil8 40
+
+#define WGPUTextureFormat_Depth32Float 41
+
+#define WGPUTextureFormat_Depth32FloatStencil8 0x60000000
+
+#define WGPUTextureFormat_R16Unorm 0x60000001
+
+#define WGPUTextureFormat_R16Snorm 0x60000002
+
+#define WGPUTextureFormat_RG16Unorm 0x60000003
+
+#define WGPUTextureFormat_RG16Snorm 0x60000004
+
+#define WGPUTextureFormat_RGBA16Unorm 0x60000005
+
+#define WGPUTextureFormat_RGBA16Snorm 0x60000006
+
+#define WGPUTextureFormat_BC1RGBAUnorm 42
+
+#define WGPUTextureFormat_BC1RGBAUnormSrgb 43
+
+#define WGPUTextureFormat_BC2RGBAUnorm 44
+
+#define WGPUTextureFormat_BC2RGBAUnormSrgb 45
+
+#define WGPUTextureFormat_BC3RGBAUnorm 46
+
+#define WGPUTextureFormat_BC3RGBAUnormSrgb 47
+
+#define WGPUTextureFormat_BC4RUnorm 48
+
+#define WGPUTextureFormat_BC4RSnorm 49
+
+#define WGPUTextureFormat_BC5RGUnorm 50
+
+#define WGPUTextureFormat_BC5RGSnorm 51
+
+#define WGPUTextureFormat_BC6HRGBUfloat 52
+
+#define WGPUTextureFormat_BC6HRGBFloat 53
+
+#define WGPUTextureFormat_BC7RGBAUnorm 54
+
+#define WGPUTextureFormat_BC7RGBAUnormSrgb 55
+
+#define WGPUTextureDimension_1D 0
+
+#define WGPUTextureDimension_2D 1
+
+#define WGPUTextureDimension_3D 2
+
+#define WGPUTextureViewDimension_Undefined 0
+
+#define WGPUTextureViewDimension_1D 1
+
+#define WGPUTextureViewDimension_2D 2
+
+#define WGPUTextureViewDimension_2DArray 3
+
+#define WGPUTextureViewDimension_Cube 4
+
+#define WGPUTextureViewDimension_CubeArray 5
+
+#define WGPUTextureViewDimension_3D 6
+
+#define WGPUTextureAspect_All 0
+
+#define WGPUTextureAspect_StencilOnly 1
+
+#define WGPUTextureAspect_DepthOnly 2
+
+#define WGPUAddressMode_Repeat 0
+
+#define WGPUAddressMode_MirrorRepeat 1
+
+#define WGPUAddressMode_ClampToEdge 2
+
+#define WGPUFilterMode_Nearest 0
+
+#define WGPUFilterMode_Linear 1
+
+#define WGPUCompareFunction_Undefined 0
+
+#define WGPUCompareFunction_Never 1
+
+#define WGPUCompareFunction_Less 2
+
+#define WGPUCompareFunction_LessEqual 3
+
+#define WGPUCompareFunction_Greater 4
+
+#define WGPUCompareFunction_GreaterEqual 5
+
+#define WGPUCompareFunction_Equal 6
+
+#define WGPUCompareFunction_NotEqual 7
+
+#define WGPUCompareFunction_Always 8
+
+#define WGPUBufferBindingType_Undefined 0
+
+#define WGPUBufferBindingType_Uniform 1
+
+#define WGPUBufferBindingType_Storage 2
+
+#define WGPUBufferBindingType_ReadOnlyStorage 3
+
+#define WGPUSamplerBindingType_Undefined 0
+
+#define WGPUSamplerBindingType_Filtering 1
+
+#define WGPUSamplerBindingType_NonFiltering 2
+
+#define WGPUSamplerBindingType_Comparison 3
+
+#define WGPUTextureSampleType_Undefined 0
+
+#define WGPUTextureSampleType_Float 1
+
+#define WGPUTextureSampleType_UnfilterableFloat 2
+
+#define WGPUTextureSampleType_Depth 3
+
+#define WGPUTextureSampleType_Sint 4
+
+#define WGPUTextureSampleType_Uint 5
+
+#define WGPUStorageTextureAccess_Undefined 0
+
+#define WGPUStorageTextureAccess_ReadOnly 1
+
+#define WGPUStorageTextureAccess_WriteOnly 2
+
+#define WGPUStorageTextureAccess_ReadWrite 3
+
+#define WGPUVertexFormat_Undefined 0
+
+#define WGPUVertexFormat_Uint8x2 1
+
+#define WGPUVertexFormat_Uint8x4 2
+
+#define WGPUVertexFormat_Sint8x2 3
+
+#define WGPUVertexFormat_Sint8x4 4
+
+#define WGPUVertexFormat_Unorm8x2 5
+
+#define WGPUVertexFormat_Unorm8x4 6
+
+#define WGPUVertexFormat_Snorm8x2 7
+
+#define WGPUVertexFormat_Snorm8x4 8
+
+#define WGPUVertexFormat_Uint16x2 9
+
+#define WGPUVertexFormat_Uint16x4 10
+
+#define WGPUVertexFormat_Sint16x2 11
+
+#define WGPUVertexFormat_Sint16x4 12
+
+#define WGPUVertexFormat_Unorm16x2 13
+
+#define WGPUVertexFormat_Unorm16x4 14
+
+#define WGPUVertexFormat_Snorm16x2 15
+
+#define WGPUVertexFormat_Snorm16x4 16
+
+#define WGPUVertexFormat_Float16x2 17
+
+#define WGPUVertexFormat_Float16x4 18
+
+#define WGPUVertexFormat_Float32 19
+
+#define WGPUVertexFormat_Float32x2 20
+
+#define WGPUVertexFormat_Float32x3 21
+
+#define WGPUVertexFormat_Float32x4 22
+
+#define WGPUVertexFormat_Uint32 23
+
+#define WGPUVertexFormat_Uint32x2 24
+
+#define WGPUVertexFormat_Uint32x3 25
+
+#define WGPUVertexFormat_Uint32x4 26
+
+#define WGPUVertexFormat_Sint32 27
+
+#define WGPUVertexFormat_Sint32x2 28
+
+#define WGPUVertexFormat_Sint32x3 29
+
+#define WGPUVertexFormat_Sint32x4 30
+
+#define WGPUInputStepMode_Vertex 0
+
+#define WGPUInputStepMode_Instance 1
+
+#define WGPUPrimitiveTopology_PointList 0
+
+#define WGPUPrimitiveTopology_LineList 1
+
+#define WGPUPrimitiveTopology_LineStrip 2
+
+#define WGPUPrimitiveTopology_TriangleList 3
+
+#define WGPUPrimitiveTopology_TriangleStrip 4
+
+#define WGPUIndexFormat_Undefined 0
+
+#define WGPUIndexFormat_Uint16 1
+
+#define WGPUIndexFormat_Uint32 2
+
+#define WGPUFrontFace_CCW 0
+
+#define WGPUFrontFace_CW 1
+
+#define WGPUCullMode_None 0
+
+#define WGPUCullMode_Front 1
+
+#define WGPUCullMode_Back 2
+
+#define WGPUBlendFactor_Zero 0
+
+#define WGPUBlendFactor_One 1
+
+#define WGPUBlendFactor_Src 2
+
+#define WGPUBlendFactor_OneMinusSrc 3
+
+#define WGPUBlendFactor_SrcAlpha 4
+
+#define WGPUBlendFactor_OneMinusSrcAlpha 5
+
+#define WGPUBlendFactor_Dst 6
+
+#define WGPUBlendFactor_OneMinusDst 7
+
+#define WGPUBlendFactor_DstAlpha 8
+
+#define WGPUBlendFactor_OneMinusDstAlpha 9
+
+#define WGPUBlendFactor_SrcAlphaSaturated 10
+
+#define WGPUBlendFactor_Constant 11
+
+#define WGPUBlendFactor_OneMinusConstant 12
+
+#define WGPUBlendOperation_Add 0
+
+#define WGPUBlendOperation_Subtract 1
+
+#define WGPUBlendOperation_ReverseSubtract 2
+
+#define WGPUBlendOperation_Min 3
+
+#define WGPUBlendOperation_Max 4
+
+#define WGPUStencilOperation_Keep 0
+
+#define WGPUStencilOperation_Zero 1
+
+#define WGPUStencilOperation_Replace 2
+
+#define WGPUStencilOperation_Invert 3
+
+#define WGPUStencilOperation_IncrementClamp 4
+
+#define WGPUStencilOperation_DecrementClamp 5
+
+#define WGPUStencilOperation_IncrementWrap 6
+
+#define WGPUStencilOperation_DecrementWrap 7
+
+#define WGPULoadOp_Clear 0
+
+#define WGPULoadOp_Load 1
+
+#define WGPUStoreOp_Store 0
+
+#define WGPUStoreOp_Clear 1
+
+typedef struct WGPUInstanceImpl WGPUInstanceImpl;
+
+typedef struct WGPUAdapterImpl WGPUAdapterImpl;
+
+typedef struct WGPUDeviceImpl WGPUDeviceImpl;
+
+typedef struct WGPUQueueImpl WGPUQueueImpl;
+
+typedef struct WGPUBufferImpl WGPUBufferImpl;
+
+typedef struct WGPUTextureViewImpl WGPUTextureViewImpl;
+
+typedef struct WGPUTextureImpl WGPUTextureImpl;
+
+typedef struct WGPUSamplerImpl WGPUSamplerImpl;
+
+typedef struct WGPUShaderModuleImpl WGPUShaderModuleImpl;
+
+typedef struct WGPUBindGroupLayoutImpl WGPUBindGroupLayoutImpl;
+
+typedef struct WGPUPipelineLayoutImpl WGPUPipelineLayoutImpl;
+
+typedef struct WGPUBindGroupImpl WGPUBindGroupImpl;
+
+typedef struct WGPUComputePipelineImpl WGPUComputePipelineImpl;
+
+typedef struct WGPURenderPipelineImpl WGPURenderPipelineImpl;
+
+typedef struct WGPUCommandEncoderImpl WGPUCommandEncoderImpl;
+
+typedef struct WGPUCommandBufferImpl WGPUCommandBufferImpl;
+
+typedef struct WGPUComputePassEncoderImpl WGPUComputePassEncoderImpl;
+
+typedef struct WGPURenderPassEncoderImpl WGPURenderPassEncoderImpl;
+
+typedef const WGPUInstanceImpl *WGPUInstance;
+
+typedef const WGPUAdapterImpl *WGPUAdapter;
+
+typedef const WGPUDeviceImpl *WGPUDevice;
+
+typedef const WGPUQueueImpl *WGPUQueue;
+
+typedef const WGPUBufferImpl *WGPUBuffer;
+
+typedef const WGPUTextureViewImpl *WGPUTextureView;
+
+typedef const WGPUTextureImpl *WGPUTexture;
+
+typedef const WGPUSamplerImpl *WGPUSampler;
+
+typedef const WGPUShaderModuleImpl *WGPUShaderModule;
+
+typedef const WGPUBindGroupLayoutImpl *WGPUBindGroupLayout;
+
+typedef const WGPUPipelineLayoutImpl *WGPUPipelineLayout;
+
+typedef const WGPUBindGroupImpl *WGPUBindGroup;
+
+typedef const WGPUComputePipelineImpl *WGPUComputePipeline;
+
+typedef const WGPURenderPipelineImpl *WGPURenderPipeline;
+
+typedef const WGPUCommandEncoderImpl *WGPUCommandEncoder;
+
+typedef const WGPUCommandBufferImpl *WGPUCommandBuffer;
+
+typedef const WGPUComputePassEncoderImpl *WGPUComputePassEncoder;
+
+typedef const WGPURenderPassEncoderImpl *WGPURenderPassEncoder;
+
+typedef uint32_t WGPUFlags;
+
+typedef WGPUFlags WGPUBufferUsageFlags;
+
+typedef WGPUFlags WGPUTextureUsageFlags;
+
+typedef WGPUFlags WGPUShaderStageFlags;
+
+typedef WGPUFlags WGPUColorWriteMaskFlags;
+
+typedef WGPUFlags WGPUMapModeFlags;
+
+typedef uint32_t WGPUSType;
+
+typedef uint32_t WGPUAdapterType;
+
+typedef uint32_t WGPUBackendType;
+
+typedef uint32_t WGPUPowerPreference;
+
+typedef uint32_t WGPURequestAdapterStatus;
+
+typedef uint32_t WGPURequestDeviceStatus;
+
+typedef uint32_t WGPUErrorType;
+
+typedef uint32_t WGPUBufferMapAsyncStatus;
+
+typedef uint32_t WGPUTextureFormat;
+
+typedef uint32_t WGPUTextureDimension;
+
+typedef uint32_t WGPUTextureViewDimension;
+
+typedef uint32_t WGPUTextureAspect;
+
+typedef uint32_t WGPUAddressMode;
+
+typedef uint32_t WGPUFilterMode;
+
+typedef uint32_t WGPUCompareFunction;
+
+typedef uint32_t WGPUBufferBindingType;
+
+typedef uint32_t WGPUSamplerBindingType;
+
+typedef uint32_t WGPUTextureSampleType;
+
+typedef uint32_t WGPUStorageTextureAccess;
+
+typedef uint32_t WGPUVertexFormat;
+
+typedef uint32_t WGPUInputStepMode;
+
+typedef uint32_t WGPUPrimitiveTopology;
+
+typedef uint32_t WGPUIndexFormat;
+
+typedef uint32_t WGPUFrontFace;
+
+typedef uint32_t WGPUCullMode;
+
+typedef uint32_t WGPUBlendFactor;
+
+typedef uint32_t WGPUBlendOperation;
+
+typedef uint32_t WGPUStencilOperation;
+
+typedef uint32_t WGPULoadOp;
+
+typedef uint32_t WGPUStoreOp;
+
+typedef struct WGPUChainedStruct {
+  const struct WGPUChainedStruct *next;
+  WGPUSType sType;
+} WGPUChainedStruct;
+
+typedef struct WGPUInstanceDescriptor {
+  const WGPUChainedStruct *nextInChain;
+} WGPUInstanceDescriptor;
+
+typedef struct WGPURequestAdapterOptions {
+  const WGPUChainedStruct *nextInChain;
+  WGPUPowerPreference powerPreference;
+} WGPURequestAdapterOptions;
+
+typedef struct WGPUAdapterProperties {
+  const WGPUChainedStruct *nextInChain;
+  uint32_t vendorID;
+  uint32_t deviceID;
+  const char *name;
+  WGPUAdapterType adapterType;
+  WGPUBackendType backendType;
+} WGPUAdapterProperties;
+
+typedef struct WGPUDeviceDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+} WGPUDeviceDescriptor;
+
+typedef struct WGPUBufferDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  WGPUBufferUsageFlags usage;
+  uint64_t size;
+  bool mappedAtCreation;
+} WGPUBufferDescriptor;
+
+typedef struct WGPUExtent3D {
+  uint32_t width;
+  uint32_t height;
+  uint32_t depthOrArrayLayers;
+} WGPUExtent3D;
+
+typedef struct WGPUOrigin3D {
+  uint32_t x;
+  uint32_t y;
+  uint32_t z;
+} WGPUOrigin3D;
+
+typedef struct WGPUTextureDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  WGPUTextureUsageFlags usage;
+  WGPUTextureDimension dimension;
+  WGPUExtent3D size;
+  WGPUTextureFormat format;
+  uint32_t mipLevelCount;
+  uint32_t sampleCount;
+} WGPUTextureDescriptor;
+
+typedef struct WGPUTextureViewDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  WGPUTextureFormat format;
+  WGPUTextureViewDimension dimension;
+  uint32_t baseMipLevel;
+  uint32_t mipLevelCount;
+  uint32_t baseArrayLayer;
+  uint32_t arrayLayerCount;
+  WGPUTextureAspect aspect;
+} WGPUTextureViewDescriptor;
+
+typedef struct WGPUSamplerDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  WGPUAddressMode addressModeU;
+  WGPUAddressMode addressModeV;
+  WGPUAddressMode addressModeW;
+  WGPUFilterMode magFilter;
+  WGPUFilterMode minFilter;
+  WGPUFilterMode mipmapFilter;
+  float lodMinClamp;
+  float lodMaxClamp;
+  WGPUCompareFunction compare;
+  uint16_t maxAnisotropy;
+} WGPUSamplerDescriptor;
+
+typedef struct WGPUShaderModuleDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+} WGPUShaderModuleDescriptor;
+
+typedef struct WGPUShaderModuleSPIRVDescriptor {
+  WGPUChainedStruct chain;
+  uint32_t codeSize;
+  const uint32_t *code;
+} WGPUShaderModuleSPIRVDescriptor;
+
+typedef struct WGPUShaderModuleWGSLDescriptor {
+  WGPUChainedStruct chain;
+  const char *source;
+} WGPUShaderModuleWGSLDescriptor;
+
+typedef struct WGPUBufferBindingLayout {
+  const WGPUChainedStruct *nextInChain;
+  WGPUBufferBindingType type;
+  bool hasDynamicOffset;
+  uint64_t minBindingSize;
+} WGPUBufferBindingLayout;
+
+typedef struct WGPUSamplerBindingLayout {
+  const WGPUChainedStruct *nextInChain;
+  WGPUSamplerBindingType type;
+} WGPUSamplerBindingLayout;
+
+typedef struct WGPUTextureBindingLayout {
+  const WGPUChainedStruct *nextInChain;
+  WGPUTextureSampleType sampleType;
+  WGPUTextureViewDimension viewDimension;
+  bool multisampled;
+} WGPUTextureBindingLayout;
+
+typedef struct WGPUStorageTextureBindingLayout {
+  const WGPUChainedStruct *nextInChain;
+  WGPUStorageTextureAccess access;
+  WGPUTextureFormat format;
+  WGPUTextureViewDimension viewDimension;
+} WGPUStorageTextureBindingLayout;
+
+typedef struct WGPUBindGroupLayoutEntry {
+  const WGPUChainedStruct *nextInChain;
+  uint32_t binding;
+  WGPUShaderStageFlags visibility;
+  WGPUBufferBindingLayout buffer;
+  WGPUSamplerBindingLayout sampler;
+  WGPUTextureBindingLayout texture;
+  WGPUStorageTextureBindingLayout storageTexture;
+} WGPUBindGroupLayoutEntry;
+
+typedef struct WGPUBindGroupLayoutDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  uint32_t entryCount;
+  const WGPUBindGroupLayoutEntry *entries;
+} WGPUBindGroupLayoutDescriptor;
+
+typedef struct WGPUPipelineLayoutDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  uint32_t bindGroupLayoutCount;
+  const WGPUBindGroupLayout *bindGroupLayouts;
+} WGPUPipelineLayoutDescriptor;
+
+typedef struct WGPUBindGroupEntry {
+  const WGPUChainedStruct *nextInChain;
+  uint32_t binding;
+  WGPUBuffer buffer;
+  uint64_t offset;
+  uint64_t size;
+  WGPUSampler sampler;
+  WGPUTextureView textureView;
+} WGPUBindGroupEntry;
+
+typedef struct WGPUBindGroupDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  WGPUBindGroupLayout layout;
+  uint32_t entryCount;
+  const WGPUBindGroupEntry *entries;
+} WGPUBindGroupDescriptor;
+
+typedef struct WGPUProgrammableStageDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  WGPUShaderModule module;
+  const char *entryPoint;
+} WGPUProgrammableStageDescriptor;
+
+typedef struct WGPUComputePipelineDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  WGPUPipelineLayout layout;
+  WGPUProgrammableStageDescriptor compute;
+} WGPUComputePipelineDescriptor;
+
+typedef struct WGPUVertexAttribute {
+  WGPUVertexFormat format;
+  uint64_t offset;
+  uint32_t shaderLocation;
+} WGPUVertexAttribute;
+
+typedef struct WGPUVertexBufferLayout {
+  uint64_t arrayStride;
+  WGPUInputStepMode stepMode;
+  uint32_t attributeCount;
+  const WGPUVertexAttribute *attributes;
+} WGPUVertexBufferLayout;
+
+typedef struct WGPUVertexState {
+  const WGPUChainedStruct *nextInChain;
+  WGPUShaderModule module;
+  const char *entryPoint;
+  uint32_t bufferCount;
+  const WGPUVertexBufferLayout *buffers;
+} WGPUVertexState;
+
+typedef struct WGPUPrimitiveState {
+  const WGPUChainedStruct *nextInChain;
+  WGPUPrimitiveTopology topology;
+  WGPUIndexFormat stripIndexFormat;
+  WGPUFrontFace frontFace;
+  WGPUCullMode cullMode;
+} WGPUPrimitiveState;
+
+typedef struct WGPUStencilFaceState {
+  WGPUCompareFunction compare;
+  WGPUStencilOperation failOp;
+  WGPUStencilOperation depthFailOp;
+  WGPUStencilOperation passOp;
+} WGPUStencilFaceState;
+
+typedef struct WGPUDepthStencilState {
+  const WGPUChainedStruct *nextInChain;
+  WGPUTextureFormat format;
+  bool depthWriteEnabled;
+  WGPUCompareFunction depthCompare;
+  WGPUStencilFaceState stencilFront;
+  WGPUStencilFaceState stencilBack;
+  uint32_t stencilReadMask;
+  uint32_t stencilWriteMask;
+  int32_t depthBias;
+  float depthBiasSlopeScale;
+  float depthBiasClamp;
+} WGPUDepthStencilState;
+
+typedef struct WGPUMultisampleState {
+  const WGPUChainedStruct *nextInChain;
+  uint32_t count;
+  uint32_t mask;
+  bool alphaToCoverageEnabled;
+} WGPUMultisampleState;
+
+typedef struct WGPUBlendComponent {
+  WGPUBlendFactor srcFactor;
+  WGPUBlendFactor dstFactor;
+  WGPUBlendOperation operation;
+} WGPUBlendComponent;
+
+typedef struct WGPUBlendState {
+  WGPUBlendComponent color;
+  WGPUBlendComponent alpha;
+} WGPUBlendState;
+
+typedef struct WGPUColorTargetState {
+  const WGPUChainedStruct *nextInChain;
+  WGPUTextureFormat format;
+  const WGPUBlendState *blend;
+  WGPUColorWriteMaskFlags writeMask;
+} WGPUColorTargetState;
+
+typedef struct WGPUFragmentState {
+  const WGPUChainedStruct *nextInChain;
+  WGPUShaderModule module;
+  const char *entryPoint;
+  uint32_t targetCount;
+  const WGPUColorTargetState *targets;
+} WGPUFragmentState;
+
+typedef struct WGPURenderPipelineDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  WGPUPipelineLayout layout;
+  WGPUVertexState vertex;
+  WGPUPrimitiveState primitive;
+  const WGPUDepthStencilState *depthStencil;
+  WGPUMultisampleState multisample;
+  const WGPUFragmentState *fragment;
+} WGPURenderPipelineDescriptor;
+
+typedef struct WGPUCommandEncoderDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+} WGPUCommandEncoderDescriptor;
+
+typedef struct WGPUCommandBufferDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+} WGPUCommandBufferDescriptor;
+
+typedef struct WGPUComputePassDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+} WGPUComputePassDescriptor;
+
+typedef struct WGPUColor {
+  double r;
+  double g;
+  double b;
+  double a;
+} WGPUColor;
+
+typedef struct WGPURenderPassColorAttachment {
+  WGPUTextureView view;
+  WGPUTextureView resolveTarget;
+  WGPULoadOp loadOp;
+  WGPUStoreOp storeOp;
+  WGPUColor clearColor;
+} WGPURenderPassColorAttachment;
+
+typedef struct WGPURenderPassDepthStencilAttachment {
+  WGPUTextureView view;
+  WGPULoadOp depthLoadOp;
+  WGPUStoreOp depthStoreOp;
+  float clearDepth;
+  bool depthReadOnly;
+  WGPULoadOp stencilLoadOp;
+  WGPUStoreOp stencilStoreOp;
+  uint32_t clearStencil;
+  bool stencilReadOnly;
+} WGPURenderPassDepthStencilAttachment;
+
+typedef struct WGPURenderPassDescriptor {
+  const WGPUChainedStruct *nextInChain;
+  const char *label;
+  uint32_t colorAttachmentCount;
+  const WGPURenderPassColorAttachment *colorAttachments;
+  const WGPURenderPassDepthStencilAttachment *depthStencilAttachment;
+} WGPURenderPassDescriptor;
+
+typedef struct WGPUTextureDataLayout {
+  const WGPUChainedStruct *nextInChain;
+  uint64_t offset;
+  uint32_t bytesPerRow;
+  uint32_t rowsPerImage;
+} WGPUTextureDataLayout;
+
+typedef struct WGPUImageCopyBuffer {
+  const WGPUChainedStruct *nextInChain;
+  WGPUTextureDataLayout layout;
+  WGPUBuffer buffer;
+} WGPUImageCopyBuffer;
+
+typedef struct WGPUImageCopyTexture {
+  const WGPUChainedStruct *nextInChain;
+  WGPUTexture texture;
+  uint32_t mipLevel;
+  WGPUOrigin3D origin;
+  WGPUTextureAspect aspect;
+} WGPUImageCopyTexture;
+
+typedef void (*WGPURequestAdapterCallback)(WGPURequestAdapterStatus status,
+                                           WGPUAdapter adapter,
+                                           const char *message,
+                                           void *userdata);
+
+typedef void (*WGPURequestDeviceCallback)(WGPURequestDeviceStatus status,
+                                          WGPUDevice device,
+                                          const char *message,
+                                          void *userdata);
+
+typedef void (*WGPUErrorCallback)(WGPUErrorType error_type, const char *message, void *userdata);
+
+typedef void (*WGPUBufferMapCallback)(WGPUBufferMapAsyncStatus status, void *userdata);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+void wgpuInstanceReference(WGPUInstance handle);
+
+void wgpuInstanceRelease(WGPUInstance handle);
+
+void wgpuAdapterReference(WGPUAdapter handle);
+
+void wgpuAdapterRelease(WGPUAdapter handle);
+
+void wgpuDeviceReference(WGPUDevice handle);
+
+void wgpuDeviceRelease(WGPUDevice handle);
+
+void wgpuQueueReference(WGPUQueue handle);
+
+void wgpuQueueRelease(WGPUQueue handle);
+
+void wgpuBufferReference(WGPUBuffer handle);
+
+void wgpuBufferRelease(WGPUBuffer handle);
+
+void wgpuTextureReference(WGPUTexture handle);
+
+void wgpuTextureRelease(WGPUTexture handle);
+
+void wgpuTextureViewReference(WGPUTextureView handle);
+
+void wgpuTextureViewRelease(WGPUTextureView handle);
+
+void wgpuSamplerReference(WGPUSampler handle);
+
+void wgpuSamplerRelease(WGPUSampler handle);
+
+void wgpuShaderModuleReference(WGPUShaderModule handle);
+
+void wgpuShaderModuleRelease(WGPUShaderModule handle);
+
+void wgpuBindGroupLayoutReference(WGPUBindGroupLayout handle);
+
+void wgpuBindGroupLayoutRelease(WGPUBindGroupLayout handle);
+
+void wgpuPipelineLayoutReference(WGPUPipelineLayout handle);
+
+void wgpuPipelineLayoutRelease(WGPUPipelineLayout handle);
+
+void wgpuBindGroupReference(WGPUBindGroup handle);
+
+void wgpuBindGroupRelease(WGPUBindGroup handle);
+
+void wgpuComputePipelineReference(WGPUComputePipeline handle);
+
+void wgpuComputePipelineRelease(WGPUComputePipeline handle);
+
+void wgpuRenderPipelineReference(WGPURenderPipeline handle);
+
+void wgpuRenderPipelineRelease(WGPURenderPipeline handle);
+
+void wgpuCommandEncoderReference(WGPUCommandEncoder handle);
+
+void wgpuCommandEncoderRelease(WGPUCommandEncoder handle);
+
+void wgpuCommandBufferReference(WGPUCommandBuffer handle);
+
+void wgpuCommandBufferRelease(WGPUCommandBuffer handle);
+
+void wgpuComputePassEncoderReference(WGPUComputePassEncoder handle);
+
+void wgpuComputePassEncoderRelease(WGPUComputePassEncoder handle);
+
+void wgpuRenderPassEncoderReference(WGPURenderPassEncoder handle);
+
+void wgpuRenderPassEncoderRelease(WGPURenderPassEncoder handle);
+
+/**
+ * The descriptor may be null.
+ */
+WGPUInstance wgpuCreateInstance(const WGPUInstanceDescriptor *_descriptor);
+
+/**
+ * The callback is called before this function returns. The options may be null.
+ */
+void wgpuInstanceRequestAdapter(WGPUInstance instance,
+                                const WGPURequestAdapterOptions *options,
+                                WGPURequestAdapterCallback callback,
+                                void *userdata);
+
+/**
+ * The strings in the properties are valid for the lifetime of the adapter.
+ */
+void wgpuAdapterGetProperties(WGPUAdapter adapter, WGPUAdapterProperties *properties);
+
+/**
+ * The callback is called before this function returns. The descriptor may be null.
+ */
+void wgpuAdapterRequestDevice(WGPUAdapter adapter,
+                              const WGPUDeviceDescriptor *descriptor,
+                              WGPURequestDeviceCallback callback,
+                              void *userdata);
+
+void wgpuDeviceSetUncapturedErrorCallback(WGPUDevice device,
+                                          WGPUErrorCallback callback,
+                                          void *userdata);
+
+WGPUQueue wgpuDeviceGetQueue(WGPUDevice device);
+
+/**
+ * Native extension: maintains the device, calling the ready buffer mapping
+ * callbacks. If `wait` is true, blocks until all the submitted work is done.
+ */
+void wgpuDevicePoll(WGPUDevice device, bool wait);
+
+void wgpuQueueSubmit(WGPUQueue queue, uint32_t command_count, const WGPUCommandBuffer *commands);
+
+void wgpuQueueWriteBuffer(WGPUQueue queue,
+                          WGPUBuffer buffer,
+                          uint64_t buffer_offset,
+                          const void *data,
+                          size_t size);
+
+void wgpuQueueWriteTexture(WGPUQueue queue,
+                           const WGPUImageCopyTexture *destination,
+                           const void *data,
+                           size_t data_size,
+                           const WGPUTextureDataLayout *data_layout,
+                           const WGPUExtent3D *write_size);
+
+WGPUBuffer wgpuDeviceCreateBuffer(WGPUDevice device, const WGPUBufferDescriptor *descriptor);
+
+/**
+ * The callback is called by `wgpuDevicePoll` or `wgpuQueueSubmit` once the buffer is mapped.
+ * A `size` of `SIZE_MAX` maps the rest of the buffer.
+ */
+void wgpuBufferMapAsync(WGPUBuffer buffer,
+                        WGPUMapModeFlags mode,
+                        size_t offset,
+                        size_t size,
+                        WGPUBufferMapCallback callback,
+                        void *userdata);
+
+/**
+ * Returns null if the range is not mapped.
+ */
+void *wgpuBufferGetMappedRange(WGPUBuffer buffer, size_t offset, size_t size);
+
+/**
+ * Returns null if the range is not mapped.
+ */
+const void *wgpuBufferGetConstMappedRange(WGPUBuffer buffer, size_t offset, size_t size);
+
+void wgpuBufferUnmap(WGPUBuffer buffer);
+
+void wgpuBufferDestroy(WGPUBuffer buffer);
+
+WGPUTexture wgpuDeviceCreateTexture(WGPUDevice device, const WGPUTextureDescriptor *descriptor);
+
+/**
+ * The descriptor may be null, to create a view of the whole texture.
+ */
+WGPUTextureView wgpuTextureCreateView(WGPUTexture texture,
+                                      const WGPUTextureViewDescriptor *descriptor);
+
+void wgpuTextureDestroy(WGPUTexture texture);
+
+WGPUSampler wgpuDeviceCreateSampler(WGPUDevice device, const WGPUSamplerDescriptor *descriptor);
+
+/**
+ * The source is given by a `WGPUShaderModuleSPIRVDescriptor`
+ * or a `WGPUShaderModuleWGSLDescriptor` chained to the descriptor.
+ */
+WGPUShaderModule wgpuDeviceCreateShaderModule(WGPUDevice device,
+                                              const WGPUShaderModuleDescriptor *descriptor);
+
+WGPUBindGroupLayout wgpuDeviceCreateBindGroupLayout(WGPUDevice device,
+                                                    const WGPUBindGroupLayoutDescriptor *descriptor);
+
+WGPUPipelineLayout wgpuDeviceCreatePipelineLayout(WGPUDevice device,
+                                                  const WGPUPipelineLayoutDescriptor *descriptor);
+
+WGPUBindGroup wgpuDeviceCreateBindGroup(WGPUDevice device,
+                                        const WGPUBindGroupDescriptor *descriptor);
+
+WGPUComputePipeline wgpuDeviceCreateComputePipeline(WGPUDevice device,
+                                                    const WGPUComputePipelineDescriptor *descriptor);
+
+WGPUBindGroupLayout wgpuComputePipelineGetBindGroupLayout(WGPUComputePipeline pipeline,
+                                                          uint32_t group_index);
+
+WGPURenderPipeline wgpuDeviceCreateRenderPipeline(WGPUDevice device,
+                                                  const WGPURenderPipelineDescriptor *descriptor);
+
+WGPUBindGroupLayout wgpuRenderPipelineGetBindGroupLayout(WGPURenderPipeline pipeline,
+                                                         uint32_t group_index);
+
+WGPUCommandEncoder wgpuDeviceCreateCommandEncoder(WGPUDevice device,
+                                                  const WGPUCommandEncoderDescriptor *descriptor);
+
+/**
+ * The descriptor may be null.
+ */
+WGPUCommandBuffer wgpuCommandEncoderFinish(WGPUCommandEncoder encoder,
+                                           const WGPUCommandBufferDescriptor *descriptor);
+
+void wgpuCommandEncoderCopyBufferToBuffer(WGPUCommandEncoder encoder,
+                                          WGPUBuffer source,
+                                          uint64_t source_offset,
+                                          WGPUBuffer destination,
+                                          uint64_t destination_offset,
+                                          uint64_t size);
+
+void wgpuCommandEncoderCopyBufferToTexture(WGPUCommandEncoder encoder,
+                                           const WGPUImageCopyBuffer *source,
+                                           const WGPUImageCopyTexture *destination,
+                                           const WGPUExtent3D *copy_size);
+
+void wgpuCommandEncoderCopyTextureToBuffer(WGPUCommandEncoder encoder,
+                                           const WGPUImageCopyTexture *source,
+                                           const WGPUImageCopyBuffer *destination,
+                                           const WGPUExtent3D *copy_size);
+
+void wgpuCommandEncoderCopyTextureToTexture(WGPUCommandEncoder encoder,
+                                            const WGPUImageCopyTexture *source,
+                                            const WGPUImageCopyTexture *destination,
+                                            const WGPUExtent3D *copy_size);
+
+/**
+ * The descriptor may be null.
+ */
+WGPUComputePassEncoder wgpuCommandEncoderBeginComputePass(WGPUCommandEncoder encoder,
+                                                          const WGPUComputePassDescriptor *descriptor);
+
+WGPURenderPassEncoder wgpuCommandEncoderBeginRenderPass(WGPUCommandEncoder encoder,
+                                                        const WGPURenderPassDescriptor *descriptor);
+
+void wgpuComputePassEncoderSetPipeline(WGPUComputePassEncoder pass_encoder,
+                                       WGPUComputePipeline pipeline);
+
+void wgpuComputePassEncoderSetBindGroup(WGPUComputePassEncoder pass_encoder,
+                                        uint32_t group_index,
+                                        WGPUBindGroup group,
+                                        uint32_t dynamic_offset_count,
+                                        const uint32_t *dynamic_offsets);
+
+void wgpuComputePassEncoderDispatch(WGPUComputePassEncoder pass_encoder,
+                                    uint32_t x,
+                                    uint32_t y,
+                                    uint32_t z);
+
+void wgpuComputePassEncoderEndPass(WGPUComputePassEncoder pass_encoder);
+
+void wgpuRenderPassEncoderSetPipeline(WGPURenderPassEncoder pass_encoder,
+                                      WGPURenderPipeline pipeline);
+
+void wgpuRenderPassEncoderSetBindGroup(WGPURenderPassEncoder pass_encoder,
+                                       uint32_t group_index,
+                                       WGPUBindGroup group,
+                                       uint32_t dynamic_offset_count,
+                                       const uint32_t *dynamic_offsets);
+
+/**
+ * A `size` of `WGPU_WHOLE_SIZE` binds the rest of the buffer.
+ */
+void wgpuRenderPassEncoderSetVertexBuffer(WGPURenderPassEncoder pass_encoder,
+                                          uint32_t slot,
+                                          WGPUBuffer buffer,
+                                          uint64_t offset,
+                                          uint64_t size);
+
+/**
+ * A `size` of `WGPU_WHOLE_SIZE` binds the rest of the buffer.
+ */
+void wgpuRenderPassEncoderSetIndexBuffer(WGPURenderPassEncoder pass_encoder,
+                                         WGPUBuffer buffer,
+                                         WGPUIndexFormat format,
+                                         uint64_t offset,
+                                         uint64_t size);
+
+void wgpuRenderPassEncoderSetViewport(WGPURenderPassEncoder pass_encoder,
+                                      float x,
+                                      float y,
+                                      float width,
+                                      float height,
+                                      float min_depth,
+                                      float max_depth);
+
+void wgpuRenderPassEncoderSetScissorRect(WGPURenderPassEncoder pass_encoder,
+                                         uint32_t x,
+                                         uint32_t y,
+                                         uint32_t width,
+                                         uint32_t height);
+
+void wgpuRenderPassEncoderDraw(WGPURenderPassEncoder pass_encoder,
+                               uint32_t vertex_count,
+                               uint32_t instance_count,
+                               uint32_t first_vertex,
+                               uint32_t first_instance);
+
+void wgpuRenderPassEncoderDrawIndexed(WGPURenderPassEncoder pass_encoder,
+                                      uint32_t index_count,
+                                      uint32_t instance_count,
+                                      uint32_t first_index,
+                                      int32_t base_vertex,
+                                      uint32_t first_instance);
+
+void wgpuRenderPassEncoderEndPass(WGPURenderPassEncoder pass_encoder);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* WGPU_H */
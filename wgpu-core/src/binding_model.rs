@@ -25,6 +25,7 @@ use serde::Serialize;
 use std::{
     borrow::{Borrow, Cow},
     ops::Range,
+    sync::Arc,
 };
 
 use thiserror::Error;
@@ -136,6 +137,8 @@ pub enum CreateBindGroupError {
     DepthStencilAspect,
     #[error("the adapter does not support simultaneous read + write storage texture access for the format {0:?}")]
     StorageReadWriteNotSupported(wgt::TextureFormat),
+    #[error("the adapter does not support atomic storage texture access for the format {0:?}")]
+    StorageAtomicsNotSupported(wgt::TextureFormat),
 }
 
 #[derive(Clone, Debug, Error)]
@@ -477,7 +480,8 @@ pub struct PipelineLayoutDescriptor<'a> {
 
 #[derive(Debug)]
 pub struct PipelineLayout<B: hal::Backend> {
-    pub(crate) raw: B::PipelineLayout,
+    /// Shared with the pipeline layouts created with the same description.
+    pub(crate) raw: Arc<B::PipelineLayout>,
     pub(crate) device_id: Stored<DeviceId>,
     pub(crate) life_guard: LifeGuard,
     pub(crate) bind_group_layout_ids: ArrayVec<[Valid<BindGroupLayoutId>; MAX_BIND_GROUPS]>,
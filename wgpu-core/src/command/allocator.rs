@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use super::CommandBuffer;
+use super::{CommandBuffer, CommandBufferMutable};
 use crate::{
     device::DeviceError, hub::GfxBackend, id::DeviceId, track::TrackerSet, FastHashMap,
     PrivateFeatures, Stored, SubmissionIndex,
@@ -11,79 +11,162 @@ use crate::{
 #[cfg(debug_assertions)]
 use crate::LabelHelpers;
 
-use hal::{command::CommandBuffer as _, device::Device as _, pool::CommandPool as _};
+use hal::{
+    command::{CommandBuffer as _, Level},
+    device::Device as _,
+    pool::CommandPool as _,
+};
 use parking_lot::Mutex;
 use thiserror::Error;
 
-use std::thread;
+use std::{sync::Arc, thread};
 
 const GROW_AMOUNT: usize = 20;
 
+/// Pool of raw command buffers, shared by the encoders allocated from it.
+///
+/// The pool has to be locked while recording into any of its command buffers,
+/// so encoders sharing a pool are recorded one at a time, even if they are
+/// recorded on different threads. The internal pool of the device is the
+/// exception: it's only recorded into while the device is borrowed mutably,
+/// which already serializes the recording.
 #[derive(Debug)]
-struct CommandPool<B: hal::Backend> {
+pub(crate) struct CommandPool<B: hal::Backend> {
     raw: B::CommandPool,
     total: usize,
     available: Vec<B::CommandBuffer>,
-    pending: Vec<(B::CommandBuffer, SubmissionIndex)>,
+    available_secondary: Vec<B::CommandBuffer>,
+    pending: Vec<(B::CommandBuffer, Level, SubmissionIndex)>,
 }
 
 impl<B: hal::Backend> CommandPool<B> {
     fn maintain(&mut self, last_done_index: SubmissionIndex) {
         for i in (0..self.pending.len()).rev() {
-            if self.pending[i].1 <= last_done_index {
-                let (cmd_buf, index) = self.pending.swap_remove(i);
+            if self.pending[i].2 <= last_done_index {
+                let (cmd_buf, level, index) = self.pending.swap_remove(i);
                 tracing::trace!(
                     "recycling cmdbuf submitted in {} when {} is last done",
                     index,
                     last_done_index,
                 );
-                self.recycle(cmd_buf);
+                self.recycle(cmd_buf, level);
             }
         }
     }
 
-    fn recycle(&mut self, mut raw: B::CommandBuffer) {
+    fn available_count(&self) -> usize {
+        self.available.len() + self.available_secondary.len()
+    }
+
+    fn recycle(&mut self, mut raw: B::CommandBuffer, level: Level) {
         unsafe {
             raw.reset(false);
         }
-        self.available.push(raw);
+        match level {
+            Level::Primary => self.available.push(raw),
+            Level::Secondary => self.available_secondary.push(raw),
+        }
     }
 
-    fn allocate(&mut self) -> B::CommandBuffer {
-        if self.available.is_empty() {
+    pub(crate) fn allocate(&mut self, level: Level) -> B::CommandBuffer {
+        let available = match level {
+            Level::Primary => &mut self.available,
+            Level::Secondary => &mut self.available_secondary,
+        };
+        if available.is_empty() {
             self.total += GROW_AMOUNT;
-            unsafe {
-                self.raw.allocate(
-                    GROW_AMOUNT,
-                    hal::command::Level::Primary,
-                    &mut self.available,
-                )
-            };
+            unsafe { self.raw.allocate(GROW_AMOUNT, level, &mut *available) };
         }
-        self.available.pop().unwrap()
+        available.pop().unwrap()
+    }
+
+    /// Gives back a primary command buffer that isn't going to be submitted.
+    pub(crate) fn discard(&mut self, raw: B::CommandBuffer) {
+        self.recycle(raw, Level::Primary);
+    }
+
+    fn new(
+        queue_family: hal::queue::QueueFamilyId,
+        device: &B::Device,
+    ) -> Result<Self, CommandAllocatorError> {
+        let raw = unsafe {
+            device
+                .create_command_pool(
+                    queue_family,
+                    hal::pool::CommandPoolCreateFlags::RESET_INDIVIDUAL,
+                )
+                .or(Err(DeviceError::OutOfMemory))?
+        };
+        Ok(Self {
+            raw,
+            total: 0,
+            available: Vec::new(),
+            available_secondary: Vec::new(),
+            pending: Vec::new(),
+        })
     }
 
     fn destroy(mut self, device: &B::Device) {
+        while let Some((raw, level, _)) = self.pending.pop() {
+            self.recycle(raw, level);
+        }
+        if self.total != self.available_count() {
+            tracing::error!(
+                "Some command buffers are still recorded, only tracking {} / {}",
+                self.available_count(),
+                self.total
+            );
+        }
         unsafe {
-            self.raw.free(self.available.into_iter());
+            self.raw.free(
+                self.available
+                    .into_iter()
+                    .chain(self.available_secondary.into_iter()),
+            );
             device.destroy_command_pool(self.raw);
         }
     }
 }
 
+/// Secondary command buffer, along with the pool it was allocated from.
+#[derive(Debug)]
+pub(crate) struct SecondaryCommandBuffer<B: hal::Backend> {
+    pub(crate) pool: Arc<Mutex<CommandPool<B>>>,
+    pub(crate) raw: B::CommandBuffer,
+}
+
 #[derive(Debug)]
 struct Inner<B: hal::Backend> {
-    pools: FastHashMap<thread::ThreadId, CommandPool<B>>,
+    pools: FastHashMap<thread::ThreadId, Arc<Mutex<CommandPool<B>>>>,
 }
 
 #[derive(Debug)]
 pub struct CommandAllocator<B: hal::Backend> {
     queue_family: hal::queue::QueueFamilyId,
-    internal_thread_id: thread::ThreadId,
+    /// Pool for the command buffers of the device itself, never shared
+    /// with the encoders of any thread.
+    internal: Mutex<CommandPool<B>>,
     inner: Mutex<Inner<B>>,
 }
 
 impl<B: GfxBackend> CommandAllocator<B> {
+    fn current_pool<'a>(
+        &self,
+        inner: &'a mut Inner<B>,
+        device: &B::Device,
+    ) -> Result<&'a Arc<Mutex<CommandPool<B>>>, CommandAllocatorError> {
+        let thread_id = thread::current().id();
+        use std::collections::hash_map::Entry;
+        Ok(match inner.pools.entry(thread_id) {
+            Entry::Vacant(e) => {
+                tracing::info!("Starting on thread {:?}", thread_id);
+                let pool = CommandPool::new(self.queue_family, device)?;
+                e.insert(Arc::new(Mutex::new(pool)))
+            }
+            Entry::Occupied(e) => e.into_mut(),
+        })
+    }
+
     pub(crate) fn allocate(
         &self,
         device_id: Stored<DeviceId>,
@@ -94,55 +177,53 @@ impl<B: GfxBackend> CommandAllocator<B> {
         #[cfg(feature = "trace")] enable_tracing: bool,
     ) -> Result<CommandBuffer<B>, CommandAllocatorError> {
         //debug_assert_eq!(device_id.backend(), B::VARIANT);
-        let thread_id = thread::current().id();
         let mut inner = self.inner.lock();
-
-        use std::collections::hash_map::Entry;
-        let pool = match inner.pools.entry(thread_id) {
-            Entry::Vacant(e) => {
-                tracing::info!("Starting on thread {:?}", thread_id);
-                let raw = unsafe {
-                    device
-                        .create_command_pool(
-                            self.queue_family,
-                            hal::pool::CommandPoolCreateFlags::RESET_INDIVIDUAL,
-                        )
-                        .or(Err(DeviceError::OutOfMemory))?
-                };
-                e.insert(CommandPool {
-                    raw,
-                    total: 0,
-                    available: Vec::new(),
-                    pending: Vec::new(),
-                })
-            }
-            Entry::Occupied(e) => e.into_mut(),
-        };
+        let pool = self.current_pool(&mut inner, device)?;
 
         //Note: we have to allocate the first buffer right here, or otherwise
         // the pool may be cleaned up by maintenance called from another thread.
+        let raw = pool.lock().allocate(Level::Primary);
 
         Ok(CommandBuffer {
-            raw: vec![pool.allocate()],
-            is_recording: true,
-            recorded_thread_id: thread_id,
-            device_id,
-            trackers: TrackerSet::new(B::VARIANT),
-            used_swap_chains: Default::default(),
-            buffer_memory_init_actions: Default::default(),
-            limits,
-            private_features,
-            has_labels: label.is_some(),
-            #[cfg(feature = "trace")]
-            commands: if enable_tracing {
-                Some(Vec::new())
-            } else {
-                None
-            },
+            pool: Arc::clone(pool),
+            data: Mutex::new(CommandBufferMutable {
+                raw: vec![raw],
+                secondary: Vec::new(),
+                fragments: Vec::new(),
+                is_recording: true,
+                device_id,
+                trackers: TrackerSet::new(B::VARIANT),
+                used_swap_chains: Default::default(),
+                buffer_memory_init_actions: Default::default(),
+                index_tracker_actions: Default::default(),
+                limits,
+                private_features,
+                has_labels: label.is_some(),
+                #[cfg(feature = "trace")]
+                commands: if enable_tracing {
+                    Some(Vec::new())
+                } else {
+                    None
+                },
+            }),
             #[cfg(debug_assertions)]
             label: label.to_string_or_default(),
         })
     }
+
+    /// Allocates a secondary command buffer from the pool of the current thread.
+    pub(crate) fn allocate_secondary(
+        &self,
+        device: &B::Device,
+    ) -> Result<SecondaryCommandBuffer<B>, CommandAllocatorError> {
+        let mut inner = self.inner.lock();
+        let pool = self.current_pool(&mut inner, device)?;
+        let raw = pool.lock().allocate(Level::Secondary);
+        Ok(SecondaryCommandBuffer {
+            pool: Arc::clone(pool),
+            raw,
+        })
+    }
 }
 
 impl<B: hal::Backend> CommandAllocator<B> {
@@ -150,71 +231,53 @@ impl<B: hal::Backend> CommandAllocator<B> {
         queue_family: hal::queue::QueueFamilyId,
         device: &B::Device,
     ) -> Result<Self, CommandAllocatorError> {
-        let internal_thread_id = thread::current().id();
-        tracing::info!("Starting on (internal) thread {:?}", internal_thread_id);
-        let mut pools = FastHashMap::default();
-        pools.insert(
-            internal_thread_id,
-            CommandPool {
-                raw: unsafe {
-                    device
-                        .create_command_pool(
-                            queue_family,
-                            hal::pool::CommandPoolCreateFlags::RESET_INDIVIDUAL,
-                        )
-                        .or(Err(DeviceError::OutOfMemory))?
-                },
-                total: 0,
-                available: Vec::new(),
-                pending: Vec::new(),
-            },
-        );
         Ok(Self {
             queue_family,
-            internal_thread_id,
-            inner: Mutex::new(Inner { pools }),
+            internal: Mutex::new(CommandPool::new(queue_family, device)?),
+            inner: Mutex::new(Inner {
+                pools: FastHashMap::default(),
+            }),
         })
     }
 
-    fn allocate_for_thread_id(&self, thread_id: thread::ThreadId) -> B::CommandBuffer {
-        let mut inner = self.inner.lock();
-        inner.pools.get_mut(&thread_id).unwrap().allocate()
-    }
-
     pub fn allocate_internal(&self) -> B::CommandBuffer {
-        self.allocate_for_thread_id(self.internal_thread_id)
+        self.internal.lock().allocate(Level::Primary)
     }
 
-    pub fn extend(&self, cmd_buf: &CommandBuffer<B>) -> B::CommandBuffer {
-        self.allocate_for_thread_id(cmd_buf.recorded_thread_id)
+    pub fn discard_internal(&self, raw: B::CommandBuffer) {
+        self.internal.lock().recycle(raw, Level::Primary);
     }
 
-    pub fn discard_internal(&self, raw: B::CommandBuffer) {
-        let mut inner = self.inner.lock();
-        inner
-            .pools
-            .get_mut(&self.internal_thread_id)
-            .unwrap()
-            .recycle(raw);
+    pub(crate) fn discard_secondary(&self, secondary: SecondaryCommandBuffer<B>) {
+        secondary
+            .pool
+            .lock()
+            .recycle(secondary.raw, Level::Secondary);
     }
 
-    pub fn discard(&self, mut cmd_buf: CommandBuffer<B>) {
-        cmd_buf.trackers.clear();
-        let mut inner = self.inner.lock();
-        let pool = inner.pools.get_mut(&cmd_buf.recorded_thread_id).unwrap();
-        for raw in cmd_buf.raw {
-            pool.recycle(raw);
+    pub fn discard(&self, cmd_buf: CommandBuffer<B>) {
+        let mut cmd_buf_data = cmd_buf.data.into_inner();
+        cmd_buf_data.trackers.clear();
+        {
+            let mut pool = cmd_buf.pool.lock();
+            for raw in cmd_buf_data.raw {
+                pool.recycle(raw, Level::Primary);
+            }
+        }
+        // Secondary command buffers may come from the pools of other threads.
+        for secondary in cmd_buf_data.secondary {
+            self.discard_secondary(secondary);
+        }
+        for fragment in cmd_buf_data.fragments {
+            self.discard_secondary(fragment.secondary);
         }
     }
 
     pub fn after_submit_internal(&self, raw: B::CommandBuffer, submit_index: SubmissionIndex) {
-        let mut inner = self.inner.lock();
-        inner
-            .pools
-            .get_mut(&self.internal_thread_id)
-            .unwrap()
+        self.internal
+            .lock()
             .pending
-            .push((raw, submit_index));
+            .push((raw, Level::Primary, submit_index));
     }
 
     pub fn after_submit(
@@ -223,54 +286,70 @@ impl<B: hal::Backend> CommandAllocator<B> {
         device: &B::Device,
         submit_index: SubmissionIndex,
     ) {
+        let cmd_buf_data = cmd_buf.data.into_inner();
         // Record this command buffer as pending
-        let mut inner = self.inner.lock();
-        let clear_label = cmd_buf.has_labels;
-        inner
-            .pools
-            .get_mut(&cmd_buf.recorded_thread_id)
-            .unwrap()
+        let clear_label = cmd_buf_data.has_labels;
+        cmd_buf
+            .pool
+            .lock()
             .pending
-            .extend(cmd_buf.raw.into_iter().map(|mut raw| {
+            .extend(cmd_buf_data.raw.into_iter().map(|mut raw| {
                 if clear_label {
                     unsafe { device.set_command_buffer_name(&mut raw, "") };
                 }
-                (raw, submit_index)
+                (raw, Level::Primary, submit_index)
             }));
+        for secondary in cmd_buf_data.secondary {
+            secondary
+                .pool
+                .lock()
+                .pending
+                .push((secondary.raw, Level::Secondary, submit_index));
+        }
+        // Fragments that were never executed can be reused right away.
+        for fragment in cmd_buf_data.fragments {
+            self.discard_secondary(fragment.secondary);
+        }
     }
 
     pub fn maintain(&self, device: &B::Device, last_done_index: SubmissionIndex) {
+        self.internal.lock().maintain(last_done_index);
         let mut inner = self.inner.lock();
         let mut remove_threads = Vec::new();
-        for (&thread_id, pool) in inner.pools.iter_mut() {
-            pool.maintain(last_done_index);
-            if pool.total == pool.available.len() && thread_id != self.internal_thread_id {
-                assert!(pool.pending.is_empty());
+        for (&thread_id, pool) in inner.pools.iter() {
+            let mut pool_guard = pool.lock();
+            pool_guard.maintain(last_done_index);
+            if pool_guard.total == pool_guard.available_count() && Arc::strong_count(pool) == 1 {
+                assert!(pool_guard.pending.is_empty());
                 remove_threads.push(thread_id);
             }
         }
         for thread_id in remove_threads {
             tracing::info!("Removing from thread {:?}", thread_id);
-            let pool = inner.pools.remove(&thread_id).unwrap();
-            pool.destroy(device);
+            // New references are only made under the `inner` lock.
+            let pool = Arc::try_unwrap(inner.pools.remove(&thread_id).unwrap())
+                .ok()
+                .unwrap();
+            pool.into_inner().destroy(device);
         }
     }
 
     pub fn destroy(self, device: &B::Device) {
-        let mut inner = self.inner.lock();
-        for (_, mut pool) in inner.pools.drain() {
-            while let Some((raw, _)) = pool.pending.pop() {
-                pool.recycle(raw);
-            }
-            if pool.total != pool.available.len() {
-                tracing::error!(
-                    "Some command buffers are still recorded, only tracking {} / {}",
-                    pool.available.len(),
-                    pool.total
-                );
-            }
+        let inner = self.inner.into_inner();
+        for (thread_id, pool) in inner.pools {
+            let pool = match Arc::try_unwrap(pool) {
+                Ok(pool) => pool.into_inner(),
+                Err(_) => {
+                    tracing::error!(
+                        "Command pool of thread {:?} is still used by live command buffers",
+                        thread_id
+                    );
+                    continue;
+                }
+            };
             pool.destroy(device);
         }
+        self.internal.into_inner().destroy(device);
     }
 }
 
@@ -34,6 +34,14 @@
     called. It goes through the commands and issues them into the native command
     buffer. Thanks to the "normalized" property, it doesn't track any bind group
     invalidations or index format changes.
+
+    ### Render pass fragments
+
+    Bundles can also make up the whole contents of a render pass. Each of them
+    is first executed into its own secondary command buffer, with
+    `command_encoder_record_render_pass_fragment`, on any thread. Then
+    `command_encoder_run_render_pass_fragments` executes these secondary
+    command buffers within the pass.
 !*/
 #![allow(clippy::reversed_empty_ranges)]
 
@@ -49,7 +57,9 @@ use crate::{
     },
     hub::{GfxBackend, GlobalIdentityHandlerFactory, Hub, Resource, Storage, Token},
     id,
+    index_tracker::{IndexDrawValidation, IndexTrackerAction},
     memory_init_tracker::{MemoryInitKind, MemoryInitTrackerAction},
+    pipeline::PipelineFlags,
     resource::BufferUse,
     span,
     track::{TrackerSet, UsageConflict},
@@ -57,7 +67,7 @@ use crate::{
     Label, LabelHelpers, LifeGuard, Stored, MAX_BIND_GROUPS,
 };
 use arrayvec::ArrayVec;
-use std::{borrow::Cow, iter, mem, ops::Range};
+use std::{borrow::Cow, iter, mem, ops::Range, str};
 use thiserror::Error;
 
 /// Describes a [`RenderBundleEncoder`].
@@ -73,6 +83,14 @@ pub struct RenderBundleEncoderDescriptor<'a> {
     /// The formats of the depth attachment that this render bundle is capable to rendering to. This
     /// must match the formats of the depth attachments in the renderpass this render bundle is executed in.
     pub depth_stencil_format: Option<wgt::TextureFormat>,
+    /// If true, the render bundle will not write to the depth aspect of the depth-stencil
+    /// attachment, and can be executed in a render pass where the depth is read-only.
+    #[cfg_attr(any(feature = "trace", feature = "replay"), serde(default))]
+    pub depth_read_only: bool,
+    /// If true, the render bundle will not write to the stencil aspect of the depth-stencil
+    /// attachment, and can be executed in a render pass where the stencil is read-only.
+    #[cfg_attr(any(feature = "trace", feature = "replay"), serde(default))]
+    pub stencil_read_only: bool,
     /// Sample count this render bundle is capable of rendering to. This must match the pipelines and
     /// the renderpasses it is used in.
     pub sample_count: u32,
@@ -84,6 +102,8 @@ pub struct RenderBundleEncoder {
     base: BasePass<RenderCommand>,
     parent_id: id::DeviceId,
     pub(crate) context: RenderPassContext,
+    pub(crate) is_depth_read_only: bool,
+    pub(crate) is_stencil_read_only: bool,
 }
 
 impl RenderBundleEncoder {
@@ -110,6 +130,8 @@ impl RenderBundleEncoder {
                     sc as u8
                 },
             },
+            is_depth_read_only: desc.depth_read_only,
+            is_stencil_read_only: desc.stencil_read_only,
         })
     }
 
@@ -125,6 +147,8 @@ impl RenderBundleEncoder {
                 },
                 sample_count: 0,
             },
+            is_depth_read_only: false,
+            is_stencil_read_only: false,
         }
     }
 
@@ -162,10 +186,30 @@ impl RenderBundleEncoder {
             used_bind_groups: 0,
             pipeline: StateChange::new(),
         };
+        let ds_aspects = match self.context.attachments.depth_stencil {
+            Some(format) => {
+                conv::map_texture_format(format, device.private_features)
+                    .surface_desc()
+                    .aspects
+            }
+            None => hal::format::Aspects::empty(),
+        };
+        let is_depth_read_only =
+            self.is_depth_read_only || !ds_aspects.contains(hal::format::Aspects::DEPTH);
+        let is_stencil_read_only =
+            self.is_stencil_read_only || !ds_aspects.contains(hal::format::Aspects::STENCIL);
+
         let mut commands = Vec::new();
         let mut base = self.base.as_ref();
         let mut pipeline_layout_id = None::<id::Valid<id::PipelineLayoutId>>;
         let mut buffer_memory_init_actions = Vec::new();
+        let mut index_tracker_actions = Vec::new();
+        let mut string_data = Vec::new();
+        let mut string_offset = 0;
+        let mut push_constant_data = Vec::new();
+        // Push constants set before the first pipeline, waiting for a pipeline layout.
+        let mut pending_push_constants = Vec::new();
+        let mut debug_scope_depth = 0u32;
 
         for &command in base.commands {
             match command {
@@ -236,7 +280,14 @@ impl RenderBundleEncoder {
                         .map_err(RenderCommandError::IncompatiblePipeline)
                         .map_pass_err(scope)?;
 
-                    //TODO: check read-only depth
+                    if (pipeline.flags.contains(PipelineFlags::WRITES_DEPTH)
+                        && self.is_depth_read_only)
+                        || (pipeline.flags.contains(PipelineFlags::WRITES_STENCIL)
+                            && self.is_stencil_read_only)
+                    {
+                        return Err(RenderCommandError::IncompatibleReadOnlyDepthStencil)
+                            .map_pass_err(scope);
+                    }
 
                     let layout = &pipeline_layout_guard[pipeline.layout_id.value];
                     pipeline_layout_id = Some(pipeline.layout_id.value);
@@ -251,6 +302,20 @@ impl RenderBundleEncoder {
                     if let Some(iter) = state.flush_push_constants() {
                         commands.extend(iter)
                     }
+                    for pending in pending_push_constants.drain(..) {
+                        if let RenderCommand::SetPushConstant {
+                            stages,
+                            offset,
+                            size_bytes,
+                            values_offset: _,
+                        } = pending
+                        {
+                            layout
+                                .validate_push_constant_ranges(stages, offset, offset + size_bytes)
+                                .map_pass_err(PassErrorScope::SetPushConstant)?;
+                        }
+                        commands.push(pending);
+                    }
                 }
                 RenderCommand::SetIndexBuffer {
                     buffer_id,
@@ -309,21 +374,37 @@ impl RenderBundleEncoder {
                     stages,
                     offset,
                     size_bytes,
-                    values_offset: _,
+                    values_offset,
                 } => {
                     let scope = PassErrorScope::SetPushConstant;
                     let end_offset = offset + size_bytes;
-
-                    let pipeline_layout_id = pipeline_layout_id
-                        .ok_or(DrawError::MissingPipeline)
+                    let values_offset = values_offset
+                        .ok_or(RenderBundleErrorInner::InvalidValuesOffset)
                         .map_pass_err(scope)?;
-                    let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id];
+                    let values_end_offset =
+                        (values_offset + size_bytes / wgt::PUSH_CONSTANT_ALIGNMENT) as usize;
 
-                    pipeline_layout
-                        .validate_push_constant_ranges(stages, offset, end_offset)
-                        .map_pass_err(scope)?;
+                    // Move the values into the bundle's own data.
+                    let command = RenderCommand::SetPushConstant {
+                        stages,
+                        offset,
+                        size_bytes,
+                        values_offset: Some(push_constant_data.len() as u32),
+                    };
+                    push_constant_data.extend_from_slice(
+                        &base.push_constant_data[(values_offset as usize)..values_end_offset],
+                    );
 
-                    commands.push(command);
+                    match pipeline_layout_id {
+                        Some(pipeline_layout_id) => {
+                            let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id];
+                            pipeline_layout
+                                .validate_push_constant_ranges(stages, offset, end_offset)
+                                .map_pass_err(scope)?;
+                            commands.push(command);
+                        }
+                        None => pending_push_constants.push(command),
+                    }
                 }
                 RenderCommand::Draw {
                     vertex_count,
@@ -363,7 +444,7 @@ impl RenderBundleEncoder {
                     index_count,
                     instance_count,
                     first_index,
-                    base_vertex: _,
+                    base_vertex,
                     first_instance,
                 } => {
                     let scope = PassErrorScope::Draw {
@@ -371,7 +452,6 @@ impl RenderBundleEncoder {
                         indirect: false,
                         pipeline: state.pipeline.last_state,
                     };
-                    //TODO: validate that base_vertex + max_index() is within the provided range
                     let vertex_limits = state.vertex_limits();
                     let index_limit = state.index.limit();
                     let last_index = first_index + index_count;
@@ -391,6 +471,20 @@ impl RenderBundleEncoder {
                         })
                         .map_pass_err(scope);
                     }
+                    if device
+                        .features
+                        .contains(wgt::Features::ROBUST_INDEX_VALIDATION)
+                        && vertex_limits.vertex_limit != u32::MAX
+                    {
+                        index_tracker_actions.push(IndexTrackerAction::Validate(
+                            state.index.draw_validation(
+                                first_index,
+                                index_count,
+                                base_vertex,
+                                &vertex_limits,
+                            ),
+                        ));
+                    }
                     commands.extend(state.index.flush());
                     commands.extend(state.flush_vertices());
                     commands.extend(state.flush_binds());
@@ -472,9 +566,28 @@ impl RenderBundleEncoder {
                 }
                 RenderCommand::MultiDrawIndirect { .. }
                 | RenderCommand::MultiDrawIndirectCount { .. } => unimplemented!(),
-                RenderCommand::PushDebugGroup { color: _, len: _ } => unimplemented!(),
-                RenderCommand::InsertDebugMarker { color: _, len: _ } => unimplemented!(),
-                RenderCommand::PopDebugGroup => unimplemented!(),
+                RenderCommand::PushDebugGroup { color: _, len } => {
+                    debug_scope_depth += 1;
+                    string_data
+                        .extend_from_slice(&base.string_data[string_offset..string_offset + len]);
+                    string_offset += len;
+                    commands.push(command);
+                }
+                RenderCommand::InsertDebugMarker { color: _, len } => {
+                    string_data
+                        .extend_from_slice(&base.string_data[string_offset..string_offset + len]);
+                    string_offset += len;
+                    commands.push(command);
+                }
+                RenderCommand::PopDebugGroup => {
+                    let scope = PassErrorScope::PopDebugGroup;
+                    if debug_scope_depth == 0 {
+                        return Err(RenderBundleErrorInner::InvalidPopDebugGroup)
+                            .map_pass_err(scope);
+                    }
+                    debug_scope_depth -= 1;
+                    commands.push(command);
+                }
                 RenderCommand::WriteTimestamp { .. }
                 | RenderCommand::BeginPipelineStatisticsQuery { .. }
                 | RenderCommand::EndPipelineStatisticsQuery => unimplemented!(),
@@ -486,13 +599,20 @@ impl RenderBundleEncoder {
             }
         }
 
+        if debug_scope_depth != 0 {
+            return Err(RenderBundleErrorInner::UnmatchedPushDebugGroup(
+                debug_scope_depth,
+            ))
+            .map_pass_err(PassErrorScope::Bundle);
+        }
+
         Ok(RenderBundle {
             base: BasePass {
                 label: desc.label.as_ref().map(|cow| cow.to_string()),
                 commands,
                 dynamic_offsets: state.flat_dynamic_offsets,
-                string_data: Vec::new(),
-                push_constant_data: Vec::new(),
+                string_data,
+                push_constant_data,
             },
             device_id: Stored {
                 value: id::Valid(self.parent_id),
@@ -500,7 +620,10 @@ impl RenderBundleEncoder {
             },
             used: state.trackers,
             buffer_memory_init_actions,
+            index_tracker_actions,
             context: self.context,
+            is_depth_read_only,
+            is_stencil_read_only,
             life_guard: LifeGuard::new(desc.label.borrow_or_default()),
         })
     }
@@ -549,7 +672,10 @@ pub struct RenderBundle {
     pub(crate) device_id: Stored<id::DeviceId>,
     pub(crate) used: TrackerSet,
     pub(crate) buffer_memory_init_actions: Vec<MemoryInitTrackerAction<id::BufferId>>,
+    pub(crate) index_tracker_actions: Vec<IndexTrackerAction>,
     pub(crate) context: RenderPassContext,
+    pub(crate) is_depth_read_only: bool,
+    pub(crate) is_stencil_read_only: bool,
     pub(crate) life_guard: LifeGuard,
 }
 
@@ -557,6 +683,12 @@ unsafe impl Send for RenderBundle {}
 unsafe impl Sync for RenderBundle {}
 
 impl RenderBundle {
+    /// Returns true if the bundle doesn't write to any aspect of
+    /// the depth-stencil attachment.
+    pub(crate) fn is_ds_read_only(&self) -> bool {
+        self.is_depth_read_only && self.is_stencil_read_only
+    }
+
     /// Actually encode the contents into a native command buffer.
     ///
     /// This is partially duplicating the logic of `command_encoder_run_render_pass`.
@@ -580,6 +712,7 @@ impl RenderBundle {
         use hal::command::CommandBuffer as _;
 
         let mut offsets = self.base.dynamic_offsets.as_slice();
+        let mut string_offset = 0;
         let mut pipeline_layout_id = None::<id::Valid<id::PipelineLayoutId>>;
         if let Some(ref label) = self.base.label {
             cmd_buf.begin_debug_marker(label, 0);
@@ -735,9 +868,23 @@ impl RenderBundle {
                 }
                 RenderCommand::MultiDrawIndirect { .. }
                 | RenderCommand::MultiDrawIndirectCount { .. } => unimplemented!(),
-                RenderCommand::PushDebugGroup { color: _, len: _ } => unimplemented!(),
-                RenderCommand::InsertDebugMarker { color: _, len: _ } => unimplemented!(),
-                RenderCommand::PopDebugGroup => unimplemented!(),
+                RenderCommand::PushDebugGroup { color, len } => {
+                    let label =
+                        str::from_utf8(&self.base.string_data[string_offset..string_offset + len])
+                            .unwrap();
+                    string_offset += len;
+                    cmd_buf.begin_debug_marker(label, color);
+                }
+                RenderCommand::InsertDebugMarker { color, len } => {
+                    let label =
+                        str::from_utf8(&self.base.string_data[string_offset..string_offset + len])
+                            .unwrap();
+                    string_offset += len;
+                    cmd_buf.insert_debug_marker(label, color);
+                }
+                RenderCommand::PopDebugGroup => {
+                    cmd_buf.end_debug_marker();
+                }
                 RenderCommand::WriteTimestamp { .. }
                 | RenderCommand::BeginPipelineStatisticsQuery { .. }
                 | RenderCommand::EndPipelineStatisticsQuery => unimplemented!(),
@@ -794,6 +941,29 @@ impl IndexState {
         ((self.range.end - self.range.start) / bytes_per_index) as u32
     }
 
+    fn draw_validation(
+        &self,
+        first_index: u32,
+        index_count: u32,
+        base_vertex: i32,
+        vertex_limits: &VertexLimitState,
+    ) -> IndexDrawValidation {
+        let bytes_per_index = match self.format {
+            wgt::IndexFormat::Uint16 => 2,
+            wgt::IndexFormat::Uint32 => 4,
+        };
+        IndexDrawValidation {
+            buffer: self.buffer.unwrap(),
+            offset: self.range.start + first_index as wgt::BufferAddress * bytes_per_index,
+            count: index_count,
+            format: self.format,
+            primitive_restart: self.pipeline_format.is_some(),
+            base_vertex,
+            vertex_limit: vertex_limits.vertex_limit,
+            vertex_limit_slot: vertex_limits.vertex_limit_slot,
+        }
+    }
+
     fn flush(&mut self) -> Option<RenderCommand> {
         if self.is_dirty {
             self.is_dirty = false;
@@ -1046,6 +1216,7 @@ impl State {
         let is_dirty = self.push_constant_ranges.is_dirty;
 
         if is_dirty {
+            self.push_constant_ranges.is_dirty = false;
             let nonoverlapping_ranges =
                 super::bind::compute_nonoverlapping_ranges(&self.push_constant_ranges.ranges);
 
@@ -1109,6 +1280,12 @@ pub(super) enum RenderBundleErrorInner {
     ResourceUsageConflict(#[from] UsageConflict),
     #[error(transparent)]
     Draw(#[from] DrawError),
+    #[error("cannot pop debug group, because number of pushed debug groups is zero")]
+    InvalidPopDebugGroup,
+    #[error("setting `values_offset` to be `None` is only for internal use in render bundles")]
+    InvalidValuesOffset,
+    #[error("{0} debug groups were pushed but not popped before the end of the bundle")]
+    UnmatchedPushDebugGroup(u32),
 }
 
 impl<T> From<T> for RenderBundleErrorInner
@@ -1134,6 +1311,10 @@ impl RenderBundleError {
         scope: PassErrorScope::Bundle,
         inner: RenderBundleErrorInner::Device(DeviceError::Invalid),
     };
+    pub(crate) const DESTROYED_DEVICE: Self = RenderBundleError {
+        scope: PassErrorScope::Bundle,
+        inner: RenderBundleErrorInner::Device(DeviceError::Destroyed),
+    };
 }
 
 impl<T, E> MapPassErr<T, RenderBundleError> for Result<T, E>
@@ -1151,7 +1332,7 @@ where
 pub mod bundle_ffi {
     use super::{RenderBundleEncoder, RenderCommand};
     use crate::{id, span, RawString};
-    use std::{convert::TryInto, slice};
+    use std::{convert::TryInto, ffi, slice};
     use wgt::{BufferAddress, BufferSize, DynamicOffset};
 
     /// # Safety
@@ -1323,17 +1504,23 @@ pub mod bundle_ffi {
     /// is a valid null-terminated stricng.
     #[no_mangle]
     pub unsafe extern "C" fn wgpu_render_bundle_push_debug_group(
-        _bundle: &mut RenderBundleEncoder,
-        _label: RawString,
+        bundle: &mut RenderBundleEncoder,
+        label: RawString,
     ) {
         span!(_guard, DEBUG, "RenderBundle::push_debug_group");
-        //TODO
+        let bytes = ffi::CStr::from_ptr(label).to_bytes();
+        bundle.base.string_data.extend_from_slice(bytes);
+
+        bundle.base.commands.push(RenderCommand::PushDebugGroup {
+            color: 0,
+            len: bytes.len(),
+        });
     }
 
     #[no_mangle]
-    pub extern "C" fn wgpu_render_bundle_pop_debug_group(_bundle: &mut RenderBundleEncoder) {
+    pub extern "C" fn wgpu_render_bundle_pop_debug_group(bundle: &mut RenderBundleEncoder) {
         span!(_guard, DEBUG, "RenderBundle::pop_debug_group");
-        //TODO
+        bundle.base.commands.push(RenderCommand::PopDebugGroup);
     }
 
     /// # Safety
@@ -1342,10 +1529,273 @@ pub mod bundle_ffi {
     /// is a valid null-terminated stricng.
     #[no_mangle]
     pub unsafe extern "C" fn wgpu_render_bundle_insert_debug_marker(
-        _bundle: &mut RenderBundleEncoder,
-        _label: RawString,
+        bundle: &mut RenderBundleEncoder,
+        label: RawString,
     ) {
         span!(_guard, DEBUG, "RenderBundle::insert_debug_marker");
-        //TODO
+        let bytes = ffi::CStr::from_ptr(label).to_bytes();
+        bundle.base.string_data.extend_from_slice(bytes);
+
+        bundle.base.commands.push(RenderCommand::InsertDebugMarker {
+            color: 0,
+            len: bytes.len(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bundle_ffi, RenderBundleEncoder, RenderBundleEncoderDescriptor, RenderBundleErrorInner,
+    };
+    use crate::{
+        binding_model::PipelineLayoutDescriptor,
+        command::{
+            render_ffi, ColorAttachmentDescriptor, LoadOp, PassChannel, RenderCommandError,
+            RenderPass, RenderPassDescriptor, StoreOp,
+        },
+        device::{
+            tests::{create_command_encoder, create_device, finish, TestGlobal},
+            DeviceDescriptor,
+        },
+        id,
+        pipeline::{
+            ProgrammableStageDescriptor, RenderPipelineDescriptor, ShaderModuleDescriptor,
+            ShaderModuleSource, VertexState,
+        },
+        resource::{TextureDescriptor, TextureViewDescriptor},
+    };
+    use std::{borrow::Cow, ffi::CString};
+
+    const TARGET_FORMAT: wgt::TextureFormat = wgt::TextureFormat::Rgba8Unorm;
+
+    fn create_target(global: &TestGlobal, device: id::DeviceId) -> id::TextureViewId {
+        let (texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 64,
+                    height: 64,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: TARGET_FORMAT,
+                usage: wgt::TextureUsage::RENDER_ATTACHMENT,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (view, error) =
+            global.dyn_texture_create_view(texture, &TextureViewDescriptor::default(), ());
+        assert!(error.is_none());
+        global.dyn_texture_drop(texture, false);
+        view
+    }
+
+    fn create_bundle_encoder(device: id::DeviceId) -> RenderBundleEncoder {
+        RenderBundleEncoder::new(
+            &RenderBundleEncoderDescriptor {
+                label: None,
+                color_formats: Cow::Borrowed(&[TARGET_FORMAT]),
+                depth_stencil_format: None,
+                depth_read_only: false,
+                stencil_read_only: false,
+                sample_count: 1,
+            },
+            device,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn debug_markers() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let group = CString::new("group").unwrap();
+        let marker = CString::new("marker").unwrap();
+
+        let mut bundle_encoder = create_bundle_encoder(device);
+        unsafe {
+            bundle_ffi::wgpu_render_bundle_push_debug_group(&mut bundle_encoder, group.as_ptr());
+            bundle_ffi::wgpu_render_bundle_insert_debug_marker(
+                &mut bundle_encoder,
+                marker.as_ptr(),
+            );
+        }
+        bundle_ffi::wgpu_render_bundle_pop_debug_group(&mut bundle_encoder);
+        let (bundle, error) = global.dyn_render_bundle_encoder_finish(
+            bundle_encoder,
+            &wgt::RenderBundleDescriptor { label: None },
+            (),
+        );
+        assert!(error.is_none());
+
+        // The markers are replayed when the bundle is executed.
+        let view = create_target(&global, device);
+        let encoder = create_command_encoder(&global, device);
+        let mut pass = RenderPass::new(
+            encoder,
+            &RenderPassDescriptor {
+                label: None,
+                color_attachments: Cow::Owned(vec![ColorAttachmentDescriptor {
+                    attachment: view,
+                    resolve_target: None,
+                    channel: PassChannel {
+                        load_op: LoadOp::Clear,
+                        store_op: StoreOp::Store,
+                        clear_value: wgt::Color::BLACK,
+                        read_only: false,
+                    },
+                }]),
+                depth_stencil_attachment: None,
+            },
+        );
+        unsafe {
+            render_ffi::wgpu_render_pass_execute_bundles(&mut pass, &bundle, 1);
+        }
+        global
+            .dyn_command_encoder_run_render_pass(encoder, &pass)
+            .unwrap();
+        let command_buffer = finish(&global, encoder);
+        global.dyn_queue_submit(device, &[command_buffer]).unwrap();
+
+        // Pops have to match the pushes.
+        let mut bundle_encoder = create_bundle_encoder(device);
+        bundle_ffi::wgpu_render_bundle_pop_debug_group(&mut bundle_encoder);
+        let (_, error) = global.dyn_render_bundle_encoder_finish(
+            bundle_encoder,
+            &wgt::RenderBundleDescriptor { label: None },
+            (),
+        );
+        assert!(matches!(
+            error.map(|e| e.inner),
+            Some(RenderBundleErrorInner::InvalidPopDebugGroup)
+        ));
+        let mut bundle_encoder = create_bundle_encoder(device);
+        unsafe {
+            bundle_ffi::wgpu_render_bundle_push_debug_group(&mut bundle_encoder, group.as_ptr());
+        }
+        let (_, error) = global.dyn_render_bundle_encoder_finish(
+            bundle_encoder,
+            &wgt::RenderBundleDescriptor { label: None },
+            (),
+        );
+        assert!(matches!(
+            error.map(|e| e.inner),
+            Some(RenderBundleErrorInner::UnmatchedPushDebugGroup(1))
+        ));
+
+        global.dyn_render_bundle_drop(bundle);
+        global.dyn_texture_view_drop(view, true).unwrap();
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn read_only_depth_bundle() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (shader_module, error) = global.dyn_device_create_shader_module(
+            device,
+            &ShaderModuleDescriptor {
+                label: None,
+                flags: wgt::ShaderFlags::VALIDATION,
+                interface: None,
+            },
+            ShaderModuleSource::Wgsl(Cow::Borrowed(
+                "[[stage(vertex)]]
+                fn main() -> [[builtin(position)]] vec4<f32> {
+                    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+                }",
+            )),
+            (),
+        );
+        assert!(error.is_none());
+        let (pipeline_layout, error) = global.dyn_device_create_pipeline_layout(
+            device,
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: Cow::Borrowed(&[]),
+                push_constant_ranges: Cow::Borrowed(&[]),
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (pipeline, _, error) = global.dyn_device_create_render_pipeline(
+            device,
+            &RenderPipelineDescriptor {
+                label: None,
+                layout: Some(pipeline_layout),
+                vertex: VertexState {
+                    stage: ProgrammableStageDescriptor {
+                        module: shader_module,
+                        entry_point: Cow::Borrowed("main"),
+                    },
+                    buffers: Cow::Borrowed(&[]),
+                },
+                primitive: wgt::PrimitiveState::default(),
+                depth_stencil: Some(wgt::DepthStencilState {
+                    format: wgt::TextureFormat::Depth32Float,
+                    depth_write_enabled: true,
+                    depth_compare: wgt::CompareFunction::Always,
+                    stencil: wgt::StencilState::default(),
+                    bias: wgt::DepthBiasState::default(),
+                    clamp_depth: false,
+                }),
+                multisample: wgt::MultisampleState::default(),
+                fragment: None,
+            },
+            (),
+            None,
+        );
+        assert!(error.is_none());
+
+        for &depth_read_only in &[false, true] {
+            let mut bundle_encoder = RenderBundleEncoder::new(
+                &RenderBundleEncoderDescriptor {
+                    label: None,
+                    color_formats: Cow::Borrowed(&[]),
+                    depth_stencil_format: Some(wgt::TextureFormat::Depth32Float),
+                    depth_read_only,
+                    stencil_read_only: false,
+                    sample_count: 1,
+                },
+                device,
+                None,
+            )
+            .unwrap();
+            bundle_ffi::wgpu_render_bundle_set_pipeline(&mut bundle_encoder, pipeline);
+            let (bundle, error) = global.dyn_render_bundle_encoder_finish(
+                bundle_encoder,
+                &wgt::RenderBundleDescriptor { label: None },
+                (),
+            );
+            if depth_read_only {
+                // The pipeline writes to the depth the bundle promised not to touch.
+                assert!(matches!(
+                    error.map(|e| e.inner),
+                    Some(RenderBundleErrorInner::RenderCommand(
+                        RenderCommandError::IncompatibleReadOnlyDepthStencil
+                    ))
+                ));
+            } else {
+                assert!(error.is_none());
+            }
+            global.dyn_render_bundle_drop(bundle);
+        }
+
+        global.dyn_render_pipeline_drop(pipeline);
+        global.dyn_pipeline_layout_drop(pipeline_layout);
+        global.dyn_shader_module_drop(shader_module);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
     }
 }
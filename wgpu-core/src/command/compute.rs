@@ -185,11 +185,22 @@ where
     }
 }
 
+/// Push constant upload issued before any pipeline was set.
+///
+/// It's validated and recorded as soon as the pipeline layout is known.
+#[derive(Debug)]
+struct PendingPushConstant {
+    offset: u32,
+    size_bytes: u32,
+    values_offset: u32,
+}
+
 #[derive(Debug)]
 struct State {
     binder: Binder,
     pipeline: StateChange<id::ComputePipelineId>,
     trackers: TrackerSet,
+    pending_push_constants: Vec<PendingPushConstant>,
     debug_scope_depth: u32,
 }
 
@@ -259,9 +270,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = B::hub(self);
         let mut token = Token::root();
 
-        let (mut cmd_buf_guard, mut token) = hub.command_buffers.write(&mut token);
-        let cmd_buf =
-            CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, encoder_id).map_pass_err(scope)?;
+        let (cmd_buf_guard, mut token) = hub.command_buffers.read(&mut token);
+        let mut cmd_buf_data =
+            CommandBuffer::get_encoder(&*cmd_buf_guard, encoder_id).map_pass_err(scope)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let raw = cmd_buf.raw.last_mut().unwrap();
 
         #[cfg(feature = "trace")]
@@ -289,6 +301,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             binder: Binder::new(),
             pipeline: StateChange::new(),
             trackers: TrackerSet::new(B::VARIANT),
+            pending_push_constants: Vec::new(),
             debug_scope_depth: 0,
         };
         let mut temp_offsets = Vec::new();
@@ -394,6 +407,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     // Rebind resources
                     if state.binder.pipeline_layout_id != Some(pipeline.layout_id.value) {
                         let pipeline_layout = &pipeline_layout_guard[pipeline.layout_id.value];
+                        // Push constants survive the layout change if the ranges are the same.
+                        let push_constants_compatible =
+                            state.binder.pipeline_layout_id.map_or(false, |old_id| {
+                                pipeline_layout_guard[old_id].push_constant_ranges
+                                    == pipeline_layout.push_constant_ranges
+                            });
 
                         let (start_index, entries) = state.binder.change_pipeline_layout(
                             &*pipeline_layout_guard,
@@ -417,23 +436,49 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         }
 
                         // Clear push constant ranges
-                        let non_overlapping = super::bind::compute_nonoverlapping_ranges(
-                            &pipeline_layout.push_constant_ranges,
-                        );
-                        for range in non_overlapping {
-                            let offset = range.range.start;
-                            let size_bytes = range.range.end - offset;
-                            super::push_constant_clear(
-                                offset,
-                                size_bytes,
-                                |clear_offset, clear_data| unsafe {
-                                    raw.push_compute_constants(
-                                        &pipeline_layout.raw,
-                                        clear_offset,
-                                        clear_data,
-                                    );
-                                },
+                        if !push_constants_compatible {
+                            let non_overlapping = super::bind::compute_nonoverlapping_ranges(
+                                &pipeline_layout.push_constant_ranges,
                             );
+                            for range in non_overlapping {
+                                let offset = range.range.start;
+                                let size_bytes = range.range.end - offset;
+                                super::push_constant_clear(
+                                    offset,
+                                    size_bytes,
+                                    |clear_offset, clear_data| unsafe {
+                                        raw.push_compute_constants(
+                                            &pipeline_layout.raw,
+                                            clear_offset,
+                                            clear_data,
+                                        );
+                                    },
+                                );
+                            }
+                        }
+
+                        // Upload the push constants that were set before the pipeline
+                        for pending in state.pending_push_constants.drain(..) {
+                            pipeline_layout
+                                .validate_push_constant_ranges(
+                                    ShaderStage::COMPUTE,
+                                    pending.offset,
+                                    pending.offset + pending.size_bytes,
+                                )
+                                .map_pass_err(PassErrorScope::SetPushConstant)?;
+
+                            let values_end_offset = (pending.values_offset
+                                + pending.size_bytes / wgt::PUSH_CONSTANT_ALIGNMENT)
+                                as usize;
+                            let data_slice = &base.push_constant_data
+                                [(pending.values_offset as usize)..values_end_offset];
+                            unsafe {
+                                raw.push_compute_constants(
+                                    &pipeline_layout.raw,
+                                    pending.offset,
+                                    data_slice,
+                                )
+                            }
                         }
                     }
                 }
@@ -450,14 +495,17 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     let data_slice =
                         &base.push_constant_data[(values_offset as usize)..values_end_offset];
 
-                    let pipeline_layout_id = state
-                        .binder
-                        .pipeline_layout_id
-                        //TODO: don't error here, lazily update the push constants
-                        .ok_or(ComputePassErrorInner::Dispatch(
-                            DispatchError::MissingPipeline,
-                        ))
-                        .map_pass_err(scope)?;
+                    let pipeline_layout_id = match state.binder.pipeline_layout_id {
+                        Some(id) => id,
+                        None => {
+                            state.pending_push_constants.push(PendingPushConstant {
+                                offset,
+                                size_bytes,
+                                values_offset,
+                            });
+                            continue;
+                        }
+                    };
                     let pipeline_layout = &pipeline_layout_guard[pipeline_layout_id];
 
                     pipeline_layout
@@ -843,3 +891,115 @@ pub mod compute_ffi {
             .push(ComputeCommand::EndPipelineStatisticsQuery);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{compute_ffi, ComputePass, ComputePassDescriptor, ComputePassErrorInner};
+    use crate::{
+        binding_model::PipelineLayoutDescriptor,
+        command::PassErrorScope,
+        device::{
+            tests::{create_command_encoder, create_device, finish},
+            DeviceDescriptor,
+        },
+        pipeline::{
+            ComputePipelineDescriptor, ProgrammableStageDescriptor, ShaderModuleDescriptor,
+            ShaderModuleSource,
+        },
+    };
+    use std::borrow::Cow;
+
+    #[test]
+    fn push_constants_before_pipeline() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor {
+            label: None,
+            features: wgt::Features::PUSH_CONSTANTS,
+            limits: wgt::Limits {
+                max_push_constant_size: 16,
+                ..Default::default()
+            },
+        }) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (shader_module, error) = global.dyn_device_create_shader_module(
+            device,
+            &ShaderModuleDescriptor {
+                label: None,
+                flags: wgt::ShaderFlags::VALIDATION,
+                interface: None,
+            },
+            ShaderModuleSource::Wgsl(Cow::Borrowed(
+                "[[stage(compute), workgroup_size(1)]]
+                fn main() {}",
+            )),
+            (),
+        );
+        assert!(error.is_none());
+        let (pipeline_layout, error) = global.dyn_device_create_pipeline_layout(
+            device,
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: Cow::Borrowed(&[]),
+                push_constant_ranges: Cow::Borrowed(&[wgt::PushConstantRange {
+                    stages: wgt::ShaderStage::COMPUTE,
+                    range: 0..16,
+                }]),
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (pipeline, _, error) = global.dyn_device_create_compute_pipeline(
+            device,
+            &ComputePipelineDescriptor {
+                label: None,
+                layout: Some(pipeline_layout),
+                stage: ProgrammableStageDescriptor {
+                    module: shader_module,
+                    entry_point: Cow::Borrowed("main"),
+                },
+            },
+            (),
+            None,
+        );
+        assert!(error.is_none());
+
+        let data = [0u8; 16];
+        // The values are uploaded once the pipeline provides a layout.
+        let encoder = create_command_encoder(&global, device);
+        let mut pass = ComputePass::new(encoder, &ComputePassDescriptor { label: None });
+        unsafe {
+            compute_ffi::wgpu_compute_pass_set_push_constant(&mut pass, 0, 16, data.as_ptr());
+        }
+        compute_ffi::wgpu_compute_pass_set_pipeline(&mut pass, pipeline);
+        compute_ffi::wgpu_compute_pass_dispatch(&mut pass, 1, 1, 1);
+        global
+            .dyn_command_encoder_run_compute_pass(encoder, &pass)
+            .unwrap();
+        let command_buffer = finish(&global, encoder);
+        global.dyn_queue_submit(device, &[command_buffer]).unwrap();
+
+        // They are still validated against the layout at that point.
+        let encoder = create_command_encoder(&global, device);
+        let mut pass = ComputePass::new(encoder, &ComputePassDescriptor { label: None });
+        unsafe {
+            compute_ffi::wgpu_compute_pass_set_push_constant(&mut pass, 16, 16, data.as_ptr());
+        }
+        compute_ffi::wgpu_compute_pass_set_pipeline(&mut pass, pipeline);
+        let error = global
+            .dyn_command_encoder_run_compute_pass(encoder, &pass)
+            .unwrap_err();
+        assert!(matches!(error.scope, PassErrorScope::SetPushConstant));
+        assert!(matches!(
+            error.inner,
+            ComputePassErrorInner::PushConstants(_)
+        ));
+        global.dyn_command_encoder_drop(encoder);
+
+        global.dyn_compute_pipeline_drop(pipeline);
+        global.dyn_pipeline_layout_drop(pipeline_layout);
+        global.dyn_shader_module_drop(shader_module);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+}
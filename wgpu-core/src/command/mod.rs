@@ -11,8 +11,8 @@ mod query;
 mod render;
 mod transfer;
 
-pub(crate) use self::allocator::CommandAllocator;
 pub use self::allocator::CommandAllocatorError;
+pub(crate) use self::allocator::{CommandAllocator, CommandPool, SecondaryCommandBuffer};
 pub use self::bundle::*;
 pub use self::compute::*;
 pub use self::draw::*;
@@ -22,8 +22,9 @@ pub use self::transfer::*;
 
 use crate::{
     device::{all_buffer_stages, all_image_stages},
-    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, Storage, Token},
+    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, InvalidId, Storage, Token},
     id,
+    index_tracker::IndexTrackerAction,
     memory_init_tracker::MemoryInitTrackerAction,
     resource::{Buffer, Texture},
     span,
@@ -32,40 +33,98 @@ use crate::{
 };
 
 use hal::command::CommandBuffer as _;
+use parking_lot::{Mutex, MutexGuard};
 use smallvec::SmallVec;
 use thiserror::Error;
 
-use std::thread::ThreadId;
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
 
 const PUSH_CONSTANT_CLEAR_ARRAY: &[u32] = &[0_u32; 64];
 
+/// Recording state of a command buffer.
+///
+/// Lives behind a mutex in `CommandBuffer`, so that commands can be recorded
+/// into different encoders from multiple threads while only holding
+/// a read lock on the command buffer storage. Resource usage is tracked
+/// per encoder, and merged into the device trackers at `queue_submit`.
+///
+/// Note: the raw command buffers are allocated from the pool of the thread
+/// that created the encoder. That pool is locked along with the recording
+/// state, see `EncoderGuard`.
 #[derive(Debug)]
-pub struct CommandBuffer<B: hal::Backend> {
+pub struct CommandBufferMutable<B: hal::Backend> {
     pub(crate) raw: Vec<B::CommandBuffer>,
+    /// Secondary command buffers executed by the render passes in `raw`.
+    pub(crate) secondary: Vec<SecondaryCommandBuffer<B>>,
+    /// Render pass fragments recorded ahead of their render pass.
+    pub(crate) fragments: Vec<render::RecordedFragment<B>>,
     is_recording: bool,
-    recorded_thread_id: ThreadId,
     pub(crate) device_id: Stored<id::DeviceId>,
     pub(crate) trackers: TrackerSet,
     pub(crate) used_swap_chains: SmallVec<[Stored<id::SwapChainId>; 1]>,
     pub(crate) buffer_memory_init_actions: Vec<MemoryInitTrackerAction<id::BufferId>>,
+    pub(crate) index_tracker_actions: Vec<IndexTrackerAction>,
     limits: wgt::Limits,
     private_features: PrivateFeatures,
     has_labels: bool,
     #[cfg(feature = "trace")]
     pub(crate) commands: Option<Vec<crate::device::trace::Command>>,
+}
+
+#[derive(Debug)]
+pub struct CommandBuffer<B: hal::Backend> {
+    /// Pool the raw command buffers are allocated from.
+    pub(crate) pool: Arc<Mutex<CommandPool<B>>>,
+    pub(crate) data: Mutex<CommandBufferMutable<B>>,
     #[cfg(debug_assertions)]
     pub(crate) label: String,
 }
 
+/// Recording state of an encoder, along with the locked pool of its raw
+/// command buffers.
+///
+/// Command buffers of a pool can't be recorded concurrently, so holding the
+/// pool serializes the encoders created on the same thread. The recording
+/// state is always locked before the pool.
+pub(crate) struct EncoderGuard<'a, B: hal::Backend> {
+    pub(crate) pool: MutexGuard<'a, CommandPool<B>>,
+    data: MutexGuard<'a, CommandBufferMutable<B>>,
+}
+
+impl<B: hal::Backend> Deref for EncoderGuard<'_, B> {
+    type Target = CommandBufferMutable<B>;
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl<B: hal::Backend> DerefMut for EncoderGuard<'_, B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
+}
+
 impl<B: GfxBackend> CommandBuffer<B> {
-    fn get_encoder_mut(
-        storage: &mut Storage<Self, id::CommandEncoderId>,
+    fn get_encoder(
+        storage: &Storage<Self, id::CommandEncoderId>,
         id: id::CommandEncoderId,
-    ) -> Result<&mut Self, CommandEncoderError> {
-        match storage.get_mut(id) {
-            Ok(cmd_buf) if cmd_buf.is_recording => Ok(cmd_buf),
-            Ok(_) => Err(CommandEncoderError::NotRecording),
-            Err(_) => Err(CommandEncoderError::Invalid),
+    ) -> Result<EncoderGuard<B>, CommandEncoderError> {
+        match storage.get(id) {
+            Ok(cmd_buf) => {
+                let data = cmd_buf.data.lock();
+                if data.is_recording {
+                    Ok(EncoderGuard {
+                        pool: cmd_buf.pool.lock(),
+                        data,
+                    })
+                } else {
+                    Err(CommandEncoderError::NotRecording)
+                }
+            }
+            Err(e) => Err(CommandEncoderError::Invalid(e)),
         }
     }
 
@@ -187,7 +246,7 @@ impl<C: Clone> BasePass<C> {
 #[derive(Clone, Debug, Error)]
 pub enum CommandEncoderError {
     #[error("command encoder is invalid")]
-    Invalid,
+    Invalid(#[source] InvalidId),
     #[error("command encoder must be active")]
     NotRecording,
 }
@@ -204,10 +263,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let mut token = Token::root();
         let (swap_chain_guard, mut token) = hub.swap_chains.read(&mut token);
         //TODO: actually close the last recorded command buffer
-        let (mut cmd_buf_guard, _) = hub.command_buffers.write(&mut token);
+        let (cmd_buf_guard, _) = hub.command_buffers.read(&mut token);
 
-        let error = match CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, encoder_id) {
-            Ok(cmd_buf) => {
+        let error = match CommandBuffer::get_encoder(&*cmd_buf_guard, encoder_id) {
+            Ok(mut cmd_buf_data) => {
+                let cmd_buf = &mut *cmd_buf_data;
                 cmd_buf.is_recording = false;
                 // stop tracking the swapchain image, if used
                 for sc_id in cmd_buf.used_swap_chains.iter() {
@@ -236,8 +296,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = B::hub(self);
         let mut token = Token::root();
 
-        let (mut cmd_buf_guard, _) = hub.command_buffers.write(&mut token);
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, encoder_id)?;
+        let (cmd_buf_guard, _) = hub.command_buffers.read(&mut token);
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let cmd_buf_raw = cmd_buf.raw.last_mut().unwrap();
 
         unsafe {
@@ -256,8 +317,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = B::hub(self);
         let mut token = Token::root();
 
-        let (mut cmd_buf_guard, _) = hub.command_buffers.write(&mut token);
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, encoder_id)?;
+        let (cmd_buf_guard, _) = hub.command_buffers.read(&mut token);
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let cmd_buf_raw = cmd_buf.raw.last_mut().unwrap();
 
         unsafe {
@@ -275,8 +337,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = B::hub(self);
         let mut token = Token::root();
 
-        let (mut cmd_buf_guard, _) = hub.command_buffers.write(&mut token);
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, encoder_id)?;
+        let (cmd_buf_guard, _) = hub.command_buffers.read(&mut token);
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let cmd_buf_raw = cmd_buf.raw.last_mut().unwrap();
 
         unsafe {
@@ -378,3 +441,245 @@ pub enum PassErrorScope {
     #[error("In a pop_debug_group command")]
     PopDebugGroup,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ColorAttachmentDescriptor, ComputePass, ComputePassDescriptor, LoadOp, PassChannel,
+        RenderBundleEncoder, RenderBundleEncoderDescriptor, RenderPass, RenderPassDescriptor,
+        StoreOp,
+    };
+    use crate::{
+        device::{tests::create_device, DeviceDescriptor},
+        hub::{Global, IdentityManagerFactory},
+        id,
+        resource::{BufferDescriptor, TextureDescriptor, TextureViewDescriptor},
+    };
+    use std::{borrow::Cow, sync::Arc, thread};
+
+    const THREAD_COUNT: usize = 16;
+    const PASS_COUNT: usize = 32;
+    const TARGET_FORMAT: wgt::TextureFormat = wgt::TextureFormat::Rgba8Unorm;
+
+    type TestGlobal = Arc<Global<IdentityManagerFactory>>;
+
+    fn create_target(
+        global: &TestGlobal,
+        device: id::DeviceId,
+    ) -> (id::TextureId, id::TextureViewId) {
+        let (texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 64,
+                    height: 64,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: TARGET_FORMAT,
+                usage: wgt::TextureUsage::RENDER_ATTACHMENT,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (view, error) =
+            global.dyn_texture_create_view(texture, &TextureViewDescriptor::default(), ());
+        assert!(error.is_none());
+        (texture, view)
+    }
+
+    fn clear_attachment(view: id::TextureViewId) -> ColorAttachmentDescriptor {
+        ColorAttachmentDescriptor {
+            attachment: view,
+            resolve_target: None,
+            channel: PassChannel {
+                load_op: LoadOp::Clear,
+                store_op: StoreOp::Store,
+                clear_value: wgt::Color::BLACK,
+                read_only: false,
+            },
+        }
+    }
+
+    #[test]
+    fn encode_from_many_threads() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some((global, adapter, device)) => (Arc::new(global), adapter, device),
+            None => return,
+        };
+
+        let buffer_desc = BufferDescriptor {
+            label: None,
+            size: 256,
+            usage: wgt::BufferUsage::COPY_SRC | wgt::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        };
+        let (source, error) = global.dyn_device_create_buffer(device, &buffer_desc, ());
+        assert!(error.is_none());
+        let (destination, error) = global.dyn_device_create_buffer(device, &buffer_desc, ());
+        assert!(error.is_none());
+        let (texture, view) = create_target(&global, device);
+
+        // Each encoder is created on its own thread, getting the command pool
+        // of that thread, and all of them are recorded concurrently.
+        let threads = (0..THREAD_COUNT)
+            .map(|_| {
+                let global = Arc::clone(&global);
+                thread::spawn(move || {
+                    let (encoder, error) = global.dyn_device_create_command_encoder(
+                        device,
+                        &wgt::CommandEncoderDescriptor { label: None },
+                        (),
+                    );
+                    assert!(error.is_none());
+                    for _ in 0..PASS_COUNT {
+                        let compute_pass = ComputePass::new(
+                            encoder,
+                            &ComputePassDescriptor {
+                                label: Some(Cow::Borrowed("compute")),
+                            },
+                        );
+                        global
+                            .dyn_command_encoder_run_compute_pass(encoder, &compute_pass)
+                            .unwrap();
+                        let render_pass = RenderPass::new(
+                            encoder,
+                            &RenderPassDescriptor {
+                                label: Some(Cow::Borrowed("render")),
+                                color_attachments: Cow::Owned(vec![clear_attachment(view)]),
+                                depth_stencil_attachment: None,
+                            },
+                        );
+                        global
+                            .dyn_command_encoder_run_render_pass(encoder, &render_pass)
+                            .unwrap();
+                        global
+                            .dyn_command_encoder_copy_buffer_to_buffer(
+                                encoder,
+                                source,
+                                0,
+                                destination,
+                                0,
+                                256,
+                            )
+                            .unwrap();
+                    }
+                    encoder
+                })
+            })
+            .collect::<Vec<_>>();
+        let command_buffers = threads
+            .into_iter()
+            .map(|thread| {
+                let encoder = thread.join().unwrap();
+                let (command_buffer, error) = global
+                    .dyn_command_encoder_finish(encoder, &wgt::CommandBufferDescriptor::default());
+                assert!(error.is_none());
+                command_buffer
+            })
+            .collect::<Vec<_>>();
+
+        global.dyn_queue_submit(device, &command_buffers).unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+
+        global.dyn_texture_view_drop(view, true).unwrap();
+        global.dyn_texture_drop(texture, true);
+        global.dyn_buffer_drop(source, true);
+        global.dyn_buffer_drop(destination, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn render_pass_fragments() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some((global, adapter, device)) => (Arc::new(global), adapter, device),
+            None => return,
+        };
+        let (texture, view) = create_target(&global, device);
+        let (encoder, error) = global.dyn_device_create_command_encoder(
+            device,
+            &wgt::CommandEncoderDescriptor { label: None },
+            (),
+        );
+        assert!(error.is_none());
+
+        // Each fragment is recorded on its own thread.
+        let threads = (0..THREAD_COUNT)
+            .map(|_| {
+                let global = Arc::clone(&global);
+                thread::spawn(move || {
+                    let bundle_encoder = RenderBundleEncoder::new(
+                        &RenderBundleEncoderDescriptor {
+                            label: None,
+                            color_formats: Cow::Borrowed(&[TARGET_FORMAT]),
+                            depth_stencil_format: None,
+                            depth_read_only: false,
+                            stencil_read_only: false,
+                            sample_count: 1,
+                        },
+                        device,
+                        None,
+                    )
+                    .unwrap();
+                    let (bundle, error) = global.dyn_render_bundle_encoder_finish(
+                        bundle_encoder,
+                        &wgt::RenderBundleDescriptor {
+                            label: Some(Cow::Borrowed("fragment")),
+                        },
+                        (),
+                    );
+                    assert!(error.is_none());
+                    global
+                        .dyn_command_encoder_record_render_pass_fragment(
+                            encoder,
+                            &RenderPassDescriptor {
+                                label: None,
+                                color_attachments: Cow::Owned(vec![clear_attachment(view)]),
+                                depth_stencil_attachment: None,
+                            },
+                            bundle,
+                        )
+                        .unwrap();
+                    bundle
+                })
+            })
+            .collect::<Vec<_>>();
+        let fragments = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect::<Vec<_>>();
+        let pass_desc = RenderPassDescriptor {
+            label: None,
+            color_attachments: Cow::Owned(vec![clear_attachment(view)]),
+            depth_stencil_attachment: None,
+        };
+
+        // A fragment listed twice fails the run without consuming anything.
+        let mut repeated = fragments.clone();
+        repeated.push(fragments[0]);
+        assert!(global
+            .dyn_command_encoder_run_render_pass_fragments(encoder, &pass_desc, &repeated)
+            .is_err());
+
+        global
+            .dyn_command_encoder_run_render_pass_fragments(encoder, &pass_desc, &fragments)
+            .unwrap();
+        let (command_buffer, error) =
+            global.dyn_command_encoder_finish(encoder, &wgt::CommandBufferDescriptor::default());
+        assert!(error.is_none());
+        global.dyn_queue_submit(device, &[command_buffer]).unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+
+        for fragment in fragments {
+            global.dyn_render_bundle_drop(fragment);
+        }
+        global.dyn_texture_view_drop(view, true).unwrap();
+        global.dyn_texture_drop(texture, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+}
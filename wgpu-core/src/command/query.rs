@@ -9,8 +9,9 @@ use crate::device::trace::Command as TraceCommand;
 use crate::{
     command::{CommandBuffer, CommandEncoderError},
     device::all_buffer_stages,
-    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, Storage, Token},
+    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, InvalidId, Storage, Token},
     id::{self, Id, TypedId},
+    index_tracker::IndexTrackerAction,
     resource::{BufferUse, QuerySet},
     track::UseExtendError,
     Epoch, FastHashMap, Index,
@@ -56,6 +57,7 @@ impl<B: hal::Backend> QueryResetMap<B> {
         for (query_set_id, (state, epoch)) in self.map.into_iter() {
             let id = Id::zip(query_set_id, epoch, backend);
             let query_set = query_set_storage.get(id).map_err(|_| id)?;
+            let raw = query_set.raw.as_ref().ok_or(id)?;
 
             debug_assert_eq!(state.len(), query_set.desc.count as usize);
 
@@ -70,7 +72,7 @@ impl<B: hal::Backend> QueryResetMap<B> {
                     // We've hit the end of a run, dispatch a reset
                     (Some(start), false) => {
                         run_start = None;
-                        unsafe { cmd_buf_raw.reset_query_pool(&query_set.raw, start..idx as u32) };
+                        unsafe { cmd_buf_raw.reset_query_pool(raw, start..idx as u32) };
                     }
                     // We're starting a run
                     (None, true) => {
@@ -109,15 +111,19 @@ pub enum QueryError {
     Use(#[from] QueryUseError),
     #[error("Error encountered while trying to resolve a query")]
     Resolve(#[from] ResolveError),
-    #[error("Buffer {0:?} is invalid or destroyed")]
-    InvalidBuffer(id::BufferId),
-    #[error("QuerySet {0:?} is invalid or destroyed")]
+    #[error("Buffer {0:?} is invalid")]
+    InvalidBuffer(id::BufferId, #[source] InvalidId),
+    #[error("QuerySet {0:?} is invalid")]
     InvalidQuerySet(id::QuerySetId),
+    #[error("QuerySet {0:?} is destroyed")]
+    DestroyedQuerySet(id::QuerySetId),
 }
 
 /// Error encountered while trying to use queries
 #[derive(Clone, Debug, Error)]
 pub enum QueryUseError {
+    #[error("QuerySet {0:?} is destroyed")]
+    Destroyed(id::QuerySetId),
     #[error("Query {query_index} is out of bounds for a query set of size {query_set_size}")]
     OutOfBounds {
         query_index: u32,
@@ -193,7 +199,10 @@ impl<B: GfxBackend> QuerySet<B> {
         }
 
         let hal_query = hal::query::Query::<B> {
-            pool: &self.raw,
+            pool: self
+                .raw
+                .as_ref()
+                .ok_or(QueryUseError::Destroyed(query_set_id))?,
             id: query_index,
         };
 
@@ -218,7 +227,7 @@ impl<B: GfxBackend> QuerySet<B> {
         unsafe {
             // If we don't have a reset state tracker which can defer resets, we must reset now.
             if needs_reset {
-                cmd_buf_raw.reset_query_pool(&self.raw, query_index..(query_index + 1));
+                cmd_buf_raw.reset_query_pool(hal_query.pool, query_index..(query_index + 1));
             }
             cmd_buf_raw.write_timestamp(hal::pso::PipelineStage::BOTTOM_OF_PIPE, hal_query);
         }
@@ -252,7 +261,7 @@ impl<B: GfxBackend> QuerySet<B> {
         unsafe {
             // If we don't have a reset state tracker which can defer resets, we must reset now.
             if needs_reset {
-                cmd_buf_raw.reset_query_pool(&self.raw, query_index..(query_index + 1));
+                cmd_buf_raw.reset_query_pool(hal_query.pool, query_index..(query_index + 1));
             }
             cmd_buf_raw.begin_query(hal_query, hal::query::ControlFlags::empty());
         }
@@ -271,7 +280,7 @@ pub(super) fn end_pipeline_statistics_query<B: GfxBackend>(
         let query_set = storage.get(query_set_id).unwrap();
 
         let hal_query = hal::query::Query::<B> {
-            pool: &query_set.raw,
+            pool: query_set.raw.as_ref().unwrap(),
             id: query_index,
         };
 
@@ -293,10 +302,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = B::hub(self);
         let mut token = Token::root();
 
-        let (mut cmd_buf_guard, mut token) = hub.command_buffers.write(&mut token);
+        let (cmd_buf_guard, mut token) = hub.command_buffers.read(&mut token);
         let (query_set_guard, _) = hub.query_sets.read(&mut token);
 
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut cmd_buf_guard, command_encoder_id)?;
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, command_encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let cmd_buf_raw = cmd_buf.raw.last_mut().unwrap();
 
         #[cfg(feature = "trace")]
@@ -333,11 +343,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = B::hub(self);
         let mut token = Token::root();
 
-        let (mut cmd_buf_guard, mut token) = hub.command_buffers.write(&mut token);
+        let (cmd_buf_guard, mut token) = hub.command_buffers.read(&mut token);
         let (query_set_guard, mut token) = hub.query_sets.read(&mut token);
         let (buffer_guard, _) = hub.buffers.read(&mut token);
 
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut cmd_buf_guard, command_encoder_id)?;
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, command_encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let cmd_buf_raw = cmd_buf.raw.last_mut().unwrap();
 
         #[cfg(feature = "trace")]
@@ -359,12 +370,16 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 UseExtendError::InvalidResource => QueryError::InvalidQuerySet(query_set_id),
                 _ => unreachable!(),
             })?;
+        let query_set_raw = query_set
+            .raw
+            .as_ref()
+            .ok_or(QueryError::DestroyedQuerySet(query_set_id))?;
 
         let (dst_buffer, dst_pending) = cmd_buf
             .trackers
             .buffers
             .use_replace(&*buffer_guard, destination, (), BufferUse::COPY_DST)
-            .map_err(QueryError::InvalidBuffer)?;
+            .map_err(|e| QueryError::InvalidBuffer(destination, e))?;
         let dst_barrier = dst_pending.map(|pending| pending.into_hal(dst_buffer));
 
         if !dst_buffer.usage.contains(wgt::BufferUsage::COPY_DST) {
@@ -399,6 +414,15 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .into());
         }
 
+        if dst_buffer.index_tracker.is_some() {
+            cmd_buf
+                .index_tracker_actions
+                .push(IndexTrackerAction::Invalidate {
+                    buffer: destination,
+                    range: buffer_start_offset..buffer_end_offset,
+                });
+        }
+
         unsafe {
             cmd_buf_raw.pipeline_barrier(
                 all_buffer_stages()..hal::pso::PipelineStage::TRANSFER,
@@ -406,7 +430,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 dst_barrier,
             );
             cmd_buf_raw.copy_query_pool_results(
-                &query_set.raw,
+                query_set_raw,
                 start_query..end_query,
                 &dst_buffer.raw.as_ref().unwrap().0,
                 destination_offset,
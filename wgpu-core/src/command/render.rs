@@ -6,8 +6,9 @@ use crate::{
     binding_model::BindError,
     command::{
         bind::Binder, end_pipeline_statistics_query, BasePass, BasePassRef, CommandBuffer,
-        CommandEncoderError, DrawError, ExecutionError, MapPassErr, PassErrorScope, QueryResetMap,
-        QueryUseError, RenderCommand, RenderCommandError, StateChange,
+        CommandBufferMutable, CommandEncoderError, DrawError, ExecutionError, MapPassErr,
+        PassErrorScope, QueryResetMap, QueryUseError, RenderBundle, RenderCommand,
+        RenderCommandError, SecondaryCommandBuffer, StateChange,
     },
     conv,
     device::{
@@ -16,6 +17,7 @@ use crate::{
     },
     hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, Storage, Token},
     id,
+    index_tracker::{IndexDrawValidation, IndexTrackerAction},
     memory_init_tracker::{MemoryInitKind, MemoryInitTrackerAction},
     pipeline::PipelineFlags,
     resource::{BufferUse, Texture, TextureUse, TextureView, TextureViewInner},
@@ -252,8 +254,38 @@ impl IndexState {
         }
     }
 
+    fn draw_validation(
+        &self,
+        first_index: u32,
+        index_count: u32,
+        base_vertex: i32,
+        vertex: &VertexState,
+    ) -> IndexDrawValidation {
+        let (buffer, ref range) = *self
+            .bound_buffer_view
+            .as_ref()
+            .expect("IndexState::draw_validation must be called after a index buffer is set");
+        let format = self.format.unwrap();
+        let bytes_per_index = match format {
+            IndexFormat::Uint16 => 2,
+            IndexFormat::Uint32 => 4,
+        };
+        IndexDrawValidation {
+            buffer: buffer.0,
+            offset: range.start + first_index as BufferAddress * bytes_per_index,
+            count: index_count,
+            format,
+            primitive_restart: self.pipeline_format.is_some(),
+            base_vertex,
+            vertex_limit: vertex.vertex_limit,
+            vertex_limit_slot: vertex.vertex_limit_slot,
+        }
+    }
+
     fn reset(&mut self) {
         self.bound_buffer_view = None;
+        self.format = None;
+        self.pipeline_format = None;
         self.limit = 0;
     }
 }
@@ -320,6 +352,7 @@ impl VertexState {
         self.inputs.clear();
         self.vertex_limit = 0;
         self.instance_limit = 0;
+        self.buffers_required = 0;
     }
 }
 
@@ -378,9 +411,18 @@ impl State {
     }
 
     /// Reset the `RenderBundle`-related states.
+    ///
+    /// After a bundle is executed, the pipeline, bind groups, vertex and
+    /// index buffers are all unset, and have to be provided again before
+    /// the next draw. Blend color, stencil reference, viewport, and scissor
+    /// are preserved.
     fn reset_bundle(&mut self) {
         self.binder.reset();
         self.pipeline.reset();
+        self.pipeline_flags = PipelineFlags::empty();
+        if self.blend_color == OptionalState::Required {
+            self.blend_color = OptionalState::Unused;
+        }
         self.index.reset();
         self.vertex.reset();
     }
@@ -439,6 +481,12 @@ pub enum RenderPassErrorInner {
     ResourceUsageConflict(#[from] UsageConflict),
     #[error("render bundle is incompatible, {0}")]
     IncompatibleRenderBundle(#[from] RenderPassCompatibilityError),
+    #[error("render bundle may write to the depth-stencil attachment of a read-only render pass")]
+    IncompatibleRenderBundleReadOnly,
+    #[error("render bundle {0:?} is not recorded as a fragment of this encoder")]
+    MissingFragment(id::RenderBundleId),
+    #[error("render bundle {0:?} is recorded as a fragment of an incompatible render pass")]
+    IncompatibleFragment(id::RenderBundleId),
     #[error(transparent)]
     RenderCommand(#[from] RenderCommandError),
     #[error(transparent)]
@@ -515,9 +563,10 @@ impl<'a, B: GfxBackend> RenderPassInfo<'a, B> {
         raw: &mut B::CommandBuffer,
         color_attachments: &[ColorAttachmentDescriptor],
         depth_stencil_attachment: Option<&DepthStencilAttachmentDescriptor>,
-        cmd_buf: &CommandBuffer<B>,
+        cmd_buf: &CommandBufferMutable<B>,
         device: &Device<B>,
         view_guard: &'a Storage<TextureView<B>, id::TextureViewId>,
+        contents: hal::command::SubpassContents,
     ) -> Result<Self, RenderPassErrorInner> {
         let sample_count_limit = device.hal_limits.framebuffer_color_sample_counts;
 
@@ -928,21 +977,21 @@ impl<'a, B: GfxBackend> RenderPassInfo<'a, B> {
             ));
 
         unsafe {
-            raw.begin_render_pass(
-                render_pass,
-                framebuffer,
-                rect,
-                attachments,
-                hal::command::SubpassContents::Inline,
-            );
-            raw.set_scissors(0, iter::once(rect));
-            raw.set_viewports(
-                0,
-                iter::once(hal::pso::Viewport {
-                    rect,
-                    depth: 0.0..1.0,
-                }),
-            );
+            raw.begin_render_pass(render_pass, framebuffer, rect, attachments, contents);
+            match contents {
+                hal::command::SubpassContents::Inline => {
+                    raw.set_scissors(0, iter::once(rect));
+                    raw.set_viewports(
+                        0,
+                        iter::once(hal::pso::Viewport {
+                            rect,
+                            depth: 0.0..1.0,
+                        }),
+                    );
+                }
+                // secondary command buffers set their own state
+                hal::command::SubpassContents::SecondaryBuffers => {}
+            }
         }
 
         Ok(Self {
@@ -994,6 +1043,201 @@ impl<'a, B: GfxBackend> RenderPassInfo<'a, B> {
     }
 }
 
+/// Render pass that fragments are recorded for, up to compatibility.
+///
+/// Render pass compatibility doesn't depend on load and store operations,
+/// nor on image layouts, so these are fixed here. This allows recording
+/// the fragments before the actual render pass starts.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FragmentTarget {
+    key: RenderPassKey,
+    resolves: ArrayVec<[bool; MAX_COLOR_TARGETS]>,
+    extent: wgt::Extent3d,
+}
+
+impl FragmentTarget {
+    fn new<B: GfxBackend>(
+        desc: &RenderPassDescriptor,
+        view_guard: &Storage<TextureView<B>, id::TextureViewId>,
+        device: &Device<B>,
+    ) -> Result<Self, RenderPassErrorInner> {
+        let mut extent = None;
+        let mut compatible_attachment =
+            |view_id: id::TextureViewId| -> Result<_, RenderPassErrorInner> {
+                let view: &TextureView<B> = view_guard
+                    .get(view_id)
+                    .map_err(|_| RenderPassErrorInner::InvalidAttachment(view_id))?;
+                extent.get_or_insert(view.extent);
+                let attachment = hal::pass::Attachment {
+                    format: Some(conv::map_texture_format(
+                        view.format,
+                        device.private_features,
+                    )),
+                    samples: view.samples,
+                    ops: hal::pass::AttachmentOps::DONT_CARE,
+                    stencil_ops: hal::pass::AttachmentOps::DONT_CARE,
+                    layouts: hal::image::Layout::General..hal::image::Layout::General,
+                };
+                Ok((attachment, hal::image::Layout::General))
+            };
+
+        let key = RenderPassKey {
+            colors: desc
+                .color_attachments
+                .iter()
+                .map(|at| compatible_attachment(at.attachment))
+                .collect::<Result<_, _>>()?,
+            resolves: desc
+                .color_attachments
+                .iter()
+                .filter_map(|at| at.resolve_target)
+                .map(&mut compatible_attachment)
+                .collect::<Result<_, _>>()?,
+            depth_stencil: desc
+                .depth_stencil_attachment
+                .map(|at| compatible_attachment(at.attachment))
+                .transpose()?,
+        };
+        Ok(Self {
+            key,
+            resolves: desc
+                .color_attachments
+                .iter()
+                .map(|at| at.resolve_target.is_some())
+                .collect(),
+            extent: extent.ok_or(RenderPassErrorInner::MissingAttachments)?,
+        })
+    }
+
+    /// Begins recording `raw` as a secondary command buffer within
+    /// a render pass compatible with this target.
+    fn begin<B: GfxBackend>(&self, raw: &mut B::CommandBuffer, device: &Device<B>) {
+        let mut lock = device.render_passes.lock();
+        let render_pass = match lock.render_passes.entry(self.key.clone()) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(entry) => {
+                let colors = (0..self.key.colors.len())
+                    .map(|i| (i, hal::image::Layout::ColorAttachmentOptimal))
+                    .collect::<ArrayVec<[_; MAX_COLOR_TARGETS]>>();
+                let mut attachment_index = colors.len();
+                let resolves = if self.key.resolves.is_empty() {
+                    ArrayVec::<[_; MAX_COLOR_TARGETS]>::new()
+                } else {
+                    self.resolves
+                        .iter()
+                        .map(|&has_resolve| {
+                            let index = if has_resolve {
+                                attachment_index += 1;
+                                attachment_index - 1
+                            } else {
+                                hal::pass::ATTACHMENT_UNUSED
+                            };
+                            (index, hal::image::Layout::ColorAttachmentOptimal)
+                        })
+                        .collect()
+                };
+                let depth_stencil = self.key.depth_stencil.as_ref().map(|_| {
+                    (
+                        attachment_index,
+                        hal::image::Layout::DepthStencilAttachmentOptimal,
+                    )
+                });
+                let subpass = hal::pass::SubpassDesc {
+                    colors: &colors,
+                    resolves: &resolves,
+                    depth_stencil: depth_stencil.as_ref(),
+                    inputs: &[],
+                    preserves: &[],
+                };
+                let all = entry.key().all().map(|&(ref at, _)| at.clone());
+                let pass = unsafe {
+                    device
+                        .raw
+                        .create_render_pass(all, iter::once(subpass), iter::empty())
+                }
+                .unwrap();
+                entry.insert(pass)
+            }
+        };
+
+        let rect = hal::pso::Rect {
+            x: 0,
+            y: 0,
+            w: self.extent.width as _,
+            h: self.extent.height as _,
+        };
+        unsafe {
+            raw.begin(
+                hal::command::CommandBufferFlags::ONE_TIME_SUBMIT
+                    | hal::command::CommandBufferFlags::RENDER_PASS_CONTINUE,
+                hal::command::CommandBufferInheritanceInfo {
+                    subpass: Some(hal::pass::Subpass {
+                        index: 0,
+                        main_pass: &*render_pass,
+                    }),
+                    ..Default::default()
+                },
+            );
+        }
+        drop(lock);
+        unsafe {
+            raw.set_scissors(0, iter::once(rect));
+            raw.set_viewports(
+                0,
+                iter::once(hal::pso::Viewport {
+                    rect,
+                    depth: 0.0..1.0,
+                }),
+            );
+        }
+    }
+}
+
+/// Render bundle recorded into a secondary command buffer, waiting for
+/// `command_encoder_run_render_pass_fragments` to execute it.
+#[derive(Debug)]
+pub(crate) struct RecordedFragment<B: hal::Backend> {
+    pub(crate) bundle_id: id::RenderBundleId,
+    pub(crate) target: FragmentTarget,
+    pub(crate) secondary: SecondaryCommandBuffer<B>,
+}
+
+/// Checks that the fragment recorded for `bundle_id` can be executed in the
+/// pass of `info`, and returns its index among the `recorded` fragments that
+/// aren't `taken` by the previous ones.
+fn check_fragment<B: GfxBackend>(
+    info: &mut RenderPassInfo<B>,
+    bundle_guard: &Storage<RenderBundle, id::RenderBundleId>,
+    recorded: &[RecordedFragment<B>],
+    taken: &[usize],
+    target: &FragmentTarget,
+    bundle_id: id::RenderBundleId,
+) -> Result<usize, RenderPassErrorInner> {
+    let bundle = info
+        .trackers
+        .bundles
+        .use_extend(bundle_guard, bundle_id, (), ())
+        .map_err(|_| RenderCommandError::InvalidRenderBundle(bundle_id))?;
+    info.context
+        .check_compatible(&bundle.context)
+        .map_err(RenderPassErrorInner::IncompatibleRenderBundle)?;
+    if info.is_ds_read_only && !bundle.is_ds_read_only() {
+        return Err(RenderPassErrorInner::IncompatibleRenderBundleReadOnly);
+    }
+
+    let index = recorded
+        .iter()
+        .enumerate()
+        .position(|(i, fragment)| fragment.bundle_id == bundle_id && !taken.contains(&i))
+        .ok_or(RenderPassErrorInner::MissingFragment(bundle_id))?;
+    if recorded[index].target != *target {
+        return Err(RenderPassErrorInner::IncompatibleFragment(bundle_id));
+    }
+
+    info.trackers.merge_extend(&bundle.used)?;
+    Ok(index)
+}
+
 // Common routines between render/compute
 
 impl<G: GlobalIdentityHandlerFactory> Global<G> {
@@ -1028,12 +1272,13 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let (cmd_buf_raw, trackers, used_swapchain, query_reset_state) = {
             // read-only lock guard
-            let (mut cmb_guard, mut token) = hub.command_buffers.write(&mut token);
+            let (cmb_guard, mut token) = hub.command_buffers.read(&mut token);
 
-            let cmd_buf =
-                CommandBuffer::get_encoder_mut(&mut *cmb_guard, encoder_id).map_pass_err(scope)?;
+            let mut cmd_buf_data =
+                CommandBuffer::get_encoder(&*cmb_guard, encoder_id).map_pass_err(scope)?;
+            let mut raw = cmd_buf_data.pool.allocate(hal::command::Level::Primary);
+            let cmd_buf = &mut *cmd_buf_data;
             let device = &device_guard[cmd_buf.device_id.value];
-            let mut raw = device.cmd_allocator.extend(cmd_buf);
             unsafe {
                 if let Some(ref label) = base.label {
                     // cmd_buf.has_labels = true; this is done later
@@ -1063,6 +1308,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 cmd_buf,
                 device,
                 &*view_guard,
+                hal::command::SubpassContents::Inline,
             )
             .map_pass_err(scope)?;
 
@@ -1181,7 +1427,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
                         state.pipeline_flags = pipeline.flags;
 
-                        if pipeline.flags.contains(PipelineFlags::WRITES_DEPTH_STENCIL)
+                        if pipeline
+                            .flags
+                            .intersects(PipelineFlags::WRITES_DEPTH | PipelineFlags::WRITES_STENCIL)
                             && info.is_ds_read_only
                         {
                             return Err(RenderCommandError::IncompatibleReadOnlyDepthStencil)
@@ -1208,6 +1456,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         // Rebind resource
                         if state.binder.pipeline_layout_id != Some(pipeline.layout_id.value) {
                             let pipeline_layout = &pipeline_layout_guard[pipeline.layout_id.value];
+                            // Push constants survive the layout change if the ranges are the same.
+                            let push_constants_compatible =
+                                state.binder.pipeline_layout_id.map_or(false, |old_id| {
+                                    pipeline_layout_guard[old_id].push_constant_ranges
+                                        == pipeline_layout.push_constant_ranges
+                                });
 
                             let (start_index, entries) = state.binder.change_pipeline_layout(
                                 &*pipeline_layout_guard,
@@ -1232,24 +1486,26 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             }
 
                             // Clear push constant ranges
-                            let non_overlapping = super::bind::compute_nonoverlapping_ranges(
-                                &pipeline_layout.push_constant_ranges,
-                            );
-                            for range in non_overlapping {
-                                let offset = range.range.start;
-                                let size_bytes = range.range.end - offset;
-                                super::push_constant_clear(
-                                    offset,
-                                    size_bytes,
-                                    |clear_offset, clear_data| unsafe {
-                                        raw.push_graphics_constants(
-                                            &pipeline_layout.raw,
-                                            conv::map_shader_stage_flags(range.stages),
-                                            clear_offset,
-                                            clear_data,
-                                        );
-                                    },
+                            if !push_constants_compatible {
+                                let non_overlapping = super::bind::compute_nonoverlapping_ranges(
+                                    &pipeline_layout.push_constant_ranges,
                                 );
+                                for range in non_overlapping {
+                                    let offset = range.range.start;
+                                    let size_bytes = range.range.end - offset;
+                                    super::push_constant_clear(
+                                        offset,
+                                        size_bytes,
+                                        |clear_offset, clear_data| unsafe {
+                                            raw.push_graphics_constants(
+                                                &pipeline_layout.raw,
+                                                conv::map_shader_stage_flags(range.stages),
+                                                clear_offset,
+                                                clear_data,
+                                            );
+                                        },
+                                    );
+                                }
                             }
                         }
 
@@ -1542,7 +1798,6 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         };
                         state.is_ready(indexed).map_pass_err(scope)?;
 
-                        //TODO: validate that base_vertex + max_index() is within the provided range
                         let last_index = first_index + index_count;
                         let index_limit = state.index.limit;
                         if last_index > index_limit {
@@ -1562,6 +1817,20 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             })
                             .map_pass_err(scope);
                         }
+                        if device
+                            .features
+                            .contains(wgt::Features::ROBUST_INDEX_VALIDATION)
+                            && state.vertex.vertex_limit != u32::MAX
+                        {
+                            cmd_buf
+                                .index_tracker_actions
+                                .push(IndexTrackerAction::Validate(state.index.draw_validation(
+                                    first_index,
+                                    index_count,
+                                    base_vertex,
+                                    &state.vertex,
+                                )));
+                        }
 
                         unsafe {
                             raw.draw_indexed(
@@ -1882,6 +2151,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             .map_err(RenderPassErrorInner::IncompatibleRenderBundle)
                             .map_pass_err(scope)?;
 
+                        if info.is_ds_read_only && !bundle.is_ds_read_only() {
+                            return Err(RenderPassErrorInner::IncompatibleRenderBundleReadOnly)
+                                .map_pass_err(scope);
+                        }
+
                         cmd_buf.buffer_memory_init_actions.extend(
                             bundle
                                 .buffer_memory_init_actions
@@ -1898,6 +2172,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                                     Err(_) => None,
                                 }),
                         );
+                        cmd_buf
+                            .index_tracker_actions
+                            .extend_from_slice(&bundle.index_tracker_actions);
 
                         unsafe {
                             bundle.execute(
@@ -1932,12 +2209,13 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             (raw, trackers, used_swapchain, query_reset_state)
         };
 
-        let (mut cmb_guard, mut token) = hub.command_buffers.write(&mut token);
+        let (cmb_guard, mut token) = hub.command_buffers.read(&mut token);
         let (query_set_guard, mut token) = hub.query_sets.read(&mut token);
         let (buffer_guard, mut token) = hub.buffers.read(&mut token);
         let (texture_guard, _) = hub.textures.read(&mut token);
-        let cmd_buf =
-            CommandBuffer::get_encoder_mut(&mut *cmb_guard, encoder_id).map_pass_err(scope)?;
+        let mut cmd_buf_data =
+            CommandBuffer::get_encoder(&*cmb_guard, encoder_id).map_pass_err(scope)?;
+        let cmd_buf = &mut *cmd_buf_data;
         cmd_buf.has_labels |= base.label.is_some();
         cmd_buf.used_swap_chains.extend(used_swapchain);
 
@@ -1975,6 +2253,246 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         Ok(())
     }
+
+    /// Records a render bundle into a secondary command buffer, to be executed
+    /// as a fragment of a render pass described by `desc`.
+    ///
+    /// The fragments of a pass can be recorded concurrently on different
+    /// threads, each of them using the command pool of its thread. The encoder
+    /// is only locked to store the result. They are executed, in the order
+    /// given there, by `command_encoder_run_render_pass_fragments`.
+    pub fn command_encoder_record_render_pass_fragment<B: GfxBackend>(
+        &self,
+        encoder_id: id::CommandEncoderId,
+        desc: &RenderPassDescriptor,
+        bundle_id: id::RenderBundleId,
+    ) -> Result<(), RenderPassError> {
+        span!(_guard, INFO, "CommandEncoder::record_render_pass_fragment");
+        let scope = PassErrorScope::ExecuteBundle;
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let (cmb_guard, mut token) = hub.command_buffers.read(&mut token);
+        let device_id = CommandBuffer::get_encoder(&*cmb_guard, encoder_id)
+            .map_pass_err(scope)?
+            .device_id
+            .value;
+        let device = &device_guard[device_id];
+
+        let (bundle_guard, mut token) = hub.render_bundles.read(&mut token);
+        let (pipeline_layout_guard, mut token) = hub.pipeline_layouts.read(&mut token);
+        let (bind_group_guard, mut token) = hub.bind_groups.read(&mut token);
+        let (pipeline_guard, mut token) = hub.render_pipelines.read(&mut token);
+        let (_, mut token) = hub.query_sets.read(&mut token);
+        let (buffer_guard, mut token) = hub.buffers.read(&mut token);
+        let (_, mut token) = hub.textures.read(&mut token);
+        let (view_guard, _) = hub.texture_views.read(&mut token);
+
+        let bundle = bundle_guard
+            .get(bundle_id)
+            .map_err(|_| RenderCommandError::InvalidRenderBundle(bundle_id))
+            .map_pass_err(scope)?;
+        let target = FragmentTarget::new(desc, &*view_guard, device).map_pass_err(scope)?;
+
+        let mut secondary = device
+            .cmd_allocator
+            .allocate_secondary(&device.raw)
+            .map_err(|_| RenderPassErrorInner::OutOfMemory)
+            .map_pass_err(scope)?;
+        let result = {
+            let _pool = secondary.pool.lock();
+            target.begin(&mut secondary.raw, device);
+            let result = unsafe {
+                bundle.execute(
+                    &mut secondary.raw,
+                    &*pipeline_layout_guard,
+                    &*bind_group_guard,
+                    &*pipeline_guard,
+                    &*buffer_guard,
+                )
+            };
+            unsafe {
+                secondary.raw.finish();
+            }
+            result
+        };
+        if let Err(e) = result {
+            device.cmd_allocator.discard_secondary(secondary);
+            return Err(match e {
+                ExecutionError::DestroyedBuffer(id) => RenderCommandError::DestroyedBuffer(id),
+            })
+            .map_pass_err(scope);
+        }
+
+        match CommandBuffer::get_encoder(&*cmb_guard, encoder_id) {
+            Ok(mut cmd_buf) => {
+                cmd_buf.fragments.push(RecordedFragment {
+                    bundle_id,
+                    target,
+                    secondary,
+                });
+                Ok(())
+            }
+            Err(e) => {
+                device.cmd_allocator.discard_secondary(secondary);
+                Err(e).map_pass_err(scope)
+            }
+        }
+    }
+
+    /// Runs a render pass made of fragments, each executed in its own
+    /// secondary command buffer.
+    ///
+    /// Fragments are render bundles compatible with the pass, recorded
+    /// beforehand with `command_encoder_record_render_pass_fragment`.
+    /// This only executes the secondary command buffers within the pass.
+    pub fn command_encoder_run_render_pass_fragments<B: GfxBackend>(
+        &self,
+        encoder_id: id::CommandEncoderId,
+        desc: &RenderPassDescriptor,
+        fragments: &[id::RenderBundleId],
+    ) -> Result<(), RenderPassError> {
+        span!(_guard, INFO, "CommandEncoder::run_render_pass_fragments");
+        let scope = PassErrorScope::Pass(encoder_id);
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let (cmb_guard, mut token) = hub.command_buffers.read(&mut token);
+        let (bundle_guard, mut token) = hub.render_bundles.read(&mut token);
+        let (_, mut token) = hub.pipeline_layouts.read(&mut token);
+        let (_, mut token) = hub.bind_groups.read(&mut token);
+        let (_, mut token) = hub.render_pipelines.read(&mut token);
+        let (_, mut token) = hub.query_sets.read(&mut token);
+        let (buffer_guard, mut token) = hub.buffers.read(&mut token);
+        let (texture_guard, mut token) = hub.textures.read(&mut token);
+        let (view_guard, _) = hub.texture_views.read(&mut token);
+
+        let mut cmd_buf_data =
+            CommandBuffer::get_encoder(&*cmb_guard, encoder_id).map_pass_err(scope)?;
+        let device = &device_guard[cmd_buf_data.device_id.value];
+        let target = FragmentTarget::new(desc, &*view_guard, device).map_pass_err(scope)?;
+
+        let mut raw = cmd_buf_data.pool.allocate(hal::command::Level::Primary);
+        unsafe {
+            if let Some(ref label) = desc.label {
+                device.raw.set_command_buffer_name(&mut raw, label);
+            }
+            raw.begin_primary(hal::command::CommandBufferFlags::ONE_TIME_SUBMIT);
+        }
+
+        // Everything that can fail is done before the fragments are taken
+        // from the encoder, and `raw` is given back to the pool on failure.
+        let started = RenderPassInfo::start(
+            &mut raw,
+            &desc.color_attachments,
+            desc.depth_stencil_attachment,
+            &*cmd_buf_data,
+            device,
+            &*view_guard,
+            hal::command::SubpassContents::SecondaryBuffers,
+        )
+        .map_pass_err(scope)
+        .and_then(|mut info| {
+            let mut taken = Vec::with_capacity(fragments.len());
+            for &bundle_id in fragments {
+                let index = check_fragment(
+                    &mut info,
+                    &*bundle_guard,
+                    &cmd_buf_data.fragments,
+                    &taken,
+                    &target,
+                    bundle_id,
+                )
+                .map_pass_err(PassErrorScope::ExecuteBundle)?;
+                taken.push(index);
+            }
+            let finished = info.finish(&*texture_guard).map_pass_err(scope)?;
+            Ok((finished, taken))
+        });
+        let ((trackers, used_swapchain), taken) = match started {
+            Ok(finished) => finished,
+            Err(e) => {
+                cmd_buf_data.pool.discard(raw);
+                return Err(e);
+            }
+        };
+
+        let cmd_buf = &mut *cmd_buf_data;
+        let first_secondary = cmd_buf.secondary.len();
+        let mut recorded = cmd_buf.fragments.drain(..).map(Some).collect::<Vec<_>>();
+        for (&bundle_id, index) in fragments.iter().zip(taken) {
+            let bundle = &bundle_guard[id::Valid(bundle_id)];
+            let fragment = recorded[index].take().unwrap();
+            cmd_buf.secondary.push(fragment.secondary);
+
+            cmd_buf.buffer_memory_init_actions.extend(
+                bundle
+                    .buffer_memory_init_actions
+                    .iter()
+                    .filter_map(|action| match buffer_guard.get(action.id) {
+                        Ok(buffer) => buffer
+                            .initialization_status
+                            .check(action.range.clone())
+                            .map(|range| MemoryInitTrackerAction {
+                                id: action.id,
+                                range,
+                                kind: action.kind,
+                            }),
+                        Err(_) => None,
+                    }),
+            );
+            cmd_buf
+                .index_tracker_actions
+                .extend_from_slice(&bundle.index_tracker_actions);
+        }
+        cmd_buf.fragments.extend(recorded.into_iter().flatten());
+
+        tracing::trace!("Merging {:?} with the render pass fragments", encoder_id);
+        unsafe {
+            if cmd_buf.secondary.len() > first_secondary {
+                raw.execute_commands(
+                    cmd_buf.secondary[first_secondary..]
+                        .iter()
+                        .map(|secondary| &secondary.raw),
+                );
+            }
+            raw.end_render_pass();
+        }
+
+        cmd_buf.has_labels |= desc.label.is_some();
+        cmd_buf.used_swap_chains.extend(used_swapchain);
+
+        #[cfg(feature = "trace")]
+        if let Some(ref mut list) = cmd_buf.commands {
+            let mut base = BasePass::new(&desc.label);
+            base.commands
+                .extend(fragments.iter().map(|&id| RenderCommand::ExecuteBundle(id)));
+            list.push(crate::device::trace::Command::RunRenderPass {
+                base,
+                target_colors: desc.color_attachments.to_vec(),
+                target_depth_stencil: desc.depth_stencil_attachment.cloned(),
+            });
+        }
+
+        let last_cmd_buf = cmd_buf.raw.last_mut().unwrap();
+        super::CommandBuffer::insert_barriers(
+            last_cmd_buf,
+            &mut cmd_buf.trackers,
+            &trackers,
+            &*buffer_guard,
+            &*texture_guard,
+        );
+        unsafe {
+            last_cmd_buf.finish();
+        }
+        cmd_buf.raw.push(raw);
+
+        Ok(())
+    }
 }
 
 pub mod render_ffi {
@@ -2378,3 +2896,115 @@ pub mod render_ffi {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        render_ffi, DepthStencilAttachmentDescriptor, LoadOp, PassChannel, RenderPass,
+        RenderPassDescriptor, RenderPassErrorInner, StoreOp,
+    };
+    use crate::{
+        command::{RenderBundleEncoder, RenderBundleEncoderDescriptor},
+        device::{
+            tests::{create_command_encoder, create_device},
+            DeviceDescriptor,
+        },
+        resource::{TextureDescriptor, TextureViewDescriptor},
+    };
+    use std::borrow::Cow;
+
+    #[test]
+    fn read_only_depth_pass() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 64,
+                    height: 64,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Depth32Float,
+                usage: wgt::TextureUsage::RENDER_ATTACHMENT,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (view, error) =
+            global.dyn_texture_create_view(texture, &TextureViewDescriptor::default(), ());
+        assert!(error.is_none());
+
+        for &bundle_read_only in &[true, false] {
+            let bundle_encoder = RenderBundleEncoder::new(
+                &RenderBundleEncoderDescriptor {
+                    label: None,
+                    color_formats: Cow::Borrowed(&[]),
+                    depth_stencil_format: Some(wgt::TextureFormat::Depth32Float),
+                    depth_read_only: bundle_read_only,
+                    stencil_read_only: false,
+                    sample_count: 1,
+                },
+                device,
+                None,
+            )
+            .unwrap();
+            let (bundle, error) = global.dyn_render_bundle_encoder_finish(
+                bundle_encoder,
+                &wgt::RenderBundleDescriptor { label: None },
+                (),
+            );
+            assert!(error.is_none());
+
+            let encoder = create_command_encoder(&global, device);
+            let mut pass = RenderPass::new(
+                encoder,
+                &RenderPassDescriptor {
+                    label: None,
+                    color_attachments: Cow::Borrowed(&[]),
+                    depth_stencil_attachment: Some(&DepthStencilAttachmentDescriptor {
+                        attachment: view,
+                        depth: PassChannel {
+                            load_op: LoadOp::Load,
+                            store_op: StoreOp::Store,
+                            clear_value: 0.0,
+                            read_only: true,
+                        },
+                        stencil: PassChannel {
+                            load_op: LoadOp::Load,
+                            store_op: StoreOp::Store,
+                            clear_value: 0,
+                            read_only: true,
+                        },
+                    }),
+                },
+            );
+            unsafe {
+                render_ffi::wgpu_render_pass_execute_bundles(&mut pass, &bundle, 1);
+            }
+            let result = global.dyn_command_encoder_run_render_pass(encoder, &pass);
+            if bundle_read_only {
+                // The stencil is read-only anyway, since the format doesn't have it.
+                result.unwrap();
+            } else {
+                assert!(matches!(
+                    result.map_err(|e| e.inner),
+                    Err(RenderPassErrorInner::IncompatibleRenderBundleReadOnly)
+                ));
+            }
+            global.dyn_command_encoder_drop(encoder);
+            global.dyn_render_bundle_drop(bundle);
+        }
+
+        global.dyn_texture_view_drop(view, true).unwrap();
+        global.dyn_texture_drop(texture, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+}
@@ -8,12 +8,14 @@ use crate::{
     command::{CommandBuffer, CommandEncoderError},
     conv,
     device::{all_buffer_stages, all_image_stages},
-    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, Storage, Token},
+    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, InvalidId, Storage, Token},
     id::{BufferId, CommandEncoderId, TextureId},
+    index_tracker::IndexTrackerAction,
     memory_init_tracker::{MemoryInitKind, MemoryInitTrackerAction},
     resource::{BufferUse, Texture, TextureErrorDimension, TextureUse},
     span,
     track::TextureSelector,
+    PrivateFeatures,
 };
 
 use hal::command::CommandBuffer as _;
@@ -36,10 +38,14 @@ pub enum CopySide {
 /// Error encountered while attempting a data transfer.
 #[derive(Clone, Debug, Error)]
 pub enum TransferError {
-    #[error("buffer {0:?} is invalid or destroyed")]
-    InvalidBuffer(BufferId),
-    #[error("texture {0:?} is invalid or destroyed")]
-    InvalidTexture(TextureId),
+    #[error("buffer {0:?} is invalid")]
+    InvalidBuffer(BufferId, #[source] InvalidId),
+    #[error("buffer {0:?} is destroyed")]
+    DestroyedBuffer(BufferId),
+    #[error("texture {0:?} is invalid")]
+    InvalidTexture(TextureId, #[source] InvalidId),
+    #[error("texture {0:?} is destroyed")]
+    DestroyedTexture(TextureId),
     #[error("Source and destination cannot be the same buffer")]
     SameSourceDestinationBuffer,
     #[error("source buffer/texture is missing the `COPY_SRC` usage flag")]
@@ -85,10 +91,21 @@ pub enum TransferError {
     InvalidRowsPerImage,
     #[error("source and destination layers have different aspects")]
     MismatchedAspects,
-    #[error("copying from textures with format {0:?} is forbidden")]
-    CopyFromForbiddenTextureFormat(wgt::TextureFormat),
-    #[error("copying to textures with format {0:?} is forbidden")]
-    CopyToForbiddenTextureFormat(wgt::TextureFormat),
+    #[error("texture format {format:?} has no {aspect:?} aspect")]
+    InvalidTextureAspect {
+        format: wgt::TextureFormat,
+        aspect: wgt::TextureAspect,
+    },
+    #[error("copying from the {aspect:?} aspect of {format:?} textures is forbidden")]
+    CopyFromForbiddenTextureFormat {
+        format: wgt::TextureFormat,
+        aspect: wgt::TextureAspect,
+    },
+    #[error("copying to the {aspect:?} aspect of {format:?} textures is forbidden")]
+    CopyToForbiddenTextureFormat {
+        format: wgt::TextureFormat,
+        aspect: wgt::TextureAspect,
+    },
 }
 
 /// Error encountered while attempting to do a copy on a command encoder.
@@ -116,7 +133,15 @@ pub(crate) fn texture_copy_view_to_hal<B: hal::Backend>(
 > {
     let texture = texture_guard
         .get(view.texture)
-        .map_err(|_| TransferError::InvalidTexture(view.texture))?;
+        .map_err(|e| TransferError::InvalidTexture(view.texture, e))?;
+
+    let aspects = texture.hal_aspects(view.aspect);
+    if !texture.aspects.contains(aspects) {
+        return Err(TransferError::InvalidTextureAspect {
+            format: texture.format,
+            aspect: view.aspect,
+        });
+    }
 
     let level = view.mip_level as hal::image::Level;
     let (layer, layer_count, z) = match texture.dimension {
@@ -133,7 +158,7 @@ pub(crate) fn texture_copy_view_to_hal<B: hal::Backend>(
     #[allow(clippy::range_plus_one)]
     Ok((
         hal::image::SubresourceLayers {
-            aspects: texture.aspects,
+            aspects,
             level,
             layers: layer..layer + layer_count,
         },
@@ -149,6 +174,52 @@ pub(crate) fn texture_copy_view_to_hal<B: hal::Backend>(
     ))
 }
 
+/// Returns the size in bytes of a texel block of the copied `aspect` of a texture
+/// in a buffer, if copying it in that direction is allowed.
+///
+/// See <https://gpuweb.github.io/gpuweb/#depth-formats> for reference.
+pub(crate) fn buffer_copy_block_size(
+    format: wgt::TextureFormat,
+    aspect: wgt::TextureAspect,
+    texture_side: CopySide,
+    private_features: PrivateFeatures,
+) -> Result<u32, TransferError> {
+    use wgt::{TextureAspect as Ta, TextureFormat as Tf};
+
+    let is_source = match texture_side {
+        CopySide::Source => true,
+        CopySide::Destination => false,
+    };
+    let block_size = match (format, aspect) {
+        (Tf::Depth16Unorm, Ta::All) | (Tf::Depth16Unorm, Ta::DepthOnly) => Some(2),
+        (Tf::Depth32Float, Ta::All)
+        | (Tf::Depth32Float, Ta::DepthOnly)
+        | (Tf::Depth32FloatStencil8, Ta::DepthOnly) => Some(4).filter(|_| is_source),
+        (Tf::Stencil8, Ta::All)
+        | (Tf::Stencil8, Ta::StencilOnly)
+        | (Tf::Depth24PlusStencil8, Ta::StencilOnly)
+        | (Tf::Depth32FloatStencil8, Ta::StencilOnly) => Some(1),
+        // Depth/stencil aspects that aren't listed above are never copyable.
+        (Tf::Depth16Unorm, _)
+        | (Tf::Depth32Float, _)
+        | (Tf::Depth24Plus, _)
+        | (Tf::Depth24PlusStencil8, _)
+        | (Tf::Stencil8, _)
+        | (Tf::Depth32FloatStencil8, _) => None,
+        (_, _) => Some(
+            conv::map_texture_format(format, private_features)
+                .surface_desc()
+                .bits as u32
+                / BITS_PER_BYTE,
+        ),
+    };
+    block_size.ok_or(if is_source {
+        TransferError::CopyFromForbiddenTextureFormat { format, aspect }
+    } else {
+        TransferError::CopyToForbiddenTextureFormat { format, aspect }
+    })
+}
+
 /// Function copied with minor modifications from webgpu standard https://gpuweb.github.io/gpuweb/#valid-texture-copy-range
 /// If successful, returns number of buffer bytes required for this copy.
 pub(crate) fn validate_linear_texture_data(
@@ -318,8 +389,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = B::hub(self);
         let mut token = Token::root();
 
-        let (mut cmd_buf_guard, mut token) = hub.command_buffers.write(&mut token);
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, command_encoder_id)?;
+        let (cmd_buf_guard, mut token) = hub.command_buffers.read(&mut token);
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, command_encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let (buffer_guard, _) = hub.buffers.read(&mut token);
 
         #[cfg(feature = "trace")]
@@ -337,11 +409,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .trackers
             .buffers
             .use_replace(&*buffer_guard, source, (), BufferUse::COPY_SRC)
-            .map_err(TransferError::InvalidBuffer)?;
+            .map_err(|e| TransferError::InvalidBuffer(source, e))?;
         let &(ref src_raw, _) = src_buffer
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidBuffer(source))?;
+            .ok_or(TransferError::DestroyedBuffer(source))?;
         if !src_buffer.usage.contains(BufferUsage::COPY_SRC) {
             return Err(TransferError::MissingCopySrcUsageFlag.into());
         }
@@ -354,11 +426,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .trackers
             .buffers
             .use_replace(&*buffer_guard, destination, (), BufferUse::COPY_DST)
-            .map_err(TransferError::InvalidBuffer)?;
+            .map_err(|e| TransferError::InvalidBuffer(destination, e))?;
         let &(ref dst_raw, _) = dst_buffer
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidBuffer(destination))?;
+            .ok_or(TransferError::DestroyedBuffer(destination))?;
         if !dst_buffer.usage.contains(BufferUsage::COPY_DST) {
             return Err(TransferError::MissingCopyDstUsageFlag(Some(destination), None).into());
         }
@@ -413,6 +485,14 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     kind: MemoryInitKind::ImplicitlyInitialized,
                 }),
         );
+        if dst_buffer.index_tracker.is_some() {
+            cmd_buf
+                .index_tracker_actions
+                .push(IndexTrackerAction::Invalidate {
+                    buffer: destination,
+                    range: destination_offset..destination_offset + size,
+                });
+        }
         cmd_buf.buffer_memory_init_actions.extend(
             src_buffer
                 .initialization_status
@@ -452,8 +532,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let hub = B::hub(self);
         let mut token = Token::root();
-        let (mut cmd_buf_guard, mut token) = hub.command_buffers.write(&mut token);
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, command_encoder_id)?;
+        let (cmd_buf_guard, mut token) = hub.command_buffers.read(&mut token);
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, command_encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let (buffer_guard, mut token) = hub.buffers.read(&mut token);
         let (texture_guard, _) = hub.textures.read(&mut token);
         let (dst_layers, dst_selector, dst_offset) =
@@ -477,11 +558,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .trackers
             .buffers
             .use_replace(&*buffer_guard, source.buffer, (), BufferUse::COPY_SRC)
-            .map_err(TransferError::InvalidBuffer)?;
+            .map_err(|e| TransferError::InvalidBuffer(source.buffer, e))?;
         let &(ref src_raw, _) = src_buffer
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidBuffer(source.buffer))?;
+            .ok_or(TransferError::DestroyedBuffer(source.buffer))?;
         if !src_buffer.usage.contains(BufferUsage::COPY_SRC) {
             return Err(TransferError::MissingCopySrcUsageFlag.into());
         }
@@ -500,7 +581,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let &(ref dst_raw, _) = dst_texture
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidTexture(destination.texture))?;
+            .ok_or(TransferError::DestroyedTexture(destination.texture))?;
         if !dst_texture.usage.contains(TextureUsage::COPY_DST) {
             return Err(
                 TransferError::MissingCopyDstUsageFlag(None, Some(destination.texture)).into(),
@@ -509,10 +590,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let dst_barriers = dst_pending.map(|pending| pending.into_hal(dst_texture));
 
         let bytes_per_row_alignment = wgt::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let bytes_per_block = conv::map_texture_format(dst_texture.format, cmd_buf.private_features)
-            .surface_desc()
-            .bits as u32
-            / BITS_PER_BYTE;
+        let bytes_per_block = buffer_copy_block_size(
+            dst_texture.format,
+            destination.aspect,
+            CopySide::Destination,
+            cmd_buf.private_features,
+        )?;
         let src_bytes_per_row = source.layout.bytes_per_row;
         if bytes_per_row_alignment % bytes_per_block != 0 {
             return Err(TransferError::UnalignedBytesPerRow.into());
@@ -548,9 +631,6 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         );
 
         let (block_width, _) = dst_texture.format.describe().block_dimensions;
-        if !conv::is_valid_copy_dst_texture_format(dst_texture.format) {
-            return Err(TransferError::CopyToForbiddenTextureFormat(dst_texture.format).into());
-        }
 
         // WebGPU uses the physical size of the texture for copies whereas vulkan uses
         // the virtual size. We have passed validation, so it's safe to use the
@@ -600,8 +680,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let hub = B::hub(self);
         let mut token = Token::root();
-        let (mut cmd_buf_guard, mut token) = hub.command_buffers.write(&mut token);
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, command_encoder_id)?;
+        let (cmd_buf_guard, mut token) = hub.command_buffers.read(&mut token);
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, command_encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let (buffer_guard, mut token) = hub.buffers.read(&mut token);
         let (texture_guard, _) = hub.textures.read(&mut token);
         let (src_layers, src_selector, src_offset) =
@@ -634,7 +715,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let &(ref src_raw, _) = src_texture
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidTexture(source.texture))?;
+            .ok_or(TransferError::DestroyedTexture(source.texture))?;
         if !src_texture.usage.contains(TextureUsage::COPY_SRC) {
             return Err(TransferError::MissingCopySrcUsageFlag.into());
         }
@@ -644,11 +725,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .trackers
             .buffers
             .use_replace(&*buffer_guard, destination.buffer, (), BufferUse::COPY_DST)
-            .map_err(TransferError::InvalidBuffer)?;
+            .map_err(|e| TransferError::InvalidBuffer(destination.buffer, e))?;
         let &(ref dst_raw, _) = dst_buffer
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidBuffer(destination.buffer))?;
+            .ok_or(TransferError::DestroyedBuffer(destination.buffer))?;
         if !dst_buffer.usage.contains(BufferUsage::COPY_DST) {
             return Err(
                 TransferError::MissingCopyDstUsageFlag(Some(destination.buffer), None).into(),
@@ -657,10 +738,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let dst_barrier = dst_barriers.map(|pending| pending.into_hal(dst_buffer));
 
         let bytes_per_row_alignment = wgt::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let bytes_per_block = conv::map_texture_format(src_texture.format, cmd_buf.private_features)
-            .surface_desc()
-            .bits as u32
-            / BITS_PER_BYTE;
+        let bytes_per_block = buffer_copy_block_size(
+            src_texture.format,
+            source.aspect,
+            CopySide::Source,
+            cmd_buf.private_features,
+        )?;
         let dst_bytes_per_row = destination.layout.bytes_per_row;
         if bytes_per_row_alignment % bytes_per_block != 0 {
             return Err(TransferError::UnalignedBytesPerRow.into());
@@ -685,9 +768,6 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         )?;
 
         let (block_width, _) = src_texture.format.describe().block_dimensions;
-        if !conv::is_valid_copy_src_texture_format(src_texture.format) {
-            return Err(TransferError::CopyFromForbiddenTextureFormat(src_texture.format).into());
-        }
 
         cmd_buf.buffer_memory_init_actions.extend(
             dst_buffer
@@ -702,6 +782,15 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     kind: MemoryInitKind::ImplicitlyInitialized,
                 }),
         );
+        if dst_buffer.index_tracker.is_some() {
+            cmd_buf
+                .index_tracker_actions
+                .push(IndexTrackerAction::Invalidate {
+                    buffer: destination.buffer,
+                    range: destination.layout.offset
+                        ..destination.layout.offset + required_buffer_bytes_in_copy,
+                });
+        }
 
         // WebGPU uses the physical size of the texture for copies whereas vulkan uses
         // the virtual size. We have passed validation, so it's safe to use the
@@ -753,8 +842,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let hub = B::hub(self);
         let mut token = Token::root();
 
-        let (mut cmd_buf_guard, mut token) = hub.command_buffers.write(&mut token);
-        let cmd_buf = CommandBuffer::get_encoder_mut(&mut *cmd_buf_guard, command_encoder_id)?;
+        let (cmd_buf_guard, mut token) = hub.command_buffers.read(&mut token);
+        let mut cmd_buf_data = CommandBuffer::get_encoder(&*cmd_buf_guard, command_encoder_id)?;
+        let cmd_buf = &mut *cmd_buf_data;
         let (_, mut token) = hub.buffers.read(&mut token); // skip token
         let (texture_guard, _) = hub.textures.read(&mut token);
         let (src_layers, src_selector, src_offset) =
@@ -792,7 +882,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let &(ref src_raw, _) = src_texture
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidTexture(source.texture))?;
+            .ok_or(TransferError::DestroyedTexture(source.texture))?;
         if !src_texture.usage.contains(TextureUsage::COPY_SRC) {
             return Err(TransferError::MissingCopySrcUsageFlag.into());
         }
@@ -815,7 +905,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let &(ref dst_raw, _) = dst_texture
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidTexture(destination.texture))?;
+            .ok_or(TransferError::DestroyedTexture(destination.texture))?;
         if !dst_texture.usage.contains(TextureUsage::COPY_DST) {
             return Err(
                 TransferError::MissingCopyDstUsageFlag(None, Some(destination.texture)).into(),
@@ -879,3 +969,179 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{buffer_copy_block_size, CopyError, CopySide, TransferError};
+    use crate::{
+        command::{BufferCopyView, TextureCopyView},
+        device::{
+            tests::{create_command_encoder, create_device, finish},
+            DeviceDescriptor,
+        },
+        resource::{BufferDescriptor, TextureDescriptor},
+        PrivateFeatures,
+    };
+
+    #[test]
+    fn block_sizes() {
+        use wgt::{TextureAspect as Ta, TextureFormat as Tf};
+
+        let private_features = PrivateFeatures {
+            anisotropic_filtering: false,
+            texture_d24: true,
+            texture_d24_s8: true,
+            texture_s8: false,
+            unified_memory: false,
+        };
+        let block_size =
+            |format, aspect, side| buffer_copy_block_size(format, aspect, side, private_features);
+        for side in &[CopySide::Source, CopySide::Destination] {
+            assert_eq!(
+                block_size(Tf::Rgba8Unorm, Ta::All, side.clone()).unwrap(),
+                4
+            );
+            assert_eq!(
+                block_size(Tf::Depth16Unorm, Ta::All, side.clone()).unwrap(),
+                2
+            );
+            assert_eq!(block_size(Tf::Stencil8, Ta::All, side.clone()).unwrap(), 1);
+            assert_eq!(
+                block_size(Tf::Depth24PlusStencil8, Ta::StencilOnly, side.clone()).unwrap(),
+                1
+            );
+            assert_eq!(
+                block_size(Tf::Depth32FloatStencil8, Ta::StencilOnly, side.clone()).unwrap(),
+                1
+            );
+            assert!(block_size(Tf::Depth24Plus, Ta::All, side.clone()).is_err());
+            assert!(block_size(Tf::Depth24PlusStencil8, Ta::All, side.clone()).is_err());
+            assert!(block_size(Tf::Depth24PlusStencil8, Ta::DepthOnly, side.clone()).is_err());
+        }
+        // Depth32 can only be read back.
+        assert_eq!(
+            block_size(Tf::Depth32Float, Ta::DepthOnly, CopySide::Source).unwrap(),
+            4
+        );
+        assert!(matches!(
+            block_size(Tf::Depth32Float, Ta::DepthOnly, CopySide::Destination),
+            Err(TransferError::CopyToForbiddenTextureFormat {
+                format: Tf::Depth32Float,
+                aspect: Ta::DepthOnly,
+            })
+        ));
+        assert_eq!(
+            block_size(Tf::Depth32FloatStencil8, Ta::DepthOnly, CopySide::Source).unwrap(),
+            4
+        );
+        assert!(matches!(
+            block_size(
+                Tf::Depth32FloatStencil8,
+                Ta::DepthOnly,
+                CopySide::Destination
+            ),
+            Err(TransferError::CopyToForbiddenTextureFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn depth24plus_stencil8_stencil_copies() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let size = wgt::Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 1,
+        };
+        let (texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Depth24PlusStencil8,
+                usage: wgt::TextureUsage::COPY_SRC | wgt::TextureUsage::COPY_DST,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (buffer, error) = global.dyn_device_create_buffer(
+            device,
+            &BufferDescriptor {
+                label: None,
+                size: 4 * wgt::COPY_BYTES_PER_ROW_ALIGNMENT as wgt::BufferAddress,
+                usage: wgt::BufferUsage::COPY_SRC | wgt::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let buffer_view = BufferCopyView {
+            buffer,
+            layout: wgt::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: wgt::COPY_BYTES_PER_ROW_ALIGNMENT,
+                rows_per_image: 0,
+            },
+        };
+        let texture_view = |aspect| TextureCopyView {
+            texture,
+            mip_level: 0,
+            origin: wgt::Origin3d::ZERO,
+            aspect,
+        };
+
+        let encoder = create_command_encoder(&global, device);
+        global
+            .dyn_command_encoder_copy_buffer_to_texture(
+                encoder,
+                &buffer_view,
+                &texture_view(wgt::TextureAspect::StencilOnly),
+                &size,
+            )
+            .unwrap();
+        global
+            .dyn_command_encoder_copy_texture_to_buffer(
+                encoder,
+                &texture_view(wgt::TextureAspect::StencilOnly),
+                &buffer_view,
+                &size,
+            )
+            .unwrap();
+        // The depth aspect stays uncopyable.
+        assert!(matches!(
+            global.dyn_command_encoder_copy_texture_to_buffer(
+                encoder,
+                &texture_view(wgt::TextureAspect::DepthOnly),
+                &buffer_view,
+                &size,
+            ),
+            Err(CopyError::Transfer(
+                TransferError::CopyFromForbiddenTextureFormat { .. }
+            ))
+        ));
+        assert!(matches!(
+            global.dyn_command_encoder_copy_buffer_to_texture(
+                encoder,
+                &buffer_view,
+                &texture_view(wgt::TextureAspect::All),
+                &size,
+            ),
+            Err(CopyError::Transfer(
+                TransferError::CopyToForbiddenTextureFormat { .. }
+            ))
+        ));
+        let command_buffer = finish(&global, encoder);
+        global.dyn_queue_submit(device, &[command_buffer]).unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+
+        global.dyn_buffer_drop(buffer, true);
+        global.dyn_texture_drop(texture, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+}
@@ -338,6 +338,8 @@ pub(crate) fn map_texture_format(
         Tf::R16Uint => H::R16Uint,
         Tf::R16Sint => H::R16Sint,
         Tf::R16Float => H::R16Sfloat,
+        Tf::R16Unorm => H::R16Unorm,
+        Tf::R16Snorm => H::R16Snorm,
         Tf::Rg8Unorm => H::Rg8Unorm,
         Tf::Rg8Snorm => H::Rg8Snorm,
         Tf::Rg8Uint => H::Rg8Uint,
@@ -350,6 +352,8 @@ pub(crate) fn map_texture_format(
         Tf::Rg16Uint => H::Rg16Uint,
         Tf::Rg16Sint => H::Rg16Sint,
         Tf::Rg16Float => H::Rg16Sfloat,
+        Tf::Rg16Unorm => H::Rg16Unorm,
+        Tf::Rg16Snorm => H::Rg16Snorm,
         Tf::Rgba8Unorm => H::Rgba8Unorm,
         Tf::Rgba8UnormSrgb => H::Rgba8Srgb,
         Tf::Rgba8Snorm => H::Rgba8Snorm,
@@ -361,6 +365,7 @@ pub(crate) fn map_texture_format(
         // Packed 32 bit formats
         Tf::Rgb10a2Unorm => H::A2r10g10b10Unorm,
         Tf::Rg11b10Float => H::B10g11r11Ufloat,
+        Tf::Rgb9e5Ufloat => H::E5b9g9r9Ufloat,
 
         // Normal 64 bit formats
         Tf::Rg32Uint => H::Rg32Uint,
@@ -369,6 +374,8 @@ pub(crate) fn map_texture_format(
         Tf::Rgba16Uint => H::Rgba16Uint,
         Tf::Rgba16Sint => H::Rgba16Sint,
         Tf::Rgba16Float => H::Rgba16Sfloat,
+        Tf::Rgba16Unorm => H::Rgba16Unorm,
+        Tf::Rgba16Snorm => H::Rgba16Snorm,
 
         // Normal 128 bit formats
         Tf::Rgba32Uint => H::Rgba32Uint,
@@ -391,6 +398,17 @@ pub(crate) fn map_texture_format(
                 H::D32SfloatS8Uint
             }
         }
+        Tf::Depth16Unorm => H::D16Unorm,
+        Tf::Stencil8 => {
+            if private_features.texture_s8 {
+                H::S8Uint
+            } else if private_features.texture_d24_s8 {
+                H::D24UnormS8Uint
+            } else {
+                H::D32SfloatS8Uint
+            }
+        }
+        Tf::Depth32FloatStencil8 => H::D32SfloatS8Uint,
 
         // BCn compressed formats
         Tf::Bc1RgbaUnorm => H::Bc1RgbaUnorm,
@@ -497,22 +515,6 @@ pub fn is_power_of_two(val: u32) -> bool {
     val != 0 && (val & (val - 1)) == 0
 }
 
-pub fn is_valid_copy_src_texture_format(format: wgt::TextureFormat) -> bool {
-    use wgt::TextureFormat as Tf;
-    match format {
-        Tf::Depth24Plus | Tf::Depth24PlusStencil8 => false,
-        _ => true,
-    }
-}
-
-pub fn is_valid_copy_dst_texture_format(format: wgt::TextureFormat) -> bool {
-    use wgt::TextureFormat as Tf;
-    match format {
-        Tf::Depth32Float | Tf::Depth24Plus | Tf::Depth24PlusStencil8 => false,
-        _ => true,
-    }
-}
-
 pub fn map_texture_dimension_size(
     dimension: wgt::TextureDimension,
     wgt::Extent3d {
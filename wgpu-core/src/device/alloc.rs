@@ -6,10 +6,28 @@ use super::DeviceError;
 use hal::device::Device as _;
 use std::{borrow::Cow, iter, ptr::NonNull};
 
+/// How the buffers of a device are placed in memory and written by the host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// All the memory of the adapter is device local, so buffers that aren't
+    /// mappable get host visible memory when the host writes to them.
+    pub unified_memory: bool,
+    /// Buffers that aren't mappable, but were given host visible memory.
+    pub host_visible_buffers: u64,
+    /// `queue_write_buffer` calls and creation mappings written in place.
+    pub in_place_writes: u64,
+    /// `queue_write_buffer` calls and creation mappings copied from staging memory.
+    pub staged_writes: u64,
+}
+
 #[derive(Debug)]
 pub struct MemoryAllocator<B: hal::Backend>(gpu_alloc::GpuAllocator<B::Memory>);
+
+/// Memory bound to a resource. It's `None` for the resources created from
+/// hal objects, whose memory is owned by the user.
 #[derive(Debug)]
-pub struct MemoryBlock<B: hal::Backend>(gpu_alloc::MemoryBlock<B::Memory>);
+pub struct MemoryBlock<B: hal::Backend>(Option<gpu_alloc::MemoryBlock<B::Memory>>);
+
 struct MemoryDevice<'a, B: hal::Backend>(&'a B::Device);
 
 impl<B: hal::Backend> MemoryAllocator<B> {
@@ -70,7 +88,7 @@ impl<B: hal::Backend> MemoryAllocator<B> {
         };
 
         unsafe { self.0.alloc(&MemoryDevice::<B>(device), request) }
-            .map(MemoryBlock)
+            .map(|block| MemoryBlock(Some(block)))
             .map_err(|err| match err {
                 gpu_alloc::AllocationError::OutOfHostMemory
                 | gpu_alloc::AllocationError::OutOfDeviceMemory => DeviceError::OutOfMemory,
@@ -79,7 +97,9 @@ impl<B: hal::Backend> MemoryAllocator<B> {
     }
 
     pub fn free(&mut self, device: &B::Device, block: MemoryBlock<B>) {
-        unsafe { self.0.dealloc(&MemoryDevice::<B>(device), block.0) }
+        if let Some(block) = block.0 {
+            unsafe { self.0.dealloc(&MemoryDevice::<B>(device), block) }
+        }
     }
 
     pub fn clear(&mut self, device: &B::Device) {
@@ -88,31 +108,64 @@ impl<B: hal::Backend> MemoryAllocator<B> {
 }
 
 impl<B: hal::Backend> MemoryBlock<B> {
+    /// Stands for the memory of a resource created from a hal object.
+    pub fn external() -> Self {
+        MemoryBlock(None)
+    }
+
+    pub fn is_external(&self) -> bool {
+        self.0.is_none()
+    }
+
+    fn allocated(&self) -> &gpu_alloc::MemoryBlock<B::Memory> {
+        self.0
+            .as_ref()
+            .expect("Memory of hal objects is not accessible")
+    }
+
+    fn allocated_mut(&mut self) -> &mut gpu_alloc::MemoryBlock<B::Memory> {
+        self.0
+            .as_mut()
+            .expect("Memory of hal objects is not accessible")
+    }
+
+    fn props(&self) -> gpu_alloc::MemoryPropertyFlags {
+        self.0
+            .as_ref()
+            .map_or(gpu_alloc::MemoryPropertyFlags::empty(), |block| {
+                block.props()
+            })
+    }
+
     pub fn bind_buffer(
         &self,
         device: &B::Device,
         buffer: &mut B::Buffer,
     ) -> Result<(), DeviceError> {
-        let mem = self.0.memory();
+        let block = self.allocated();
         unsafe {
             device
-                .bind_buffer_memory(mem, self.0.offset(), buffer)
+                .bind_buffer_memory(block.memory(), block.offset(), buffer)
                 .map_err(DeviceError::from_bind)
         }
     }
 
     pub fn bind_image(&self, device: &B::Device, image: &mut B::Image) -> Result<(), DeviceError> {
-        let mem = self.0.memory();
+        let block = self.allocated();
         unsafe {
             device
-                .bind_image_memory(mem, self.0.offset(), image)
+                .bind_image_memory(block.memory(), block.offset(), image)
                 .map_err(DeviceError::from_bind)
         }
     }
 
+    pub fn is_host_visible(&self) -> bool {
+        self.props()
+            .contains(gpu_alloc::MemoryPropertyFlags::HOST_VISIBLE)
+    }
+
     pub fn is_coherent(&self) -> bool {
-        self.0
-            .props()
+        self.props()
             .contains(gpu_alloc::MemoryPropertyFlags::HOST_COHERENT)
     }
 
@@ -124,14 +177,14 @@ impl<B: hal::Backend> MemoryBlock<B> {
     ) -> Result<NonNull<u8>, DeviceError> {
         let offset = inner_offset;
         unsafe {
-            self.0
+            self.allocated_mut()
                 .map(&MemoryDevice::<B>(device), offset, size as usize)
                 .map_err(DeviceError::from)
         }
     }
 
     pub fn unmap(&mut self, device: &B::Device) {
-        unsafe { self.0.unmap(&MemoryDevice::<B>(device)) };
+        unsafe { self.allocated_mut().unmap(&MemoryDevice::<B>(device)) };
     }
 
     pub fn write_bytes(
@@ -142,7 +195,7 @@ impl<B: hal::Backend> MemoryBlock<B> {
     ) -> Result<(), DeviceError> {
         let offset = inner_offset;
         unsafe {
-            self.0
+            self.allocated_mut()
                 .write_bytes(&MemoryDevice::<B>(device), offset, data)
                 .map_err(DeviceError::from)
         }
@@ -156,7 +209,7 @@ impl<B: hal::Backend> MemoryBlock<B> {
     ) -> Result<(), DeviceError> {
         let offset = inner_offset;
         unsafe {
-            self.0
+            self.allocated_mut()
                 .read_bytes(&MemoryDevice::<B>(device), offset, data)
                 .map_err(DeviceError::from)
         }
@@ -167,9 +220,10 @@ impl<B: hal::Backend> MemoryBlock<B> {
         inner_offset: wgt::BufferAddress,
         size: Option<wgt::BufferAddress>,
     ) -> hal::memory::Segment {
+        let block = self.allocated();
         hal::memory::Segment {
-            offset: self.0.offset() + inner_offset,
-            size: size.or_else(|| Some(self.0.size())),
+            offset: block.offset() + inner_offset,
+            size: size.or_else(|| Some(block.size())),
         }
     }
 
@@ -180,7 +234,7 @@ impl<B: hal::Backend> MemoryBlock<B> {
         size: Option<wgt::BufferAddress>,
     ) -> Result<(), DeviceError> {
         let segment = self.segment(inner_offset, size);
-        let mem = self.0.memory();
+        let mem = self.allocated().memory();
         unsafe {
             device
                 .flush_mapped_memory_ranges(iter::once((mem, segment)))
@@ -195,7 +249,7 @@ impl<B: hal::Backend> MemoryBlock<B> {
         size: Option<wgt::BufferAddress>,
     ) -> Result<(), DeviceError> {
         let segment = self.segment(inner_offset, size);
-        let mem = self.0.memory();
+        let mem = self.allocated().memory();
         unsafe {
             device
                 .invalidate_mapped_memory_ranges(iter::once((mem, segment)))
@@ -288,3 +342,16 @@ impl<B: hal::Backend> gpu_alloc::MemoryDevice<B::Memory> for MemoryDevice<'_, B>
             .map_err(|_| gpu_alloc::OutOfMemory::OutOfHostMemory)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryBlock;
+
+    #[test]
+    fn external_block() {
+        let block = MemoryBlock::<crate::backend::Empty>::external();
+        assert!(block.is_external());
+        assert!(!block.is_host_visible());
+        assert!(!block.is_coherent());
+    }
+}
@@ -0,0 +1,83 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Sharing of the samplers and pipeline layouts created with equal descriptions.
+//!
+//! Every `device_create_sampler` and `device_create_pipeline_layout` call still
+//! gets its own id and `LifeGuard`, but the hal object behind it is reference
+//! counted, and reused while any id refers to it. The cache only keeps weak
+//! references: the `LifetimeTracker` releases the object of an id once its
+//! submissions are done, and the last release destroys it.
+
+use crate::{id, FastHashMap};
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) struct SamplerKey {
+    pub(crate) address_modes: [wgt::AddressMode; 3],
+    pub(crate) mag_filter: wgt::FilterMode,
+    pub(crate) min_filter: wgt::FilterMode,
+    pub(crate) mipmap_filter: wgt::FilterMode,
+    /// Bits of the minimum and maximum level of detail.
+    pub(crate) lod_clamp: [u32; 2],
+    pub(crate) compare: Option<wgt::CompareFunction>,
+    /// Clamp actually given to the backend.
+    pub(crate) anisotropy_clamp: Option<u8>,
+    pub(crate) border_color: Option<wgt::SamplerBorderColor>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) struct PipelineLayoutKey {
+    pub(crate) bind_group_layouts: Vec<id::BindGroupLayoutId>,
+    pub(crate) push_constant_ranges: Vec<wgt::PushConstantRange>,
+}
+
+#[derive(Debug)]
+pub(crate) struct DedupCache<K, T> {
+    map: FastHashMap<K, Weak<T>>,
+}
+
+impl<K, T> Default for DedupCache<K, T> {
+    fn default() -> Self {
+        Self {
+            map: FastHashMap::default(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, T> DedupCache<K, T> {
+    /// Returns the live object created for `key`, if any.
+    pub(crate) fn get(&self, key: &K) -> Option<Arc<T>> {
+        self.map.get(key).and_then(Weak::upgrade)
+    }
+
+    pub(crate) fn insert(&mut self, key: K, object: &Arc<T>) {
+        self.map.retain(|_, weak| weak.strong_count() != 0);
+        self.map.insert(key, Arc::downgrade(object));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DedupCache;
+    use std::sync::Arc;
+
+    #[test]
+    fn objects_are_shared_while_alive() {
+        let mut cache = DedupCache::default();
+        let first = Arc::new(1);
+        cache.insert("a", &first);
+        assert!(Arc::ptr_eq(&cache.get(&"a").unwrap(), &first));
+        assert!(cache.get(&"b").is_none());
+
+        drop(first);
+        assert!(cache.get(&"a").is_none());
+        let second = Arc::new(2);
+        cache.insert("b", &second);
+        assert_eq!(cache.map.len(), 1);
+    }
+}
@@ -22,7 +22,7 @@ use hal::device::Device as _;
 use parking_lot::Mutex;
 use thiserror::Error;
 
-use std::sync::atomic::Ordering;
+use std::sync::{atomic::Ordering, Arc};
 
 const CLEANUP_WAIT_MS: u64 = 5000;
 
@@ -96,13 +96,13 @@ struct NonReferencedResources<B: hal::Backend> {
     // Note: we keep the associated ID here in order to be able to check
     // at any point what resources are used in a submission.
     image_views: Vec<(id::Valid<id::TextureViewId>, B::ImageView)>,
-    samplers: Vec<B::Sampler>,
+    samplers: Vec<Arc<B::Sampler>>,
     framebuffers: Vec<B::Framebuffer>,
     desc_sets: Vec<DescriptorSet<B>>,
     compute_pipes: Vec<B::ComputePipeline>,
     graphics_pipes: Vec<B::GraphicsPipeline>,
     descriptor_set_layouts: Vec<B::DescriptorSetLayout>,
-    pipeline_layouts: Vec<B::PipelineLayout>,
+    pipeline_layouts: Vec<Arc<B::PipelineLayout>>,
     query_sets: Vec<B::QueryPool>,
 }
 
@@ -160,8 +160,11 @@ impl<B: hal::Backend> NonReferencedResources<B> {
         for (_, raw) in self.image_views.drain(..) {
             device.destroy_image_view(raw);
         }
+        // Shared objects are destroyed with their last user.
         for raw in self.samplers.drain(..) {
-            device.destroy_sampler(raw);
+            if let Ok(raw) = Arc::try_unwrap(raw) {
+                device.destroy_sampler(raw);
+            }
         }
         for raw in self.framebuffers.drain(..) {
             device.destroy_framebuffer(raw);
@@ -183,7 +186,9 @@ impl<B: hal::Backend> NonReferencedResources<B> {
             device.destroy_descriptor_set_layout(raw);
         }
         for raw in self.pipeline_layouts.drain(..) {
-            device.destroy_pipeline_layout(raw);
+            if let Ok(raw) = Arc::try_unwrap(raw) {
+                device.destroy_pipeline_layout(raw);
+            }
         }
         for raw in self.query_sets.drain(..) {
             device.destroy_query_pool(raw);
@@ -284,6 +289,11 @@ impl<B: hal::Backend> LifetimeTracker<B> {
         });
     }
 
+    /// Returns the oldest submission that may still be executing, if any.
+    pub(crate) fn oldest_active_submission(&self) -> Option<SubmissionIndex> {
+        self.active.first().map(|a| a.index)
+    }
+
     pub(crate) fn map(&mut self, value: id::Valid<id::BufferId>, ref_count: RefCount) {
         self.mapped.push(Stored { value, ref_count });
     }
@@ -372,6 +382,19 @@ impl<B: hal::Backend> LifetimeTracker<B> {
             TempResource::Image(raw) => resources.images.push((raw, memory)),
         }
     }
+
+    pub fn schedule_query_set_destruction(
+        &mut self,
+        raw: B::QueryPool,
+        last_submit_index: SubmissionIndex,
+    ) {
+        self.active
+            .iter_mut()
+            .find(|a| a.index == last_submit_index)
+            .map_or(&mut self.free_resources, |a| &mut a.last_resources)
+            .query_sets
+            .push(raw);
+    }
 }
 
 impl<B: GfxBackend> LifetimeTracker<B> {
@@ -379,7 +402,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
         &mut self,
         hub: &Hub<B, G>,
         trackers: &Mutex<TrackerSet>,
-        #[cfg(feature = "trace")] trace: Option<&Mutex<trace::Trace>>,
+        #[cfg(feature = "trace")] trace: Option<&Mutex<trace::Tracer>>,
         token: &mut Token<super::Device<B>>,
     ) {
         if !self.suspected_resources.render_bundles.is_empty() {
@@ -390,7 +413,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if trackers.bundles.remove_abandoned(id) {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyRenderBundle(id.0));
+                        t.lock().record(trace::Action::DestroyRenderBundle(id.0));
                     }
 
                     if let Some(res) = hub.render_bundles.unregister_locked(id.0, &mut *guard) {
@@ -408,7 +431,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if trackers.bind_groups.remove_abandoned(id) {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyBindGroup(id.0));
+                        t.lock().record(trace::Action::DestroyBindGroup(id.0));
                     }
 
                     if let Some(res) = hub.bind_groups.unregister_locked(id.0, &mut *guard) {
@@ -434,7 +457,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if trackers.views.remove_abandoned(id) {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyTextureView(id.0));
+                        t.lock().record(trace::Action::DestroyTextureView(id.0));
                     }
 
                     if let Some(res) = hub.texture_views.unregister_locked(id.0, &mut *guard) {
@@ -466,7 +489,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if trackers.textures.remove_abandoned(id) {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyTexture(id.0));
+                        t.lock().record(trace::Action::DestroyTexture(id.0));
                     }
 
                     if let Some(res) = hub.textures.unregister_locked(id.0, &mut *guard) {
@@ -490,7 +513,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if trackers.samplers.remove_abandoned(id) {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroySampler(id.0));
+                        t.lock().record(trace::Action::DestroySampler(id.0));
                     }
 
                     if let Some(res) = hub.samplers.unregister_locked(id.0, &mut *guard) {
@@ -514,7 +537,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if trackers.buffers.remove_abandoned(id) {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyBuffer(id.0));
+                        t.lock().record(trace::Action::DestroyBuffer(id.0));
                     }
                     tracing::debug!("Buffer {:?} is detached", id);
 
@@ -549,7 +572,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if trackers.compute_pipes.remove_abandoned(id) {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyComputePipeline(id.0));
+                        t.lock().record(trace::Action::DestroyComputePipeline(id.0));
                     }
 
                     if let Some(res) = hub.compute_pipelines.unregister_locked(id.0, &mut *guard) {
@@ -573,7 +596,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if trackers.render_pipes.remove_abandoned(id) {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyRenderPipeline(id.0));
+                        t.lock().record(trace::Action::DestroyRenderPipeline(id.0));
                     }
 
                     if let Some(res) = hub.render_pipelines.unregister_locked(id.0, &mut *guard) {
@@ -601,7 +624,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if ref_count.load() == 1 {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyPipelineLayout(id.0));
+                        t.lock().record(trace::Action::DestroyPipelineLayout(id.0));
                     }
 
                     if let Some(lay) = hub.pipeline_layouts.unregister_locked(id.0, &mut *guard) {
@@ -625,7 +648,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                 if guard[id].multi_ref_count.dec_and_check_empty() {
                     #[cfg(feature = "trace")]
                     if let Some(t) = trace {
-                        t.lock().add(trace::Action::DestroyBindGroupLayout(id.0));
+                        t.lock().record(trace::Action::DestroyBindGroupLayout(id.0));
                     }
                     if let Some(lay) = hub.bind_group_layouts.unregister_locked(id.0, &mut *guard) {
                         self.free_resources.descriptor_set_layouts.push(lay.raw);
@@ -649,7 +672,7 @@ impl<B: GfxBackend> LifetimeTracker<B> {
                             .find(|a| a.index == submit_index)
                             .map_or(&mut self.free_resources, |a| &mut a.last_resources)
                             .query_sets
-                            .push(res.raw);
+                            .extend(res.raw);
                     }
                 }
             }
@@ -4,11 +4,10 @@
 
 use crate::{
     binding_model, command, conv,
-    device::life::WaitIdleError,
-    hub::{
-        GfxBackend, Global, GlobalIdentityHandlerFactory, Hub, Input, InvalidId, Storage, Token,
-    },
-    id, instance,
+    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, Hub, Input, Storage, Token},
+    id,
+    index_tracker::IndexTracker,
+    instance,
     memory_init_tracker::{MemoryInitKind, MemoryInitTracker, MemoryInitTrackerAction},
     pipeline, resource, span, swap_chain,
     track::{BufferState, TextureSelector, TextureState, TrackerSet},
@@ -38,16 +37,24 @@ use std::{
     mem,
     ops::Range,
     ptr,
-    sync::atomic::Ordering,
+    sync::{atomic::Ordering, Arc},
 };
 
 pub mod alloc;
+mod dedup;
 pub mod descriptor;
 mod life;
 pub mod queue;
+mod shader_cache;
+#[cfg(feature = "trace")]
+mod snapshot;
+mod staging_belt;
 #[cfg(any(feature = "trace", feature = "replay"))]
 pub mod trace;
 
+pub use self::life::WaitIdleError;
+pub use self::shader_cache::ShaderCacheStats;
+
 use smallvec::SmallVec;
 
 pub const MAX_COLOR_TARGETS: usize = 4;
@@ -273,6 +280,12 @@ pub struct Device<B: hal::Backend> {
     //Note: The submission index here corresponds to the last submission that is done.
     pub(crate) life_guard: LifeGuard,
     pub(crate) active_submission_index: SubmissionIndex,
+    /// Set by `device_destroy`. The memory of all the resources is released,
+    /// and no more work can be submitted.
+    pub(crate) destroyed: bool,
+    /// Set by `device_drop` while `QueueWriteBufferView`s into the pending writes
+    /// are alive. The device is dropped once the last of them is.
+    pub(crate) drop_deferred: bool,
     /// Has to be locked temporarily only (locked last)
     pub(crate) trackers: Mutex<TrackerSet>,
     pub(crate) render_passes: Mutex<RenderPassLock<B>>,
@@ -284,11 +297,17 @@ pub struct Device<B: hal::Backend> {
     pub(crate) limits: wgt::Limits,
     pub(crate) features: wgt::Features,
     spv_options: naga::back::spv::Options,
+    shader_cache: Mutex<shader_cache::ShaderCache>,
+    sampler_cache: Mutex<dedup::DedupCache<dedup::SamplerKey, B::Sampler>>,
+    pipeline_layout_cache: Mutex<dedup::DedupCache<dedup::PipelineLayoutKey, B::PipelineLayout>>,
+    memory_report: Mutex<alloc::MemoryReport>,
     //TODO: move this behind another mutex. This would allow several methods to switch
     // to borrow Device immutably, such as `write_buffer`, `write_texture`, and `buffer_unmap`.
     pending_writes: queue::PendingWrites<B>,
+    /// Created by the first small enough `queue_write_buffer` or `queue_write_texture`.
+    staging_belt: Option<staging_belt::StagingBelt<B>>,
     #[cfg(feature = "trace")]
-    pub(crate) trace: Option<Mutex<trace::Trace>>,
+    pub(crate) trace: Option<Mutex<trace::Tracer>>,
 }
 
 #[derive(Clone, Debug, Error)]
@@ -308,6 +327,7 @@ impl<B: GfxBackend> Device<B> {
         private_features: PrivateFeatures,
         desc: &DeviceDescriptor,
         trace_path: Option<&std::path::Path>,
+        traceable: bool,
     ) -> Result<Self, CreateDeviceError> {
         let cmd_allocator = command::CommandAllocator::new(queue_group.family, &raw)
             .or(Err(CreateDeviceError::OutOfMemory))?;
@@ -315,9 +335,8 @@ impl<B: GfxBackend> Device<B> {
         let mem_allocator = alloc::MemoryAllocator::new(mem_props, hal_limits);
         let descriptors = descriptor::DescriptorAllocator::new();
         #[cfg(not(feature = "trace"))]
-        match trace_path {
-            Some(_) => tracing::error!("Feature 'trace' is not enabled"),
-            None => (),
+        if trace_path.is_some() || traceable {
+            tracing::error!("Feature 'trace' is not enabled");
         }
 
         let spv_options = {
@@ -350,6 +369,8 @@ impl<B: GfxBackend> Device<B> {
             queue_group,
             life_guard: LifeGuard::new("<device>"),
             active_submission_index: 0,
+            destroyed: false,
+            drop_deferred: false,
             trackers: Mutex::new(TrackerSet::new(B::VARIANT)),
             render_passes: Mutex::new(RenderPassLock {
                 render_passes: FastHashMap::default(),
@@ -358,25 +379,39 @@ impl<B: GfxBackend> Device<B> {
             life_tracker: Mutex::new(life::LifetimeTracker::new()),
             temp_suspected: life::SuspectedResources::default(),
             #[cfg(feature = "trace")]
-            trace: trace_path.and_then(|path| match trace::Trace::new(path) {
-                Ok(mut trace) => {
-                    trace.add(trace::Action::Init {
-                        desc: desc.clone(),
-                        backend: B::VARIANT,
-                    });
-                    Some(Mutex::new(trace))
-                }
-                Err(e) => {
-                    tracing::error!("Unable to start a trace in '{:?}': {:?}", path, e);
-                    None
-                }
-            }),
+            trace: if traceable || trace_path.is_some() {
+                Some(Mutex::new(trace::Tracer::new(trace_path.and_then(
+                    |path| match trace::Trace::new(path) {
+                        Ok(mut trace) => {
+                            trace.add(trace::Action::Init {
+                                desc: desc.clone(),
+                                backend: B::VARIANT,
+                            });
+                            Some(trace)
+                        }
+                        Err(e) => {
+                            tracing::error!("Unable to start a trace in '{:?}': {:?}", path, e);
+                            None
+                        }
+                    },
+                ))))
+            } else {
+                None
+            },
             hal_limits,
             private_features,
             limits: desc.limits.clone(),
             features: desc.features,
             spv_options,
+            shader_cache: Mutex::new(shader_cache::ShaderCache::default()),
+            sampler_cache: Mutex::new(dedup::DedupCache::default()),
+            pipeline_layout_cache: Mutex::new(dedup::DedupCache::default()),
+            memory_report: Mutex::new(alloc::MemoryReport {
+                unified_memory: private_features.unified_memory,
+                ..alloc::MemoryReport::default()
+            }),
             pending_writes: queue::PendingWrites::new(),
+            staging_belt: None,
         })
     }
 
@@ -399,6 +434,13 @@ impl<B: GfxBackend> Device<B> {
         Self::lock_life_internal(&self.life_tracker, token)
     }
 
+    /// Locks the trace that is being recorded, if any.
+    #[cfg(feature = "trace")]
+    pub(crate) fn active_trace(&self) -> Option<parking_lot::MappedMutexGuard<trace::Trace>> {
+        let tracer = self.trace.as_ref()?.lock();
+        MutexGuard::try_map(tracer, |tracer| tracer.active.as_mut()).ok()
+    }
+
     fn maintain<'this, 'token: 'this, G: GlobalIdentityHandlerFactory>(
         &'this self,
         hub: &Hub<B, G>,
@@ -492,13 +534,26 @@ impl<B: GfxBackend> Device<B> {
             .extend(&self.temp_suspected);
     }
 
+    /// Creates a buffer, or wraps `hal_buffer` if given.
     fn create_buffer(
         &self,
         self_id: id::DeviceId,
         desc: &resource::BufferDescriptor,
         transient: bool,
+        hal_buffer: Option<B::Buffer>,
     ) -> Result<resource::Buffer<B>, resource::CreateBufferError> {
         debug_assert_eq!(self_id.backend(), B::VARIANT);
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
+        if hal_buffer.is_some()
+            && (desc.mapped_at_creation
+                || desc
+                    .usage
+                    .intersects(wgt::BufferUsage::MAP_READ | wgt::BufferUsage::MAP_WRITE))
+        {
+            return Err(resource::CreateBufferError::HalBufferMapping);
+        }
         let (mut usage, _memory_properties) = conv::map_buffer_usage(desc.usage);
         if desc.mapped_at_creation {
             if desc.size % wgt::COPY_BUFFER_ALIGNMENT != 0 {
@@ -518,6 +573,11 @@ impl<B: GfxBackend> Device<B> {
             return Err(resource::CreateBufferError::EmptyUsage);
         }
 
+        let host_writable_device_buffer = self.private_features.unified_memory
+            && !desc
+                .usage
+                .intersects(wgt::BufferUsage::MAP_READ | wgt::BufferUsage::MAP_WRITE)
+            && (desc.mapped_at_creation || desc.usage.contains(wgt::BufferUsage::COPY_DST));
         let mem_usage = {
             use gpu_alloc::UsageFlags as Uf;
             use wgt::BufferUsage as Bu;
@@ -529,6 +589,10 @@ impl<B: GfxBackend> Device<B> {
             if map_flags.is_empty() || !(desc.usage - map_copy_flags).is_empty() {
                 flags |= Uf::FAST_DEVICE_ACCESS;
             }
+            // With unified memory, the buffers written by the host can be written in place.
+            if host_writable_device_buffer {
+                flags |= Uf::HOST_ACCESS;
+            }
             if transient {
                 flags |= Uf::TRANSIENT;
             }
@@ -559,24 +623,43 @@ impl<B: GfxBackend> Device<B> {
             flags
         };
 
-        let mut buffer = unsafe {
-            self.raw
-                .create_buffer(desc.size.max(1), usage, hal::memory::SparseFlags::empty())
-        }
-        .map_err(|err| match err {
-            hal::buffer::CreationError::OutOfMemory(_) => DeviceError::OutOfMemory,
-            _ => panic!("failed to create buffer: {}", err),
-        })?;
-        if let Some(ref label) = desc.label {
-            unsafe { self.raw.set_buffer_name(&mut buffer, label) };
-        }
+        let (buffer, block) = match hal_buffer {
+            Some(buffer) => (buffer, alloc::MemoryBlock::external()),
+            None => {
+                let mut buffer = unsafe {
+                    self.raw.create_buffer(
+                        desc.size.max(1),
+                        usage,
+                        hal::memory::SparseFlags::empty(),
+                    )
+                }
+                .map_err(|err| match err {
+                    hal::buffer::CreationError::OutOfMemory(_) => DeviceError::OutOfMemory,
+                    _ => panic!("failed to create buffer: {}", err),
+                })?;
+                if let Some(ref label) = desc.label {
+                    unsafe { self.raw.set_buffer_name(&mut buffer, label) };
+                }
 
-        let requirements = unsafe { self.raw.get_buffer_requirements(&buffer) };
-        let block = self
-            .mem_allocator
-            .lock()
-            .allocate(&self.raw, requirements, mem_usage)?;
-        block.bind_buffer(&self.raw, &mut buffer)?;
+                let requirements = unsafe { self.raw.get_buffer_requirements(&buffer) };
+                let block =
+                    self.mem_allocator
+                        .lock()
+                        .allocate(&self.raw, requirements, mem_usage)?;
+                block.bind_buffer(&self.raw, &mut buffer)?;
+                if host_writable_device_buffer && block.is_host_visible() {
+                    self.memory_report.lock().host_visible_buffers += 1;
+                }
+                (buffer, block)
+            }
+        };
+
+        // The contents of a hal buffer are defined by its creator.
+        let is_external = block.is_external();
+        let mut initialization_status = MemoryInitTracker::new(desc.size);
+        if is_external {
+            initialization_status.clear(0..desc.size);
+        }
 
         Ok(resource::Buffer {
             raw: Some((buffer, block)),
@@ -586,20 +669,38 @@ impl<B: GfxBackend> Device<B> {
             },
             usage: desc.usage,
             size: desc.size,
-            initialization_status: MemoryInitTracker::new(desc.size),
+            initialization_status,
+            // Storage buffers can be written by shaders, and hal buffers outside of wgpu-core,
+            // so their contents are never known.
+            index_tracker: if desc.usage.contains(wgt::BufferUsage::INDEX)
+                && !desc.usage.contains(wgt::BufferUsage::STORAGE)
+                && !is_external
+                && self
+                    .features
+                    .contains(wgt::Features::ROBUST_INDEX_VALIDATION)
+            {
+                Some(IndexTracker::new(desc.size))
+            } else {
+                None
+            },
             sync_mapped_writes: None,
             map_state: resource::BufferMapState::Idle,
             life_guard: LifeGuard::new(desc.label.borrow_or_default()),
         })
     }
 
+    /// Creates a texture, or wraps `hal_texture` if given.
     fn create_texture(
         &self,
         self_id: id::DeviceId,
         adapter: &crate::instance::Adapter<B>,
         desc: &resource::TextureDescriptor,
+        hal_texture: Option<B::Image>,
     ) -> Result<resource::Texture<B>, resource::CreateTextureError> {
         debug_assert_eq!(self_id.backend(), B::VARIANT);
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
 
         let format_desc = desc.format.describe();
         let required_features = format_desc.required_features;
@@ -610,17 +711,14 @@ impl<B: GfxBackend> Device<B> {
             ));
         }
 
-        // Ensure `D24Plus` textures cannot be copied
-        match desc.format {
-            TextureFormat::Depth24Plus | TextureFormat::Depth24PlusStencil8 => {
-                if desc
-                    .usage
-                    .intersects(wgt::TextureUsage::COPY_SRC | wgt::TextureUsage::COPY_DST)
-                {
-                    return Err(resource::CreateTextureError::CannotCopyD24Plus);
-                }
-            }
-            _ => {}
+        // Ensure `Depth24Plus` textures cannot be copied. Only the stencil aspect
+        // of `Depth24PlusStencil8` can, which is checked when copying.
+        if desc.format == TextureFormat::Depth24Plus
+            && desc
+                .usage
+                .intersects(wgt::TextureUsage::COPY_SRC | wgt::TextureUsage::COPY_DST)
+        {
+            return Err(resource::CreateTextureError::CannotCopyD24Plus);
         }
 
         if desc.usage.is_empty() {
@@ -672,36 +770,42 @@ impl<B: GfxBackend> Device<B> {
 
         // TODO: 2D arrays, cubemap arrays
 
-        let mut image = unsafe {
-            let mut image = self
-                .raw
-                .create_image(
-                    kind,
-                    desc.mip_level_count as hal::image::Level,
-                    format,
-                    hal::image::Tiling::Optimal,
-                    usage,
-                    hal::memory::SparseFlags::empty(),
-                    view_caps,
-                )
-                .map_err(|err| match err {
-                    hal::image::CreationError::OutOfMemory(_) => DeviceError::OutOfMemory,
-                    _ => panic!("failed to create texture: {}", err),
-                })?;
-            if let Some(ref label) = desc.label {
-                self.raw.set_image_name(&mut image, label);
+        let (image, block) = match hal_texture {
+            Some(image) => (image, alloc::MemoryBlock::external()),
+            None => {
+                let mut image = unsafe {
+                    let mut image = self
+                        .raw
+                        .create_image(
+                            kind,
+                            desc.mip_level_count as hal::image::Level,
+                            format,
+                            hal::image::Tiling::Optimal,
+                            usage,
+                            hal::memory::SparseFlags::empty(),
+                            view_caps,
+                        )
+                        .map_err(|err| match err {
+                            hal::image::CreationError::OutOfMemory(_) => DeviceError::OutOfMemory,
+                            _ => panic!("failed to create texture: {}", err),
+                        })?;
+                    if let Some(ref label) = desc.label {
+                        self.raw.set_image_name(&mut image, label);
+                    }
+                    image
+                };
+
+                let requirements = unsafe { self.raw.get_image_requirements(&image) };
+                let block = self.mem_allocator.lock().allocate(
+                    &self.raw,
+                    requirements,
+                    gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+                )?;
+                block.bind_image(&self.raw, &mut image)?;
+                (image, block)
             }
-            image
         };
 
-        let requirements = unsafe { self.raw.get_image_requirements(&image) };
-        let block = self.mem_allocator.lock().allocate(
-            &self.raw,
-            requirements,
-            gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
-        )?;
-        block.bind_image(&self.raw, &mut image)?;
-
         Ok(resource::Texture {
             raw: Some((image, block)),
             device_id: Stored {
@@ -733,10 +837,13 @@ impl<B: GfxBackend> Device<B> {
         texture_id: id::TextureId,
         desc: &resource::TextureViewDescriptor,
     ) -> Result<resource::TextureView<B>, resource::CreateTextureViewError> {
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
         let &(ref texture_raw, _) = texture
             .raw
             .as_ref()
-            .ok_or(resource::CreateTextureViewError::InvalidTexture)?;
+            .ok_or(resource::CreateTextureViewError::DestroyedTexture)?;
 
         let view_dim =
             match desc.dimension {
@@ -805,11 +912,7 @@ impl<B: GfxBackend> Device<B> {
             });
         };
 
-        let aspects = match desc.aspect {
-            wgt::TextureAspect::All => texture.aspects,
-            wgt::TextureAspect::DepthOnly => hal::format::Aspects::DEPTH,
-            wgt::TextureAspect::StencilOnly => hal::format::Aspects::STENCIL,
-        };
+        let aspects = texture.hal_aspects(desc.aspect);
         if !texture.aspects.contains(aspects) {
             return Err(resource::CreateTextureViewError::InvalidAspect {
                 requested: aspects,
@@ -901,6 +1004,9 @@ impl<B: GfxBackend> Device<B> {
         self_id: id::DeviceId,
         desc: &resource::SamplerDescriptor,
     ) -> Result<resource::Sampler<B>, resource::CreateSamplerError> {
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
         let clamp_to_border_enabled = self
             .features
             .contains(wgt::Features::ADDRESS_MODE_CLAMP_TO_BORDER);
@@ -954,15 +1060,34 @@ impl<B: GfxBackend> Device<B> {
             anisotropy_clamp: actual_clamp,
         };
 
-        let raw = unsafe {
-            self.raw.create_sampler(&info).map_err(|err| match err {
-                hal::device::AllocationError::OutOfMemory(_) => {
-                    resource::CreateSamplerError::Device(DeviceError::OutOfMemory)
-                }
-                hal::device::AllocationError::TooManyObjects => {
-                    resource::CreateSamplerError::TooManyObjects
-                }
-            })?
+        let key = dedup::SamplerKey {
+            address_modes: desc.address_modes,
+            mag_filter: desc.mag_filter,
+            min_filter: desc.min_filter,
+            mipmap_filter: desc.mipmap_filter,
+            lod_clamp: [desc.lod_min_clamp.to_bits(), desc.lod_max_clamp.to_bits()],
+            compare: desc.compare,
+            anisotropy_clamp: actual_clamp,
+            border_color: desc.border_color,
+        };
+        let mut sampler_cache = self.sampler_cache.lock();
+        let raw = match sampler_cache.get(&key) {
+            Some(raw) => raw,
+            None => {
+                let raw = unsafe {
+                    self.raw.create_sampler(&info).map_err(|err| match err {
+                        hal::device::AllocationError::OutOfMemory(_) => {
+                            resource::CreateSamplerError::Device(DeviceError::OutOfMemory)
+                        }
+                        hal::device::AllocationError::TooManyObjects => {
+                            resource::CreateSamplerError::TooManyObjects
+                        }
+                    })?
+                };
+                let raw = Arc::new(raw);
+                sampler_cache.insert(key, &raw);
+                raw
+            }
         };
         Ok(resource::Sampler {
             raw,
@@ -981,8 +1106,104 @@ impl<B: GfxBackend> Device<B> {
         desc: &pipeline::ShaderModuleDescriptor<'a>,
         source: pipeline::ShaderModuleSource<'a>,
     ) -> Result<pipeline::ShaderModule<B>, pipeline::CreateShaderModuleError> {
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
+        let passthrough = desc.flags.contains(wgt::ShaderFlags::SPIRV_PASSTHROUGH);
+        if passthrough
+            && !self
+                .features
+                .contains(wgt::Features::SPIRV_SHADER_PASSTHROUGH)
+        {
+            return Err(pipeline::CreateShaderModuleError::MissingFeature(
+                wgt::Features::SPIRV_SHADER_PASSTHROUGH,
+            ));
+        }
+
+        let cache_key = if passthrough {
+            None
+        } else {
+            shader_cache::ShaderCacheKey::new(&source, desc.flags)
+        };
+        let cached = cache_key
+            .as_ref()
+            .and_then(|key| self.shader_cache.lock().get(key));
+        let shader = match cached {
+            Some(shader) => shader,
+            None => {
+                let shader = self.parse_shader_module(desc, source)?;
+                if let Some(key) = cache_key {
+                    self.shader_cache.lock().insert(key, shader.clone());
+                }
+                shader
+            }
+        };
+
+        // Attempt to give the Naga module to gfx-hal directly.
+        let shader_cache::CachedShader {
+            naga,
+            interface,
+            spv,
+        } = shader;
+        let naga_result = match naga {
+            Some(naga)
+                if desc
+                    .flags
+                    .contains(wgt::ShaderFlags::EXPERIMENTAL_TRANSLATION) =>
+            {
+                // The backend consumes the module, so the cached one is copied.
+                let (module, analysis) =
+                    Arc::try_unwrap(naga).unwrap_or_else(|naga| (*naga).clone());
+                let shader = hal::device::NagaShader { module, analysis };
+                match unsafe { self.raw.create_shader_module_from_naga(shader) } {
+                    Ok(raw) => Ok(raw),
+                    Err((hal::device::ShaderError::CompilationFailed(msg), shader)) => {
+                        tracing::warn!("Shader module compilation failed: {}", msg);
+                        Err(Some(shader))
+                    }
+                    Err((_, shader)) => Err(Some(shader)),
+                }
+            }
+            _ => Err(None),
+        };
+
+        // Otherwise, fall back to SPIR-V.
+        let raw = match naga_result {
+            Ok(raw) => Ok(raw),
+            Err(maybe_shader) => match spv {
+                Some(spv) => unsafe { self.raw.create_shader_module(&spv) },
+                None => {
+                    // Only modules translated by the backend have no SPIR-V yet.
+                    let shader = maybe_shader.unwrap();
+                    match naga::back::spv::write_vec(
+                        &shader.module,
+                        &shader.analysis,
+                        &self.spv_options,
+                    ) {
+                        Ok(spv) => unsafe { self.raw.create_shader_module(&spv) },
+                        Err(e) => Err(hal::device::ShaderError::CompilationFailed(format!(
+                            "{}",
+                            e
+                        ))),
+                    }
+                }
+            },
+        };
+
+        self.wrap_shader_module(self_id, desc, raw, interface)
+    }
+
+    /// Parses and validates the source of a shader module.
+    fn parse_shader_module<'a>(
+        &self,
+        desc: &pipeline::ShaderModuleDescriptor<'a>,
+        source: pipeline::ShaderModuleSource<'a>,
+    ) -> Result<shader_cache::CachedShader, pipeline::CreateShaderModuleError> {
+        let passthrough = desc.flags.contains(wgt::ShaderFlags::SPIRV_PASSTHROUGH);
+
         // First, try to produce a Naga module.
         let (spv, module) = match source {
+            pipeline::ShaderModuleSource::SpirV(spv) if passthrough => (Some(spv), None),
             pipeline::ShaderModuleSource::SpirV(spv) => {
                 // Parse the given shader code and store its representation.
                 let parser =
@@ -1010,11 +1231,24 @@ impl<B: GfxBackend> Device<B> {
                     }
                 }
             }
+            pipeline::ShaderModuleSource::Glsl {
+                code,
+                stage,
+                defines,
+            } => {
+                let options = naga::front::glsl::Options {
+                    entry_points: iter::once(("main".to_string(), stage)).collect(),
+                    defines,
+                };
+                let module = naga::front::glsl::parse_str(&code, &options)
+                    .map_err(pipeline::CreateShaderModuleError::ParsingGlsl)?;
+                (None, Some(module))
+            }
             pipeline::ShaderModuleSource::Naga(module) => (None, Some(module)),
         };
 
-        let (naga_result, interface) = match module {
-            // If succeeded, then validate it and attempt to give it to gfx-hal directly.
+        let (naga, interface) = match module {
+            // If succeeded, then validate it.
             Some(module) if desc.flags.contains(wgt::ShaderFlags::VALIDATION) || spv.is_none() => {
                 let analysis = naga::proc::Validator::new().validate(&module)?;
                 if !self.features.contains(wgt::Features::PUSH_CONSTANTS)
@@ -1028,56 +1262,60 @@ impl<B: GfxBackend> Device<B> {
                     ));
                 }
                 let interface = validation::Interface::new(&module, &analysis);
-                let shader = hal::device::NagaShader { module, analysis };
-                let naga_result = if desc
-                    .flags
-                    .contains(wgt::ShaderFlags::EXPERIMENTAL_TRANSLATION)
-                {
-                    match unsafe { self.raw.create_shader_module_from_naga(shader) } {
-                        Ok(raw) => Ok(raw),
-                        Err((hal::device::ShaderError::CompilationFailed(msg), shader)) => {
-                            tracing::warn!("Shader module compilation failed: {}", msg);
-                            Err(Some(shader))
-                        }
-                        Err((_, shader)) => Err(Some(shader)),
+                (
+                    Some(Arc::new((module, analysis))),
+                    Some(Arc::new(interface)),
+                )
+            }
+            None if passthrough => {
+                let interface = match desc.interface {
+                    Some(ref declared) => {
+                        Some(Arc::new(validation::Interface::from_declared(declared)?))
                     }
-                } else {
-                    Err(Some(shader))
+                    None => None,
                 };
-                (naga_result, Some(interface))
+                (None, interface)
             }
-            _ => (Err(None), None),
+            _ => (None, None),
         };
 
-        // Otherwise, fall back to SPIR-V.
-        let spv_result = match naga_result {
-            Ok(raw) => Ok(raw),
-            Err(maybe_shader) => {
-                let spv = match spv {
-                    Some(data) => Ok(data),
-                    None => {
-                        // Produce a SPIR-V from the Naga module
-                        let shader = maybe_shader.unwrap();
-                        naga::back::spv::write_vec(
-                            &shader.module,
-                            &shader.analysis,
-                            &self.spv_options,
-                        )
-                        .map(Cow::Owned)
+        // Translate to SPIR-V, unless the backend is asked to do it.
+        let spv = match spv {
+            Some(spv) => Some(Arc::from(&*spv)),
+            None if desc
+                .flags
+                .contains(wgt::ShaderFlags::EXPERIMENTAL_TRANSLATION) =>
+            {
+                None
+            }
+            None => {
+                let &(ref module, ref analysis) = &**naga.as_ref().unwrap();
+                match naga::back::spv::write_vec(module, analysis, &self.spv_options) {
+                    Ok(spv) => Some(spv.into()),
+                    Err(error) => {
+                        tracing::error!("Shader error: {}", error);
+                        return Err(pipeline::CreateShaderModuleError::Parsing);
                     }
-                };
-                match spv {
-                    Ok(data) => unsafe { self.raw.create_shader_module(&data) },
-                    Err(e) => Err(hal::device::ShaderError::CompilationFailed(format!(
-                        "{}",
-                        e
-                    ))),
                 }
             }
         };
 
+        Ok(shader_cache::CachedShader {
+            naga,
+            interface,
+            spv,
+        })
+    }
+
+    fn wrap_shader_module(
+        &self,
+        self_id: id::DeviceId,
+        desc: &pipeline::ShaderModuleDescriptor,
+        raw: Result<B::ShaderModule, hal::device::ShaderError>,
+        interface: Option<Arc<validation::Interface>>,
+    ) -> Result<pipeline::ShaderModule<B>, pipeline::CreateShaderModuleError> {
         Ok(pipeline::ShaderModule {
-            raw: match spv_result {
+            raw: match raw {
                 Ok(raw) => raw,
                 Err(hal::device::ShaderError::OutOfMemory(_)) => {
                     return Err(DeviceError::OutOfMemory.into());
@@ -1163,6 +1401,9 @@ impl<B: GfxBackend> Device<B> {
         label: Option<&str>,
         entry_map: binding_model::BindEntryMap,
     ) -> Result<binding_model::BindGroupLayout<B>, binding_model::CreateBindGroupLayoutError> {
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
         let mut desc_count = descriptor::DescriptorTotalCount::default();
         for binding in entry_map.values() {
             use wgt::BindingType as Bt;
@@ -1271,6 +1512,9 @@ impl<B: GfxBackend> Device<B> {
         token: &mut Token<binding_model::BindGroupLayout<B>>,
     ) -> Result<binding_model::BindGroup<B>, binding_model::CreateBindGroupError> {
         use crate::binding_model::{BindingResource as Br, CreateBindGroupError as Error};
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
         {
             // Check that the number of entries in the descriptor matches
             // the number of entries in the layout.
@@ -1452,7 +1696,14 @@ impl<B: GfxBackend> Device<B> {
                                     view_samples: view.samples as u32,
                                 });
                             }
-                            match (sample_type, format_info.sample_type, view.format_features.filterable ) {
+                            // Stencil aspects are sampled as unsigned integers.
+                            let view_sample_type = if view.aspects == hal::format::Aspects::STENCIL
+                            {
+                                Tst::Uint
+                            } else {
+                                format_info.sample_type
+                            };
+                            match (sample_type, view_sample_type, view.format_features.filterable ) {
                                 (Tst::Uint, Tst::Uint, ..) |
                                 (Tst::Sint, Tst::Sint, ..) |
                                 (Tst::Depth, Tst::Depth, ..) |
@@ -1521,6 +1772,23 @@ impl<B: GfxBackend> Device<B> {
                                         });
                                     }
 
+                                    resource::TextureUse::STORAGE_STORE
+                                        | resource::TextureUse::STORAGE_LOAD
+                                }
+                                wgt::StorageTextureAccess::Atomic => {
+                                    if !view.format_features.flags.contains(
+                                        wgt::TextureFormatFeatureFlags::STORAGE_READ_WRITE
+                                            | wgt::TextureFormatFeatureFlags::STORAGE_ATOMICS,
+                                    ) {
+                                        return Err(if self.features.contains(
+                                            wgt::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+                                        ) {
+                                            Error::StorageAtomicsNotSupported(view.format)
+                                        } else {
+                                            Error::MissingFeatures(wgt::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+                                        });
+                                    }
+
                                     resource::TextureUse::STORAGE_STORE
                                         | resource::TextureUse::STORAGE_LOAD
                                 }
@@ -1686,6 +1954,10 @@ impl<B: GfxBackend> Device<B> {
     ) -> Result<binding_model::PipelineLayout<B>, binding_model::CreatePipelineLayoutError> {
         use crate::binding_model::CreatePipelineLayoutError as Error;
 
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
+
         let bind_group_layouts_count = desc.bind_group_layouts.len();
         let device_max_bind_groups = self.limits.max_bind_groups as usize;
         if bind_group_layouts_count > device_max_bind_groups {
@@ -1747,25 +2019,38 @@ impl<B: GfxBackend> Device<B> {
             .validate(&self.limits)
             .map_err(Error::TooManyBindings)?;
 
-        let descriptor_set_layouts = desc
-            .bind_group_layouts
-            .iter()
-            .map(|&id| &bgl_guard.get(id).unwrap().raw);
-        let push_constants = desc
-            .push_constant_ranges
-            .iter()
-            .map(|pc| (conv::map_shader_stage_flags(pc.stages), pc.range.clone()));
-
-        let raw = unsafe {
-            let raw_layout = self
-                .raw
-                .create_pipeline_layout(descriptor_set_layouts, push_constants)
-                .or(Err(DeviceError::OutOfMemory))?;
-            if let Some(_) = desc.label {
-                //TODO-0.6: needs gfx changes published
-                //self.raw.set_pipeline_layout_name(&mut raw_layout, label);
+        let key = dedup::PipelineLayoutKey {
+            bind_group_layouts: desc.bind_group_layouts.to_vec(),
+            push_constant_ranges: desc.push_constant_ranges.to_vec(),
+        };
+        let mut pipeline_layout_cache = self.pipeline_layout_cache.lock();
+        let raw = match pipeline_layout_cache.get(&key) {
+            Some(raw) => raw,
+            None => {
+                let descriptor_set_layouts = desc
+                    .bind_group_layouts
+                    .iter()
+                    .map(|&id| &bgl_guard.get(id).unwrap().raw);
+                let push_constants = desc
+                    .push_constant_ranges
+                    .iter()
+                    .map(|pc| (conv::map_shader_stage_flags(pc.stages), pc.range.clone()));
+
+                let raw = unsafe {
+                    let raw_layout = self
+                        .raw
+                        .create_pipeline_layout(descriptor_set_layouts, push_constants)
+                        .or(Err(DeviceError::OutOfMemory))?;
+                    if let Some(_) = desc.label {
+                        //TODO-0.6: needs gfx changes published
+                        //self.raw.set_pipeline_layout_name(&mut raw_layout, label);
+                    }
+                    raw_layout
+                };
+                let raw = Arc::new(raw);
+                pipeline_layout_cache.insert(key, &raw);
+                raw
             }
-            raw_layout
         };
 
         Ok(binding_model::PipelineLayout {
@@ -1859,6 +2144,9 @@ impl<B: GfxBackend> Device<B> {
         ),
         pipeline::CreateComputePipelineError,
     > {
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
         //TODO: only lock mutable if the layout is derived
         let (mut pipeline_layout_guard, mut token) = hub.pipeline_layouts.write(token);
         let (mut bgl_guard, mut token) = hub.bind_group_layouts.write(&mut token);
@@ -1896,7 +2184,7 @@ impl<B: GfxBackend> Device<B> {
                     &mut derived_group_layouts,
                     &entry_point_name,
                     flag,
-                    io,
+                    Some(io),
                 )
                 .map_err(pipeline::CreateComputePipelineError::Stage)?;
         } else if desc.layout.is_none() {
@@ -1977,6 +2265,9 @@ impl<B: GfxBackend> Device<B> {
         ),
         pipeline::CreateRenderPipelineError,
     > {
+        if self.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
         //TODO: only lock mutable if the layout is derived
         let (mut pipeline_layout_guard, mut token) = hub.pipeline_layouts.write(token);
         let (mut bgl_guard, mut token) = hub.bind_group_layouts.write(&mut token);
@@ -1992,7 +2283,7 @@ impl<B: GfxBackend> Device<B> {
         let rasterizer =
             conv::map_primitive_state_to_rasterizer(&desc.primitive, depth_stencil_state);
 
-        let mut io = validation::StageIo::default();
+        let mut vertex_inputs = validation::StageIo::default();
         let mut validated_stages = wgt::ShaderStage::empty();
 
         let desc_vbs = &desc.vertex.buffers;
@@ -2061,7 +2352,7 @@ impl<B: GfxBackend> Device<B> {
                         offset: attribute.offset as u32,
                     },
                 });
-                io.insert(
+                vertex_inputs.insert(
                     attribute.shader_location,
                     validation::NumericType::from_vertex_format(attribute.format),
                 );
@@ -2206,6 +2497,7 @@ impl<B: GfxBackend> Device<B> {
 
         let (shader_module_guard, _) = hub.shader_modules.read(&mut token);
 
+        let mut io = Some(vertex_inputs);
         let vertex = {
             let stage = &desc.vertex.stage;
             let flag = wgt::ShaderStage::VERTEX;
@@ -2297,7 +2589,8 @@ impl<B: GfxBackend> Device<B> {
             None => None,
         };
 
-        if validated_stages.contains(wgt::ShaderStage::FRAGMENT) {
+        // The outputs of entry points with a declared interface are unknown.
+        if let Some(io) = io.filter(|_| validated_stages.contains(wgt::ShaderStage::FRAGMENT)) {
             for (i, state) in color_states.iter().enumerate() {
                 match io.get(&(i as wgt::ShaderLocation)) {
                     Some(output) if validation::check_texture_format(state.format, output) => {}
@@ -2419,8 +2712,11 @@ impl<B: GfxBackend> Device<B> {
             if ds.stencil.is_enabled() && ds.stencil.needs_ref_value() {
                 flags |= pipeline::PipelineFlags::STENCIL_REFERENCE;
             }
-            if !ds.is_read_only() {
-                flags |= pipeline::PipelineFlags::WRITES_DEPTH_STENCIL;
+            if ds.depth_write_enabled {
+                flags |= pipeline::PipelineFlags::WRITES_DEPTH;
+            }
+            if !ds.stencil.is_read_only() {
+                flags |= pipeline::PipelineFlags::WRITES_STENCIL;
             }
         }
 
@@ -2498,6 +2794,9 @@ impl<B: hal::Backend> Device<B> {
         let mut mem_alloc = self.mem_allocator.into_inner();
         self.pending_writes
             .dispose(&self.raw, &self.cmd_allocator, &mut mem_alloc);
+        if let Some(belt) = self.staging_belt {
+            belt.dispose(&self.raw, &mut mem_alloc);
+        }
         self.cmd_allocator.destroy(&self.raw);
         unsafe {
             desc_alloc.cleanup(&self.raw);
@@ -2525,12 +2824,31 @@ impl<B: hal::Backend> crate::hub::Resource for Device<B> {
 #[error("device is invalid")]
 pub struct InvalidDevice;
 
+#[cfg(feature = "trace")]
+#[derive(Clone, Debug, Error)]
+pub enum StartTraceError {
+    #[error(transparent)]
+    Device(#[from] DeviceError),
+    #[error("device is already being traced")]
+    AlreadyTracing,
+    #[error("device was not requested as traceable")]
+    NotTraceable,
+    #[error("unable to create the trace file: {0:?}")]
+    Io(std::io::ErrorKind),
+    #[error(transparent)]
+    Submit(#[from] queue::QueueSubmitError),
+    #[error(transparent)]
+    WaitIdle(#[from] WaitIdleError),
+}
+
 #[derive(Clone, Debug, Error)]
 pub enum DeviceError {
     #[error("parent device is invalid")]
     Invalid,
     #[error("parent device is lost")]
     Lost,
+    #[error("parent device is destroyed")]
+    Destroyed,
     #[error("not enough memory left")]
     OutOfMemory,
 }
@@ -2627,6 +2945,34 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         Ok(device.features)
     }
 
+    pub fn device_shader_cache_stats<B: GfxBackend>(
+        &self,
+        device_id: id::DeviceId,
+    ) -> Result<ShaderCacheStats, InvalidDevice> {
+        span!(_guard, INFO, "Device::shader_cache_stats");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        let device = device_guard.get(device_id).map_err(|_| InvalidDevice)?;
+
+        Ok(device.shader_cache.lock().stats())
+    }
+
+    pub fn device_memory_report<B: GfxBackend>(
+        &self,
+        device_id: id::DeviceId,
+    ) -> Result<alloc::MemoryReport, InvalidDevice> {
+        span!(_guard, INFO, "Device::memory_report");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        let device = device_guard.get(device_id).map_err(|_| InvalidDevice)?;
+
+        Ok(*device.memory_report.lock())
+    }
+
     pub fn device_limits<B: GfxBackend>(
         &self,
         device_id: id::DeviceId,
@@ -2668,10 +3014,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 }
                 trace
                     .lock()
-                    .add(trace::Action::CreateBuffer(fid.id(), desc));
+                    .record(trace::Action::CreateBuffer(fid.id(), desc));
             }
 
-            let mut buffer = match device.create_buffer(device_id, desc, false) {
+            let mut buffer = match device.create_buffer(device_id, desc, false, None) {
                 Ok(buffer) => buffer,
                 Err(e) => break e,
             };
@@ -2679,8 +3025,14 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
             let buffer_use = if !desc.mapped_at_creation {
                 resource::BufferUse::EMPTY
-            } else if desc.usage.contains(wgt::BufferUsage::MAP_WRITE) {
-                // buffer is mappable, so we are just doing that at start
+            } else if desc.usage.contains(wgt::BufferUsage::MAP_WRITE)
+                || buffer
+                    .raw
+                    .as_ref()
+                    .map_or(false, |&(_, ref block)| block.is_host_visible())
+            {
+                // buffer is mappable, or lives in host visible memory,
+                // so we are just doing that at start
                 let map_size = buffer.size;
                 let ptr = match map_buffer(&device.raw, &mut buffer, 0, map_size, HostMap::Write) {
                     Ok(ptr) => ptr,
@@ -2699,6 +3051,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     sub_range: hal::buffer::SubRange::WHOLE,
                     host: HostMap::Write,
                 };
+                if !desc.usage.contains(wgt::BufferUsage::MAP_WRITE) {
+                    device.memory_report.lock().in_place_writes += 1;
+                }
                 resource::BufferUse::MAP_WRITE
             } else {
                 // buffer needs staging area for initialization only
@@ -2708,7 +3063,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     usage: wgt::BufferUsage::MAP_WRITE | wgt::BufferUsage::COPY_SRC,
                     mapped_at_creation: false,
                 };
-                let mut stage = match device.create_buffer(device_id, &stage_desc, true) {
+                let mut stage = match device.create_buffer(device_id, &stage_desc, true, None) {
                     Ok(stage) => stage,
                     Err(e) => {
                         let (raw, memory) = buffer.raw.unwrap();
@@ -2752,6 +3107,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     stage_buffer,
                     stage_memory,
                 };
+                device.memory_report.lock().staged_writes += 1;
                 resource::BufferUse::COPY_DST
             };
 
@@ -2771,6 +3127,70 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         (id, Some(error))
     }
 
+    /// Creates a buffer wrapping `hal_buffer`, which is destroyed with the buffer.
+    ///
+    /// The contents of `hal_buffer` are considered initialized, and it's tracked
+    /// as unused until the first submission that uses the buffer.
+    ///
+    /// # Safety
+    ///
+    /// - `hal_buffer` must be created from the hal device of `device_id`, with
+    ///   the hal usage that `desc.usage` maps to, and a size of `desc.size`.
+    /// - It must be bound to memory that outlives it, and no longer be used
+    ///   outside of wgpu-core, other than through `buffer_as_hal`.
+    /// - If an error is returned, `hal_buffer` is dropped without being destroyed.
+    pub unsafe fn device_create_buffer_from_hal<B: GfxBackend>(
+        &self,
+        hal_buffer: B::Buffer,
+        device_id: id::DeviceId,
+        desc: &resource::BufferDescriptor,
+        id_in: Input<G, id::BufferId>,
+    ) -> (id::BufferId, Option<resource::CreateBufferError>) {
+        span!(_guard, INFO, "Device::create_buffer_from_hal");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let fid = hub.buffers.prepare(id_in);
+
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let error = loop {
+            let device = match device_guard.get(device_id) {
+                Ok(device) => device,
+                Err(_) => break DeviceError::Invalid.into(),
+            };
+            #[cfg(feature = "trace")]
+            if let Some(ref trace) = device.trace {
+                trace
+                    .lock()
+                    .record(trace::Action::CreateBufferFromHal(fid.id(), desc.clone()));
+            }
+
+            let buffer = match device.create_buffer(device_id, desc, false, Some(hal_buffer)) {
+                Ok(buffer) => buffer,
+                Err(e) => break e,
+            };
+            let ref_count = buffer.life_guard.add_ref();
+
+            let id = fid.assign(buffer, &mut token);
+            tracing::info!("Created buffer {:?} from hal with {:?}", id, desc);
+
+            device
+                .trackers
+                .lock()
+                .buffers
+                .init(
+                    id,
+                    ref_count,
+                    BufferState::with_usage(resource::BufferUse::EMPTY),
+                )
+                .unwrap();
+            return (id.0, None);
+        };
+
+        let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
+        (id, Some(error))
+    }
+
     #[cfg(feature = "replay")]
     pub fn device_wait_for_buffer<B: GfxBackend>(
         &self,
@@ -2813,13 +3233,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .map_err(|_| DeviceError::Invalid)?;
         let buffer = buffer_guard
             .get_mut(buffer_id)
-            .map_err(|_| resource::BufferAccessError::Invalid)?;
+            .map_err(resource::BufferAccessError::Invalid)?;
         check_buffer_usage(buffer.usage, wgt::BufferUsage::MAP_WRITE)?;
         //assert!(buffer isn't used by the GPU);
 
         #[cfg(feature = "trace")]
-        if let Some(ref trace) = device.trace {
-            let mut trace = trace.lock();
+        if let Some(mut trace) = device.active_trace() {
             let data_path = trace.make_binary("bin", data);
             trace.add(trace::Action::WriteBuffer {
                 id: buffer_id,
@@ -2858,7 +3277,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .map_err(|_| DeviceError::Invalid)?;
         let buffer = buffer_guard
             .get_mut(buffer_id)
-            .map_err(|_| resource::BufferAccessError::Invalid)?;
+            .map_err(resource::BufferAccessError::Invalid)?;
         check_buffer_usage(buffer.usage, wgt::BufferUsage::MAP_READ)?;
         //assert!(buffer isn't used by the GPU);
 
@@ -2876,9 +3295,36 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         B::hub(self).buffers.label_for_resource(id)
     }
 
-    pub fn buffer_destroy<B: GfxBackend>(
+    /// Calls `hal_buffer_callback` with the hal buffer of `id`, or `None` if the
+    /// buffer is invalid or destroyed. The buffers of the hub stay locked for
+    /// reading during the call.
+    ///
+    /// # Safety
+    ///
+    /// The hal buffer must not be destroyed, and any use of it must be done
+    /// with the device idle, or synchronized with the submissions using `id`.
+    pub unsafe fn buffer_as_hal<B: GfxBackend, F: FnOnce(Option<&B::Buffer>) -> R, R>(
         &self,
-        buffer_id: id::BufferId,
+        id: id::BufferId,
+        hal_buffer_callback: F,
+    ) -> R {
+        span!(_guard, INFO, "Buffer::as_hal");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let (buffer_guard, _) = hub.buffers.read(&mut token);
+        let hal_buffer = buffer_guard
+            .get(id)
+            .ok()
+            .and_then(|buffer| buffer.raw.as_ref())
+            .map(|&(ref raw, _)| raw);
+
+        hal_buffer_callback(hal_buffer)
+    }
+
+    pub fn buffer_destroy<B: GfxBackend>(
+        &self,
+        buffer_id: id::BufferId,
     ) -> Result<(), resource::DestroyError> {
         span!(_guard, INFO, "Buffer::destroy");
 
@@ -2892,13 +3338,13 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (mut buffer_guard, _) = hub.buffers.write(&mut token);
         let buffer = buffer_guard
             .get_mut(buffer_id)
-            .map_err(|_| resource::DestroyError::Invalid)?;
+            .map_err(resource::DestroyError::Invalid)?;
 
         let device = &mut device_guard[buffer.device_id.value];
 
         #[cfg(feature = "trace")]
         if let Some(ref trace) = device.trace {
-            trace.lock().add(trace::Action::FreeBuffer(buffer_id));
+            trace.lock().record(trace::Action::FreeBuffer(buffer_id));
         }
 
         let (raw, memory) = buffer
@@ -2938,7 +3384,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         buffer.life_guard.submission_index.load(Ordering::Acquire);
                     (ref_count, last_submit_index, buffer.device_id.value)
                 }
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.buffers.unregister_locked(buffer_id, &mut *buffer_guard);
                     return;
                 }
@@ -2994,11 +3440,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             if let Some(ref trace) = device.trace {
                 trace
                     .lock()
-                    .add(trace::Action::CreateTexture(fid.id(), desc.clone()));
+                    .record(trace::Action::CreateTexture(fid.id(), desc.clone()));
             }
 
             let adapter = &adapter_guard[device.adapter_id.value];
-            let texture = match device.create_texture(device_id, adapter, desc) {
+            let texture = match device.create_texture(device_id, adapter, desc, None) {
                 Ok(texture) => texture,
                 Err(error) => break error,
             };
@@ -3022,10 +3468,117 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         (id, Some(error))
     }
 
+    /// Creates a texture wrapping `hal_texture`, which is destroyed with the texture.
+    ///
+    /// All the subresources of `hal_texture` are tracked as being in the layout
+    /// and access of `initial_use`, which has to be a single ordered use.
+    /// `TextureUse::UNINITIALIZED` discards the contents on the first use.
+    ///
+    /// # Safety
+    ///
+    /// - `hal_texture` must be created from the hal device of `device_id`, with
+    ///   the kind, format, usage and view capabilities that `desc` maps to.
+    /// - It must be bound to memory that outlives it, be in the state of
+    ///   `initial_use`, and no longer be used outside of wgpu-core, other than
+    ///   through `texture_as_hal`.
+    /// - If an error is returned, `hal_texture` is dropped without being destroyed.
+    pub unsafe fn device_create_texture_from_hal<B: GfxBackend>(
+        &self,
+        hal_texture: B::Image,
+        device_id: id::DeviceId,
+        desc: &resource::TextureDescriptor,
+        initial_use: resource::TextureUse,
+        id_in: Input<G, id::TextureId>,
+    ) -> (id::TextureId, Option<resource::CreateTextureError>) {
+        span!(_guard, INFO, "Device::create_texture_from_hal");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let fid = hub.textures.prepare(id_in);
+
+        let (adapter_guard, mut token) = hub.adapters.read(&mut token);
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+        let error = loop {
+            let device = match device_guard.get(device_id) {
+                Ok(device) => device,
+                Err(_) => break DeviceError::Invalid.into(),
+            };
+            #[cfg(feature = "trace")]
+            if let Some(ref trace) = device.trace {
+                trace
+                    .lock()
+                    .record(trace::Action::CreateTextureFromHal(fid.id(), desc.clone()));
+            }
+
+            // Other uses don't map to a single layout, or need a barrier anyway.
+            if initial_use != resource::TextureUse::UNINITIALIZED
+                && (initial_use.bits().count_ones() != 1
+                    || !resource::TextureUse::ORDERED.contains(initial_use))
+            {
+                break resource::CreateTextureError::InvalidInitialUse(initial_use);
+            }
+
+            let adapter = &adapter_guard[device.adapter_id.value];
+            let texture = match device.create_texture(device_id, adapter, desc, Some(hal_texture)) {
+                Ok(texture) => texture,
+                Err(error) => break error,
+            };
+            let num_levels = texture.full_range.levels.end;
+            let num_layers = texture.full_range.layers.end;
+            let ref_count = texture.life_guard.add_ref();
+
+            let id = fid.assign(texture, &mut token);
+            tracing::info!("Created texture {:?} from hal with {:?}", id, desc);
+
+            device
+                .trackers
+                .lock()
+                .textures
+                .init(
+                    id,
+                    ref_count,
+                    TextureState::with_usage(num_levels, num_layers, initial_use),
+                )
+                .unwrap();
+            return (id.0, None);
+        };
+
+        let id = fid.assign_error(desc.label.borrow_or_default(), &mut token);
+        (id, Some(error))
+    }
+
     pub fn texture_label<B: GfxBackend>(&self, id: id::TextureId) -> String {
         B::hub(self).textures.label_for_resource(id)
     }
 
+    /// Calls `hal_texture_callback` with the hal image of `id`, or `None` if the
+    /// texture is invalid or destroyed. The textures of the hub stay locked for
+    /// reading during the call.
+    ///
+    /// # Safety
+    ///
+    /// The hal image must not be destroyed, and any use of it must be done
+    /// with the device idle, or synchronized with the submissions using `id`.
+    /// It must be left in the layouts that wgpu-core is tracking for it.
+    pub unsafe fn texture_as_hal<B: GfxBackend, F: FnOnce(Option<&B::Image>) -> R, R>(
+        &self,
+        id: id::TextureId,
+        hal_texture_callback: F,
+    ) -> R {
+        span!(_guard, INFO, "Texture::as_hal");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let (texture_guard, _) = hub.textures.read(&mut token);
+        let hal_texture = texture_guard
+            .get(id)
+            .ok()
+            .and_then(|texture| texture.raw.as_ref())
+            .map(|&(ref raw, _)| raw);
+
+        hal_texture_callback(hal_texture)
+    }
+
     pub fn texture_destroy<B: GfxBackend>(
         &self,
         texture_id: id::TextureId,
@@ -3042,13 +3595,13 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (mut texture_guard, _) = hub.textures.write(&mut token);
         let texture = texture_guard
             .get_mut(texture_id)
-            .map_err(|_| resource::DestroyError::Invalid)?;
+            .map_err(resource::DestroyError::Invalid)?;
 
         let device = &mut device_guard[texture.device_id.value];
 
         #[cfg(feature = "trace")]
         if let Some(ref trace) = device.trace {
-            trace.lock().add(trace::Action::FreeTexture(texture_id));
+            trace.lock().record(trace::Action::FreeTexture(texture_id));
         }
 
         let (raw, memory) = texture
@@ -3087,7 +3640,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         texture.life_guard.submission_index.load(Ordering::Acquire);
                     (ref_count, last_submit_index, texture.device_id.value)
                 }
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.textures
                         .unregister_locked(texture_id, &mut *texture_guard);
                     return;
@@ -3138,12 +3691,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let error = loop {
             let texture = match texture_guard.get(texture_id) {
                 Ok(texture) => texture,
-                Err(_) => break resource::CreateTextureViewError::InvalidTexture,
+                Err(e) => break resource::CreateTextureViewError::InvalidTexture(e),
             };
             let device = &device_guard[texture.device_id.value];
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
-                trace.lock().add(trace::Action::CreateTextureView {
+                trace.lock().record(trace::Action::CreateTextureView {
                     id: fid.id(),
                     parent_id: texture_id,
                     desc: desc.clone(),
@@ -3203,7 +3756,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     };
                     (last_submit_index, device_id)
                 }
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.texture_views
                         .unregister_locked(texture_view_id, &mut *texture_view_guard);
                     return Ok(());
@@ -3254,7 +3807,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             if let Some(ref trace) = device.trace {
                 trace
                     .lock()
-                    .add(trace::Action::CreateSampler(fid.id(), desc.clone()));
+                    .record(trace::Action::CreateSampler(fid.id(), desc.clone()));
             }
 
             let sampler = match device.create_sampler(device_id, desc) {
@@ -3294,7 +3847,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     sampler.life_guard.ref_count.take();
                     sampler.device_id.value
                 }
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.samplers
                         .unregister_locked(sampler_id, &mut *sampler_guard);
                     return;
@@ -3331,11 +3884,15 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 Ok(device) => device,
                 Err(_) => break DeviceError::Invalid.into(),
             };
+            // Checked before deduplication, which doesn't create anything.
+            if device.destroyed {
+                break DeviceError::Destroyed.into();
+            }
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
                 trace
                     .lock()
-                    .add(trace::Action::CreateBindGroupLayout(fid.id(), desc.clone()));
+                    .record(trace::Action::CreateBindGroupLayout(fid.id(), desc.clone()));
             }
 
             let mut entry_map = FastHashMap::default();
@@ -3392,7 +3949,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             let (mut bind_group_layout_guard, _) = hub.bind_group_layouts.write(&mut token);
             match bind_group_layout_guard.get_mut(bind_group_layout_id) {
                 Ok(layout) => layout.device_id.value,
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.bind_group_layouts
                         .unregister_locked(bind_group_layout_id, &mut *bind_group_layout_guard);
                     return;
@@ -3433,7 +3990,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             if let Some(ref trace) = device.trace {
                 trace
                     .lock()
-                    .add(trace::Action::CreatePipelineLayout(fid.id(), desc.clone()));
+                    .record(trace::Action::CreatePipelineLayout(fid.id(), desc.clone()));
             }
 
             let layout = {
@@ -3468,7 +4025,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     layout.device_id.value,
                     layout.life_guard.ref_count.take().unwrap(),
                 ),
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.pipeline_layouts
                         .unregister_locked(pipeline_layout_id, &mut *pipeline_layout_guard);
                     return;
@@ -3511,7 +4068,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             if let Some(ref trace) = device.trace {
                 trace
                     .lock()
-                    .add(trace::Action::CreateBindGroup(fid.id(), desc.clone()));
+                    .record(trace::Action::CreateBindGroup(fid.id(), desc.clone()));
             }
 
             let bind_group_layout = match bind_group_layout_guard.get(desc.layout) {
@@ -3567,7 +4124,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     bind_group.life_guard.ref_count.take();
                     bind_group.device_id.value
                 }
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.bind_groups
                         .unregister_locked(bind_group_id, &mut *bind_group_guard);
                     return;
@@ -3608,26 +4165,41 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
                 let mut trace = trace.lock();
+                let mut defines = Vec::new();
                 let data = match source {
                     pipeline::ShaderModuleSource::SpirV(ref spv) => {
-                        trace.make_binary("spv", unsafe {
+                        trace.make_resource_binary("spv", unsafe {
                             std::slice::from_raw_parts(spv.as_ptr() as *const u8, spv.len() * 4)
                         })
                     }
                     pipeline::ShaderModuleSource::Wgsl(ref code) => {
-                        trace.make_binary("wgsl", code.as_bytes())
+                        trace.make_resource_binary("wgsl", code.as_bytes())
+                    }
+                    pipeline::ShaderModuleSource::Glsl {
+                        ref code,
+                        stage,
+                        defines: ref glsl_defines,
+                    } => {
+                        defines.extend(glsl_defines.iter().map(|(k, v)| (k.clone(), v.clone())));
+                        defines.sort();
+                        let &(extension, _) = trace::GLSL_EXTENSIONS
+                            .iter()
+                            .find(|&&(_, s)| s == stage)
+                            .unwrap();
+                        trace.make_resource_binary(extension, code.as_bytes())
                     }
                     pipeline::ShaderModuleSource::Naga(_) => {
                         // we don't want to enable Naga serialization just for this alone
-                        trace.make_binary("ron", &[])
+                        trace.make_resource_binary("ron", &[])
                     }
                 };
-                trace.add(trace::Action::CreateShaderModule {
+                trace.record(trace::Action::CreateShaderModule {
                     id: fid.id(),
                     desc: desc.clone(),
                     data,
+                    defines,
                 });
-            };
+            }
 
             let shader = match device.create_shader_module(device_id, desc, source) {
                 Ok(shader) => shader,
@@ -3658,7 +4230,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             if let Some(ref trace) = device.trace {
                 trace
                     .lock()
-                    .add(trace::Action::DestroyShaderModule(shader_module_id));
+                    .record(trace::Action::DestroyShaderModule(shader_module_id));
             }
             unsafe {
                 device.raw.destroy_shader_module(module.raw);
@@ -3684,6 +4256,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 Ok(device) => device,
                 Err(_) => break DeviceError::Invalid.into(),
             };
+            if device.destroyed {
+                break DeviceError::Destroyed.into();
+            }
 
             let dev_stored = Stored {
                 value: id::Valid(device_id),
@@ -3697,13 +4272,13 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 device.private_features,
                 &desc.label,
                 #[cfg(feature = "trace")]
-                device.trace.is_some(),
+                device.active_trace().is_some(),
             ) {
                 Ok(cmd_buf) => cmd_buf,
                 Err(e) => break e,
             };
 
-            let mut raw = command_buffer.raw.first_mut().unwrap();
+            let mut raw = command_buffer.data.get_mut().raw.first_mut().unwrap();
             unsafe {
                 if let Some(ref label) = desc.label {
                     device.raw.set_command_buffer_name(&mut raw, label);
@@ -3733,9 +4308,10 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (cmdbuf, _) = hub
             .command_buffers
             .unregister(command_encoder_id, &mut token);
-        if let Some(cmdbuf) = cmdbuf {
-            let device = &mut device_guard[cmdbuf.device_id.value];
-            device.untrack::<G>(&hub, &cmdbuf.trackers, &mut token);
+        if let Some(mut cmdbuf) = cmdbuf {
+            let data = cmdbuf.data.get_mut();
+            let device = &mut device_guard[data.device_id.value];
+            device.untrack::<G>(&hub, &data.trackers, &mut token);
             device.cmd_allocator.discard(cmdbuf);
         }
     }
@@ -3779,13 +4355,18 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 Ok(device) => device,
                 Err(_) => break command::RenderBundleError::INVALID_DEVICE,
             };
+            if device.destroyed {
+                break command::RenderBundleError::DESTROYED_DEVICE;
+            }
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
-                trace.lock().add(trace::Action::CreateRenderBundle {
+                trace.lock().record(trace::Action::CreateRenderBundle {
                     id: fid.id(),
                     desc: trace::new_render_bundle_encoder_descriptor(
                         desc.label.clone(),
                         &bundle_encoder.context,
+                        bundle_encoder.is_depth_read_only,
+                        bundle_encoder.is_stencil_read_only,
                     ),
                     base: bundle_encoder.to_base_pass(),
                 });
@@ -3830,7 +4411,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     bundle.life_guard.ref_count.take();
                     bundle.device_id.value
                 }
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.render_bundles
                         .unregister_locked(render_bundle_id, &mut *bundle_guard);
                     return;
@@ -3863,9 +4444,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 Ok(device) => device,
                 Err(_) => break DeviceError::Invalid.into(),
             };
+            if device.destroyed {
+                break DeviceError::Destroyed.into();
+            }
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
-                trace.lock().add(trace::Action::CreateQuerySet {
+                trace.lock().record(trace::Action::CreateQuerySet {
                     id: fid.id(),
                     desc: desc.clone(),
                 });
@@ -3906,7 +4490,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 let (hal_type, elements) = conv::map_query_type(&desc.ty);
 
                 resource::QuerySet {
-                    raw: unsafe { device.raw.create_query_pool(hal_type, desc.count).unwrap() },
+                    raw: Some(unsafe {
+                        device.raw.create_query_pool(hal_type, desc.count).unwrap()
+                    }),
                     device_id: Stored {
                         value: id::Valid(device_id),
                         ref_count: device.life_guard.add_ref(),
@@ -3934,6 +4520,49 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         (id, Some(error))
     }
 
+    pub fn query_set_destroy<B: GfxBackend>(
+        &self,
+        query_set_id: id::QuerySetId,
+    ) -> Result<(), resource::DestroyError> {
+        span!(_guard, INFO, "QuerySet::destroy");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+
+        let (device_guard, mut token) = hub.devices.read(&mut token);
+
+        tracing::info!("QuerySet {:?} is destroyed", query_set_id);
+        let (mut query_set_guard, _) = hub.query_sets.write(&mut token);
+        let query_set = query_set_guard
+            .get_mut(query_set_id)
+            .map_err(resource::DestroyError::Invalid)?;
+
+        let device = &device_guard[query_set.device_id.value];
+
+        let raw = query_set
+            .raw
+            .take()
+            .ok_or(resource::DestroyError::AlreadyDestroyed)?;
+
+        // Only successful destructions are recorded, since they are unwrapped on replay.
+        #[cfg(feature = "trace")]
+        if let Some(ref trace) = device.trace {
+            trace
+                .lock()
+                .record(trace::Action::FreeQuerySet(query_set_id));
+        }
+        let last_submit_index = query_set
+            .life_guard
+            .submission_index
+            .load(Ordering::Acquire);
+        drop(query_set_guard);
+        device
+            .lock_life(&mut token)
+            .schedule_query_set_destruction(raw, last_submit_index);
+
+        Ok(())
+    }
+
     pub fn query_set_drop<B: GfxBackend>(&self, query_set_id: id::QuerySetId) {
         span!(_guard, INFO, "QuerySet::drop");
 
@@ -3942,9 +4571,17 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
         let device_id = {
             let (mut query_set_guard, _) = hub.query_sets.write(&mut token);
-            let query_set = query_set_guard.get_mut(query_set_id).unwrap();
-            query_set.life_guard.ref_count.take();
-            query_set.device_id.value
+            match query_set_guard.get_mut(query_set_id) {
+                Ok(query_set) => {
+                    query_set.life_guard.ref_count.take();
+                    query_set.device_id.value
+                }
+                Err(_) => {
+                    hub.query_sets
+                        .unregister_locked(query_set_id, &mut *query_set_guard);
+                    return;
+                }
+            }
         };
 
         let (device_guard, mut token) = hub.devices.read(&mut token);
@@ -3954,7 +4591,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         if let Some(ref trace) = device.trace {
             trace
                 .lock()
-                .add(trace::Action::DestroyQuerySet(query_set_id));
+                .record(trace::Action::DestroyQuerySet(query_set_id));
         }
 
         device
@@ -3991,7 +4628,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
-                trace.lock().add(trace::Action::CreateRenderPipeline {
+                trace.lock().record(trace::Action::CreateRenderPipeline {
                     id: fid.id(),
                     desc: desc.clone(),
                     implicit_context: implicit_context.clone(),
@@ -4073,7 +4710,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     pipeline.life_guard.ref_count.take();
                     (pipeline.device_id.value, pipeline.layout_id.clone())
                 }
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.render_pipelines
                         .unregister_locked(render_pipeline_id, &mut *pipeline_guard);
                     return;
@@ -4119,7 +4756,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             };
             #[cfg(feature = "trace")]
             if let Some(ref trace) = device.trace {
-                trace.lock().add(trace::Action::CreateComputePipeline {
+                trace.lock().record(trace::Action::CreateComputePipeline {
                     id: fid.id(),
                     desc: desc.clone(),
                     implicit_context: implicit_context.clone(),
@@ -4201,7 +4838,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     pipeline.life_guard.ref_count.take();
                     (pipeline.device_id.value, pipeline.layout_id.clone())
                 }
-                Err(InvalidId) => {
+                Err(_) => {
                     hub.compute_pipelines
                         .unregister_locked(compute_pipeline_id, &mut *pipeline_guard);
                     return;
@@ -4271,6 +4908,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let device = device_guard
             .get(device_id)
             .map_err(|_| DeviceError::Invalid)?;
+        if device.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
         let surface = surface_guard
             .get_mut(surface_id)
             .map_err(|_| swap_chain::CreateSwapChainError::InvalidSurface)?;
@@ -4325,7 +4965,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         if let Some(ref trace) = device.trace {
             trace
                 .lock()
-                .add(trace::Action::CreateSwapChain(sc_id, desc.clone()));
+                .record(trace::Action::CreateSwapChain(sc_id, desc.clone()));
         }
 
         let swap_chain = swap_chain::SwapChain {
@@ -4389,6 +5029,97 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         Ok(())
     }
 
+    /// Starts tracing the device into the `path` directory.
+    ///
+    /// The trace begins with the creation of all the live resources, followed by
+    /// the contents of the buffers and textures that can be restored, so that it
+    /// can be replayed without the actions preceding this call.
+    /// Command buffers that are being recorded at this point are not traced.
+    ///
+    /// Only devices requested with `adapter_request_traceable_device`, or with
+    /// a trace path, keep track of their live resources and can be traced.
+    #[cfg(feature = "trace")]
+    pub fn device_start_trace<B: GfxBackend>(
+        &self,
+        device_id: id::DeviceId,
+        path: &std::path::Path,
+    ) -> Result<(), StartTraceError> {
+        span!(_guard, INFO, "Device::start_trace");
+
+        let hub = B::hub(self);
+        {
+            let mut token = Token::root();
+            let (device_guard, _) = hub.devices.read(&mut token);
+            let device = device_guard
+                .get(device_id)
+                .map_err(|_| DeviceError::Invalid)?;
+            match device.trace {
+                Some(ref tracer) if tracer.lock().active.is_some() => {
+                    return Err(StartTraceError::AlreadyTracing)
+                }
+                Some(_) => {}
+                None => return Err(StartTraceError::NotTraceable),
+            }
+        }
+        // Flush the pending writes and wait for all the work to be done,
+        // so that the contents we read back are up to date.
+        self.queue_submit::<B>(device_id, &[])?;
+        self.device_poll::<B>(device_id, true)?;
+
+        let mut token = Token::root();
+        let (mut device_guard, mut token) = hub.devices.write(&mut token);
+        let (mut buffer_guard, mut token) = hub.buffers.write(&mut token);
+        let (texture_guard, _) = hub.textures.read(&mut token);
+        let device = device_guard
+            .get_mut(device_id)
+            .map_err(|_| DeviceError::Invalid)?;
+
+        let mut trace = trace::Trace::new(path).map_err(|e| StartTraceError::Io(e.kind()))?;
+        trace.add(trace::Action::Init {
+            desc: DeviceDescriptor {
+                label: None,
+                features: device.features,
+                limits: device.limits.clone(),
+            },
+            backend: B::VARIANT,
+        });
+        let contents = device.read_contents(device_id, &mut *buffer_guard, &*texture_guard)?;
+
+        let mut tracer = device.trace.as_ref().unwrap().lock();
+        let explicit_destructions = tracer.snapshot_into(&mut trace);
+        for item in contents {
+            let action = item.into_action(&mut trace);
+            trace.add(action);
+        }
+        tracer.active = Some(trace);
+        tracer.add_serialized(&explicit_destructions);
+        Ok(())
+    }
+
+    /// Stops tracing the device, finishing the trace file.
+    #[cfg(feature = "trace")]
+    pub fn device_stop_trace<B: GfxBackend>(
+        &self,
+        device_id: id::DeviceId,
+    ) -> Result<(), DeviceError> {
+        span!(_guard, INFO, "Device::stop_trace");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let (device_guard, _) = hub.devices.read(&mut token);
+        let device = device_guard
+            .get(device_id)
+            .map_err(|_| DeviceError::Invalid)?;
+        let trace = device
+            .trace
+            .as_ref()
+            .and_then(|tracer| tracer.lock().active.take());
+        if trace.is_none() {
+            tracing::warn!("Device {:?} is not being traced", device_id);
+        }
+        Ok(())
+    }
+
     fn poll_devices<B: GfxBackend>(
         &self,
         force_wait: bool,
@@ -4436,12 +5167,128 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         B::hub(self).devices.label_for_resource(id)
     }
 
+    /// Release the memory of all the buffers, textures, and query sets of the device
+    /// as soon as the GPU is done with them. The device can't be used for any new
+    /// work afterwards, but the IDs stay valid until they are dropped.
+    pub fn device_destroy<B: GfxBackend>(
+        &self,
+        device_id: id::DeviceId,
+    ) -> Result<(), resource::DestroyError> {
+        span!(_guard, INFO, "Device::destroy");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+
+        let callbacks = {
+            let (mut device_guard, mut token) = hub.devices.write(&mut token);
+            let device = device_guard
+                .get_mut(device_id)
+                .map_err(resource::DestroyError::Invalid)?;
+            if device.destroyed {
+                return Err(resource::DestroyError::AlreadyDestroyed);
+            }
+            tracing::info!("Device {:?} is destroyed", device_id);
+            device.destroyed = true;
+
+            // Pending writes are never going to be submitted now.
+            if let Some(cmdbuf) = device.pending_writes.finish() {
+                device.cmd_allocator.discard_internal(cmdbuf);
+            }
+            let pending_temp_resources = mem::take(&mut device.pending_writes.temp_resources);
+
+            let mut life_lock = device.lock_life(&mut token);
+            if let Err(error) = life_lock.triage_submissions(&device.raw, true) {
+                tracing::error!("failed to triage submissions: {}", error);
+            }
+            for (temp, memory) in pending_temp_resources {
+                life_lock.schedule_resource_destruction(temp, memory, 0);
+            }
+
+            let mut callbacks = Vec::new();
+            let (mut query_set_guard, mut token) = hub.query_sets.write(&mut token);
+            for (_, query_set) in query_set_guard.iter_mut(B::VARIANT) {
+                if query_set.device_id.value.0 != device_id {
+                    continue;
+                }
+                if let Some(raw) = query_set.raw.take() {
+                    life_lock.schedule_query_set_destruction(raw, 0);
+                }
+            }
+            let (mut buffer_guard, mut token) = hub.buffers.write(&mut token);
+            for (_, buffer) in buffer_guard.iter_mut(B::VARIANT) {
+                if buffer.device_id.value.0 != device_id {
+                    continue;
+                }
+                match mem::replace(&mut buffer.map_state, resource::BufferMapState::Idle) {
+                    resource::BufferMapState::Init {
+                        stage_buffer,
+                        stage_memory,
+                        ..
+                    } => life_lock.schedule_resource_destruction(
+                        queue::TempResource::Buffer(stage_buffer),
+                        stage_memory,
+                        0,
+                    ),
+                    resource::BufferMapState::Waiting(pending) => {
+                        callbacks.push((pending.op, resource::BufferMapAsyncStatus::ContextLost));
+                    }
+                    resource::BufferMapState::Active { .. } => {
+                        if let Err(error) = unmap_buffer(&device.raw, buffer) {
+                            tracing::error!("failed to unmap buffer: {}", error);
+                        }
+                    }
+                    resource::BufferMapState::Idle => {}
+                }
+                if let Some((raw, memory)) = buffer.raw.take() {
+                    life_lock.schedule_resource_destruction(
+                        queue::TempResource::Buffer(raw),
+                        memory,
+                        0,
+                    );
+                }
+            }
+            let (mut texture_guard, _) = hub.textures.write(&mut token);
+            for (_, texture) in texture_guard.iter_mut(B::VARIANT) {
+                if texture.device_id.value.0 != device_id {
+                    continue;
+                }
+                if let Some((raw, memory)) = texture.raw.take() {
+                    life_lock.schedule_resource_destruction(
+                        queue::TempResource::Image(raw),
+                        memory,
+                        0,
+                    );
+                }
+            }
+
+            life_lock.cleanup(&device.raw, &device.mem_allocator, &device.desc_allocator);
+            callbacks
+        };
+
+        fire_map_callbacks(callbacks);
+        Ok(())
+    }
+
     pub fn device_drop<B: GfxBackend>(&self, device_id: id::DeviceId) {
         span!(_guard, INFO, "Device::drop");
 
         let hub = B::hub(self);
         let mut token = Token::root();
-        let (device, _) = hub.devices.unregister(device_id, &mut token);
+        let device = {
+            let (mut device_guard, _) = hub.devices.write(&mut token);
+            match device_guard.get_mut(device_id) {
+                // The views point into the staging memory of the pending writes.
+                Ok(device) if device.pending_writes.has_write_views() => {
+                    tracing::info!(
+                        "Deferring the drop of device {:?} until its write views are dropped",
+                        device_id
+                    );
+                    device.drop_deferred = true;
+                    None
+                }
+                _ => hub.devices.unregister_locked(device_id, &mut *device_guard),
+            }
+        };
         if let Some(mut device) = device {
             device.prepare_to_die();
 
@@ -4457,6 +5304,26 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         }
     }
 
+    /// Drops the device if its drop was deferred by `device_drop`, and the last
+    /// write view is gone.
+    pub(crate) fn device_drop_deferred<B: GfxBackend>(&self, device_id: id::DeviceId) {
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let ready = {
+            let (mut device_guard, _) = hub.devices.write(&mut token);
+            match device_guard.get_mut(device_id) {
+                Ok(device) if device.drop_deferred && !device.pending_writes.has_write_views() => {
+                    device.drop_deferred = false;
+                    true
+                }
+                _ => false,
+            }
+        };
+        if ready {
+            self.device_drop::<B>(device_id);
+        }
+    }
+
     pub fn buffer_map_async<B: GfxBackend>(
         &self,
         buffer_id: id::BufferId,
@@ -4483,7 +5350,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             let (mut buffer_guard, _) = hub.buffers.write(&mut token);
             let buffer = buffer_guard
                 .get_mut(buffer_id)
-                .map_err(|_| resource::BufferAccessError::Invalid)?;
+                .map_err(resource::BufferAccessError::Invalid)?;
 
             check_buffer_usage(buffer.usage, pub_usage)?;
             buffer.map_state = match buffer.map_state {
@@ -4535,7 +5402,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (buffer_guard, _) = hub.buffers.read(&mut token);
         let buffer = buffer_guard
             .get(buffer_id)
-            .map_err(|_| resource::BufferAccessError::Invalid)?;
+            .map_err(resource::BufferAccessError::Invalid)?;
 
         match buffer.map_state {
             resource::BufferMapState::Init { ptr, .. }
@@ -4561,7 +5428,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let (mut buffer_guard, _) = hub.buffers.write(&mut token);
         let buffer = buffer_guard
             .get_mut(buffer_id)
-            .map_err(|_| resource::BufferAccessError::Invalid)?;
+            .map_err(resource::BufferAccessError::Invalid)?;
         let device = &mut device_guard[buffer.device_id.value];
 
         tracing::debug!("Buffer {:?} map state -> Idle", buffer_id);
@@ -4573,8 +5440,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 needs_flush,
             } => {
                 #[cfg(feature = "trace")]
-                if let Some(ref trace) = device.trace {
-                    let mut trace = trace.lock();
+                if let Some(mut trace) = device.active_trace() {
                     let data = trace.make_binary("bin", unsafe {
                         std::slice::from_raw_parts(ptr.as_ptr(), buffer.size as usize)
                     });
@@ -4585,7 +5451,11 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         queued: true,
                     });
                 }
-                let _ = ptr;
+                if let Some(ref mut index_tracker) = buffer.index_tracker {
+                    index_tracker.write(0, unsafe {
+                        std::slice::from_raw_parts(ptr.as_ptr(), buffer.size as usize)
+                    });
+                }
 
                 if needs_flush {
                     stage_memory.flush_range(&device.raw, 0, None)?;
@@ -4643,8 +5513,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             } => {
                 if host == HostMap::Write {
                     #[cfg(feature = "trace")]
-                    if let Some(ref trace) = device.trace {
-                        let mut trace = trace.lock();
+                    if let Some(mut trace) = device.active_trace() {
                         let size = sub_range.size_to(buffer.size);
                         let data = trace.make_binary("bin", unsafe {
                             std::slice::from_raw_parts(ptr.as_ptr(), size as usize)
@@ -4653,10 +5522,16 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             id: buffer_id,
                             data,
                             range: sub_range.offset..sub_range.offset + size,
-                            queued: false,
+                            // Buffers mapped at creation in place aren't mappable on replay.
+                            queued: !buffer.usage.contains(wgt::BufferUsage::MAP_WRITE),
+                        });
+                    }
+                    if let Some(ref mut index_tracker) = buffer.index_tracker {
+                        let size = sub_range.size_to(buffer.size);
+                        index_tracker.write(sub_range.offset, unsafe {
+                            std::slice::from_raw_parts(ptr.as_ptr(), size as usize)
                         });
                     }
-                    let _ = (ptr, sub_range);
                 }
                 unmap_buffer(&device.raw, buffer)?;
             }
@@ -4673,3 +5548,1451 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             .map(|pending_callback| fire_map_callbacks(pending_callback.into_iter()))
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{alloc, conv, DeviceDescriptor, DeviceError, HostMap, ShaderCacheStats};
+    use crate::{
+        binding_model::{
+            BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindingResource,
+            CreateBindGroupError, CreateBindGroupLayoutError, PipelineLayoutDescriptor,
+        },
+        command::{
+            render_ffi, ColorAttachmentDescriptor, CommandAllocatorError, CommandEncoderError,
+            CopyError, LoadOp, PassChannel, RenderPass, RenderPassDescriptor, StoreOp,
+            TransferError,
+        },
+        device::queue::{QueueSubmitError, QueueWriteError},
+        hub::{GfxBackend, Global, IdentityManagerFactory, InvalidId, Token},
+        id,
+        instance::{AdapterInputs, RequestAdapterOptions},
+        pipeline::{
+            ComputePipelineDescriptor, CreateShaderModuleError, FragmentState,
+            ProgrammableStageDescriptor, RenderPipelineDescriptor, ShaderEntryPoint,
+            ShaderModuleDescriptor, ShaderModuleSource, VertexBufferLayout, VertexState,
+        },
+        resource::{
+            BufferDescriptor, BufferMapAsyncStatus, BufferMapOperation, BufferUse,
+            CreateBufferError, CreateSamplerError, CreateTextureError, CreateTextureViewError,
+            DestroyError, SamplerDescriptor, TextureDescriptor, TextureUse, TextureViewDescriptor,
+        },
+    };
+    use hal::device::Device as _;
+    use std::borrow::Cow;
+
+    pub(crate) type TestGlobal = Global<IdentityManagerFactory>;
+
+    unsafe extern "C" fn map_callback(status: BufferMapAsyncStatus, user_data: *mut u8) {
+        *(user_data as *mut bool) = matches!(status, BufferMapAsyncStatus::Success);
+    }
+
+    /// Creates a device on the primary adapter, if there is one supporting
+    /// the requested features and push constant size.
+    ///
+    /// Tests that get `None` have nothing to run on, and are reported as
+    /// skipped in the output.
+    pub(crate) fn create_device(
+        desc: &DeviceDescriptor,
+    ) -> Option<(TestGlobal, id::AdapterId, id::DeviceId)> {
+        // The test harness names the thread of every test after it.
+        let test = std::thread::current()
+            .name()
+            .unwrap_or("<unnamed>")
+            .to_string();
+        let global = Global::new("test", IdentityManagerFactory, wgt::BackendBit::PRIMARY);
+        let adapter = match global.request_adapter(
+            &RequestAdapterOptions::default(),
+            AdapterInputs::Mask(wgt::BackendBit::PRIMARY, |_| ()),
+        ) {
+            Ok(adapter) => adapter,
+            Err(_) => {
+                println!("Skipped '{}' due to a missing adapter", test);
+                return None;
+            }
+        };
+        let features = global.dyn_adapter_features(adapter).unwrap();
+        let limits = global.dyn_adapter_limits(adapter).unwrap();
+        if !features.contains(desc.features) {
+            println!(
+                "Skipped '{}' due to missing features {:?}",
+                test,
+                desc.features - features
+            );
+            global.dyn_adapter_drop(adapter);
+            return None;
+        }
+        if limits.max_push_constant_size < desc.limits.max_push_constant_size {
+            println!(
+                "Skipped '{}' due to a push constant size limit of {}",
+                test, limits.max_push_constant_size
+            );
+            global.dyn_adapter_drop(adapter);
+            return None;
+        }
+        let (device, error) = global.dyn_adapter_request_device(adapter, desc, None, ());
+        assert!(error.is_none());
+        Some((global, adapter, device))
+    }
+
+    fn create_buffer(global: &TestGlobal, device: id::DeviceId) -> id::BufferId {
+        let (buffer, error) = global.dyn_device_create_buffer(
+            device,
+            &BufferDescriptor {
+                label: None,
+                size: 256,
+                usage: wgt::BufferUsage::COPY_SRC | wgt::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        buffer
+    }
+
+    pub(crate) fn create_command_encoder(
+        global: &TestGlobal,
+        device: id::DeviceId,
+    ) -> id::CommandEncoderId {
+        let (encoder, error) = global.dyn_device_create_command_encoder(
+            device,
+            &wgt::CommandEncoderDescriptor { label: None },
+            (),
+        );
+        assert!(error.is_none());
+        encoder
+    }
+
+    pub(crate) fn finish(
+        global: &TestGlobal,
+        encoder: id::CommandEncoderId,
+    ) -> id::CommandBufferId {
+        let (command_buffer, error) =
+            global.dyn_command_encoder_finish(encoder, &wgt::CommandBufferDescriptor::default());
+        assert!(error.is_none());
+        command_buffer
+    }
+
+    #[test]
+    fn create_on_destroyed_device() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        global.dyn_device_destroy(device).unwrap();
+
+        let (_, error) =
+            global.dyn_device_create_sampler(device, &SamplerDescriptor::default(), ());
+        assert!(matches!(
+            error,
+            Some(CreateSamplerError::Device(DeviceError::Destroyed))
+        ));
+        let (_, error) = global.dyn_device_create_bind_group_layout(
+            device,
+            &BindGroupLayoutDescriptor {
+                label: None,
+                entries: Cow::Borrowed(&[]),
+            },
+            (),
+        );
+        assert!(matches!(
+            error,
+            Some(CreateBindGroupLayoutError::Device(DeviceError::Destroyed))
+        ));
+        let (_, error) = global.dyn_device_create_shader_module(
+            device,
+            &ShaderModuleDescriptor {
+                label: None,
+                flags: wgt::ShaderFlags::VALIDATION,
+                interface: None,
+            },
+            ShaderModuleSource::Wgsl(Cow::Borrowed("")),
+            (),
+        );
+        assert!(matches!(
+            error,
+            Some(CreateShaderModuleError::Device(DeviceError::Destroyed))
+        ));
+        let (_, error) = global.dyn_device_create_command_encoder(
+            device,
+            &wgt::CommandEncoderDescriptor { label: None },
+            (),
+        );
+        assert!(matches!(
+            error,
+            Some(CommandAllocatorError::Device(DeviceError::Destroyed))
+        ));
+        assert!(matches!(
+            global.dyn_queue_submit(device, &[]),
+            Err(QueueSubmitError::Queue(DeviceError::Destroyed))
+        ));
+
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn destroyed_buffer_in_copy() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let source = create_buffer(&global, device);
+        let destination = create_buffer(&global, device);
+
+        // Destroyed before recording: the copy itself fails.
+        global.dyn_buffer_destroy(destination).unwrap();
+        let encoder = create_command_encoder(&global, device);
+        assert!(matches!(
+            global.dyn_command_encoder_copy_buffer_to_buffer(
+                encoder,
+                source,
+                0,
+                destination,
+                0,
+                256
+            ),
+            Err(CopyError::Transfer(TransferError::DestroyedBuffer(buffer))) if buffer == destination
+        ));
+        global.dyn_command_encoder_drop(encoder);
+
+        // Destroyed after recording: the submission fails.
+        let destination = create_buffer(&global, device);
+        let encoder = create_command_encoder(&global, device);
+        global
+            .dyn_command_encoder_copy_buffer_to_buffer(encoder, source, 0, destination, 0, 256)
+            .unwrap();
+        let command_buffer = finish(&global, encoder);
+        global.dyn_buffer_destroy(source).unwrap();
+        assert!(matches!(
+            global.dyn_queue_submit(device, &[command_buffer]),
+            Err(QueueSubmitError::DestroyedBuffer(buffer)) if buffer == source
+        ));
+
+        global.dyn_buffer_drop(source, true);
+        global.dyn_buffer_drop(destination, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn failed_submit_keeps_pending_writes() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let buffer = create_buffer(&global, device);
+        let destroyed = create_buffer(&global, device);
+        let (readback, error) = global.dyn_device_create_buffer(
+            device,
+            &BufferDescriptor {
+                label: None,
+                size: 256,
+                usage: wgt::BufferUsage::MAP_READ | wgt::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            },
+            (),
+        );
+        assert!(error.is_none());
+
+        global
+            .dyn_queue_write_buffer(device, buffer, 0, &[7; 256])
+            .unwrap();
+        let encoder = create_command_encoder(&global, device);
+        global
+            .dyn_command_encoder_copy_buffer_to_buffer(encoder, destroyed, 0, buffer, 0, 256)
+            .unwrap();
+        let command_buffer = finish(&global, encoder);
+        global.dyn_buffer_destroy(destroyed).unwrap();
+        assert!(matches!(
+            global.dyn_queue_submit(device, &[command_buffer]),
+            Err(QueueSubmitError::DestroyedBuffer(id)) if id == destroyed
+        ));
+        global.dyn_command_buffer_drop(command_buffer);
+
+        // The write goes with the next submission.
+        let encoder = create_command_encoder(&global, device);
+        global
+            .dyn_command_encoder_copy_buffer_to_buffer(encoder, buffer, 0, readback, 0, 256)
+            .unwrap();
+        let command_buffer = finish(&global, encoder);
+        global.dyn_queue_submit(device, &[command_buffer]).unwrap();
+        let mut mapped = false;
+        let user_data: *mut bool = &mut mapped;
+        global
+            .dyn_buffer_map_async(
+                readback,
+                0..256,
+                BufferMapOperation {
+                    host: HostMap::Read,
+                    callback: map_callback,
+                    user_data: user_data as *mut u8,
+                },
+            )
+            .unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+        assert!(mapped);
+        let ptr = global
+            .dyn_buffer_get_mapped_range(readback, 0, None)
+            .unwrap();
+        assert_eq!(
+            unsafe { std::slice::from_raw_parts(ptr, 256) },
+            &[7; 256][..]
+        );
+        global.dyn_buffer_unmap(readback).unwrap();
+
+        global.dyn_buffer_drop(buffer, true);
+        global.dyn_buffer_drop(destroyed, true);
+        global.dyn_buffer_drop(readback, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn drop_device_with_live_write_view() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let buffer = create_buffer(&global, device);
+
+        let mut view = global
+            .dyn_queue_write_buffer_with(device, buffer, 0, wgt::BufferSize::new(16).unwrap())
+            .unwrap();
+        global.dyn_buffer_drop(buffer, false);
+        global.dyn_device_drop(device);
+
+        // The staging memory stays mapped until the view is gone.
+        assert!(global.dyn_device_features(device).is_ok());
+        view.copy_from_slice(&[1; 16]);
+        assert_eq!(&view[..], &[1; 16]);
+        assert!(matches!(
+            view.finish(),
+            Err(QueueWriteError::Queue(DeviceError::Invalid))
+        ));
+        assert!(global.dyn_device_features(device).is_err());
+
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn memory_report_write_paths() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let report = global.dyn_device_memory_report(device).unwrap();
+        let new_buffer = |usage| {
+            let (buffer, error) = global.dyn_device_create_buffer(
+                device,
+                &BufferDescriptor {
+                    label: None,
+                    size: 16,
+                    usage,
+                    mapped_at_creation: false,
+                },
+                (),
+            );
+            assert!(error.is_none());
+            buffer
+        };
+        // Mappable buffers are host visible on every adapter.
+        let buffer = new_buffer(
+            wgt::BufferUsage::MAP_WRITE | wgt::BufferUsage::COPY_SRC | wgt::BufferUsage::COPY_DST,
+        );
+        let readback = new_buffer(wgt::BufferUsage::MAP_READ | wgt::BufferUsage::COPY_DST);
+        let copy_to_readback = || {
+            let encoder = create_command_encoder(&global, device);
+            global
+                .dyn_command_encoder_copy_buffer_to_buffer(encoder, buffer, 0, readback, 0, 16)
+                .unwrap();
+            let command_buffer = finish(&global, encoder);
+            global.dyn_queue_submit(device, &[command_buffer]).unwrap();
+        };
+
+        global
+            .dyn_queue_write_buffer(device, buffer, 0, &[1; 16])
+            .unwrap();
+        let idle = global.dyn_device_memory_report(device).unwrap();
+        assert_eq!(idle.in_place_writes, report.in_place_writes + 1);
+        assert_eq!(idle.staged_writes, report.staged_writes);
+
+        // The submission isn't triaged before the writes, so the GPU may still
+        // be reading the buffer, and the writes go through staging memory.
+        copy_to_readback();
+        global
+            .dyn_queue_write_buffer(device, buffer, 0, &[2; 8])
+            .unwrap();
+        global
+            .dyn_queue_write_buffer(device, buffer, 8, &[3; 8])
+            .unwrap();
+        let in_flight = global.dyn_device_memory_report(device).unwrap();
+        assert_eq!(in_flight.in_place_writes, idle.in_place_writes);
+        assert_eq!(in_flight.staged_writes, idle.staged_writes + 2);
+
+        global.dyn_queue_submit(device, &[]).unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+        copy_to_readback();
+        global.dyn_device_poll(device, true).unwrap();
+        let mut mapped = false;
+        let user_data: *mut bool = &mut mapped;
+        global
+            .dyn_buffer_map_async(
+                readback,
+                0..16,
+                BufferMapOperation {
+                    host: HostMap::Read,
+                    callback: map_callback,
+                    user_data: user_data as *mut u8,
+                },
+            )
+            .unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+        assert!(mapped);
+        let ptr = global
+            .dyn_buffer_get_mapped_range(readback, 0, None)
+            .unwrap();
+        let contents = unsafe { std::slice::from_raw_parts(ptr, 16) };
+        assert_eq!(contents[..8], [2; 8]);
+        assert_eq!(contents[8..], [3; 8]);
+        global.dyn_buffer_unmap(readback).unwrap();
+
+        global
+            .dyn_queue_write_buffer(device, buffer, 0, &[4; 16])
+            .unwrap();
+        let done = global.dyn_device_memory_report(device).unwrap();
+        assert_eq!(done.in_place_writes, in_flight.in_place_writes + 1);
+        assert_eq!(done.staged_writes, in_flight.staged_writes);
+
+        global.dyn_buffer_drop(buffer, true);
+        global.dyn_buffer_drop(readback, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn invalid_id_source() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (invalid_buffer, error) = global.dyn_device_create_buffer(
+            device,
+            &BufferDescriptor {
+                label: None,
+                size: 256,
+                usage: wgt::BufferUsage::empty(),
+                mapped_at_creation: false,
+            },
+            (),
+        );
+        assert!(error.is_some());
+        assert!(matches!(
+            global.dyn_buffer_destroy(invalid_buffer),
+            Err(DestroyError::Invalid(InvalidId::Failed { .. }))
+        ));
+
+        let destination = create_buffer(&global, device);
+        let encoder = create_command_encoder(&global, device);
+        let error = global
+            .dyn_command_encoder_copy_buffer_to_buffer(
+                encoder,
+                invalid_buffer,
+                0,
+                destination,
+                0,
+                256,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            CopyError::Transfer(TransferError::InvalidBuffer(buffer, InvalidId::Failed { .. }))
+                if buffer == invalid_buffer
+        ));
+        let source = std::error::Error::source(&error)
+            .and_then(std::error::Error::source)
+            .unwrap();
+        assert!(source.is::<InvalidId>());
+        global.dyn_command_encoder_drop(encoder);
+
+        let (invalid_texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 64,
+                    height: 64,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Rgba8Unorm,
+                usage: wgt::TextureUsage::empty(),
+            },
+            (),
+        );
+        assert!(error.is_some());
+        let (_, error) =
+            global.dyn_texture_create_view(invalid_texture, &TextureViewDescriptor::default(), ());
+        assert!(matches!(
+            error,
+            Some(CreateTextureViewError::InvalidTexture(
+                InvalidId::Failed { .. }
+            ))
+        ));
+
+        global.dyn_buffer_drop(destination, true);
+        global.dyn_device_destroy(device).unwrap();
+        let (invalid_encoder, error) = global.dyn_device_create_command_encoder(
+            device,
+            &wgt::CommandEncoderDescriptor { label: None },
+            (),
+        );
+        assert!(error.is_some());
+        let (_, error) = global
+            .dyn_command_encoder_finish(invalid_encoder, &wgt::CommandBufferDescriptor::default());
+        assert!(matches!(
+            error,
+            Some(CommandEncoderError::Invalid(InvalidId::Failed { .. }))
+        ));
+
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn duplicate_command_buffer_in_submit() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let encoder = create_command_encoder(&global, device);
+        let command_buffer = finish(&global, encoder);
+        assert!(matches!(
+            global.dyn_queue_submit(device, &[command_buffer, command_buffer]),
+            Err(QueueSubmitError::DuplicateCommandBuffer(duplicate)) if duplicate == command_buffer
+        ));
+        // The rejected submission leaves the command buffer intact.
+        global.dyn_queue_submit(device, &[command_buffer]).unwrap();
+
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn destroyed_texture_in_pass() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 64,
+                    height: 64,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Rgba8Unorm,
+                usage: wgt::TextureUsage::RENDER_ATTACHMENT,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (view, error) =
+            global.dyn_texture_create_view(texture, &TextureViewDescriptor::default(), ());
+        assert!(error.is_none());
+
+        let encoder = create_command_encoder(&global, device);
+        let pass = RenderPass::new(
+            encoder,
+            &RenderPassDescriptor {
+                label: None,
+                color_attachments: Cow::Owned(vec![ColorAttachmentDescriptor {
+                    attachment: view,
+                    resolve_target: None,
+                    channel: PassChannel {
+                        load_op: LoadOp::Clear,
+                        store_op: StoreOp::Store,
+                        clear_value: wgt::Color::BLACK,
+                        read_only: false,
+                    },
+                }]),
+                depth_stencil_attachment: None,
+            },
+        );
+        global
+            .dyn_command_encoder_run_render_pass(encoder, &pass)
+            .unwrap();
+        let command_buffer = finish(&global, encoder);
+        global.dyn_texture_destroy(texture).unwrap();
+        assert!(matches!(
+            global.dyn_queue_submit(device, &[command_buffer]),
+            Err(QueueSubmitError::DestroyedTexture(destroyed)) if destroyed == texture
+        ));
+
+        global.dyn_texture_view_drop(view, true).unwrap();
+        global.dyn_texture_drop(texture, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    /// Binds a storage texture view of `format` with atomic access.
+    fn bind_atomic_storage(
+        global: &TestGlobal,
+        device: id::DeviceId,
+        format: wgt::TextureFormat,
+    ) -> Option<CreateBindGroupError> {
+        let entries = [wgt::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgt::ShaderStage::COMPUTE,
+            ty: wgt::BindingType::StorageTexture {
+                access: wgt::StorageTextureAccess::Atomic,
+                format,
+                view_dimension: wgt::TextureViewDimension::D2,
+            },
+            count: None,
+        }];
+        let (layout, error) = global.dyn_device_create_bind_group_layout(
+            device,
+            &BindGroupLayoutDescriptor {
+                label: None,
+                entries: Cow::Borrowed(&entries),
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 16,
+                    height: 16,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format,
+                usage: wgt::TextureUsage::STORAGE,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (view, error) =
+            global.dyn_texture_create_view(texture, &TextureViewDescriptor::default(), ());
+        assert!(error.is_none());
+
+        let (bind_group, error) = global.dyn_device_create_bind_group(
+            device,
+            &BindGroupDescriptor {
+                label: None,
+                layout,
+                entries: Cow::Owned(vec![BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(view),
+                }]),
+            },
+            (),
+        );
+
+        global.dyn_bind_group_drop(bind_group);
+        global.dyn_texture_view_drop(view, true).unwrap();
+        global.dyn_texture_drop(texture, true);
+        global.dyn_bind_group_layout_drop(layout);
+        error
+    }
+
+    #[test]
+    fn storage_atomics_binding() {
+        let format = wgt::TextureFormat::R32Uint;
+
+        // Atomics aren't part of the guaranteed format features.
+        if let Some((global, adapter, device)) = create_device(&DeviceDescriptor::default()) {
+            assert!(matches!(
+                bind_atomic_storage(&global, device, format),
+                Some(CreateBindGroupError::MissingFeatures(features))
+                    if features == wgt::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            ));
+            global.dyn_device_drop(device);
+            global.dyn_adapter_drop(adapter);
+        }
+
+        // With adapter specific features, they depend on the adapter.
+        let (global, adapter, device) = match create_device(&DeviceDescriptor {
+            label: None,
+            features: wgt::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            limits: wgt::Limits::default(),
+        }) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let format_features = global
+            .dyn_adapter_get_texture_format_features(adapter, format)
+            .unwrap();
+        let error = bind_atomic_storage(&global, device, format);
+        if format_features.flags.contains(
+            wgt::TextureFormatFeatureFlags::STORAGE_READ_WRITE
+                | wgt::TextureFormatFeatureFlags::STORAGE_ATOMICS,
+        ) {
+            assert!(error.is_none());
+        } else {
+            assert!(matches!(
+                error,
+                Some(CreateBindGroupError::StorageAtomicsNotSupported(f)) if f == format
+            ));
+        }
+
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn unknown_indices_are_conservative() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor {
+            label: None,
+            features: wgt::Features::ROBUST_INDEX_VALIDATION,
+            limits: wgt::Limits::default(),
+        }) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (shader_module, error) = global.dyn_device_create_shader_module(
+            device,
+            &ShaderModuleDescriptor {
+                label: None,
+                flags: wgt::ShaderFlags::VALIDATION,
+                interface: None,
+            },
+            ShaderModuleSource::Wgsl(Cow::Borrowed(
+                "[[stage(vertex)]]
+                fn vs_main([[location(0)]] position: vec2<f32>) -> [[builtin(position)]] vec4<f32> {
+                    return vec4<f32>(position, 0.0, 1.0);
+                }
+
+                [[stage(fragment)]]
+                fn fs_main() -> [[location(0)]] vec4<f32> {
+                    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+                }",
+            )),
+            (),
+        );
+        assert!(error.is_none());
+        let (pipeline_layout, error) = global.dyn_device_create_pipeline_layout(
+            device,
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: Cow::Borrowed(&[]),
+                push_constant_ranges: Cow::Borrowed(&[]),
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let attributes = [wgt::VertexAttribute {
+            format: wgt::VertexFormat::Float32x2,
+            offset: 0,
+            shader_location: 0,
+        }];
+        let vertex_buffers = [VertexBufferLayout {
+            array_stride: 8,
+            step_mode: wgt::InputStepMode::Vertex,
+            attributes: Cow::Borrowed(&attributes),
+        }];
+        let targets = [wgt::ColorTargetState::from(wgt::TextureFormat::Rgba8Unorm)];
+        let (pipeline, _, error) = global.dyn_device_create_render_pipeline(
+            device,
+            &RenderPipelineDescriptor {
+                label: None,
+                layout: Some(pipeline_layout),
+                vertex: VertexState {
+                    stage: ProgrammableStageDescriptor {
+                        module: shader_module,
+                        entry_point: Cow::Borrowed("vs_main"),
+                    },
+                    buffers: Cow::Borrowed(&vertex_buffers),
+                },
+                primitive: wgt::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgt::MultisampleState::default(),
+                fragment: Some(FragmentState {
+                    stage: ProgrammableStageDescriptor {
+                        module: shader_module,
+                        entry_point: Cow::Borrowed("fs_main"),
+                    },
+                    targets: Cow::Borrowed(&targets),
+                }),
+            },
+            (),
+            None,
+        );
+        assert!(error.is_none());
+        let (texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 64,
+                    height: 64,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Rgba8Unorm,
+                usage: wgt::TextureUsage::RENDER_ATTACHMENT,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (view, error) =
+            global.dyn_texture_create_view(texture, &TextureViewDescriptor::default(), ());
+        assert!(error.is_none());
+
+        let new_buffer = |size, usage| {
+            let (buffer, error) = global.dyn_device_create_buffer(
+                device,
+                &BufferDescriptor {
+                    label: None,
+                    size,
+                    usage,
+                    mapped_at_creation: false,
+                },
+                (),
+            );
+            assert!(error.is_none());
+            buffer
+        };
+        // Three vertices, so index 3 and above are out of bounds.
+        let vertex_buffer = new_buffer(24, wgt::BufferUsage::VERTEX);
+        let full_vertex_buffer = new_buffer(
+            (u16::MAX as wgt::BufferAddress + 1) * 8,
+            wgt::BufferUsage::VERTEX,
+        );
+        let indices = |values: [u16; 4]| {
+            values
+                .iter()
+                .flat_map(|index| index.to_ne_bytes().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let source = new_buffer(8, wgt::BufferUsage::COPY_SRC | wgt::BufferUsage::COPY_DST);
+        global
+            .dyn_queue_write_buffer(device, source, 0, &indices([0, 1, 2, 0]))
+            .unwrap();
+        let copied = new_buffer(8, wgt::BufferUsage::INDEX | wgt::BufferUsage::COPY_DST);
+        let storage = new_buffer(8, wgt::BufferUsage::INDEX | wgt::BufferUsage::STORAGE);
+        let written = new_buffer(8, wgt::BufferUsage::INDEX | wgt::BufferUsage::COPY_DST);
+        global
+            .dyn_queue_write_buffer(device, written, 0, &indices([0, 1, 5, 0]))
+            .unwrap();
+
+        let draw = |index_buffer, vertex_buffer, copy| {
+            let encoder = create_command_encoder(&global, device);
+            if copy {
+                global
+                    .dyn_command_encoder_copy_buffer_to_buffer(
+                        encoder,
+                        source,
+                        0,
+                        index_buffer,
+                        0,
+                        8,
+                    )
+                    .unwrap();
+            }
+            let mut pass = RenderPass::new(
+                encoder,
+                &RenderPassDescriptor {
+                    label: None,
+                    color_attachments: Cow::Owned(vec![ColorAttachmentDescriptor {
+                        attachment: view,
+                        resolve_target: None,
+                        channel: PassChannel {
+                            load_op: LoadOp::Clear,
+                            store_op: StoreOp::Store,
+                            clear_value: wgt::Color::BLACK,
+                            read_only: false,
+                        },
+                    }]),
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_ffi::wgpu_render_pass_set_pipeline(&mut pass, pipeline);
+            render_ffi::wgpu_render_pass_set_vertex_buffer(&mut pass, 0, vertex_buffer, 0, None);
+            pass.set_index_buffer(index_buffer, wgt::IndexFormat::Uint16, 0, None);
+            render_ffi::wgpu_render_pass_draw_indexed(&mut pass, 3, 1, 0, 0, 0);
+            global
+                .dyn_command_encoder_run_render_pass(encoder, &pass)
+                .unwrap();
+            finish(&global, encoder)
+        };
+
+        // Indices copied by the GPU, or written by shaders, are unknown,
+        // so they may reference any vertex the format can represent.
+        for &(index_buffer, copy) in &[(copied, true), (storage, false)] {
+            let command_buffer = draw(index_buffer, vertex_buffer, copy);
+            assert!(matches!(
+                global.dyn_queue_submit(device, &[command_buffer]),
+                Err(QueueSubmitError::IndexBeyondVertexLimit {
+                    buffer,
+                    max_index,
+                    ..
+                }) if buffer == index_buffer && max_index == u16::MAX as u32
+            ));
+        }
+        // Unless the vertex buffers cover every such index.
+        let command_buffer = draw(copied, full_vertex_buffer, true);
+        global.dyn_queue_submit(device, &[command_buffer]).unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+        // Indices written by the CPU are validated against their values.
+        let command_buffer = draw(written, vertex_buffer, false);
+        assert!(matches!(
+            global.dyn_queue_submit(device, &[command_buffer]),
+            Err(QueueSubmitError::IndexBeyondVertexLimit {
+                buffer,
+                max_index: 5,
+                ..
+            }) if buffer == written
+        ));
+
+        for &buffer in &[
+            vertex_buffer,
+            full_vertex_buffer,
+            source,
+            copied,
+            storage,
+            written,
+        ] {
+            global.dyn_buffer_drop(buffer, true);
+        }
+        global.dyn_texture_view_drop(view, true).unwrap();
+        global.dyn_texture_drop(texture, true);
+        global.dyn_render_pipeline_drop(pipeline);
+        global.dyn_pipeline_layout_drop(pipeline_layout);
+        global.dyn_shader_module_drop(shader_module);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    impl TestGlobal {
+        fn texture_view_aspects<B: GfxBackend>(
+            &self,
+            view: id::TextureViewId,
+        ) -> hal::format::Aspects {
+            let hub = B::hub(self);
+            let mut token = Token::root();
+            let (texture_view_guard, _) = hub.texture_views.read(&mut token);
+            texture_view_guard.get(view).unwrap().aspects
+        }
+
+        /// Creates hal objects the way a user of the interop would, with memory
+        /// that the test frees after wgpu-core is done with them.
+        fn check_hal_interop<B: GfxBackend>(&self, device_id: id::DeviceId) {
+            let hub = B::hub(self);
+            let buffer_desc = BufferDescriptor {
+                label: None,
+                size: 256,
+                usage: wgt::BufferUsage::COPY_SRC | wgt::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            };
+            let texture_desc = TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 4,
+                    height: 4,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Rgba8Unorm,
+                usage: wgt::TextureUsage::COPY_DST | wgt::TextureUsage::SAMPLED,
+            };
+            let new_hal_buffer = || {
+                let mut token = Token::root();
+                let (device_guard, _) = hub.devices.read(&mut token);
+                let device = device_guard.get(device_id).unwrap();
+                unsafe {
+                    let mut buffer = device
+                        .raw
+                        .create_buffer(
+                            buffer_desc.size,
+                            hal::buffer::Usage::TRANSFER_SRC | hal::buffer::Usage::TRANSFER_DST,
+                            hal::memory::SparseFlags::empty(),
+                        )
+                        .unwrap();
+                    let requirements = device.raw.get_buffer_requirements(&buffer);
+                    let block = device
+                        .mem_allocator
+                        .lock()
+                        .allocate(
+                            &device.raw,
+                            requirements,
+                            gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+                        )
+                        .unwrap();
+                    block.bind_buffer(&device.raw, &mut buffer).unwrap();
+                    (buffer, block)
+                }
+            };
+            let new_hal_texture = || {
+                let mut token = Token::root();
+                let (device_guard, _) = hub.devices.read(&mut token);
+                let device = device_guard.get(device_id).unwrap();
+                let kind = conv::map_texture_dimension_size(
+                    texture_desc.dimension,
+                    texture_desc.size,
+                    texture_desc.sample_count,
+                    &device.limits,
+                )
+                .unwrap();
+                let format = conv::map_texture_format(texture_desc.format, device.private_features);
+                let usage =
+                    conv::map_texture_usage(texture_desc.usage, format.surface_desc().aspects);
+                unsafe {
+                    let mut image = device
+                        .raw
+                        .create_image(
+                            kind,
+                            1,
+                            format,
+                            hal::image::Tiling::Optimal,
+                            usage,
+                            hal::memory::SparseFlags::empty(),
+                            hal::image::ViewCapabilities::empty(),
+                        )
+                        .unwrap();
+                    let requirements = device.raw.get_image_requirements(&image);
+                    let block = device
+                        .mem_allocator
+                        .lock()
+                        .allocate(
+                            &device.raw,
+                            requirements,
+                            gpu_alloc::UsageFlags::FAST_DEVICE_ACCESS,
+                        )
+                        .unwrap();
+                    block.bind_image(&device.raw, &mut image).unwrap();
+                    (image, block)
+                }
+            };
+            let is_external = |buffer: id::BufferId, texture: id::TextureId| {
+                let mut token = Token::root();
+                let (buffer_guard, mut token) = hub.buffers.read(&mut token);
+                let (texture_guard, _) = hub.textures.read(&mut token);
+                let &(_, ref buffer_block) =
+                    buffer_guard.get(buffer).unwrap().raw.as_ref().unwrap();
+                let &(_, ref texture_block) =
+                    texture_guard.get(texture).unwrap().raw.as_ref().unwrap();
+                buffer_block.is_external() && texture_block.is_external()
+            };
+
+            let (hal_buffer, buffer_memory) = new_hal_buffer();
+            let (buffer, error) = unsafe {
+                self.device_create_buffer_from_hal::<B>(hal_buffer, device_id, &buffer_desc, ())
+            };
+            assert!(error.is_none());
+            let (hal_texture, texture_memory) = new_hal_texture();
+            let (texture, error) = unsafe {
+                self.device_create_texture_from_hal::<B>(
+                    hal_texture,
+                    device_id,
+                    &texture_desc,
+                    TextureUse::COPY_DST,
+                    (),
+                )
+            };
+            assert!(error.is_none());
+            assert!(is_external(buffer, texture));
+            {
+                let mut token = Token::root();
+                let (device_guard, mut token) = hub.devices.read(&mut token);
+                let (texture_guard, _) = hub.textures.read(&mut token);
+                let trackers = device_guard.get(device_id).unwrap().trackers.lock();
+                assert_eq!(
+                    trackers.buffers.query(id::Valid(buffer), ()),
+                    Some(BufferUse::EMPTY)
+                );
+                let full_range = texture_guard.get(texture).unwrap().full_range.clone();
+                assert_eq!(
+                    trackers.textures.query(id::Valid(texture), full_range),
+                    Some(TextureUse::COPY_DST)
+                );
+            }
+            assert!(unsafe { self.buffer_as_hal::<B, _, _>(buffer, |raw| raw.is_some()) });
+            assert!(unsafe { self.texture_as_hal::<B, _, _>(texture, |raw| raw.is_some()) });
+
+            // External memory is never host visible, so it's written through staging.
+            let report = self.device_memory_report::<B>(device_id).unwrap();
+            self.queue_write_buffer::<B>(device_id, buffer, 0, &[1; 16])
+                .unwrap();
+            let written = self.device_memory_report::<B>(device_id).unwrap();
+            assert_eq!(written.in_place_writes, report.in_place_writes);
+            assert_eq!(written.staged_writes, report.staged_writes + 1);
+            self.queue_submit::<B>(device_id, &[]).unwrap();
+
+            let (hal_buffer, mappable_memory) = new_hal_buffer();
+            let (mappable, error) = unsafe {
+                self.device_create_buffer_from_hal::<B>(
+                    hal_buffer,
+                    device_id,
+                    &BufferDescriptor {
+                        usage: wgt::BufferUsage::MAP_READ | wgt::BufferUsage::COPY_DST,
+                        ..buffer_desc.clone()
+                    },
+                    (),
+                )
+            };
+            assert!(matches!(error, Some(CreateBufferError::HalBufferMapping)));
+            for &initial_use in &[
+                TextureUse::EMPTY,
+                TextureUse::STORAGE_STORE,
+                TextureUse::COPY_SRC | TextureUse::SAMPLED,
+            ] {
+                let (hal_texture, rejected_memory) = new_hal_texture();
+                let (rejected, error) = unsafe {
+                    self.device_create_texture_from_hal::<B>(
+                        hal_texture,
+                        device_id,
+                        &texture_desc,
+                        initial_use,
+                        (),
+                    )
+                };
+                assert!(matches!(
+                    error,
+                    Some(CreateTextureError::InvalidInitialUse(used)) if used == initial_use
+                ));
+                self.texture_drop::<B>(rejected, false);
+                self.free_test_memory::<B>(device_id, rejected_memory);
+            }
+
+            self.buffer_destroy::<B>(buffer).unwrap();
+            self.texture_destroy::<B>(texture).unwrap();
+            assert!(!unsafe { self.buffer_as_hal::<B, _, _>(buffer, |raw| raw.is_some()) });
+            assert!(!unsafe { self.texture_as_hal::<B, _, _>(texture, |raw| raw.is_some()) });
+            self.buffer_drop::<B>(buffer, true);
+            self.buffer_drop::<B>(mappable, false);
+            self.texture_drop::<B>(texture, true);
+            self.device_poll::<B>(device_id, true).unwrap();
+
+            // The hal objects are gone, and their memory is still ours to free.
+            self.free_test_memory::<B>(device_id, buffer_memory);
+            self.free_test_memory::<B>(device_id, mappable_memory);
+            self.free_test_memory::<B>(device_id, texture_memory);
+        }
+
+        fn free_test_memory<B: GfxBackend>(
+            &self,
+            device_id: id::DeviceId,
+            memory: alloc::MemoryBlock<B>,
+        ) {
+            let hub = B::hub(self);
+            let mut token = Token::root();
+            let (device_guard, _) = hub.devices.read(&mut token);
+            let device = device_guard.get(device_id).unwrap();
+            assert!(!memory.is_external());
+            device.mem_allocator.lock().free(&device.raw, memory);
+        }
+    }
+
+    #[test]
+    fn hal_interop() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        gfx_select!(device => global.check_hal_interop(device));
+
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn stencil8_view_aspects() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (texture, error) = global.dyn_device_create_texture(
+            device,
+            &TextureDescriptor {
+                label: None,
+                size: wgt::Extent3d {
+                    width: 4,
+                    height: 4,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgt::TextureDimension::D2,
+                format: wgt::TextureFormat::Stencil8,
+                usage: wgt::TextureUsage::SAMPLED | wgt::TextureUsage::RENDER_ATTACHMENT,
+            },
+            (),
+        );
+        assert!(error.is_none());
+        // Only the stencil is viewed, even when the texture is backed by a
+        // depth/stencil format.
+        for &aspect in &[wgt::TextureAspect::All, wgt::TextureAspect::StencilOnly] {
+            let (view, error) = global.dyn_texture_create_view(
+                texture,
+                &TextureViewDescriptor {
+                    aspect,
+                    ..Default::default()
+                },
+                (),
+            );
+            assert!(error.is_none());
+            assert_eq!(
+                gfx_select!(view => global.texture_view_aspects(view)),
+                hal::format::Aspects::STENCIL
+            );
+            global.dyn_texture_view_drop(view, true).unwrap();
+        }
+
+        global.dyn_texture_drop(texture, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn depth32float_stencil8_requires_feature() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let desc = TextureDescriptor {
+            label: None,
+            size: wgt::Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgt::TextureDimension::D2,
+            format: wgt::TextureFormat::Depth32FloatStencil8,
+            usage: wgt::TextureUsage::RENDER_ATTACHMENT,
+        };
+        let (_, error) = global.dyn_device_create_texture(device, &desc, ());
+        assert!(matches!(
+            error,
+            Some(CreateTextureError::MissingFeature(
+                wgt::Features::DEPTH32FLOAT_STENCIL8,
+                wgt::TextureFormat::Depth32FloatStencil8
+            ))
+        ));
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+
+        let (global, adapter, device) = match create_device(&DeviceDescriptor {
+            label: None,
+            features: wgt::Features::DEPTH32FLOAT_STENCIL8,
+            limits: wgt::Limits::default(),
+        }) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (texture, error) = global.dyn_device_create_texture(device, &desc, ());
+        assert!(error.is_none());
+        global.dyn_texture_drop(texture, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn passthrough_fragment_outputs() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor {
+            label: None,
+            features: wgt::Features::SPIRV_SHADER_PASSTHROUGH,
+            limits: wgt::Limits::default(),
+        }) {
+            Some(objects) => objects,
+            None => return,
+        };
+        let (vertex_module, error) = global.dyn_device_create_shader_module(
+            device,
+            &ShaderModuleDescriptor {
+                label: None,
+                flags: wgt::ShaderFlags::VALIDATION,
+                interface: None,
+            },
+            ShaderModuleSource::Wgsl(Cow::Borrowed(
+                "[[stage(vertex)]]
+                fn main() -> [[builtin(position)]] vec4<f32> {
+                    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+                }",
+            )),
+            (),
+        );
+        assert!(error.is_none());
+        let module = naga::front::wgsl::parse_str(
+            "[[stage(fragment)]]
+            fn main() -> [[location(0)]] vec4<f32> {
+                return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+            }",
+        )
+        .unwrap();
+        let analysis = naga::proc::Validator::new().validate(&module).unwrap();
+        let spv = naga::back::spv::write_vec(
+            &module,
+            &analysis,
+            &naga::back::spv::Options {
+                lang_version: (1, 0),
+                capabilities: std::iter::once(naga::back::spv::Capability::Shader).collect(),
+                flags: naga::back::spv::WriterFlags::empty(),
+            },
+        )
+        .unwrap();
+        let entry_points = [ShaderEntryPoint {
+            name: Cow::Borrowed("main"),
+            stage: wgt::ShaderStage::FRAGMENT,
+            bindings: Cow::Borrowed(&[]),
+        }];
+        let (fragment_module, error) = global.dyn_device_create_shader_module(
+            device,
+            &ShaderModuleDescriptor {
+                label: None,
+                flags: wgt::ShaderFlags::SPIRV_PASSTHROUGH,
+                interface: Some(Cow::Borrowed(&entry_points)),
+            },
+            ShaderModuleSource::SpirV(Cow::Owned(spv)),
+            (),
+        );
+        assert!(error.is_none());
+
+        // The outputs of the declared entry point are unknown, so the color
+        // target isn't checked against them.
+        let (pipeline_layout, error) = global.dyn_device_create_pipeline_layout(
+            device,
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: Cow::Borrowed(&[]),
+                push_constant_ranges: Cow::Borrowed(&[]),
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let targets = [wgt::ColorTargetState::from(wgt::TextureFormat::Rgba8Unorm)];
+        let (pipeline, _, error) = global.dyn_device_create_render_pipeline(
+            device,
+            &RenderPipelineDescriptor {
+                label: None,
+                layout: Some(pipeline_layout),
+                vertex: VertexState {
+                    stage: ProgrammableStageDescriptor {
+                        module: vertex_module,
+                        entry_point: Cow::Borrowed("main"),
+                    },
+                    buffers: Cow::Borrowed(&[]),
+                },
+                primitive: wgt::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgt::MultisampleState::default(),
+                fragment: Some(FragmentState {
+                    stage: ProgrammableStageDescriptor {
+                        module: fragment_module,
+                        entry_point: Cow::Borrowed("main"),
+                    },
+                    targets: Cow::Borrowed(&targets),
+                }),
+            },
+            (),
+            None,
+        );
+        assert!(error.is_none());
+
+        global.dyn_render_pipeline_drop(pipeline);
+        global.dyn_pipeline_layout_drop(pipeline_layout);
+        global.dyn_shader_module_drop(fragment_module);
+        global.dyn_shader_module_drop(vertex_module);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+
+    #[test]
+    fn shader_cache_stats() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        const VERTEX: &str = "[[stage(vertex)]]
+            fn main() -> [[builtin(position)]] vec4<f32> {
+                return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+            }";
+        const COMPUTE: &str = "[[stage(compute), workgroup_size(1)]]
+            fn main() {}";
+        let create_shader_module = |code, flags| {
+            let (shader_module, error) = global.dyn_device_create_shader_module(
+                device,
+                &ShaderModuleDescriptor {
+                    label: None,
+                    flags,
+                    interface: None,
+                },
+                ShaderModuleSource::Wgsl(Cow::Borrowed(code)),
+                (),
+            );
+            assert!(error.is_none());
+            shader_module
+        };
+        let experimental =
+            wgt::ShaderFlags::VALIDATION | wgt::ShaderFlags::EXPERIMENTAL_TRANSLATION;
+
+        let shader_modules = [
+            create_shader_module(VERTEX, wgt::ShaderFlags::VALIDATION),
+            create_shader_module(VERTEX, wgt::ShaderFlags::VALIDATION),
+            create_shader_module(COMPUTE, wgt::ShaderFlags::VALIDATION),
+            // The backend gets a copy of the cached Naga module.
+            create_shader_module(VERTEX, experimental),
+            create_shader_module(VERTEX, experimental),
+        ];
+        assert_eq!(
+            global.dyn_device_shader_cache_stats(device).unwrap(),
+            ShaderCacheStats {
+                hits: 2,
+                misses: 3,
+                entries: 3,
+                evictions: 0,
+            }
+        );
+        // A pipeline can be created from a module created from the cache.
+        let (pipeline_layout, error) = global.dyn_device_create_pipeline_layout(
+            device,
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: Cow::Borrowed(&[]),
+                push_constant_ranges: Cow::Borrowed(&[]),
+            },
+            (),
+        );
+        assert!(error.is_none());
+        let (pipeline, _, error) = global.dyn_device_create_compute_pipeline(
+            device,
+            &ComputePipelineDescriptor {
+                label: None,
+                layout: Some(pipeline_layout),
+                stage: ProgrammableStageDescriptor {
+                    module: shader_modules[2],
+                    entry_point: Cow::Borrowed("main"),
+                },
+            },
+            (),
+            None,
+        );
+        assert!(error.is_none());
+
+        global.dyn_compute_pipeline_drop(pipeline);
+        global.dyn_pipeline_layout_drop(pipeline_layout);
+        for &shader_module in shader_modules.iter() {
+            global.dyn_shader_module_drop(shader_module);
+        }
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+}
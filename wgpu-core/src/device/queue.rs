@@ -6,26 +6,43 @@
 use crate::device::trace::Action;
 use crate::{
     command::{
-        texture_copy_view_to_hal, validate_linear_texture_data, validate_texture_copy_range,
-        CommandAllocator, CommandBuffer, CopySide, TextureCopyView, TransferError, BITS_PER_BYTE,
+        buffer_copy_block_size, texture_copy_view_to_hal, validate_linear_texture_data,
+        validate_texture_copy_range, CommandAllocator, CommandBuffer, CopySide, TextureCopyView,
+        TransferError,
     },
     conv,
-    device::{alloc, DeviceError, WaitIdleError},
-    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, Token},
+    device::{alloc, staging_belt, DeviceError, WaitIdleError},
+    gfx_select,
+    hub::{GfxBackend, Global, GlobalIdentityHandlerFactory, InvalidId, Storage, Token},
     id,
+    index_tracker::IndexTrackerAction,
     memory_init_tracker::MemoryInitKind,
-    resource::{BufferAccessError, BufferMapState, BufferUse, TextureUse},
-    span, FastHashMap, FastHashSet,
+    resource::{Buffer, BufferAccessError, BufferMapState, BufferUse, TextureUse},
+    span, FastHashMap, FastHashSet, SubmissionIndex,
 };
 
 use hal::{command::CommandBuffer as _, device::Device as _, queue::Queue as _};
 use smallvec::SmallVec;
-use std::{iter, ops::Range, ptr};
+use std::{
+    iter,
+    ops::{Deref, DerefMut, Range},
+    ptr, slice,
+    sync::atomic::Ordering,
+};
 use thiserror::Error;
 
+enum StagingBuffer<B: hal::Backend> {
+    /// Sub-allocated from the staging belt of the device, at this offset.
+    Belt(wgt::BufferAddress),
+    Dedicated {
+        buffer: B::Buffer,
+        memory: alloc::MemoryBlock<B>,
+    },
+}
+
 struct StagingData<B: hal::Backend> {
-    buffer: B::Buffer,
-    memory: alloc::MemoryBlock<B>,
+    buffer: StagingBuffer<B>,
+    size: wgt::BufferAddress,
     cmdbuf: B::CommandBuffer,
 }
 
@@ -41,6 +58,9 @@ pub(crate) struct PendingWrites<B: hal::Backend> {
     pub temp_resources: Vec<(TempResource<B>, alloc::MemoryBlock<B>)>,
     pub dst_buffers: FastHashSet<id::BufferId>,
     pub dst_textures: FastHashSet<id::TextureId>,
+    /// Mapped staging buffers of the live `QueueWriteBufferView`s.
+    write_views: FastHashMap<u64, (B::Buffer, alloc::MemoryBlock<B>)>,
+    next_write_view: u64,
 }
 
 impl<B: hal::Backend> PendingWrites<B> {
@@ -50,9 +70,15 @@ impl<B: hal::Backend> PendingWrites<B> {
             temp_resources: Vec::new(),
             dst_buffers: FastHashSet::default(),
             dst_textures: FastHashSet::default(),
+            write_views: FastHashMap::default(),
+            next_write_view: 0,
         }
     }
 
+    pub(super) fn has_write_views(&self) -> bool {
+        !self.write_views.is_empty()
+    }
+
     pub fn dispose(
         self,
         device: &B::Device,
@@ -62,6 +88,13 @@ impl<B: hal::Backend> PendingWrites<B> {
         if let Some(raw) = self.command_buffer {
             cmd_allocator.discard_internal(raw);
         }
+        for (_, (buffer, mut memory)) in self.write_views {
+            memory.unmap(device);
+            mem_allocator.free(device, memory);
+            unsafe {
+                device.destroy_buffer(buffer);
+            }
+        }
         for (resource, memory) in self.temp_resources {
             mem_allocator.free(device, memory);
             match resource {
@@ -80,8 +113,10 @@ impl<B: hal::Backend> PendingWrites<B> {
     }
 
     fn consume(&mut self, stage: StagingData<B>) {
-        self.temp_resources
-            .push((TempResource::Buffer(stage.buffer), stage.memory));
+        if let StagingBuffer::Dedicated { buffer, memory } = stage.buffer {
+            self.temp_resources
+                .push((TempResource::Buffer(buffer), memory));
+        }
         self.command_buffer = Some(stage.cmdbuf);
     }
 
@@ -108,7 +143,65 @@ impl<B: hal::Backend> super::Device<B> {
         self.pending_writes.command_buffer.as_mut().unwrap()
     }
 
-    fn prepare_stage(&mut self, size: wgt::BufferAddress) -> Result<StagingData<B>, DeviceError> {
+    /// Starts the next submission, once its command buffers are validated.
+    /// The pending writes and the staging belt regions in use go with it.
+    fn start_submission(&mut self) -> Result<(B::Fence, Option<B::CommandBuffer>), DeviceError> {
+        let fence = self
+            .raw
+            .create_fence(false)
+            .or(Err(DeviceError::OutOfMemory))?;
+        self.active_submission_index += 1;
+        if let Some(ref mut belt) = self.staging_belt {
+            belt.submit(self.active_submission_index);
+        }
+        Ok((fence, self.pending_writes.finish()))
+    }
+
+    /// Gives back the staging belt regions of the submissions that are done,
+    /// given the oldest one still active.
+    fn recycle_staging_belt(&mut self, oldest_active: Option<SubmissionIndex>) {
+        if let Some(ref mut belt) = self.staging_belt {
+            belt.recycle(oldest_active);
+        }
+    }
+
+    fn prepare_stage(
+        &mut self,
+        size: wgt::BufferAddress,
+        alignment: wgt::BufferAddress,
+    ) -> Result<StagingData<B>, DeviceError> {
+        let mut belt_offset = None;
+        if size <= staging_belt::MAX_BELT_UPLOAD {
+            if self.staging_belt.is_none() {
+                let belt =
+                    staging_belt::StagingBelt::new(&self.raw, &mut self.mem_allocator.lock())?;
+                self.staging_belt = Some(belt);
+            }
+            belt_offset = self
+                .staging_belt
+                .as_mut()
+                .unwrap()
+                .allocate(size, alignment);
+        }
+
+        let buffer = match belt_offset {
+            Some(offset) => StagingBuffer::Belt(offset),
+            None => {
+                let (buffer, memory) = self.create_staging_buffer(size)?;
+                StagingBuffer::Dedicated { buffer, memory }
+            }
+        };
+        Ok(StagingData {
+            buffer,
+            size,
+            cmdbuf: self.take_pending_command_buffer(),
+        })
+    }
+
+    fn create_staging_buffer(
+        &self,
+        size: wgt::BufferAddress,
+    ) -> Result<(B::Buffer, alloc::MemoryBlock<B>), DeviceError> {
         let mut buffer = unsafe {
             self.raw
                 .create_buffer(
@@ -127,14 +220,17 @@ impl<B: hal::Backend> super::Device<B> {
             self.raw.get_buffer_requirements(&buffer)
         };
 
-        let block = self.mem_allocator.lock().allocate(
+        let memory = self.mem_allocator.lock().allocate(
             &self.raw,
             requirements,
             gpu_alloc::UsageFlags::UPLOAD | gpu_alloc::UsageFlags::TRANSIENT,
         )?;
-        block.bind_buffer(&self.raw, &mut buffer)?;
+        memory.bind_buffer(&self.raw, &mut buffer)?;
+        Ok((buffer, memory))
+    }
 
-        let cmdbuf = match self.pending_writes.command_buffer.take() {
+    fn take_pending_command_buffer(&mut self) -> B::CommandBuffer {
+        match self.pending_writes.command_buffer.take() {
             Some(cmdbuf) => cmdbuf,
             None => {
                 let mut cmdbuf = self.cmd_allocator.allocate_internal();
@@ -143,12 +239,144 @@ impl<B: hal::Backend> super::Device<B> {
                 }
                 cmdbuf
             }
+        }
+    }
+
+    /// Lets `fill` write the staged data into the mapped staging memory.
+    fn write_stage(
+        &self,
+        stage: &mut StagingData<B>,
+        fill: impl FnOnce(ptr::NonNull<u8>),
+    ) -> Result<(), DeviceError> {
+        match stage.buffer {
+            StagingBuffer::Belt(offset) => self.staging_belt.as_ref().unwrap().write(
+                &self.raw,
+                offset,
+                stage.size,
+                self.hal_limits.non_coherent_atom_size as wgt::BufferAddress,
+                fill,
+            ),
+            StagingBuffer::Dedicated { ref mut memory, .. } => {
+                let ptr = memory.map(&self.raw, 0, stage.size)?;
+                fill(ptr);
+                memory.unmap(&self.raw);
+                if !memory.is_coherent() {
+                    memory.flush_range(&self.raw, 0, None)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the buffer holding the staged data, and the offset of the data in it.
+    fn stage_buffer<'a>(
+        &'a self,
+        buffer: &'a StagingBuffer<B>,
+    ) -> (&'a B::Buffer, wgt::BufferAddress) {
+        match *buffer {
+            StagingBuffer::Belt(offset) => (&self.staging_belt.as_ref().unwrap().buffer, offset),
+            StagingBuffer::Dedicated { ref buffer, .. } => (buffer, 0),
+        }
+    }
+
+    /// Records the copy of the staged data into a buffer that passed `validate_buffer_write`.
+    fn copy_stage_to_buffer(
+        &mut self,
+        buffer_guard: &Storage<Buffer<B>, id::BufferId>,
+        mut stage: StagingData<B>,
+        buffer_id: id::BufferId,
+        buffer_offset: wgt::BufferAddress,
+    ) -> Result<(), TransferError> {
+        let mut trackers = self.trackers.lock();
+        let (dst, transition) = trackers
+            .buffers
+            .use_replace(buffer_guard, buffer_id, (), BufferUse::COPY_DST)
+            .map_err(|e| TransferError::InvalidBuffer(buffer_id, e))?;
+        let &(ref dst_raw, _) = dst
+            .raw
+            .as_ref()
+            .ok_or(TransferError::DestroyedBuffer(buffer_id))?;
+        dst.life_guard.use_at(self.active_submission_index + 1);
+
+        let (stage_buffer, stage_offset) = self.stage_buffer(&stage.buffer);
+        let region = hal::command::BufferCopy {
+            src: stage_offset,
+            dst: buffer_offset,
+            size: stage.size,
         };
-        Ok(StagingData {
-            buffer,
-            memory: block,
-            cmdbuf,
-        })
+        unsafe {
+            stage.cmdbuf.pipeline_barrier(
+                super::all_buffer_stages()..hal::pso::PipelineStage::TRANSFER,
+                hal::memory::Dependencies::empty(),
+                iter::once(hal::memory::Barrier::Buffer {
+                    states: hal::buffer::Access::HOST_WRITE..hal::buffer::Access::TRANSFER_READ,
+                    target: stage_buffer,
+                    range: hal::buffer::SubRange {
+                        offset: stage_offset,
+                        size: Some(stage.size),
+                    },
+                    families: None,
+                })
+                .chain(transition.map(|pending| pending.into_hal(dst))),
+            );
+            stage
+                .cmdbuf
+                .copy_buffer(stage_buffer, dst_raw, iter::once(region));
+        }
+
+        self.pending_writes.consume(stage);
+        self.pending_writes.dst_buffers.insert(buffer_id);
+        Ok(())
+    }
+}
+
+fn validate_buffer_write<B: hal::Backend>(
+    buffer_guard: &Storage<Buffer<B>, id::BufferId>,
+    buffer_id: id::BufferId,
+    buffer_offset: wgt::BufferAddress,
+    data_size: wgt::BufferAddress,
+) -> Result<(), TransferError> {
+    let dst = buffer_guard
+        .get(buffer_id)
+        .map_err(|e| TransferError::InvalidBuffer(buffer_id, e))?;
+    if dst.raw.is_none() {
+        return Err(TransferError::DestroyedBuffer(buffer_id));
+    }
+    if !dst.usage.contains(wgt::BufferUsage::COPY_DST) {
+        return Err(TransferError::MissingCopyDstUsageFlag(
+            Some(buffer_id),
+            None,
+        ));
+    }
+    if data_size % wgt::COPY_BUFFER_ALIGNMENT != 0 {
+        return Err(TransferError::UnalignedCopySize(data_size));
+    }
+    if buffer_offset % wgt::COPY_BUFFER_ALIGNMENT != 0 {
+        return Err(TransferError::UnalignedBufferOffset(buffer_offset));
+    }
+    if buffer_offset + data_size > dst.size {
+        return Err(TransferError::BufferOverrun {
+            start_offset: buffer_offset,
+            end_offset: buffer_offset + data_size,
+            buffer_size: dst.size,
+            side: CopySide::Destination,
+        });
+    }
+    Ok(())
+}
+
+/// Ensures the overwritten bytes are marked as initialized so they don't need to be nulled
+/// prior to mapping or binding.
+fn mark_buffer_written<B: hal::Backend>(
+    buffer: &mut Buffer<B>,
+    buffer_offset: wgt::BufferAddress,
+    data: &[u8],
+) {
+    buffer
+        .initialization_status
+        .clear(buffer_offset..buffer_offset + data.len() as wgt::BufferAddress);
+    if let Some(ref mut index_tracker) = buffer.index_tracker {
+        index_tracker.write(buffer_offset, data);
     }
 }
 
@@ -170,18 +398,93 @@ pub enum QueueSubmitError {
     Queue(#[from] DeviceError),
     #[error("command buffer {0:?} is invalid")]
     InvalidCommandBuffer(id::CommandBufferId),
+    #[error("command buffer {0:?} is submitted more than once")]
+    DuplicateCommandBuffer(id::CommandBufferId),
+    #[error("buffer {0:?} is invalid")]
+    InvalidBuffer(id::BufferId, #[source] InvalidId),
     #[error("buffer {0:?} is destroyed")]
     DestroyedBuffer(id::BufferId),
     #[error("texture {0:?} is destroyed")]
     DestroyedTexture(id::TextureId),
+    #[error("query set {0:?} is destroyed")]
+    DestroyedQuerySet(id::QuerySetId),
     #[error(transparent)]
     Unmap(#[from] BufferAccessError),
     #[error("swap chain output was dropped before the command buffer got submitted")]
     SwapChainOutputDropped,
+    #[error("indexed draw reads index {max_index} from buffer {buffer:?}, which with base vertex {base_vertex} is beyond the vertex limit {vertex_limit} of the buffer in slot {slot}")]
+    IndexBeyondVertexLimit {
+        buffer: id::BufferId,
+        max_index: u32,
+        base_vertex: i32,
+        vertex_limit: u32,
+        slot: u32,
+    },
     #[error("GPU got stuck :(")]
     StuckGpu,
 }
 
+/// Mapped staging memory returned by `queue_write_buffer_with`, to be filled in place.
+///
+/// The copy into the destination buffer is scheduled when the view is dropped,
+/// logging errors, or by `finish`, which returns them. Dropping the device while
+/// the view is alive is deferred until the view is dropped, and the write is lost.
+pub struct QueueWriteBufferView<'a, G: GlobalIdentityHandlerFactory> {
+    global: &'a Global<G>,
+    queue_id: id::QueueId,
+    buffer_id: id::BufferId,
+    buffer_offset: wgt::BufferAddress,
+    index: u64,
+    ptr: ptr::NonNull<u8>,
+    size: wgt::BufferSize,
+    finished: bool,
+}
+
+impl<G: GlobalIdentityHandlerFactory> QueueWriteBufferView<'_, G> {
+    pub fn finish(mut self) -> Result<(), QueueWriteError> {
+        self.schedule()
+    }
+
+    fn schedule(&mut self) -> Result<(), QueueWriteError> {
+        self.finished = true;
+        let global = self.global;
+        let data = &**self;
+        let result = gfx_select!(self.queue_id => global.queue_schedule_write_view(
+            self.queue_id,
+            self.index,
+            self.buffer_id,
+            self.buffer_offset,
+            data
+        ));
+        gfx_select!(self.queue_id => global.device_drop_deferred(self.queue_id));
+        result
+    }
+}
+
+impl<G: GlobalIdentityHandlerFactory> Deref for QueueWriteBufferView<'_, G> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.size.get() as usize) }
+    }
+}
+
+impl<G: GlobalIdentityHandlerFactory> DerefMut for QueueWriteBufferView<'_, G> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.size.get() as usize) }
+    }
+}
+
+impl<G: GlobalIdentityHandlerFactory> Drop for QueueWriteBufferView<'_, G> {
+    fn drop(&mut self) {
+        if !self.finished {
+            if let Err(error) = self.schedule() {
+                tracing::error!("Failed to write buffer {:?}: {}", self.buffer_id, error);
+            }
+        }
+    }
+}
+
 //TODO: move out common parts of write_xxx.
 
 impl<G: GlobalIdentityHandlerFactory> Global<G> {
@@ -200,11 +503,17 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let device = device_guard
             .get_mut(queue_id)
             .map_err(|_| DeviceError::Invalid)?;
-        let (buffer_guard, _) = hub.buffers.read(&mut token);
+        if device.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
+        let oldest_active = device.lock_life(&mut token).oldest_active_submission();
+        device.recycle_staging_belt(oldest_active);
+        // The buffers are locked for writing up front, so that the map state
+        // checked here can't change before the buffer is written in place.
+        let (mut buffer_guard, _) = hub.buffers.write(&mut token);
 
         #[cfg(feature = "trace")]
-        if let Some(ref trace) = device.trace {
-            let mut trace = trace.lock();
+        if let Some(mut trace) = device.active_trace() {
             let data_path = trace.make_binary("bin", data);
             trace.add(Action::WriteBuffer {
                 id: buffer_id,
@@ -220,74 +529,176 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             return Ok(());
         }
 
-        let mut stage = device.prepare_stage(data_size)?;
-        stage.memory.write_bytes(&device.raw, 0, data)?;
+        validate_buffer_write(&*buffer_guard, buffer_id, buffer_offset, data_size)?;
 
-        let mut trackers = device.trackers.lock();
-        let (dst, transition) = trackers
-            .buffers
-            .use_replace(&*buffer_guard, buffer_id, (), BufferUse::COPY_DST)
-            .map_err(TransferError::InvalidBuffer)?;
-        let &(ref dst_raw, _) = dst
+        // Host visible buffers are written in place, unless the GPU may still
+        // be using them, or a staged write to them is pending.
+        let dst = buffer_guard.get(buffer_id).unwrap();
+        let in_place = dst
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidBuffer(buffer_id))?;
-        if !dst.usage.contains(wgt::BufferUsage::COPY_DST) {
-            return Err(TransferError::MissingCopyDstUsageFlag(Some(buffer_id), None).into());
-        }
-        dst.life_guard.use_at(device.active_submission_index + 1);
+            .map_or(false, |&(_, ref block)| block.is_host_visible())
+            && matches!(dst.map_state, BufferMapState::Idle)
+            && !device.pending_writes.dst_buffers.contains(&buffer_id)
+            && oldest_active.map_or(true, |index| {
+                dst.life_guard.submission_index.load(Ordering::Acquire) < index
+            });
 
-        if data_size % wgt::COPY_BUFFER_ALIGNMENT != 0 {
-            return Err(TransferError::UnalignedCopySize(data_size).into());
+        if in_place {
+            let dst = buffer_guard.get_mut(buffer_id).unwrap();
+            let &mut (_, ref mut block) = dst.raw.as_mut().unwrap();
+            block.write_bytes(&device.raw, buffer_offset, data)?;
+            device.memory_report.lock().in_place_writes += 1;
+        } else {
+            let stage_alignment = get_lowest_common_denom(
+                device.hal_limits.optimal_buffer_copy_offset_alignment as u32,
+                wgt::COPY_BUFFER_ALIGNMENT as u32,
+            );
+            let mut stage =
+                device.prepare_stage(data_size, stage_alignment as wgt::BufferAddress)?;
+            device.write_stage(&mut stage, |ptr| unsafe {
+                ptr::copy_nonoverlapping(data.as_ptr(), ptr.as_ptr(), data.len());
+            })?;
+            device.copy_stage_to_buffer(&*buffer_guard, stage, buffer_id, buffer_offset)?;
+            device.memory_report.lock().staged_writes += 1;
         }
-        if buffer_offset % wgt::COPY_BUFFER_ALIGNMENT != 0 {
-            return Err(TransferError::UnalignedBufferOffset(buffer_offset).into());
+        mark_buffer_written(
+            buffer_guard.get_mut(buffer_id).unwrap(),
+            buffer_offset,
+            data,
+        );
+
+        Ok(())
+    }
+
+    /// Returns zeroed staging memory for the caller to fill, which is copied into
+    /// `size` bytes of the buffer at `buffer_offset` once the view is dropped.
+    pub fn queue_write_buffer_with<B: GfxBackend>(
+        &self,
+        queue_id: id::QueueId,
+        buffer_id: id::BufferId,
+        buffer_offset: wgt::BufferAddress,
+        size: wgt::BufferSize,
+    ) -> Result<QueueWriteBufferView<G>, QueueWriteError> {
+        span!(_guard, INFO, "Queue::write_buffer_with");
+
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let (mut device_guard, mut token) = hub.devices.write(&mut token);
+        let device = device_guard
+            .get_mut(queue_id)
+            .map_err(|_| DeviceError::Invalid)?;
+        if device.destroyed {
+            return Err(DeviceError::Destroyed.into());
         }
-        if buffer_offset + data_size > dst.size {
-            return Err(TransferError::BufferOverrun {
-                start_offset: buffer_offset,
-                end_offset: buffer_offset + data_size,
-                buffer_size: dst.size,
-                side: CopySide::Destination,
+        let (buffer_guard, _) = hub.buffers.read(&mut token);
+        validate_buffer_write(&*buffer_guard, buffer_id, buffer_offset, size.get())?;
+
+        // The staging memory can't come from the staging belt, since its region
+        // would be recycled by any submission done before the view is dropped.
+        let (buffer, mut memory) = device.create_staging_buffer(size.get())?;
+        let ptr = match memory.map(&device.raw, 0, size.get()) {
+            Ok(ptr) => ptr,
+            Err(error) => {
+                device
+                    .pending_writes
+                    .consume_temp(TempResource::Buffer(buffer), memory);
+                return Err(error.into());
             }
-            .into());
-        }
-
-        let region = hal::command::BufferCopy {
-            src: 0,
-            dst: buffer_offset,
-            size: data.len() as _,
         };
         unsafe {
-            stage.cmdbuf.pipeline_barrier(
-                super::all_buffer_stages()..hal::pso::PipelineStage::TRANSFER,
-                hal::memory::Dependencies::empty(),
-                iter::once(hal::memory::Barrier::Buffer {
-                    states: hal::buffer::Access::HOST_WRITE..hal::buffer::Access::TRANSFER_READ,
-                    target: &stage.buffer,
-                    range: hal::buffer::SubRange::WHOLE,
-                    families: None,
-                })
-                .chain(transition.map(|pending| pending.into_hal(dst))),
-            );
-            stage
-                .cmdbuf
-                .copy_buffer(&stage.buffer, dst_raw, iter::once(region));
+            ptr::write_bytes(ptr.as_ptr(), 0, size.get() as usize);
         }
 
-        device.pending_writes.consume(stage);
-        device.pending_writes.dst_buffers.insert(buffer_id);
+        let index = device.pending_writes.next_write_view;
+        device.pending_writes.next_write_view += 1;
+        device
+            .pending_writes
+            .write_views
+            .insert(index, (buffer, memory));
+
+        Ok(QueueWriteBufferView {
+            global: self,
+            queue_id,
+            buffer_id,
+            buffer_offset,
+            index,
+            ptr,
+            size,
+            finished: false,
+        })
+    }
 
-        // Ensure the overwritten bytes are marked as initialized so they don't need to be nulled prior to mapping or binding.
-        {
-            drop(buffer_guard);
-            let (mut buffer_guard, _) = hub.buffers.write(&mut token);
+    fn queue_schedule_write_view<B: GfxBackend>(
+        &self,
+        queue_id: id::QueueId,
+        index: u64,
+        buffer_id: id::BufferId,
+        buffer_offset: wgt::BufferAddress,
+        data: &[u8],
+    ) -> Result<(), QueueWriteError> {
+        span!(_guard, INFO, "Queue::schedule_write_view");
 
-            let dst = buffer_guard.get_mut(buffer_id).unwrap();
-            dst.initialization_status
-                .clear(buffer_offset..(buffer_offset + data_size));
+        let hub = B::hub(self);
+        let mut token = Token::root();
+        let (mut device_guard, mut token) = hub.devices.write(&mut token);
+        let device = device_guard
+            .get_mut(queue_id)
+            .map_err(|_| DeviceError::Invalid)?;
+        let (buffer, mut memory) = device
+            .pending_writes
+            .write_views
+            .remove(&index)
+            .expect("write view is not registered");
+        let (mut buffer_guard, _) = hub.buffers.write(&mut token);
+
+        #[cfg(feature = "trace")]
+        if let Some(mut trace) = device.active_trace() {
+            let data_path = trace.make_binary("bin", data);
+            trace.add(Action::WriteBuffer {
+                id: buffer_id,
+                data: data_path,
+                range: buffer_offset..buffer_offset + data.len() as wgt::BufferAddress,
+                queued: true,
+            });
+        }
+
+        let data_size = data.len() as wgt::BufferAddress;
+        let result = if device.drop_deferred {
+            Err(DeviceError::Invalid.into())
+        } else if device.destroyed {
+            Err(DeviceError::Destroyed.into())
+        } else {
+            validate_buffer_write(&*buffer_guard, buffer_id, buffer_offset, data_size)
+                .map_err(QueueWriteError::from)
+        };
+        if result.is_ok() {
+            mark_buffer_written(
+                buffer_guard.get_mut(buffer_id).unwrap(),
+                buffer_offset,
+                data,
+            );
+        }
+        // `data` points into this memory, and can't be used any more.
+        let flushed = if memory.is_coherent() {
+            Ok(())
+        } else {
+            memory.flush_range(&device.raw, 0, None)
+        };
+        memory.unmap(&device.raw);
+        if let Err(error) = result.and(flushed.map_err(QueueWriteError::from)) {
+            device
+                .pending_writes
+                .consume_temp(TempResource::Buffer(buffer), memory);
+            return Err(error);
         }
 
+        let stage = StagingData {
+            buffer: StagingBuffer::Dedicated { buffer, memory },
+            size: data_size,
+            cmdbuf: device.take_pending_command_buffer(),
+        };
+        device.copy_stage_to_buffer(&*buffer_guard, stage, buffer_id, buffer_offset)?;
         Ok(())
     }
 
@@ -307,13 +718,17 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let device = device_guard
             .get_mut(queue_id)
             .map_err(|_| DeviceError::Invalid)?;
+        if device.destroyed {
+            return Err(DeviceError::Destroyed.into());
+        }
+        let oldest_active = device.lock_life(&mut token).oldest_active_submission();
+        device.recycle_staging_belt(oldest_active);
         let (texture_guard, _) = hub.textures.read(&mut token);
         let (image_layers, image_range, image_offset) =
             texture_copy_view_to_hal(destination, size, &*texture_guard)?;
 
         #[cfg(feature = "trace")]
-        if let Some(ref trace) = device.trace {
-            let mut trace = trace.lock();
+        if let Some(mut trace) = device.active_trace() {
             let data_path = trace.make_binary("bin", data);
             trace.add(Action::WriteTexture {
                 to: destination.clone(),
@@ -329,10 +744,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         }
 
         let texture_format = texture_guard.get(destination.texture).unwrap().format;
-        let bytes_per_block = conv::map_texture_format(texture_format, device.private_features)
-            .surface_desc()
-            .bits as u32
-            / BITS_PER_BYTE;
+        let bytes_per_block = buffer_copy_block_size(
+            texture_format,
+            destination.aspect,
+            CopySide::Destination,
+            device.private_features,
+        )?;
         validate_linear_texture_data(
             data_layout,
             texture_format,
@@ -346,9 +763,6 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let block_width = block_width as u32;
         let block_height = block_height as u32;
 
-        if !conv::is_valid_copy_dst_texture_format(texture_format) {
-            return Err(TransferError::CopyToForbiddenTextureFormat(texture_format).into());
-        }
         let width_blocks = size.width / block_width;
         let height_blocks = size.height / block_width;
 
@@ -364,7 +778,14 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let block_rows_in_copy =
             (size.depth_or_array_layers - 1) * block_rows_per_image + height_blocks;
         let stage_size = stage_bytes_per_row as u64 * block_rows_in_copy as u64;
-        let mut stage = device.prepare_stage(stage_size)?;
+        let stage_alignment = get_lowest_common_denom(
+            get_lowest_common_denom(
+                device.hal_limits.optimal_buffer_copy_offset_alignment as u32,
+                wgt::COPY_BUFFER_ALIGNMENT as u32,
+            ),
+            bytes_per_block,
+        );
+        let mut stage = device.prepare_stage(stage_size, stage_alignment as wgt::BufferAddress)?;
 
         let mut trackers = device.trackers.lock();
         let (dst, transition) = trackers
@@ -379,7 +800,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let &(ref dst_raw, _) = dst
             .raw
             .as_ref()
-            .ok_or(TransferError::InvalidTexture(destination.texture))?;
+            .ok_or(TransferError::DestroyedTexture(destination.texture))?;
 
         if !dst.usage.contains(wgt::TextureUsage::COPY_DST) {
             return Err(
@@ -395,8 +816,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         )?;
         dst.life_guard.use_at(device.active_submission_index + 1);
 
-        let ptr = stage.memory.map(&device.raw, 0, stage_size)?;
-        unsafe {
+        device.write_stage(&mut stage, |ptr| unsafe {
             //TODO: https://github.com/zakarumych/gpu-alloc/issues/13
             if stage_bytes_per_row == data_layout.bytes_per_row {
                 // Fast path if the data isalready being aligned optimally.
@@ -420,11 +840,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     }
                 }
             }
-        }
-        stage.memory.unmap(&device.raw);
-        if !stage.memory.is_coherent() {
-            stage.memory.flush_range(&device.raw, 0, None)?;
-        }
+        })?;
 
         // WebGPU uses the physical size of the texture for copies whereas vulkan uses
         // the virtual size. We have passed validation, so it's safe to use the
@@ -437,8 +853,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             depth_or_array_layers: size.depth_or_array_layers,
         };
 
+        let (stage_buffer, stage_offset) = device.stage_buffer(&stage.buffer);
         let region = hal::command::BufferImageCopy {
-            buffer_offset: 0,
+            buffer_offset: stage_offset,
             buffer_width: (stage_bytes_per_row / bytes_per_block) * block_width,
             buffer_height: texel_rows_per_image,
             image_layers,
@@ -452,14 +869,17 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 hal::memory::Dependencies::empty(),
                 iter::once(hal::memory::Barrier::Buffer {
                     states: hal::buffer::Access::HOST_WRITE..hal::buffer::Access::TRANSFER_READ,
-                    target: &stage.buffer,
-                    range: hal::buffer::SubRange::WHOLE,
+                    target: stage_buffer,
+                    range: hal::buffer::SubRange {
+                        offset: stage_offset,
+                        size: Some(stage_size),
+                    },
                     families: None,
                 })
                 .chain(transition.map(|pending| pending.into_hal(dst))),
             );
             stage.cmdbuf.copy_buffer_to_image(
-                &stage.buffer,
+                stage_buffer,
                 dst_raw,
                 hal::image::Layout::TransferDstOptimal,
                 iter::once(region),
@@ -500,7 +920,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             for &cmb_id in command_buffer_ids {
                 let cmdbuf = command_buffer_guard
                     .get(cmb_id)
-                    .map_err(|_| QueueSubmitError::InvalidCommandBuffer(cmb_id))?;
+                    .map_err(|_| QueueSubmitError::InvalidCommandBuffer(cmb_id))?
+                    .data
+                    .lock();
 
                 if cmdbuf.buffer_memory_init_actions.is_empty() {
                     continue;
@@ -511,7 +933,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 for buffer_use in cmdbuf.buffer_memory_init_actions.iter() {
                     let buffer = buffer_guard
                         .get_mut(buffer_use.id)
-                        .map_err(|_| QueueSubmitError::DestroyedBuffer(buffer_use.id))?;
+                        .map_err(|e| QueueSubmitError::InvalidBuffer(buffer_use.id, e))?;
 
                     let uninitialized_ranges =
                         buffer.initialization_status.drain(buffer_use.range.clone());
@@ -570,7 +992,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             );
             let buffer = buffer_guard
                 .get(buffer_id)
-                .map_err(|_| QueueSubmitError::DestroyedBuffer(buffer_id))?;
+                .map_err(|e| QueueSubmitError::InvalidBuffer(buffer_id, e))?;
             let &(ref buffer_raw, _) = buffer
                 .raw
                 .as_ref()
@@ -611,6 +1033,15 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
     ) -> Result<(), QueueSubmitError> {
         span!(_guard, INFO, "Queue::submit");
 
+        // Each command buffer is locked and consumed once below.
+        let mut unique_ids = FastHashSet::default();
+        if let Some(&cmb_id) = command_buffer_ids
+            .iter()
+            .find(|&&cmb_id| !unique_ids.insert(cmb_id))
+        {
+            return Err(QueueSubmitError::DuplicateCommandBuffer(cmb_id));
+        }
+
         self.initialize_used_uninitialized_memory::<B>(queue_id, command_buffer_ids)?;
 
         let hub = B::hub(self);
@@ -621,27 +1052,97 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
             let device = device_guard
                 .get_mut(queue_id)
                 .map_err(|_| DeviceError::Invalid)?;
-            let pending_write_command_buffer = device.pending_writes.finish();
+            if device.destroyed {
+                return Err(DeviceError::Destroyed.into());
+            }
             device.temp_suspected.clear();
-            device.active_submission_index += 1;
-            let submit_index = device.active_submission_index;
+            let submit_index = device.active_submission_index + 1;
 
-            let fence = {
+            let (fence, pending_write_command_buffer) = {
                 let mut signal_swapchain_semaphores = SmallVec::<[_; 1]>::new();
                 let (mut swap_chain_guard, mut token) = hub.swap_chains.write(&mut token);
                 let (mut command_buffer_guard, mut token) = hub.command_buffers.write(&mut token);
 
-                if !command_buffer_ids.is_empty() {
+                let (mut fence, pending_write_command_buffer) = if !command_buffer_ids.is_empty() {
                     let (render_bundle_guard, mut token) = hub.render_bundles.read(&mut token);
                     let (_, mut token) = hub.pipeline_layouts.read(&mut token);
                     let (bind_group_guard, mut token) = hub.bind_groups.read(&mut token);
                     let (compute_pipe_guard, mut token) = hub.compute_pipelines.read(&mut token);
                     let (render_pipe_guard, mut token) = hub.render_pipelines.read(&mut token);
+                    let (query_set_guard, mut token) = hub.query_sets.read(&mut token);
                     let (mut buffer_guard, mut token) = hub.buffers.write(&mut token);
                     let (texture_guard, mut token) = hub.textures.write(&mut token);
                     let (texture_view_guard, mut token) = hub.texture_views.read(&mut token);
                     let (sampler_guard, _) = hub.samplers.read(&mut token);
 
+                    // Validate all the command buffers before anything is consumed, so that
+                    // a failed submission keeps the pending writes for the next one.
+                    for &cmb_id in command_buffer_ids {
+                        let cmdbuf = command_buffer_guard
+                            .get_mut(cmb_id)
+                            .map_err(|_| QueueSubmitError::InvalidCommandBuffer(cmb_id))?
+                            .data
+                            .get_mut();
+
+                        for sc_id in cmdbuf.used_swap_chains.iter() {
+                            if swap_chain_guard[sc_id.value].acquired_view_id.is_none() {
+                                return Err(QueueSubmitError::SwapChainOutputDropped);
+                            }
+                        }
+
+                        // check the indexed draws against the contents of the index buffers,
+                        // in the order the GPU is going to write and read them
+                        for action in cmdbuf.index_tracker_actions.iter() {
+                            match *action {
+                                IndexTrackerAction::Validate(ref validation) => {
+                                    let buffer =
+                                        buffer_guard.get_mut(validation.buffer).map_err(|e| {
+                                            QueueSubmitError::InvalidBuffer(validation.buffer, e)
+                                        })?;
+                                    validation.check(buffer.index_tracker.as_mut()).map_err(
+                                        |max_index| QueueSubmitError::IndexBeyondVertexLimit {
+                                            buffer: validation.buffer,
+                                            max_index,
+                                            base_vertex: validation.base_vertex,
+                                            vertex_limit: validation.vertex_limit,
+                                            slot: validation.vertex_limit_slot,
+                                        },
+                                    )?;
+                                }
+                                // Applied even if the submission fails later on, which only
+                                // makes the following checks more conservative.
+                                IndexTrackerAction::Invalidate { buffer, ref range } => {
+                                    if let Ok(buffer) = buffer_guard.get_mut(buffer) {
+                                        if let Some(ref mut index_tracker) = buffer.index_tracker {
+                                            index_tracker.invalidate(range.clone());
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
+                        // optimize the tracked states
+                        cmdbuf.trackers.optimize();
+
+                        for id in cmdbuf.trackers.buffers.used() {
+                            if buffer_guard[id].raw.is_none() {
+                                return Err(QueueSubmitError::DestroyedBuffer(id.0));
+                            }
+                        }
+                        for id in cmdbuf.trackers.textures.used() {
+                            if texture_guard[id].raw.is_none() {
+                                return Err(QueueSubmitError::DestroyedTexture(id.0));
+                            }
+                        }
+                        for id in cmdbuf.trackers.query_sets.used() {
+                            if query_set_guard[id].raw.is_none() {
+                                return Err(QueueSubmitError::DestroyedQuerySet(id.0));
+                            }
+                        }
+                    }
+
+                    let started = device.start_submission()?;
+
                     //Note: locking the trackers has to be done after the storages
                     let mut trackers = device.trackers.lock();
 
@@ -651,22 +1152,23 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
 
                     // finish all the command buffers first
                     for &cmb_id in command_buffer_ids {
-                        let cmdbuf = command_buffer_guard
+                        let command_buffer = command_buffer_guard
                             .get_mut(cmb_id)
                             .map_err(|_| QueueSubmitError::InvalidCommandBuffer(cmb_id))?;
+                        let cmdbuf = command_buffer.data.get_mut();
                         #[cfg(feature = "trace")]
-                        if let Some(ref trace) = device.trace {
-                            trace.lock().add(Action::Submit(
-                                submit_index,
-                                cmdbuf.commands.take().unwrap(),
-                            ));
+                        if let Some(mut trace) = device.active_trace() {
+                            match cmdbuf.commands.take() {
+                                Some(commands) => trace.add(Action::Submit(submit_index, commands)),
+                                None => tracing::warn!(
+                                    "Command buffer {:?} was recorded before the trace started",
+                                    cmb_id
+                                ),
+                            }
                         }
 
                         for sc_id in cmdbuf.used_swap_chains.drain(..) {
                             let sc = &mut swap_chain_guard[sc_id.value];
-                            if sc.acquired_view_id.is_none() {
-                                return Err(QueueSubmitError::SwapChainOutputDropped);
-                            }
                             if sc.active_submission_index != submit_index {
                                 sc.active_submission_index = submit_index;
                                 // Only add a signal if this is the first time for this swapchain
@@ -675,15 +1177,12 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                             }
                         }
 
-                        // optimize the tracked states
-                        cmdbuf.trackers.optimize();
+                        // already validated and applied
+                        cmdbuf.index_tracker_actions.clear();
 
                         // update submission IDs
                         for id in cmdbuf.trackers.buffers.used() {
                             let buffer = &mut buffer_guard[id];
-                            if buffer.raw.is_none() {
-                                return Err(QueueSubmitError::DestroyedBuffer(id.0));
-                            }
                             if !buffer.life_guard.use_at(submit_index) {
                                 if let BufferMapState::Active { .. } = buffer.map_state {
                                     tracing::warn!("Dropped buffer has a pending mapping.");
@@ -699,9 +1198,6 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         }
                         for id in cmdbuf.trackers.textures.used() {
                             let texture = &texture_guard[id];
-                            if texture.raw.is_none() {
-                                return Err(QueueSubmitError::DestroyedTexture(id.0));
-                            }
                             if !texture.life_guard.use_at(submit_index) {
                                 device.temp_suspected.textures.push(id);
                             }
@@ -736,9 +1232,16 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                                 device.temp_suspected.render_bundles.push(id);
                             }
                         }
+                        for id in cmdbuf.trackers.query_sets.used() {
+                            let query_set = &query_set_guard[id];
+                            if !query_set.life_guard.use_at(submit_index) {
+                                device.temp_suspected.query_sets.push(id);
+                            }
+                        }
 
                         // execute resource transitions
-                        let mut transit = device.cmd_allocator.extend(cmdbuf);
+                        let mut pool = command_buffer.pool.lock();
+                        let mut transit = pool.allocate(hal::command::Level::Primary);
                         unsafe {
                             // the last buffer was open, closing now
                             cmdbuf.raw.last_mut().unwrap().finish();
@@ -760,18 +1263,20 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                     }
 
                     tracing::trace!("Device after submission {}: {:#?}", submit_index, trackers);
-                }
+                    started
+                } else {
+                    device.start_submission()?
+                };
 
                 // now prepare the GPU submission
-                let mut fence = device
-                    .raw
-                    .create_fence(false)
-                    .or(Err(DeviceError::OutOfMemory))?;
-                let command_buffers = pending_write_command_buffer.as_ref().into_iter().chain(
-                    command_buffer_ids.iter().flat_map(|&cmd_buf_id| {
-                        command_buffer_guard.get(cmd_buf_id).unwrap().raw.iter()
-                    }),
-                );
+                let command_buffer_data = command_buffer_ids
+                    .iter()
+                    .map(|&cmd_buf_id| command_buffer_guard.get(cmd_buf_id).unwrap().data.lock())
+                    .collect::<Vec<_>>();
+                let command_buffers = pending_write_command_buffer
+                    .as_ref()
+                    .into_iter()
+                    .chain(command_buffer_data.iter().flat_map(|data| data.raw.iter()));
                 let signal_semaphores = signal_swapchain_semaphores
                     .into_iter()
                     .map(|sc_id| &swap_chain_guard[sc_id].semaphore);
@@ -784,7 +1289,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                         Some(&mut fence),
                     );
                 }
-                fence
+                (fence, pending_write_command_buffer)
             };
 
             if let Some(comb_raw) = pending_write_command_buffer {
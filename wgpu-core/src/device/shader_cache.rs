@@ -0,0 +1,218 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Cache of the shader modules parsed by a device.
+//!
+//! Shader modules created from the same source and flags share the parsed
+//! `Naga` module with its analysis, the interface, and the SPIR-V given to
+//! the backend, so that only the first one is parsed and validated. Modules
+//! with `ShaderFlags::EXPERIMENTAL_TRANSLATION` hand a copy of the cached
+//! `Naga` module to the backend.
+//!
+//! The cache holds a bounded number of entries, evicting the least recently
+//! used one when it's full.
+
+use crate::{pipeline::ShaderModuleSource, validation::Interface, FastHashMap};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+enum SourceKey {
+    SpirV(Vec<u32>),
+    Wgsl(String),
+    Glsl {
+        code: String,
+        stage: naga::ShaderStage,
+        /// Sorted by name.
+        defines: Vec<(String, String)>,
+    },
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) struct ShaderCacheKey {
+    source: SourceKey,
+    flags: wgt::ShaderFlags,
+}
+
+impl ShaderCacheKey {
+    /// Returns `None` for sources that can't be cached.
+    pub(crate) fn new(source: &ShaderModuleSource, flags: wgt::ShaderFlags) -> Option<Self> {
+        let source = match *source {
+            ShaderModuleSource::SpirV(ref spv) => SourceKey::SpirV(spv.to_vec()),
+            ShaderModuleSource::Wgsl(ref code) => SourceKey::Wgsl(code.to_string()),
+            ShaderModuleSource::Glsl {
+                ref code,
+                stage,
+                ref defines,
+            } => {
+                let mut defines = defines
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect::<Vec<_>>();
+                defines.sort();
+                SourceKey::Glsl {
+                    code: code.to_string(),
+                    stage,
+                    defines,
+                }
+            }
+            ShaderModuleSource::Naga(_) => return None,
+        };
+        Some(Self { source, flags })
+    }
+}
+
+/// Maximum number of entries of the cache of a device.
+const MAX_ENTRIES: usize = 256;
+
+#[derive(Clone, Debug)]
+pub(crate) struct CachedShader {
+    /// Validated module, if the source was parsed by `Naga`.
+    pub(crate) naga: Option<Arc<(naga::Module, naga::proc::analyzer::Analysis)>>,
+    pub(crate) interface: Option<Arc<Interface>>,
+    /// SPIR-V given by the user, or translated from `naga`. It's only missing
+    /// for modules that the backend translated itself.
+    pub(crate) spv: Option<Arc<[u32]>>,
+}
+
+/// Statistics of the shader cache of a device, for profiling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShaderCacheStats {
+    /// Shader modules created from a cached translation.
+    pub hits: u64,
+    /// Shader modules that had to be parsed and translated.
+    pub misses: u64,
+    /// Modules held by the cache.
+    pub entries: usize,
+    /// Modules dropped from the cache to make room for new ones.
+    pub evictions: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct ShaderCache {
+    /// Entries along with the last time they were used.
+    entries: FastHashMap<ShaderCacheKey, (CachedShader, u64)>,
+    capacity: usize,
+    uses: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl Default for ShaderCache {
+    fn default() -> Self {
+        Self {
+            entries: FastHashMap::default(),
+            capacity: MAX_ENTRIES,
+            uses: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+}
+
+impl ShaderCache {
+    pub(crate) fn get(&mut self, key: &ShaderCacheKey) -> Option<CachedShader> {
+        match self.entries.get_mut(key) {
+            Some(&mut (ref shader, ref mut last_use)) => {
+                self.uses += 1;
+                *last_use = self.uses;
+                self.hits += 1;
+                Some(shader.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: ShaderCacheKey, shader: CachedShader) {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|&(_, &(_, last_use))| last_use)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+                self.evictions += 1;
+            }
+        }
+        self.uses += 1;
+        self.entries.insert(key, (shader, self.uses));
+    }
+
+    pub(crate) fn stats(&self) -> ShaderCacheStats {
+        ShaderCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+            evictions: self.evictions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CachedShader, ShaderCache, ShaderCacheKey, ShaderCacheStats};
+    use crate::pipeline::ShaderModuleSource;
+    use std::borrow::Cow;
+
+    fn glsl_key(defines: &[(&str, &str)]) -> ShaderCacheKey {
+        let source = ShaderModuleSource::Glsl {
+            code: Cow::Borrowed("void main() {}"),
+            stage: naga::ShaderStage::Compute,
+            defines: defines
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        };
+        ShaderCacheKey::new(&source, wgt::ShaderFlags::VALIDATION).unwrap()
+    }
+
+    fn shader() -> CachedShader {
+        CachedShader {
+            naga: None,
+            interface: None,
+            spv: Some(vec![0x0723_0203].into()),
+        }
+    }
+
+    #[test]
+    fn hits_and_misses() {
+        let mut cache = ShaderCache::default();
+        let key = glsl_key(&[("A", "1"), ("B", "2")]);
+        assert!(cache.get(&key).is_none());
+        cache.insert(key, shader());
+        assert!(cache.get(&glsl_key(&[("B", "2"), ("A", "1")])).is_some());
+        assert!(cache.get(&glsl_key(&[("A", "1")])).is_none());
+        assert_eq!(
+            cache.stats(),
+            ShaderCacheStats {
+                hits: 1,
+                misses: 2,
+                entries: 1,
+                evictions: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let mut cache = ShaderCache {
+            capacity: 2,
+            ..ShaderCache::default()
+        };
+        cache.insert(glsl_key(&[("A", "1")]), shader());
+        cache.insert(glsl_key(&[("A", "2")]), shader());
+        assert!(cache.get(&glsl_key(&[("A", "1")])).is_some());
+        cache.insert(glsl_key(&[("A", "3")]), shader());
+        assert!(cache.get(&glsl_key(&[("A", "2")])).is_none());
+        assert!(cache.get(&glsl_key(&[("A", "1")])).is_some());
+        assert!(cache.get(&glsl_key(&[("A", "3")])).is_some());
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+}
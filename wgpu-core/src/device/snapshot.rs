@@ -0,0 +1,333 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use crate::{
+    command::{TextureCopyView, BITS_PER_BYTE},
+    conv,
+    device::{alloc, trace, DeviceError},
+    hub::{GfxBackend, Storage},
+    id,
+    resource::{Buffer, BufferMapState, BufferUse, Texture, TextureUse},
+};
+
+use hal::{command::CommandBuffer as _, device::Device as _, queue::Queue as _};
+use std::iter;
+
+/// Contents of a resource, read back for a new trace.
+pub(super) enum Contents {
+    Buffer {
+        id: id::BufferId,
+        data: Vec<u8>,
+        queued: bool,
+    },
+    Texture {
+        to: TextureCopyView,
+        layout: wgt::TextureDataLayout,
+        size: wgt::Extent3d,
+        data: Vec<u8>,
+    },
+}
+
+impl Contents {
+    pub(super) fn into_action(self, trace: &mut trace::Trace) -> trace::Action<'static> {
+        match self {
+            Contents::Buffer { id, data, queued } => trace::Action::WriteBuffer {
+                id,
+                range: 0..data.len() as wgt::BufferAddress,
+                data: trace.make_binary("bin", &data),
+                queued,
+            },
+            Contents::Texture {
+                to,
+                layout,
+                size,
+                data,
+            } => trace::Action::WriteTexture {
+                to,
+                data: trace.make_binary("bin", &data),
+                layout,
+                size,
+            },
+        }
+    }
+}
+
+/// A GPU copy into a host-visible staging buffer.
+struct Readback<B: hal::Backend> {
+    buffer: B::Buffer,
+    memory: alloc::MemoryBlock<B>,
+    size: wgt::BufferAddress,
+    contents: Contents,
+}
+
+impl<B: GfxBackend> super::Device<B> {
+    fn create_readback_buffer(
+        &self,
+        size: wgt::BufferAddress,
+    ) -> Result<(B::Buffer, alloc::MemoryBlock<B>), DeviceError> {
+        let mut buffer = unsafe {
+            self.raw
+                .create_buffer(
+                    size,
+                    hal::buffer::Usage::TRANSFER_DST,
+                    hal::memory::SparseFlags::empty(),
+                )
+                .map_err(|err| match err {
+                    hal::buffer::CreationError::OutOfMemory(_) => DeviceError::OutOfMemory,
+                    _ => panic!("failed to create readback buffer: {}", err),
+                })?
+        };
+        let requirements = unsafe {
+            self.raw.set_buffer_name(&mut buffer, "<trace_readback>");
+            self.raw.get_buffer_requirements(&buffer)
+        };
+        let block = self.mem_allocator.lock().allocate(
+            &self.raw,
+            requirements,
+            gpu_alloc::UsageFlags::DOWNLOAD | gpu_alloc::UsageFlags::TRANSIENT,
+        )?;
+        block.bind_buffer(&self.raw, &mut buffer)?;
+        Ok((buffer, block))
+    }
+
+    /// Reads back the contents of all the buffers and textures of this device
+    /// that can be restored by a trace. The device is expected to be idle.
+    pub(super) fn read_contents(
+        &mut self,
+        self_id: id::DeviceId,
+        buffer_guard: &mut Storage<Buffer<B>, id::BufferId>,
+        texture_guard: &Storage<Texture<B>, id::TextureId>,
+    ) -> Result<Vec<Contents>, DeviceError> {
+        let mut contents = Vec::new();
+        let mut copied_buffers = Vec::new();
+
+        for (id, buffer) in buffer_guard.iter_mut(B::VARIANT) {
+            if buffer.device_id.value.0 != self_id {
+                continue;
+            }
+            let queued = if buffer.usage.contains(wgt::BufferUsage::MAP_WRITE) {
+                false
+            } else if buffer.usage.contains(wgt::BufferUsage::COPY_DST) {
+                true
+            } else {
+                tracing::warn!("Contents of {:?} can't be restored in the trace", id);
+                continue;
+            };
+            let block = match (&mut buffer.raw, &buffer.map_state) {
+                (&mut Some((_, ref mut block)), &BufferMapState::Idle) => block,
+                (&mut Some(_), _) => {
+                    tracing::warn!("Contents of {:?} are skipped, since it's mapped", id);
+                    continue;
+                }
+                (&mut None, _) => continue,
+            };
+            if buffer
+                .usage
+                .intersects(wgt::BufferUsage::MAP_READ | wgt::BufferUsage::MAP_WRITE)
+            {
+                let mut data = vec![0; buffer.size as usize];
+                block.read_bytes(&self.raw, 0, &mut data)?;
+                contents.push(Contents::Buffer { id, data, queued });
+            } else if buffer.usage.contains(wgt::BufferUsage::COPY_SRC) {
+                copied_buffers.push((id, queued));
+            } else {
+                tracing::warn!("Contents of {:?} can't be read for the trace", id);
+            }
+        }
+
+        let mut readbacks = Vec::<Readback<B>>::new();
+        let mut cmdbuf = self.cmd_allocator.allocate_internal();
+        unsafe {
+            cmdbuf.begin_primary(hal::command::CommandBufferFlags::ONE_TIME_SUBMIT);
+        }
+        let mut trackers = self.trackers.lock();
+
+        for (id, queued) in copied_buffers {
+            let (buffer, transition) = trackers
+                .buffers
+                .use_replace(&*buffer_guard, id, (), BufferUse::COPY_SRC)
+                .unwrap();
+            let (stage, memory) = self.create_readback_buffer(buffer.size)?;
+            let region = hal::command::BufferCopy {
+                src: 0,
+                dst: 0,
+                size: buffer.size,
+            };
+            unsafe {
+                cmdbuf.pipeline_barrier(
+                    super::all_buffer_stages()..hal::pso::PipelineStage::TRANSFER,
+                    hal::memory::Dependencies::empty(),
+                    transition.map(|pending| pending.into_hal(buffer)),
+                );
+                cmdbuf.copy_buffer(&buffer.raw.as_ref().unwrap().0, &stage, iter::once(region));
+            }
+            readbacks.push(Readback {
+                buffer: stage,
+                memory,
+                size: buffer.size,
+                contents: Contents::Buffer {
+                    id,
+                    data: Vec::new(),
+                    queued,
+                },
+            });
+        }
+
+        for (id, texture) in texture_guard.iter(B::VARIANT) {
+            if texture.device_id.value.0 != self_id || texture.raw.is_none() {
+                continue;
+            }
+            if !texture
+                .usage
+                .contains(wgt::TextureUsage::COPY_SRC | wgt::TextureUsage::COPY_DST)
+                || texture.aspects != hal::format::Aspects::COLOR
+                || texture.kind.num_samples() != 1
+            {
+                tracing::warn!("Contents of {:?} can't be restored in the trace", id);
+                continue;
+            }
+
+            let (_, transition) = trackers
+                .textures
+                .use_replace(
+                    texture_guard,
+                    id,
+                    texture.full_range.clone(),
+                    TextureUse::COPY_SRC,
+                )
+                .unwrap();
+            unsafe {
+                cmdbuf.pipeline_barrier(
+                    super::all_image_stages()..hal::pso::PipelineStage::TRANSFER,
+                    hal::memory::Dependencies::empty(),
+                    transition.map(|pending| pending.into_hal(texture)),
+                );
+            }
+
+            let bytes_per_block = conv::map_texture_format(texture.format, self.private_features)
+                .surface_desc()
+                .bits as u32
+                / BITS_PER_BYTE;
+            let (block_width, block_height) = texture.format.describe().block_dimensions;
+            let (block_width, block_height) = (block_width as u32, block_height as u32);
+            let pitch_alignment = wgt::COPY_BYTES_PER_ROW_ALIGNMENT
+                .max(self.hal_limits.optimal_buffer_copy_pitch_alignment as u32);
+            let num_layers = texture.full_range.layers.end as u32;
+
+            for level in texture.full_range.levels.clone() {
+                let extent = texture.kind.level_extent(level);
+                let width_blocks = (extent.width + block_width - 1) / block_width;
+                let height_blocks = (extent.height + block_height - 1) / block_height;
+                let bytes_per_row = {
+                    let unaligned = width_blocks * bytes_per_block;
+                    (unaligned + pitch_alignment - 1) / pitch_alignment * pitch_alignment
+                };
+                let depth_or_array_layers = match texture.dimension {
+                    wgt::TextureDimension::D3 => extent.depth,
+                    _ => num_layers,
+                };
+                let size = bytes_per_row as wgt::BufferAddress
+                    * height_blocks as wgt::BufferAddress
+                    * depth_or_array_layers as wgt::BufferAddress;
+                let (stage, memory) = self.create_readback_buffer(size)?;
+
+                let region = hal::command::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_width: bytes_per_row / bytes_per_block * block_width,
+                    buffer_height: height_blocks * block_height,
+                    image_layers: hal::image::SubresourceLayers {
+                        aspects: hal::format::Aspects::COLOR,
+                        level,
+                        layers: texture.full_range.layers.clone(),
+                    },
+                    image_offset: hal::image::Offset::ZERO,
+                    image_extent: extent,
+                };
+                unsafe {
+                    cmdbuf.copy_image_to_buffer(
+                        &texture.raw.as_ref().unwrap().0,
+                        hal::image::Layout::TransferSrcOptimal,
+                        &stage,
+                        iter::once(region),
+                    );
+                }
+                readbacks.push(Readback {
+                    buffer: stage,
+                    memory,
+                    size,
+                    contents: Contents::Texture {
+                        to: TextureCopyView {
+                            texture: id,
+                            mip_level: level as u32,
+                            origin: wgt::Origin3d::ZERO,
+                            aspect: wgt::TextureAspect::All,
+                        },
+                        layout: wgt::TextureDataLayout {
+                            offset: 0,
+                            bytes_per_row,
+                            rows_per_image: height_blocks * block_height,
+                        },
+                        size: wgt::Extent3d {
+                            width: width_blocks * block_width,
+                            height: height_blocks * block_height,
+                            depth_or_array_layers,
+                        },
+                        data: Vec::new(),
+                    },
+                });
+            }
+        }
+        drop(trackers);
+
+        unsafe {
+            cmdbuf.pipeline_barrier(
+                hal::pso::PipelineStage::TRANSFER..hal::pso::PipelineStage::HOST,
+                hal::memory::Dependencies::empty(),
+                readbacks
+                    .iter()
+                    .map(|readback| hal::memory::Barrier::Buffer {
+                        states: hal::buffer::Access::TRANSFER_WRITE..hal::buffer::Access::HOST_READ,
+                        target: &readback.buffer,
+                        range: hal::buffer::SubRange::WHOLE,
+                        families: None,
+                    }),
+            );
+            cmdbuf.finish();
+        }
+
+        let mut fence = self
+            .raw
+            .create_fence(false)
+            .or(Err(DeviceError::OutOfMemory))?;
+        let status = unsafe {
+            self.queue_group.queues[0].submit(
+                iter::once(&cmdbuf),
+                iter::empty(),
+                iter::empty(),
+                Some(&mut fence),
+            );
+            let status = self.raw.wait_for_fence(&fence, !0);
+            self.raw.destroy_fence(fence);
+            status
+        };
+        self.cmd_allocator.discard_internal(cmdbuf);
+        status.map_err(DeviceError::from)?;
+
+        for mut readback in readbacks {
+            let result = match readback.contents {
+                Contents::Buffer { ref mut data, .. } | Contents::Texture { ref mut data, .. } => {
+                    *data = vec![0; readback.size as usize];
+                    readback.memory.read_bytes(&self.raw, 0, data)
+                }
+            };
+            self.mem_allocator.lock().free(&self.raw, readback.memory);
+            unsafe {
+                self.raw.destroy_buffer(readback.buffer);
+            }
+            result?;
+            contents.push(readback.contents);
+        }
+        Ok(contents)
+    }
+}
@@ -0,0 +1,276 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Persistent staging memory for `queue_write_buffer` and `queue_write_texture`.
+//!
+//! Every device lazily creates one upload buffer, mapped for its whole
+//! lifetime, and sub-allocates the staged data of the writes from it as a
+//! ring. The bytes allocated between two submissions form a region, tagged
+//! with the index of the submission that reads them. Regions are given back
+//! once the `LifetimeTracker` no longer has their submission active.
+//!
+//! Uploads bigger than `MAX_BELT_UPLOAD`, or that don't fit in the free part
+//! of the ring, get a dedicated staging buffer instead.
+
+use super::{alloc, DeviceError};
+use crate::SubmissionIndex;
+
+use hal::device::Device as _;
+use std::{collections::VecDeque, ptr::NonNull};
+
+/// Size of the staging belt of a device.
+pub(super) const STAGING_BELT_SIZE: wgt::BufferAddress = 4 << 20;
+/// Largest upload sub-allocated from the staging belt.
+pub(super) const MAX_BELT_UPLOAD: wgt::BufferAddress = STAGING_BELT_SIZE / 4;
+
+#[derive(Debug)]
+struct Region {
+    submission: SubmissionIndex,
+    end: wgt::BufferAddress,
+    bytes: wgt::BufferAddress,
+}
+
+/// Offset bookkeeping of the staging belt.
+#[derive(Debug)]
+struct Ring {
+    size: wgt::BufferAddress,
+    /// Start of the oldest allocation in use.
+    tail: wgt::BufferAddress,
+    /// End of the newest allocation.
+    head: wgt::BufferAddress,
+    /// Bytes in use, including the ones skipped when wrapping around.
+    used: wgt::BufferAddress,
+    /// Bytes allocated since the last submission.
+    pending: wgt::BufferAddress,
+    /// Regions read by submissions in flight, oldest first.
+    in_flight: VecDeque<Region>,
+}
+
+impl Ring {
+    fn new(size: wgt::BufferAddress) -> Self {
+        Self {
+            size,
+            tail: 0,
+            head: 0,
+            used: 0,
+            pending: 0,
+            in_flight: VecDeque::new(),
+        }
+    }
+
+    fn allocate(
+        &mut self,
+        size: wgt::BufferAddress,
+        alignment: wgt::BufferAddress,
+    ) -> Option<wgt::BufferAddress> {
+        if self.used == 0 {
+            self.head = 0;
+            self.tail = 0;
+        }
+        let start = match self.head % alignment {
+            0 => self.head,
+            other => self.head - other + alignment,
+        };
+        let offset = if self.used != 0 && self.head <= self.tail {
+            // The free space is all between the head and the tail.
+            if start + size > self.tail {
+                return None;
+            }
+            start
+        } else if start + size <= self.size {
+            start
+        } else if size <= self.tail {
+            // Wrap around, skipping the end of the ring.
+            0
+        } else {
+            return None;
+        };
+
+        let end = offset + size;
+        let bytes = if offset >= self.head {
+            end - self.head
+        } else {
+            self.size - self.head + end
+        };
+        self.head = end;
+        self.used += bytes;
+        self.pending += bytes;
+        Some(offset)
+    }
+
+    fn submit(&mut self, submission: SubmissionIndex) {
+        if self.pending != 0 {
+            self.in_flight.push_back(Region {
+                submission,
+                end: self.head,
+                bytes: self.pending,
+            });
+            self.pending = 0;
+        }
+    }
+
+    fn recycle(&mut self, oldest_active: Option<SubmissionIndex>) {
+        while let Some(region) = self.in_flight.front() {
+            if oldest_active.map_or(false, |index| region.submission >= index) {
+                break;
+            }
+            self.tail = if region.end == self.size {
+                0
+            } else {
+                region.end
+            };
+            self.used -= region.bytes;
+            self.in_flight.pop_front();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct StagingBelt<B: hal::Backend> {
+    pub(super) buffer: B::Buffer,
+    memory: alloc::MemoryBlock<B>,
+    ptr: NonNull<u8>,
+    ring: Ring,
+}
+
+unsafe impl<B: hal::Backend> Send for StagingBelt<B> {}
+unsafe impl<B: hal::Backend> Sync for StagingBelt<B> {}
+
+impl<B: hal::Backend> StagingBelt<B> {
+    pub(super) fn new(
+        device: &B::Device,
+        mem_allocator: &mut alloc::MemoryAllocator<B>,
+    ) -> Result<Self, DeviceError> {
+        let mut buffer = unsafe {
+            device
+                .create_buffer(
+                    STAGING_BELT_SIZE,
+                    hal::buffer::Usage::TRANSFER_SRC,
+                    hal::memory::SparseFlags::empty(),
+                )
+                .map_err(|err| match err {
+                    hal::buffer::CreationError::OutOfMemory(_) => DeviceError::OutOfMemory,
+                    _ => panic!("failed to create staging belt: {}", err),
+                })?
+        };
+        let requirements = unsafe {
+            device.set_buffer_name(&mut buffer, "<staging_belt>");
+            device.get_buffer_requirements(&buffer)
+        };
+        let mut memory =
+            mem_allocator.allocate(device, requirements, gpu_alloc::UsageFlags::UPLOAD)?;
+        let ptr = memory
+            .bind_buffer(device, &mut buffer)
+            .and_then(|()| memory.map(device, 0, STAGING_BELT_SIZE));
+        match ptr {
+            Ok(ptr) => Ok(Self {
+                buffer,
+                memory,
+                ptr,
+                ring: Ring::new(STAGING_BELT_SIZE),
+            }),
+            Err(err) => {
+                mem_allocator.free(device, memory);
+                unsafe {
+                    device.destroy_buffer(buffer);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns the offset of `size` bytes aligned to `alignment`, if the belt
+    /// has room for them.
+    pub(super) fn allocate(
+        &mut self,
+        size: wgt::BufferAddress,
+        alignment: wgt::BufferAddress,
+    ) -> Option<wgt::BufferAddress> {
+        self.ring.allocate(size, alignment)
+    }
+
+    /// Lets `fill` write `size` bytes at `offset`, which were returned by
+    /// `allocate`, and makes them visible to the device.
+    pub(super) fn write(
+        &self,
+        device: &B::Device,
+        offset: wgt::BufferAddress,
+        size: wgt::BufferAddress,
+        non_coherent_atom_size: wgt::BufferAddress,
+        fill: impl FnOnce(NonNull<u8>),
+    ) -> Result<(), DeviceError> {
+        fill(unsafe { NonNull::new_unchecked(self.ptr.as_ptr().offset(offset as isize)) });
+        if self.memory.is_coherent() {
+            return Ok(());
+        }
+        let start = offset - offset % non_coherent_atom_size;
+        let end = match (offset + size) % non_coherent_atom_size {
+            0 => offset + size,
+            other => offset + size - other + non_coherent_atom_size,
+        };
+        self.memory
+            .flush_range(device, start, Some(end.min(STAGING_BELT_SIZE) - start))
+    }
+
+    /// Tags everything allocated since the last call as read by `submission`.
+    pub(super) fn submit(&mut self, submission: SubmissionIndex) {
+        self.ring.submit(submission);
+    }
+
+    /// Gives back the regions of the submissions that are done, given the
+    /// oldest one still active.
+    pub(super) fn recycle(&mut self, oldest_active: Option<SubmissionIndex>) {
+        self.ring.recycle(oldest_active);
+    }
+
+    pub(super) fn dispose(
+        mut self,
+        device: &B::Device,
+        mem_allocator: &mut alloc::MemoryAllocator<B>,
+    ) {
+        self.memory.unmap(device);
+        mem_allocator.free(device, self.memory);
+        unsafe {
+            device.destroy_buffer(self.buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Ring;
+
+    #[test]
+    fn allocations_are_aligned() {
+        let mut ring = Ring::new(64);
+        assert_eq!(ring.allocate(6, 4), Some(0));
+        assert_eq!(ring.allocate(4, 4), Some(8));
+        assert_eq!(ring.allocate(4, 16), Some(16));
+        assert_eq!(ring.used, 20);
+        assert_eq!(ring.allocate(48, 4), None);
+    }
+
+    #[test]
+    fn regions_are_recycled_by_submission() {
+        let mut ring = Ring::new(64);
+        assert_eq!(ring.allocate(32, 4), Some(0));
+        ring.submit(1);
+        assert_eq!(ring.allocate(16, 4), Some(32));
+        ring.submit(2);
+        assert_eq!(ring.allocate(32, 4), None);
+
+        ring.recycle(Some(1));
+        assert_eq!(ring.allocate(32, 4), None);
+        ring.recycle(Some(2));
+        // The end of the ring is skipped when wrapping around.
+        assert_eq!(ring.allocate(24, 4), Some(0));
+        assert_eq!(ring.used, 56);
+        assert_eq!(ring.allocate(12, 4), None);
+        ring.submit(3);
+
+        ring.recycle(None);
+        assert_eq!(ring.used, 0);
+        assert_eq!(ring.allocate(64, 4), Some(0));
+    }
+}
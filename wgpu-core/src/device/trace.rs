@@ -3,6 +3,8 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use crate::id;
+#[cfg(feature = "trace")]
+use crate::FastHashMap;
 use std::ops::Range;
 #[cfg(feature = "trace")]
 use std::{borrow::Cow, io::Write as _};
@@ -13,15 +15,26 @@ type FileName = String;
 
 pub const FILE_NAME: &str = "trace.ron";
 
+/// Shader stages of GLSL sources, keyed by the extension of their data file.
+pub const GLSL_EXTENSIONS: [(&str, naga::ShaderStage); 3] = [
+    ("vert", naga::ShaderStage::Vertex),
+    ("frag", naga::ShaderStage::Fragment),
+    ("comp", naga::ShaderStage::Compute),
+];
+
 #[cfg(feature = "trace")]
 pub(crate) fn new_render_bundle_encoder_descriptor<'a>(
     label: crate::Label<'a>,
     context: &'a super::RenderPassContext,
+    depth_read_only: bool,
+    stencil_read_only: bool,
 ) -> crate::command::RenderBundleEncoderDescriptor<'a> {
     crate::command::RenderBundleEncoderDescriptor {
         label,
         color_formats: Cow::Borrowed(&context.attachments.colors),
         depth_stencil_format: context.attachments.depth_stencil,
+        depth_read_only,
+        stencil_read_only,
         sample_count: context.sample_count as u32,
     }
 }
@@ -36,9 +49,13 @@ pub enum Action<'a> {
         backend: wgt::Backend,
     },
     CreateBuffer(id::BufferId, crate::resource::BufferDescriptor<'a>),
+    /// Buffer wrapping a hal object, whose contents aren't in the trace.
+    CreateBufferFromHal(id::BufferId, crate::resource::BufferDescriptor<'a>),
     FreeBuffer(id::BufferId),
     DestroyBuffer(id::BufferId),
     CreateTexture(id::TextureId, crate::resource::TextureDescriptor<'a>),
+    /// Texture wrapping a hal object, whose contents aren't in the trace.
+    CreateTextureFromHal(id::TextureId, crate::resource::TextureDescriptor<'a>),
     FreeTexture(id::TextureId),
     DestroyTexture(id::TextureId),
     CreateTextureView {
@@ -74,6 +91,9 @@ pub enum Action<'a> {
         id: id::ShaderModuleId,
         desc: crate::pipeline::ShaderModuleDescriptor<'a>,
         data: FileName,
+        /// Preprocessor definitions of a GLSL source.
+        #[cfg_attr(feature = "replay", serde(default))]
+        defines: Vec<(String, String)>,
     },
     DestroyShaderModule(id::ShaderModuleId),
     CreateComputePipeline {
@@ -100,6 +120,7 @@ pub enum Action<'a> {
         id: id::QuerySetId,
         desc: wgt::QuerySetDescriptor,
     },
+    FreeQuerySet(id::QuerySetId),
     DestroyQuerySet(id::QuerySetId),
     WriteBuffer {
         id: id::BufferId,
@@ -193,13 +214,200 @@ impl Trace {
         name
     }
 
+    fn write_binary(&self, name: &str, data: &[u8]) {
+        let _ = std::fs::write(self.path.join(name), data);
+    }
+
     pub(crate) fn add(&mut self, action: Action) {
-        match ron::ser::to_string_pretty(&action, self.config.clone()) {
-            Ok(string) => {
-                let _ = writeln!(self.file, "{},", string);
+        if let Some(string) = serialize(&action, &self.config) {
+            self.add_serialized(&string);
+        }
+    }
+
+    fn add_serialized(&mut self, string: &str) {
+        let _ = writeln!(self.file, "{},", string);
+    }
+}
+
+#[cfg(feature = "trace")]
+fn serialize(action: &Action, config: &ron::ser::PrettyConfig) -> Option<String> {
+    match ron::ser::to_string_pretty(action, config.clone()) {
+        Ok(string) => Some(string),
+        Err(e) => {
+            tracing::warn!("RON serialization failure: {:?}", e);
+            None
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+type LiveKey = (&'static str, id::Index, id::Epoch);
+
+#[cfg(feature = "trace")]
+fn live_key<I: id::TypedId>(kind: &'static str, id: I) -> LiveKey {
+    let (index, epoch, _) = id.unzip();
+    (kind, index, epoch)
+}
+
+/// Effect of an action on the lifetime of a resource.
+#[cfg(feature = "trace")]
+enum Lifetime {
+    Create(LiveKey),
+    Free(LiveKey),
+    Destroy(LiveKey),
+}
+
+#[cfg(feature = "trace")]
+impl Action<'_> {
+    fn lifetime(&self) -> Option<Lifetime> {
+        use Lifetime as L;
+        Some(match *self {
+            Action::CreateBuffer(id, _) | Action::CreateBufferFromHal(id, _) => {
+                L::Create(live_key("Buffer", id))
+            }
+            Action::FreeBuffer(id) => L::Free(live_key("Buffer", id)),
+            Action::DestroyBuffer(id) => L::Destroy(live_key("Buffer", id)),
+            Action::CreateTexture(id, _) | Action::CreateTextureFromHal(id, _) => {
+                L::Create(live_key("Texture", id))
+            }
+            Action::FreeTexture(id) => L::Free(live_key("Texture", id)),
+            Action::DestroyTexture(id) => L::Destroy(live_key("Texture", id)),
+            Action::CreateTextureView { id, .. } => L::Create(live_key("TextureView", id)),
+            Action::DestroyTextureView(id) => L::Destroy(live_key("TextureView", id)),
+            Action::CreateSampler(id, _) => L::Create(live_key("Sampler", id)),
+            Action::DestroySampler(id) => L::Destroy(live_key("Sampler", id)),
+            Action::CreateSwapChain(id, _) => L::Create(live_key("SwapChain", id)),
+            Action::CreateBindGroupLayout(id, _) => L::Create(live_key("BindGroupLayout", id)),
+            Action::DestroyBindGroupLayout(id) => L::Destroy(live_key("BindGroupLayout", id)),
+            Action::CreatePipelineLayout(id, _) => L::Create(live_key("PipelineLayout", id)),
+            Action::DestroyPipelineLayout(id) => L::Destroy(live_key("PipelineLayout", id)),
+            Action::CreateBindGroup(id, _) => L::Create(live_key("BindGroup", id)),
+            Action::DestroyBindGroup(id) => L::Destroy(live_key("BindGroup", id)),
+            Action::CreateShaderModule { id, .. } => L::Create(live_key("ShaderModule", id)),
+            Action::DestroyShaderModule(id) => L::Destroy(live_key("ShaderModule", id)),
+            Action::CreateComputePipeline { id, .. } => L::Create(live_key("ComputePipeline", id)),
+            Action::DestroyComputePipeline(id) => L::Destroy(live_key("ComputePipeline", id)),
+            Action::CreateRenderPipeline { id, .. } => L::Create(live_key("RenderPipeline", id)),
+            Action::DestroyRenderPipeline(id) => L::Destroy(live_key("RenderPipeline", id)),
+            Action::CreateRenderBundle { id, .. } => L::Create(live_key("RenderBundle", id)),
+            Action::DestroyRenderBundle(id) => L::Destroy(live_key("RenderBundle", id)),
+            Action::CreateQuerySet { id, .. } => L::Create(live_key("QuerySet", id)),
+            Action::FreeQuerySet(id) => L::Free(live_key("QuerySet", id)),
+            Action::DestroyQuerySet(id) => L::Destroy(live_key("QuerySet", id)),
+            _ => return None,
+        })
+    }
+}
+
+/// Actions needed to re-create a live resource in a new trace.
+#[cfg(feature = "trace")]
+#[derive(Debug)]
+struct LiveResource {
+    /// Creation order, which respects the dependencies between resources.
+    order: usize,
+    create: String,
+    /// Explicit destruction, if any, which has to happen after all the creations.
+    free: Option<String>,
+    data: Vec<(FileName, Vec<u8>)>,
+}
+
+/// Tracing state of a traceable device.
+///
+/// The actions affecting the lifetime of resources are always recorded, even
+/// if there is no active trace, so that a trace can be started on a live device.
+/// Devices that are not traceable don't have this state, and don't pay for it.
+#[cfg(feature = "trace")]
+#[derive(Debug, Default)]
+pub(crate) struct Tracer {
+    pub(crate) active: Option<Trace>,
+    config: ron::ser::PrettyConfig,
+    live: FastHashMap<LiveKey, LiveResource>,
+    next_order: usize,
+    binary_id: usize,
+    pending_data: Vec<(FileName, Vec<u8>)>,
+}
+
+#[cfg(feature = "trace")]
+impl Tracer {
+    pub(crate) fn new(active: Option<Trace>) -> Self {
+        Self {
+            active,
+            ..Self::default()
+        }
+    }
+
+    /// Stores data referenced by the creation action that is recorded next.
+    pub(crate) fn make_resource_binary(&mut self, kind: &str, data: &[u8]) -> FileName {
+        self.binary_id += 1;
+        let name = format!("resource{}.{}", self.binary_id, kind);
+        if let Some(ref trace) = self.active {
+            trace.write_binary(&name, data);
+        }
+        self.pending_data.push((name.clone(), data.to_vec()));
+        name
+    }
+
+    /// Records an action into the active trace, and updates the live resources
+    /// if it creates or destroys one of them.
+    pub(crate) fn record(&mut self, action: Action) {
+        let lifetime = action.lifetime();
+        if lifetime.is_none() && self.active.is_none() {
+            return;
+        }
+        let string = match serialize(&action, &self.config) {
+            Some(string) => string,
+            None => return,
+        };
+        if let Some(ref mut trace) = self.active {
+            trace.add_serialized(&string);
+        }
+        match lifetime {
+            Some(Lifetime::Create(key)) => {
+                self.next_order += 1;
+                self.live.insert(
+                    key,
+                    LiveResource {
+                        order: self.next_order,
+                        create: string,
+                        free: None,
+                        data: self.pending_data.drain(..).collect(),
+                    },
+                );
+            }
+            Some(Lifetime::Free(key)) => {
+                if let Some(resource) = self.live.get_mut(&key) {
+                    resource.free = Some(string);
+                }
+            }
+            Some(Lifetime::Destroy(key)) => {
+                self.live.remove(&key);
             }
-            Err(e) => {
-                tracing::warn!("RON serialization failure: {:?}", e);
+            None => {}
+        }
+    }
+
+    /// Writes the creation actions of all the live resources into `trace`,
+    /// returning the explicit destructions that need to go after the contents.
+    pub(crate) fn snapshot_into(&self, trace: &mut Trace) -> Vec<String> {
+        let mut resources = self.live.values().collect::<Vec<_>>();
+        resources.sort_by_key(|resource| resource.order);
+        for resource in resources.iter() {
+            for &(ref name, ref data) in resource.data.iter() {
+                trace.write_binary(name, data);
+            }
+            trace.add_serialized(&resource.create);
+        }
+        resources
+            .into_iter()
+            .filter_map(|resource| resource.free.clone())
+            .collect()
+    }
+
+    /// Writes previously serialized actions into the active trace.
+    pub(crate) fn add_serialized(&mut self, actions: &[String]) {
+        if let Some(ref mut trace) = self.active {
+            for string in actions {
+                trace.add_serialized(string);
             }
         }
     }
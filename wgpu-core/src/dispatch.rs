@@ -0,0 +1,529 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/*! Backend-agnostic entry points.
+ *
+ * Every `Global` method that is generic over the backend has a `dyn_` counterpart here,
+ * which selects the backend from the first id argument, like `gfx_select!` does.
+ * This is meant for the users that can't name a backend type, such as language bindings.
+ * The hal interop methods are the exception, since their arguments are backend types.
+!*/
+
+use crate::{
+    binding_model, command, device, gfx_select,
+    hub::{Global, GlobalIdentityHandlerFactory, Input},
+    id, instance, pipeline, resource, swap_chain,
+};
+
+use std::{ops::Range, path::Path};
+
+/// Generates the backend-agnostic methods.
+///
+/// Each entry dispatches on its first argument, unless an expression
+/// producing the id is given in brackets after the generic method name.
+macro_rules! dispatch {
+    (@select $first:ident) => { $first };
+    (@select $first:ident $select:expr) => { $select };
+    ($(
+        $(#[$attr:meta])*
+        fn $name:ident => $method:ident $([$select:expr])?
+            ($first:ident: $first_ty:ty $(, $arg:ident: $arg_ty:ty)* $(,)?) $(-> $ret:ty)?;
+    )*) => {
+        impl<G: GlobalIdentityHandlerFactory> Global<G> {
+            $(
+                $(#[$attr])*
+                pub fn $name(&self, $first: $first_ty $(, $arg: $arg_ty)*) $(-> $ret)? {
+                    let global = self;
+                    gfx_select!(
+                        dispatch!(@select $first $($select)?) => global.$method($first $(, $arg)*)
+                    )
+                }
+            )*
+        }
+    };
+}
+
+dispatch! {
+    // Adapter
+    fn dyn_adapter_get_info => adapter_get_info(
+        adapter_id: id::AdapterId,
+    ) -> Result<wgt::AdapterInfo, instance::InvalidAdapter>;
+    fn dyn_adapter_get_texture_format_features => adapter_get_texture_format_features(
+        adapter_id: id::AdapterId,
+        format: wgt::TextureFormat,
+    ) -> Result<wgt::TextureFormatFeatures, instance::InvalidAdapter>;
+    fn dyn_adapter_features => adapter_features(
+        adapter_id: id::AdapterId,
+    ) -> Result<wgt::Features, instance::InvalidAdapter>;
+    fn dyn_adapter_limits => adapter_limits(
+        adapter_id: id::AdapterId,
+    ) -> Result<wgt::Limits, instance::InvalidAdapter>;
+    fn dyn_adapter_get_swap_chain_preferred_format => adapter_get_swap_chain_preferred_format(
+        adapter_id: id::AdapterId,
+        surface_id: id::SurfaceId,
+    ) -> Result<wgt::TextureFormat, instance::GetSwapChainPreferredFormatError>;
+    fn dyn_adapter_request_device => adapter_request_device(
+        adapter_id: id::AdapterId,
+        desc: &device::DeviceDescriptor,
+        trace_path: Option<&Path>,
+        id_in: Input<G, id::DeviceId>,
+    ) -> (id::DeviceId, Option<instance::RequestDeviceError>);
+    #[cfg(feature = "trace")]
+    fn dyn_adapter_request_traceable_device => adapter_request_traceable_device(
+        adapter_id: id::AdapterId,
+        desc: &device::DeviceDescriptor,
+        id_in: Input<G, id::DeviceId>,
+    ) -> (id::DeviceId, Option<instance::RequestDeviceError>);
+    fn dyn_adapter_drop => adapter_drop(adapter_id: id::AdapterId);
+
+    // Device
+    fn dyn_device_features => device_features(
+        device_id: id::DeviceId,
+    ) -> Result<wgt::Features, device::InvalidDevice>;
+    fn dyn_device_limits => device_limits(
+        device_id: id::DeviceId,
+    ) -> Result<wgt::Limits, device::InvalidDevice>;
+    fn dyn_device_shader_cache_stats => device_shader_cache_stats(
+        device_id: id::DeviceId,
+    ) -> Result<device::ShaderCacheStats, device::InvalidDevice>;
+    fn dyn_device_memory_report => device_memory_report(
+        device_id: id::DeviceId,
+    ) -> Result<device::alloc::MemoryReport, device::InvalidDevice>;
+    fn dyn_device_create_buffer => device_create_buffer(
+        device_id: id::DeviceId,
+        desc: &resource::BufferDescriptor,
+        id_in: Input<G, id::BufferId>,
+    ) -> (id::BufferId, Option<resource::CreateBufferError>);
+    #[cfg(feature = "replay")]
+    fn dyn_device_wait_for_buffer => device_wait_for_buffer(
+        device_id: id::DeviceId,
+        buffer_id: id::BufferId,
+    ) -> Result<(), device::WaitIdleError>;
+    fn dyn_device_set_buffer_sub_data => device_set_buffer_sub_data(
+        device_id: id::DeviceId,
+        buffer_id: id::BufferId,
+        offset: wgt::BufferAddress,
+        data: &[u8],
+    ) -> Result<(), resource::BufferAccessError>;
+    fn dyn_device_get_buffer_sub_data => device_get_buffer_sub_data(
+        device_id: id::DeviceId,
+        buffer_id: id::BufferId,
+        offset: wgt::BufferAddress,
+        data: &mut [u8],
+    ) -> Result<(), resource::BufferAccessError>;
+    fn dyn_device_create_texture => device_create_texture(
+        device_id: id::DeviceId,
+        desc: &resource::TextureDescriptor,
+        id_in: Input<G, id::TextureId>,
+    ) -> (id::TextureId, Option<resource::CreateTextureError>);
+    fn dyn_device_create_sampler => device_create_sampler(
+        device_id: id::DeviceId,
+        desc: &resource::SamplerDescriptor,
+        id_in: Input<G, id::SamplerId>,
+    ) -> (id::SamplerId, Option<resource::CreateSamplerError>);
+    fn dyn_device_create_bind_group_layout => device_create_bind_group_layout(
+        device_id: id::DeviceId,
+        desc: &binding_model::BindGroupLayoutDescriptor,
+        id_in: Input<G, id::BindGroupLayoutId>,
+    ) -> (
+        id::BindGroupLayoutId,
+        Option<binding_model::CreateBindGroupLayoutError>,
+    );
+    fn dyn_device_create_pipeline_layout => device_create_pipeline_layout(
+        device_id: id::DeviceId,
+        desc: &binding_model::PipelineLayoutDescriptor,
+        id_in: Input<G, id::PipelineLayoutId>,
+    ) -> (
+        id::PipelineLayoutId,
+        Option<binding_model::CreatePipelineLayoutError>,
+    );
+    fn dyn_device_create_bind_group => device_create_bind_group(
+        device_id: id::DeviceId,
+        desc: &binding_model::BindGroupDescriptor,
+        id_in: Input<G, id::BindGroupId>,
+    ) -> (id::BindGroupId, Option<binding_model::CreateBindGroupError>);
+    fn dyn_device_create_shader_module => device_create_shader_module(
+        device_id: id::DeviceId,
+        desc: &pipeline::ShaderModuleDescriptor,
+        source: pipeline::ShaderModuleSource,
+        id_in: Input<G, id::ShaderModuleId>,
+    ) -> (id::ShaderModuleId, Option<pipeline::CreateShaderModuleError>);
+    fn dyn_device_create_command_encoder => device_create_command_encoder(
+        device_id: id::DeviceId,
+        desc: &wgt::CommandEncoderDescriptor<crate::Label>,
+        id_in: Input<G, id::CommandEncoderId>,
+    ) -> (id::CommandEncoderId, Option<command::CommandAllocatorError>);
+    fn dyn_device_create_query_set => device_create_query_set(
+        device_id: id::DeviceId,
+        desc: &wgt::QuerySetDescriptor,
+        id_in: Input<G, id::QuerySetId>,
+    ) -> (id::QuerySetId, Option<resource::CreateQuerySetError>);
+    fn dyn_device_create_render_pipeline => device_create_render_pipeline(
+        device_id: id::DeviceId,
+        desc: &pipeline::RenderPipelineDescriptor,
+        id_in: Input<G, id::RenderPipelineId>,
+        implicit_pipeline_ids: Option<device::ImplicitPipelineIds<G>>,
+    ) -> (
+        id::RenderPipelineId,
+        pipeline::ImplicitBindGroupCount,
+        Option<pipeline::CreateRenderPipelineError>,
+    );
+    fn dyn_device_create_compute_pipeline => device_create_compute_pipeline(
+        device_id: id::DeviceId,
+        desc: &pipeline::ComputePipelineDescriptor,
+        id_in: Input<G, id::ComputePipelineId>,
+        implicit_pipeline_ids: Option<device::ImplicitPipelineIds<G>>,
+    ) -> (
+        id::ComputePipelineId,
+        pipeline::ImplicitBindGroupCount,
+        Option<pipeline::CreateComputePipelineError>,
+    );
+    fn dyn_device_create_swap_chain => device_create_swap_chain(
+        device_id: id::DeviceId,
+        surface_id: id::SurfaceId,
+        desc: &wgt::SwapChainDescriptor,
+    ) -> Result<id::SwapChainId, swap_chain::CreateSwapChainError>;
+    #[cfg(feature = "replay")]
+    fn dyn_device_maintain_ids => device_maintain_ids(
+        device_id: id::DeviceId,
+    ) -> Result<(), device::InvalidDevice>;
+    fn dyn_device_poll => device_poll(
+        device_id: id::DeviceId,
+        force_wait: bool,
+    ) -> Result<(), device::WaitIdleError>;
+    #[cfg(feature = "trace")]
+    fn dyn_device_start_trace => device_start_trace(
+        device_id: id::DeviceId,
+        path: &Path,
+    ) -> Result<(), device::StartTraceError>;
+    #[cfg(feature = "trace")]
+    fn dyn_device_stop_trace => device_stop_trace(
+        device_id: id::DeviceId,
+    ) -> Result<(), device::DeviceError>;
+    fn dyn_device_label => device_label(id: id::DeviceId) -> String;
+    fn dyn_device_destroy => device_destroy(
+        device_id: id::DeviceId,
+    ) -> Result<(), resource::DestroyError>;
+    fn dyn_device_drop => device_drop(device_id: id::DeviceId);
+
+    // Queue
+    fn dyn_queue_write_buffer => queue_write_buffer(
+        queue_id: id::QueueId,
+        buffer_id: id::BufferId,
+        buffer_offset: wgt::BufferAddress,
+        data: &[u8],
+    ) -> Result<(), device::queue::QueueWriteError>;
+    fn dyn_queue_write_buffer_with => queue_write_buffer_with(
+        queue_id: id::QueueId,
+        buffer_id: id::BufferId,
+        buffer_offset: wgt::BufferAddress,
+        size: wgt::BufferSize,
+    ) -> Result<device::queue::QueueWriteBufferView<G>, device::queue::QueueWriteError>;
+    fn dyn_queue_write_texture => queue_write_texture(
+        queue_id: id::QueueId,
+        destination: &command::TextureCopyView,
+        data: &[u8],
+        data_layout: &wgt::TextureDataLayout,
+        size: &wgt::Extent3d,
+    ) -> Result<(), device::queue::QueueWriteError>;
+    fn dyn_queue_submit => queue_submit(
+        queue_id: id::QueueId,
+        command_buffer_ids: &[id::CommandBufferId],
+    ) -> Result<(), device::queue::QueueSubmitError>;
+    fn dyn_queue_get_timestamp_period => queue_get_timestamp_period(
+        queue_id: id::QueueId,
+    ) -> Result<f32, device::queue::InvalidQueue>;
+
+    // Buffer
+    fn dyn_buffer_map_async => buffer_map_async(
+        buffer_id: id::BufferId,
+        range: Range<wgt::BufferAddress>,
+        op: resource::BufferMapOperation,
+    ) -> Result<(), resource::BufferAccessError>;
+    fn dyn_buffer_get_mapped_range => buffer_get_mapped_range(
+        buffer_id: id::BufferId,
+        offset: wgt::BufferAddress,
+        size: Option<wgt::BufferSize>,
+    ) -> Result<*mut u8, resource::BufferAccessError>;
+    fn dyn_buffer_unmap => buffer_unmap(
+        buffer_id: id::BufferId,
+    ) -> Result<(), resource::BufferAccessError>;
+    fn dyn_buffer_label => buffer_label(id: id::BufferId) -> String;
+    fn dyn_buffer_destroy => buffer_destroy(
+        buffer_id: id::BufferId,
+    ) -> Result<(), resource::DestroyError>;
+    fn dyn_buffer_drop => buffer_drop(buffer_id: id::BufferId, wait: bool);
+
+    // Texture
+    fn dyn_texture_create_view => texture_create_view(
+        texture_id: id::TextureId,
+        desc: &resource::TextureViewDescriptor,
+        id_in: Input<G, id::TextureViewId>,
+    ) -> (id::TextureViewId, Option<resource::CreateTextureViewError>);
+    fn dyn_texture_label => texture_label(id: id::TextureId) -> String;
+    fn dyn_texture_destroy => texture_destroy(
+        texture_id: id::TextureId,
+    ) -> Result<(), resource::DestroyError>;
+    fn dyn_texture_drop => texture_drop(texture_id: id::TextureId, wait: bool);
+    fn dyn_texture_view_label => texture_view_label(id: id::TextureViewId) -> String;
+    fn dyn_texture_view_drop => texture_view_drop(
+        texture_view_id: id::TextureViewId,
+        wait: bool,
+    ) -> Result<(), resource::TextureViewDestroyError>;
+
+    // Other resources
+    fn dyn_sampler_label => sampler_label(id: id::SamplerId) -> String;
+    fn dyn_sampler_drop => sampler_drop(sampler_id: id::SamplerId);
+    fn dyn_bind_group_layout_label => bind_group_layout_label(
+        id: id::BindGroupLayoutId,
+    ) -> String;
+    fn dyn_bind_group_layout_drop => bind_group_layout_drop(
+        bind_group_layout_id: id::BindGroupLayoutId,
+    );
+    fn dyn_pipeline_layout_label => pipeline_layout_label(id: id::PipelineLayoutId) -> String;
+    fn dyn_pipeline_layout_drop => pipeline_layout_drop(pipeline_layout_id: id::PipelineLayoutId);
+    fn dyn_bind_group_label => bind_group_label(id: id::BindGroupId) -> String;
+    fn dyn_bind_group_drop => bind_group_drop(bind_group_id: id::BindGroupId);
+    fn dyn_shader_module_label => shader_module_label(id: id::ShaderModuleId) -> String;
+    fn dyn_shader_module_drop => shader_module_drop(shader_module_id: id::ShaderModuleId);
+    fn dyn_query_set_destroy => query_set_destroy(
+        query_set_id: id::QuerySetId,
+    ) -> Result<(), resource::DestroyError>;
+    fn dyn_query_set_drop => query_set_drop(query_set_id: id::QuerySetId);
+
+    // Pipelines
+    fn dyn_render_pipeline_get_bind_group_layout => render_pipeline_get_bind_group_layout(
+        pipeline_id: id::RenderPipelineId,
+        index: u32,
+        id_in: Input<G, id::BindGroupLayoutId>,
+    ) -> (
+        id::BindGroupLayoutId,
+        Option<binding_model::GetBindGroupLayoutError>,
+    );
+    fn dyn_render_pipeline_label => render_pipeline_label(id: id::RenderPipelineId) -> String;
+    fn dyn_render_pipeline_drop => render_pipeline_drop(render_pipeline_id: id::RenderPipelineId);
+    fn dyn_compute_pipeline_get_bind_group_layout => compute_pipeline_get_bind_group_layout(
+        pipeline_id: id::ComputePipelineId,
+        index: u32,
+        id_in: Input<G, id::BindGroupLayoutId>,
+    ) -> (
+        id::BindGroupLayoutId,
+        Option<binding_model::GetBindGroupLayoutError>,
+    );
+    fn dyn_compute_pipeline_label => compute_pipeline_label(id: id::ComputePipelineId) -> String;
+    fn dyn_compute_pipeline_drop => compute_pipeline_drop(
+        compute_pipeline_id: id::ComputePipelineId,
+    );
+
+    // Command encoding
+    fn dyn_command_encoder_finish => command_encoder_finish(
+        encoder_id: id::CommandEncoderId,
+        desc: &wgt::CommandBufferDescriptor<crate::Label>,
+    ) -> (id::CommandBufferId, Option<command::CommandEncoderError>);
+    fn dyn_command_encoder_push_debug_group => command_encoder_push_debug_group(
+        encoder_id: id::CommandEncoderId,
+        label: &str,
+    ) -> Result<(), command::CommandEncoderError>;
+    fn dyn_command_encoder_insert_debug_marker => command_encoder_insert_debug_marker(
+        encoder_id: id::CommandEncoderId,
+        label: &str,
+    ) -> Result<(), command::CommandEncoderError>;
+    fn dyn_command_encoder_pop_debug_group => command_encoder_pop_debug_group(
+        encoder_id: id::CommandEncoderId,
+    ) -> Result<(), command::CommandEncoderError>;
+    fn dyn_command_encoder_write_timestamp => command_encoder_write_timestamp(
+        command_encoder_id: id::CommandEncoderId,
+        query_set_id: id::QuerySetId,
+        query_index: u32,
+    ) -> Result<(), command::QueryError>;
+    fn dyn_command_encoder_resolve_query_set => command_encoder_resolve_query_set(
+        command_encoder_id: id::CommandEncoderId,
+        query_set_id: id::QuerySetId,
+        start_query: u32,
+        query_count: u32,
+        destination: id::BufferId,
+        destination_offset: wgt::BufferAddress,
+    ) -> Result<(), command::QueryError>;
+    fn dyn_command_encoder_copy_buffer_to_buffer => command_encoder_copy_buffer_to_buffer(
+        command_encoder_id: id::CommandEncoderId,
+        source: id::BufferId,
+        source_offset: wgt::BufferAddress,
+        destination: id::BufferId,
+        destination_offset: wgt::BufferAddress,
+        size: wgt::BufferAddress,
+    ) -> Result<(), command::CopyError>;
+    fn dyn_command_encoder_copy_buffer_to_texture => command_encoder_copy_buffer_to_texture(
+        command_encoder_id: id::CommandEncoderId,
+        source: &command::BufferCopyView,
+        destination: &command::TextureCopyView,
+        copy_size: &wgt::Extent3d,
+    ) -> Result<(), command::CopyError>;
+    fn dyn_command_encoder_copy_texture_to_buffer => command_encoder_copy_texture_to_buffer(
+        command_encoder_id: id::CommandEncoderId,
+        source: &command::TextureCopyView,
+        destination: &command::BufferCopyView,
+        copy_size: &wgt::Extent3d,
+    ) -> Result<(), command::CopyError>;
+    fn dyn_command_encoder_copy_texture_to_texture => command_encoder_copy_texture_to_texture(
+        command_encoder_id: id::CommandEncoderId,
+        source: &command::TextureCopyView,
+        destination: &command::TextureCopyView,
+        copy_size: &wgt::Extent3d,
+    ) -> Result<(), command::CopyError>;
+    fn dyn_command_encoder_run_compute_pass => command_encoder_run_compute_pass(
+        encoder_id: id::CommandEncoderId,
+        pass: &command::ComputePass,
+    ) -> Result<(), command::ComputePassError>;
+    fn dyn_command_encoder_run_compute_pass_impl => command_encoder_run_compute_pass_impl(
+        encoder_id: id::CommandEncoderId,
+        base: command::BasePassRef<command::ComputeCommand>,
+    ) -> Result<(), command::ComputePassError>;
+    fn dyn_command_encoder_run_render_pass => command_encoder_run_render_pass(
+        encoder_id: id::CommandEncoderId,
+        pass: &command::RenderPass,
+    ) -> Result<(), command::RenderPassError>;
+    fn dyn_command_encoder_run_render_pass_impl => command_encoder_run_render_pass_impl(
+        encoder_id: id::CommandEncoderId,
+        base: command::BasePassRef<command::RenderCommand>,
+        color_attachments: &[command::ColorAttachmentDescriptor],
+        depth_stencil_attachment: Option<&command::DepthStencilAttachmentDescriptor>,
+    ) -> Result<(), command::RenderPassError>;
+    fn dyn_command_encoder_record_render_pass_fragment => command_encoder_record_render_pass_fragment(
+        encoder_id: id::CommandEncoderId,
+        desc: &command::RenderPassDescriptor,
+        bundle_id: id::RenderBundleId,
+    ) -> Result<(), command::RenderPassError>;
+    fn dyn_command_encoder_run_render_pass_fragments => command_encoder_run_render_pass_fragments(
+        encoder_id: id::CommandEncoderId,
+        desc: &command::RenderPassDescriptor,
+        fragments: &[id::RenderBundleId],
+    ) -> Result<(), command::RenderPassError>;
+    fn dyn_command_encoder_drop => command_encoder_drop(command_encoder_id: id::CommandEncoderId);
+    fn dyn_command_buffer_label => command_buffer_label(id: id::CommandBufferId) -> String;
+    fn dyn_command_buffer_drop => command_buffer_drop(command_buffer_id: id::CommandBufferId);
+
+    // Render bundles
+    fn dyn_render_bundle_encoder_finish => render_bundle_encoder_finish[bundle_encoder.parent()](
+        bundle_encoder: command::RenderBundleEncoder,
+        desc: &command::RenderBundleDescriptor,
+        id_in: Input<G, id::RenderBundleId>,
+    ) -> (id::RenderBundleId, Option<command::RenderBundleError>);
+    fn dyn_render_bundle_label => render_bundle_label(id: id::RenderBundleId) -> String;
+    fn dyn_render_bundle_drop => render_bundle_drop(render_bundle_id: id::RenderBundleId);
+
+    // Swap chain
+    fn dyn_swap_chain_get_current_texture_view => swap_chain_get_current_texture_view(
+        swap_chain_id: id::SwapChainId,
+        view_id_in: Input<G, id::TextureViewId>,
+    ) -> Result<swap_chain::SwapChainOutput, swap_chain::SwapChainError>;
+    fn dyn_swap_chain_present => swap_chain_present(
+        swap_chain_id: id::SwapChainId,
+    ) -> Result<wgt::SwapChainStatus, swap_chain::SwapChainError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        device::{tests::create_device, DeviceDescriptor, HostMap},
+        gfx_select,
+        resource::{BufferDescriptor, BufferMapAsyncStatus, BufferMapOperation},
+    };
+    use std::borrow::Cow;
+
+    unsafe extern "C" fn map_callback(status: BufferMapAsyncStatus, user_data: *mut u8) {
+        *(user_data as *mut bool) = matches!(status, BufferMapAsyncStatus::Success);
+    }
+
+    #[test]
+    fn parity() {
+        let (global, adapter, device) = match create_device(&DeviceDescriptor::default()) {
+            Some(objects) => objects,
+            None => return,
+        };
+        assert_eq!(
+            global.dyn_adapter_get_info(adapter).unwrap(),
+            gfx_select!(adapter => global.adapter_get_info(adapter)).unwrap(),
+        );
+        assert_eq!(
+            global.dyn_device_features(device).unwrap(),
+            gfx_select!(device => global.device_features(device)).unwrap(),
+        );
+        assert_eq!(
+            global.dyn_device_limits(device).unwrap(),
+            gfx_select!(device => global.device_limits(device)).unwrap(),
+        );
+
+        let data = [1u8, 2, 3, 4];
+        let desc = BufferDescriptor {
+            label: Some(Cow::Borrowed("parity")),
+            size: data.len() as wgt::BufferAddress,
+            usage: wgt::BufferUsage::MAP_READ | wgt::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        };
+        let (buffer, error) = global.dyn_device_create_buffer(device, &desc, ());
+        assert!(error.is_none());
+        assert_eq!(
+            global.dyn_buffer_label(buffer),
+            gfx_select!(buffer => global.buffer_label(buffer)),
+        );
+
+        global
+            .dyn_queue_write_buffer(device, buffer, 0, &data)
+            .unwrap();
+        global.dyn_queue_submit(device, &[]).unwrap();
+        let mut mapped = false;
+        let user_data: *mut bool = &mut mapped;
+        global
+            .dyn_buffer_map_async(
+                buffer,
+                0..desc.size,
+                BufferMapOperation {
+                    host: HostMap::Read,
+                    callback: map_callback,
+                    user_data: user_data as *mut u8,
+                },
+            )
+            .unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+        assert!(mapped);
+        let ptr = gfx_select!(buffer => global.buffer_get_mapped_range(buffer, 0, None)).unwrap();
+        assert_eq!(
+            unsafe { std::slice::from_raw_parts(ptr, data.len()) },
+            &data[..]
+        );
+        global.dyn_buffer_unmap(buffer).unwrap();
+
+        let mut view = global
+            .dyn_queue_write_buffer_with(device, buffer, 0, wgt::BufferSize::new(4).unwrap())
+            .unwrap();
+        assert_eq!(&view[..], &[0; 4]);
+        view.copy_from_slice(&[4, 3, 2, 1]);
+        view.finish().unwrap();
+        global.dyn_queue_submit(device, &[]).unwrap();
+        mapped = false;
+        global
+            .dyn_buffer_map_async(
+                buffer,
+                0..desc.size,
+                BufferMapOperation {
+                    host: HostMap::Read,
+                    callback: map_callback,
+                    user_data: user_data as *mut u8,
+                },
+            )
+            .unwrap();
+        global.dyn_device_poll(device, true).unwrap();
+        assert!(mapped);
+        let ptr = global.dyn_buffer_get_mapped_range(buffer, 0, None).unwrap();
+        assert_eq!(
+            unsafe { std::slice::from_raw_parts(ptr, data.len()) },
+            &[4, 3, 2, 1]
+        );
+        global.dyn_buffer_unmap(buffer).unwrap();
+
+        global.dyn_buffer_drop(buffer, true);
+        global.dyn_device_drop(device);
+        global.dyn_adapter_drop(adapter);
+    }
+}
@@ -0,0 +1,613 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::{
+    borrow, clone_arc, conv, make_handle, make_slice, map_image_copy_buffer,
+    map_image_copy_texture, map_label, types::*, WGPUBindGroup, WGPUBuffer, WGPUComputePipeline,
+    WGPUDevice, WGPUDeviceImpl, WGPURenderPipeline,
+};
+use crate::{
+    command::{
+        compute_ffi, render_ffi, ColorAttachmentDescriptor, ComputePass, ComputePassDescriptor,
+        DepthStencilAttachmentDescriptor, PassChannel, RenderPass, RenderPassDescriptor,
+    },
+    gfx_select, id,
+};
+
+use parking_lot::Mutex;
+use std::{
+    borrow::Cow,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+pub struct WGPUCommandEncoderImpl {
+    device: Arc<WGPUDeviceImpl>,
+    id: id::CommandEncoderId,
+    finished: AtomicBool,
+}
+
+pub type WGPUCommandEncoder = *const WGPUCommandEncoderImpl;
+
+impl Drop for WGPUCommandEncoderImpl {
+    fn drop(&mut self) {
+        // A finished encoder lives on as the command buffer.
+        if !*self.finished.get_mut() {
+            let global = &*self.device.context;
+            gfx_select!(self.id => global.command_encoder_drop(self.id));
+        }
+    }
+}
+
+pub struct WGPUCommandBufferImpl {
+    device: Arc<WGPUDeviceImpl>,
+    id: id::CommandBufferId,
+    submitted: AtomicBool,
+}
+
+pub type WGPUCommandBuffer = *const WGPUCommandBufferImpl;
+
+impl WGPUCommandBufferImpl {
+    /// Marks the command buffer as submitted, since the queue takes ownership of it.
+    pub(super) fn consume(&self) -> id::CommandBufferId {
+        self.submitted.store(true, Ordering::Relaxed);
+        self.id
+    }
+}
+
+impl Drop for WGPUCommandBufferImpl {
+    fn drop(&mut self) {
+        if !*self.submitted.get_mut() {
+            let global = &*self.device.context;
+            gfx_select!(self.id => global.command_buffer_drop(self.id));
+        }
+    }
+}
+
+/// The pass is recorded on the CPU side, and run on the encoder when it ends.
+pub struct WGPUComputePassEncoderImpl {
+    encoder: Arc<WGPUCommandEncoderImpl>,
+    pass: Mutex<Option<ComputePass>>,
+}
+
+pub type WGPUComputePassEncoder = *const WGPUComputePassEncoderImpl;
+
+/// The pass is recorded on the CPU side, and run on the encoder when it ends.
+pub struct WGPURenderPassEncoderImpl {
+    encoder: Arc<WGPUCommandEncoderImpl>,
+    pass: Mutex<Option<RenderPass>>,
+}
+
+pub type WGPURenderPassEncoder = *const WGPURenderPassEncoderImpl;
+
+impl WGPUComputePassEncoderImpl {
+    fn record(&self, function: &str, record: impl FnOnce(&mut ComputePass)) {
+        match *self.pass.lock() {
+            Some(ref mut pass) => record(pass),
+            None => report_ended(&self.encoder, function),
+        }
+    }
+}
+
+impl WGPURenderPassEncoderImpl {
+    fn record(&self, function: &str, record: impl FnOnce(&mut RenderPass)) {
+        match *self.pass.lock() {
+            Some(ref mut pass) => record(pass),
+            None => report_ended(&self.encoder, function),
+        }
+    }
+}
+
+fn report_ended(encoder: &WGPUCommandEncoderImpl, function: &str) {
+    encoder.device.report(
+        WGPUErrorType_Validation,
+        format!("{}: the pass has already ended", function),
+    );
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateCommandEncoder(
+    device: WGPUDevice,
+    descriptor: *const WGPUCommandEncoderDescriptor,
+) -> WGPUCommandEncoder {
+    let device = non_null!(clone_arc(device));
+    let desc = wgt::CommandEncoderDescriptor {
+        label: descriptor
+            .as_ref()
+            .and_then(|descriptor| map_label(descriptor.label)),
+    };
+    let global = &*device.context;
+    let (id, error) =
+        gfx_select!(device.id => global.device_create_command_encoder(device.id, &desc, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUCommandEncoderImpl {
+        device,
+        id,
+        finished: AtomicBool::new(false),
+    })
+}
+
+/// The descriptor may be null.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuCommandEncoderFinish(
+    encoder: WGPUCommandEncoder,
+    descriptor: *const WGPUCommandBufferDescriptor,
+) -> WGPUCommandBuffer {
+    let encoder = non_null!(borrow(encoder));
+    let device = Arc::clone(&encoder.device);
+    if encoder.finished.swap(true, Ordering::Relaxed) {
+        device.report(
+            WGPUErrorType_Validation,
+            "wgpuCommandEncoderFinish: the encoder is already finished".to_string(),
+        );
+        return ptr::null();
+    }
+    let desc = wgt::CommandBufferDescriptor {
+        label: descriptor
+            .as_ref()
+            .and_then(|descriptor| map_label(descriptor.label)),
+    };
+    let global = &*device.context;
+    let (id, error) = gfx_select!(encoder.id => global.command_encoder_finish(encoder.id, &desc));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUCommandBufferImpl {
+        device,
+        id,
+        submitted: AtomicBool::new(false),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuCommandEncoderCopyBufferToBuffer(
+    encoder: WGPUCommandEncoder,
+    source: WGPUBuffer,
+    source_offset: u64,
+    destination: WGPUBuffer,
+    destination_offset: u64,
+    size: u64,
+) {
+    let encoder = non_null!(borrow(encoder));
+    let function = "wgpuCommandEncoderCopyBufferToBuffer";
+    let source = non_null!(borrow(source), encoder.device, function);
+    let destination = non_null!(borrow(destination), encoder.device, function);
+    let global = &*encoder.device.context;
+    if let Err(error) = gfx_select!(encoder.id => global.command_encoder_copy_buffer_to_buffer(
+        encoder.id,
+        source.id,
+        source_offset,
+        destination.id,
+        destination_offset,
+        size
+    )) {
+        encoder.device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuCommandEncoderCopyBufferToTexture(
+    encoder: WGPUCommandEncoder,
+    source: &WGPUImageCopyBuffer,
+    destination: &WGPUImageCopyTexture,
+    copy_size: &WGPUExtent3D,
+) {
+    let encoder = non_null!(borrow(encoder));
+    let (source, destination) = match (
+        map_image_copy_buffer(source),
+        map_image_copy_texture(destination),
+    ) {
+        (Some(source), Some(destination)) => (source, destination),
+        _ => {
+            return encoder
+                .device
+                .handle_invalid_descriptor("wgpuCommandEncoderCopyBufferToTexture")
+        }
+    };
+    let global = &*encoder.device.context;
+    if let Err(error) = gfx_select!(encoder.id => global.command_encoder_copy_buffer_to_texture(
+        encoder.id,
+        &source,
+        &destination,
+        &conv::map_extent(copy_size)
+    )) {
+        encoder.device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuCommandEncoderCopyTextureToBuffer(
+    encoder: WGPUCommandEncoder,
+    source: &WGPUImageCopyTexture,
+    destination: &WGPUImageCopyBuffer,
+    copy_size: &WGPUExtent3D,
+) {
+    let encoder = non_null!(borrow(encoder));
+    let (source, destination) = match (
+        map_image_copy_texture(source),
+        map_image_copy_buffer(destination),
+    ) {
+        (Some(source), Some(destination)) => (source, destination),
+        _ => {
+            return encoder
+                .device
+                .handle_invalid_descriptor("wgpuCommandEncoderCopyTextureToBuffer")
+        }
+    };
+    let global = &*encoder.device.context;
+    if let Err(error) = gfx_select!(encoder.id => global.command_encoder_copy_texture_to_buffer(
+        encoder.id,
+        &source,
+        &destination,
+        &conv::map_extent(copy_size)
+    )) {
+        encoder.device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuCommandEncoderCopyTextureToTexture(
+    encoder: WGPUCommandEncoder,
+    source: &WGPUImageCopyTexture,
+    destination: &WGPUImageCopyTexture,
+    copy_size: &WGPUExtent3D,
+) {
+    let encoder = non_null!(borrow(encoder));
+    let (source, destination) = match (
+        map_image_copy_texture(source),
+        map_image_copy_texture(destination),
+    ) {
+        (Some(source), Some(destination)) => (source, destination),
+        _ => {
+            return encoder
+                .device
+                .handle_invalid_descriptor("wgpuCommandEncoderCopyTextureToTexture")
+        }
+    };
+    let global = &*encoder.device.context;
+    if let Err(error) = gfx_select!(encoder.id => global.command_encoder_copy_texture_to_texture(
+        encoder.id,
+        &source,
+        &destination,
+        &conv::map_extent(copy_size)
+    )) {
+        encoder.device.handle_error(error);
+    }
+}
+
+/// The descriptor may be null.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuCommandEncoderBeginComputePass(
+    encoder: WGPUCommandEncoder,
+    descriptor: *const WGPUComputePassDescriptor,
+) -> WGPUComputePassEncoder {
+    let encoder = non_null!(clone_arc(encoder));
+    let desc = ComputePassDescriptor {
+        label: descriptor
+            .as_ref()
+            .and_then(|descriptor| map_label(descriptor.label)),
+    };
+    let pass = ComputePass::new(encoder.id, &desc);
+    make_handle(WGPUComputePassEncoderImpl {
+        encoder,
+        pass: Mutex::new(Some(pass)),
+    })
+}
+
+unsafe fn map_color_attachment(
+    attachment: &WGPURenderPassColorAttachment,
+) -> Option<ColorAttachmentDescriptor> {
+    Some(ColorAttachmentDescriptor {
+        attachment: borrow(attachment.view)?.id,
+        resolve_target: attachment.resolveTarget.as_ref().map(|view| view.id),
+        channel: PassChannel {
+            load_op: conv::map_load_op(attachment.loadOp)?,
+            store_op: conv::map_store_op(attachment.storeOp)?,
+            clear_value: wgt::Color {
+                r: attachment.clearColor.r,
+                g: attachment.clearColor.g,
+                b: attachment.clearColor.b,
+                a: attachment.clearColor.a,
+            },
+            read_only: false,
+        },
+    })
+}
+
+unsafe fn map_depth_stencil_attachment(
+    attachment: &WGPURenderPassDepthStencilAttachment,
+) -> Option<DepthStencilAttachmentDescriptor> {
+    Some(DepthStencilAttachmentDescriptor {
+        attachment: borrow(attachment.view)?.id,
+        depth: PassChannel {
+            load_op: conv::map_load_op(attachment.depthLoadOp)?,
+            store_op: conv::map_store_op(attachment.depthStoreOp)?,
+            clear_value: attachment.clearDepth,
+            read_only: attachment.depthReadOnly,
+        },
+        stencil: PassChannel {
+            load_op: conv::map_load_op(attachment.stencilLoadOp)?,
+            store_op: conv::map_store_op(attachment.stencilStoreOp)?,
+            clear_value: attachment.clearStencil,
+            read_only: attachment.stencilReadOnly,
+        },
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuCommandEncoderBeginRenderPass(
+    encoder: WGPUCommandEncoder,
+    descriptor: &WGPURenderPassDescriptor,
+) -> WGPURenderPassEncoder {
+    let encoder = non_null!(clone_arc(encoder));
+    let color_attachments = make_slice(
+        descriptor.colorAttachments,
+        descriptor.colorAttachmentCount as usize,
+    )
+    .iter()
+    .map(|attachment| map_color_attachment(attachment))
+    .collect::<Option<Vec<_>>>();
+    let depth_stencil_attachment = match descriptor.depthStencilAttachment.as_ref() {
+        Some(attachment) => map_depth_stencil_attachment(attachment).map(Some),
+        None => Some(None),
+    };
+    let (color_attachments, depth_stencil_attachment) =
+        match (color_attachments, depth_stencil_attachment) {
+            (Some(color), Some(depth_stencil)) => (color, depth_stencil),
+            _ => {
+                encoder
+                    .device
+                    .handle_invalid_descriptor("wgpuCommandEncoderBeginRenderPass");
+                return ptr::null();
+            }
+        };
+    let desc = RenderPassDescriptor {
+        label: map_label(descriptor.label),
+        color_attachments: Cow::Owned(color_attachments),
+        depth_stencil_attachment: depth_stencil_attachment.as_ref(),
+    };
+    let pass = RenderPass::new(encoder.id, &desc);
+    make_handle(WGPURenderPassEncoderImpl {
+        encoder,
+        pass: Mutex::new(Some(pass)),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuComputePassEncoderSetPipeline(
+    pass_encoder: WGPUComputePassEncoder,
+    pipeline: WGPUComputePipeline,
+) {
+    let pass_encoder = non_null!(borrow(pass_encoder));
+    let function = "wgpuComputePassEncoderSetPipeline";
+    let pipeline = non_null!(borrow(pipeline), pass_encoder.encoder.device, function);
+    pass_encoder.record(function, |pass| {
+        compute_ffi::wgpu_compute_pass_set_pipeline(pass, pipeline.id)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuComputePassEncoderSetBindGroup(
+    pass_encoder: WGPUComputePassEncoder,
+    group_index: u32,
+    group: WGPUBindGroup,
+    dynamic_offset_count: u32,
+    dynamic_offsets: *const u32,
+) {
+    let pass_encoder = non_null!(borrow(pass_encoder));
+    let function = "wgpuComputePassEncoderSetBindGroup";
+    let group = non_null!(borrow(group), pass_encoder.encoder.device, function);
+    pass_encoder.record(function, |pass| {
+        compute_ffi::wgpu_compute_pass_set_bind_group(
+            pass,
+            group_index,
+            group.id,
+            dynamic_offsets,
+            dynamic_offset_count as usize,
+        )
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuComputePassEncoderDispatch(
+    pass_encoder: WGPUComputePassEncoder,
+    x: u32,
+    y: u32,
+    z: u32,
+) {
+    non_null!(borrow(pass_encoder)).record("wgpuComputePassEncoderDispatch", |pass| {
+        compute_ffi::wgpu_compute_pass_dispatch(pass, x, y, z)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuComputePassEncoderEndPass(pass_encoder: WGPUComputePassEncoder) {
+    let pass_encoder = non_null!(borrow(pass_encoder));
+    let encoder = &*pass_encoder.encoder;
+    let pass = match pass_encoder.pass.lock().take() {
+        Some(pass) => pass,
+        None => return report_ended(encoder, "wgpuComputePassEncoderEndPass"),
+    };
+    let global = &*encoder.device.context;
+    if let Err(error) =
+        gfx_select!(encoder.id => global.command_encoder_run_compute_pass(encoder.id, &pass))
+    {
+        encoder.device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderSetPipeline(
+    pass_encoder: WGPURenderPassEncoder,
+    pipeline: WGPURenderPipeline,
+) {
+    let pass_encoder = non_null!(borrow(pass_encoder));
+    let function = "wgpuRenderPassEncoderSetPipeline";
+    let pipeline = non_null!(borrow(pipeline), pass_encoder.encoder.device, function);
+    pass_encoder.record(function, |pass| {
+        render_ffi::wgpu_render_pass_set_pipeline(pass, pipeline.id)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderSetBindGroup(
+    pass_encoder: WGPURenderPassEncoder,
+    group_index: u32,
+    group: WGPUBindGroup,
+    dynamic_offset_count: u32,
+    dynamic_offsets: *const u32,
+) {
+    let pass_encoder = non_null!(borrow(pass_encoder));
+    let function = "wgpuRenderPassEncoderSetBindGroup";
+    let group = non_null!(borrow(group), pass_encoder.encoder.device, function);
+    pass_encoder.record(function, |pass| {
+        render_ffi::wgpu_render_pass_set_bind_group(
+            pass,
+            group_index,
+            group.id,
+            dynamic_offsets,
+            dynamic_offset_count as usize,
+        )
+    })
+}
+
+/// A `size` of `WGPU_WHOLE_SIZE` binds the rest of the buffer.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderSetVertexBuffer(
+    pass_encoder: WGPURenderPassEncoder,
+    slot: u32,
+    buffer: WGPUBuffer,
+    offset: u64,
+    size: u64,
+) {
+    let pass_encoder = non_null!(borrow(pass_encoder));
+    let function = "wgpuRenderPassEncoderSetVertexBuffer";
+    let size = match size {
+        WGPU_WHOLE_SIZE => None,
+        size => wgt::BufferSize::new(size),
+    };
+    let buffer = non_null!(borrow(buffer), pass_encoder.encoder.device, function);
+    pass_encoder.record(function, |pass| {
+        render_ffi::wgpu_render_pass_set_vertex_buffer(pass, slot, buffer.id, offset, size)
+    })
+}
+
+/// A `size` of `WGPU_WHOLE_SIZE` binds the rest of the buffer.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderSetIndexBuffer(
+    pass_encoder: WGPURenderPassEncoder,
+    buffer: WGPUBuffer,
+    format: WGPUIndexFormat,
+    offset: u64,
+    size: u64,
+) {
+    let pass_encoder = non_null!(borrow(pass_encoder));
+    let function = "wgpuRenderPassEncoderSetIndexBuffer";
+    let format = match conv::map_index_format(format) {
+        Some(format) => format,
+        None => {
+            return pass_encoder
+                .encoder
+                .device
+                .handle_invalid_descriptor(function)
+        }
+    };
+    let size = match size {
+        WGPU_WHOLE_SIZE => None,
+        size => wgt::BufferSize::new(size),
+    };
+    let buffer = non_null!(borrow(buffer), pass_encoder.encoder.device, function);
+    pass_encoder.record(function, |pass| {
+        pass.set_index_buffer(buffer.id, format, offset, size)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderSetViewport(
+    pass_encoder: WGPURenderPassEncoder,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    min_depth: f32,
+    max_depth: f32,
+) {
+    non_null!(borrow(pass_encoder)).record("wgpuRenderPassEncoderSetViewport", |pass| {
+        render_ffi::wgpu_render_pass_set_viewport(pass, x, y, width, height, min_depth, max_depth)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderSetScissorRect(
+    pass_encoder: WGPURenderPassEncoder,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) {
+    non_null!(borrow(pass_encoder)).record("wgpuRenderPassEncoderSetScissorRect", |pass| {
+        render_ffi::wgpu_render_pass_set_scissor_rect(pass, x, y, width, height)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderDraw(
+    pass_encoder: WGPURenderPassEncoder,
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+) {
+    non_null!(borrow(pass_encoder)).record("wgpuRenderPassEncoderDraw", |pass| {
+        render_ffi::wgpu_render_pass_draw(
+            pass,
+            vertex_count,
+            instance_count,
+            first_vertex,
+            first_instance,
+        )
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderDrawIndexed(
+    pass_encoder: WGPURenderPassEncoder,
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+) {
+    non_null!(borrow(pass_encoder)).record("wgpuRenderPassEncoderDrawIndexed", |pass| {
+        render_ffi::wgpu_render_pass_draw_indexed(
+            pass,
+            index_count,
+            instance_count,
+            first_index,
+            base_vertex,
+            first_instance,
+        )
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPassEncoderEndPass(pass_encoder: WGPURenderPassEncoder) {
+    let pass_encoder = non_null!(borrow(pass_encoder));
+    let encoder = &*pass_encoder.encoder;
+    let pass = match pass_encoder.pass.lock().take() {
+        Some(pass) => pass,
+        None => return report_ended(encoder, "wgpuRenderPassEncoderEndPass"),
+    };
+    let global = &*encoder.device.context;
+    if let Err(error) =
+        gfx_select!(encoder.id => global.command_encoder_run_render_pass(encoder.id, &pass))
+    {
+        encoder.device.handle_error(error);
+    }
+}
@@ -0,0 +1,476 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::types::*;
+use crate::command::{LoadOp, StoreOp};
+
+pub fn map_texture_format(value: WGPUTextureFormat) -> Option<wgt::TextureFormat> {
+    use wgt::TextureFormat as Tf;
+    Some(match value {
+        WGPUTextureFormat_R8Unorm => Tf::R8Unorm,
+        WGPUTextureFormat_R8Snorm => Tf::R8Snorm,
+        WGPUTextureFormat_R8Uint => Tf::R8Uint,
+        WGPUTextureFormat_R8Sint => Tf::R8Sint,
+        WGPUTextureFormat_R16Uint => Tf::R16Uint,
+        WGPUTextureFormat_R16Sint => Tf::R16Sint,
+        WGPUTextureFormat_R16Float => Tf::R16Float,
+        WGPUTextureFormat_RG8Unorm => Tf::Rg8Unorm,
+        WGPUTextureFormat_RG8Snorm => Tf::Rg8Snorm,
+        WGPUTextureFormat_RG8Uint => Tf::Rg8Uint,
+        WGPUTextureFormat_RG8Sint => Tf::Rg8Sint,
+        WGPUTextureFormat_R32Float => Tf::R32Float,
+        WGPUTextureFormat_R32Uint => Tf::R32Uint,
+        WGPUTextureFormat_R32Sint => Tf::R32Sint,
+        WGPUTextureFormat_RG16Uint => Tf::Rg16Uint,
+        WGPUTextureFormat_RG16Sint => Tf::Rg16Sint,
+        WGPUTextureFormat_RG16Float => Tf::Rg16Float,
+        WGPUTextureFormat_RGBA8Unorm => Tf::Rgba8Unorm,
+        WGPUTextureFormat_RGBA8UnormSrgb => Tf::Rgba8UnormSrgb,
+        WGPUTextureFormat_RGBA8Snorm => Tf::Rgba8Snorm,
+        WGPUTextureFormat_RGBA8Uint => Tf::Rgba8Uint,
+        WGPUTextureFormat_RGBA8Sint => Tf::Rgba8Sint,
+        WGPUTextureFormat_BGRA8Unorm => Tf::Bgra8Unorm,
+        WGPUTextureFormat_BGRA8UnormSrgb => Tf::Bgra8UnormSrgb,
+        WGPUTextureFormat_RGB10A2Unorm => Tf::Rgb10a2Unorm,
+        WGPUTextureFormat_RG11B10Ufloat => Tf::Rg11b10Float,
+        WGPUTextureFormat_RGB9E5Ufloat => Tf::Rgb9e5Ufloat,
+        WGPUTextureFormat_RG32Float => Tf::Rg32Float,
+        WGPUTextureFormat_RG32Uint => Tf::Rg32Uint,
+        WGPUTextureFormat_RG32Sint => Tf::Rg32Sint,
+        WGPUTextureFormat_RGBA16Uint => Tf::Rgba16Uint,
+        WGPUTextureFormat_RGBA16Sint => Tf::Rgba16Sint,
+        WGPUTextureFormat_RGBA16Float => Tf::Rgba16Float,
+        WGPUTextureFormat_RGBA32Float => Tf::Rgba32Float,
+        WGPUTextureFormat_RGBA32Uint => Tf::Rgba32Uint,
+        WGPUTextureFormat_RGBA32Sint => Tf::Rgba32Sint,
+        WGPUTextureFormat_Stencil8 => Tf::Stencil8,
+        WGPUTextureFormat_Depth16Unorm => Tf::Depth16Unorm,
+        WGPUTextureFormat_Depth24Plus => Tf::Depth24Plus,
+        WGPUTextureFormat_Depth24PlusStencil8 => Tf::Depth24PlusStencil8,
+        WGPUTextureFormat_Depth32Float => Tf::Depth32Float,
+        WGPUTextureFormat_Depth32FloatStencil8 => Tf::Depth32FloatStencil8,
+        WGPUTextureFormat_R16Unorm => Tf::R16Unorm,
+        WGPUTextureFormat_R16Snorm => Tf::R16Snorm,
+        WGPUTextureFormat_RG16Unorm => Tf::Rg16Unorm,
+        WGPUTextureFormat_RG16Snorm => Tf::Rg16Snorm,
+        WGPUTextureFormat_RGBA16Unorm => Tf::Rgba16Unorm,
+        WGPUTextureFormat_RGBA16Snorm => Tf::Rgba16Snorm,
+        WGPUTextureFormat_BC1RGBAUnorm => Tf::Bc1RgbaUnorm,
+        WGPUTextureFormat_BC1RGBAUnormSrgb => Tf::Bc1RgbaUnormSrgb,
+        WGPUTextureFormat_BC2RGBAUnorm => Tf::Bc2RgbaUnorm,
+        WGPUTextureFormat_BC2RGBAUnormSrgb => Tf::Bc2RgbaUnormSrgb,
+        WGPUTextureFormat_BC3RGBAUnorm => Tf::Bc3RgbaUnorm,
+        WGPUTextureFormat_BC3RGBAUnormSrgb => Tf::Bc3RgbaUnormSrgb,
+        WGPUTextureFormat_BC4RUnorm => Tf::Bc4RUnorm,
+        WGPUTextureFormat_BC4RSnorm => Tf::Bc4RSnorm,
+        WGPUTextureFormat_BC5RGUnorm => Tf::Bc5RgUnorm,
+        WGPUTextureFormat_BC5RGSnorm => Tf::Bc5RgSnorm,
+        WGPUTextureFormat_BC6HRGBUfloat => Tf::Bc6hRgbUfloat,
+        WGPUTextureFormat_BC6HRGBFloat => Tf::Bc6hRgbSfloat,
+        WGPUTextureFormat_BC7RGBAUnorm => Tf::Bc7RgbaUnorm,
+        WGPUTextureFormat_BC7RGBAUnormSrgb => Tf::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+pub fn map_texture_dimension(value: WGPUTextureDimension) -> Option<wgt::TextureDimension> {
+    Some(match value {
+        WGPUTextureDimension_1D => wgt::TextureDimension::D1,
+        WGPUTextureDimension_2D => wgt::TextureDimension::D2,
+        WGPUTextureDimension_3D => wgt::TextureDimension::D3,
+        _ => return None,
+    })
+}
+
+pub fn map_texture_view_dimension(
+    value: WGPUTextureViewDimension,
+) -> Option<wgt::TextureViewDimension> {
+    use wgt::TextureViewDimension as Tvd;
+    Some(match value {
+        WGPUTextureViewDimension_1D => Tvd::D1,
+        WGPUTextureViewDimension_2D => Tvd::D2,
+        WGPUTextureViewDimension_2DArray => Tvd::D2Array,
+        WGPUTextureViewDimension_Cube => Tvd::Cube,
+        WGPUTextureViewDimension_CubeArray => Tvd::CubeArray,
+        WGPUTextureViewDimension_3D => Tvd::D3,
+        _ => return None,
+    })
+}
+
+pub fn map_texture_aspect(value: WGPUTextureAspect) -> Option<wgt::TextureAspect> {
+    Some(match value {
+        WGPUTextureAspect_All => wgt::TextureAspect::All,
+        WGPUTextureAspect_StencilOnly => wgt::TextureAspect::StencilOnly,
+        WGPUTextureAspect_DepthOnly => wgt::TextureAspect::DepthOnly,
+        _ => return None,
+    })
+}
+
+pub fn map_address_mode(value: WGPUAddressMode) -> Option<wgt::AddressMode> {
+    Some(match value {
+        WGPUAddressMode_Repeat => wgt::AddressMode::Repeat,
+        WGPUAddressMode_MirrorRepeat => wgt::AddressMode::MirrorRepeat,
+        WGPUAddressMode_ClampToEdge => wgt::AddressMode::ClampToEdge,
+        _ => return None,
+    })
+}
+
+pub fn map_filter_mode(value: WGPUFilterMode) -> Option<wgt::FilterMode> {
+    Some(match value {
+        WGPUFilterMode_Nearest => wgt::FilterMode::Nearest,
+        WGPUFilterMode_Linear => wgt::FilterMode::Linear,
+        _ => return None,
+    })
+}
+
+/// Maps a comparison function, where `Undefined` maps to `Some(None)`.
+pub fn map_compare_function(value: WGPUCompareFunction) -> Option<Option<wgt::CompareFunction>> {
+    use wgt::CompareFunction as Cf;
+    Some(Some(match value {
+        WGPUCompareFunction_Undefined => return Some(None),
+        WGPUCompareFunction_Never => Cf::Never,
+        WGPUCompareFunction_Less => Cf::Less,
+        WGPUCompareFunction_LessEqual => Cf::LessEqual,
+        WGPUCompareFunction_Greater => Cf::Greater,
+        WGPUCompareFunction_GreaterEqual => Cf::GreaterEqual,
+        WGPUCompareFunction_Equal => Cf::Equal,
+        WGPUCompareFunction_NotEqual => Cf::NotEqual,
+        WGPUCompareFunction_Always => Cf::Always,
+        _ => return None,
+    }))
+}
+
+pub fn map_binding_type(entry: &WGPUBindGroupLayoutEntry) -> Option<wgt::BindingType> {
+    let buffer_ty = match entry.buffer.r#type {
+        WGPUBufferBindingType_Undefined => None,
+        WGPUBufferBindingType_Uniform => Some(wgt::BufferBindingType::Uniform),
+        WGPUBufferBindingType_Storage => Some(wgt::BufferBindingType::Storage { read_only: false }),
+        WGPUBufferBindingType_ReadOnlyStorage => {
+            Some(wgt::BufferBindingType::Storage { read_only: true })
+        }
+        _ => return None,
+    };
+    if let Some(ty) = buffer_ty {
+        return Some(wgt::BindingType::Buffer {
+            ty,
+            has_dynamic_offset: entry.buffer.hasDynamicOffset,
+            min_binding_size: wgt::BufferSize::new(entry.buffer.minBindingSize),
+        });
+    }
+
+    let (filtering, comparison) = match entry.sampler.r#type {
+        WGPUSamplerBindingType_Undefined => (None, false),
+        WGPUSamplerBindingType_Filtering => (Some(true), false),
+        WGPUSamplerBindingType_NonFiltering => (Some(false), false),
+        WGPUSamplerBindingType_Comparison => (Some(true), true),
+        _ => return None,
+    };
+    if let Some(filtering) = filtering {
+        return Some(wgt::BindingType::Sampler {
+            filtering,
+            comparison,
+        });
+    }
+
+    let sample_type = match entry.texture.sampleType {
+        WGPUTextureSampleType_Undefined => None,
+        WGPUTextureSampleType_Float => Some(wgt::TextureSampleType::Float { filterable: true }),
+        WGPUTextureSampleType_UnfilterableFloat => {
+            Some(wgt::TextureSampleType::Float { filterable: false })
+        }
+        WGPUTextureSampleType_Depth => Some(wgt::TextureSampleType::Depth),
+        WGPUTextureSampleType_Sint => Some(wgt::TextureSampleType::Sint),
+        WGPUTextureSampleType_Uint => Some(wgt::TextureSampleType::Uint),
+        _ => return None,
+    };
+    if let Some(sample_type) = sample_type {
+        return Some(wgt::BindingType::Texture {
+            sample_type,
+            view_dimension: map_view_dimension_or_2d(entry.texture.viewDimension)?,
+            multisampled: entry.texture.multisampled,
+        });
+    }
+
+    let access = match entry.storageTexture.access {
+        WGPUStorageTextureAccess_ReadOnly => wgt::StorageTextureAccess::ReadOnly,
+        WGPUStorageTextureAccess_WriteOnly => wgt::StorageTextureAccess::WriteOnly,
+        WGPUStorageTextureAccess_ReadWrite => wgt::StorageTextureAccess::ReadWrite,
+        _ => return None,
+    };
+    Some(wgt::BindingType::StorageTexture {
+        access,
+        format: map_texture_format(entry.storageTexture.format)?,
+        view_dimension: map_view_dimension_or_2d(entry.storageTexture.viewDimension)?,
+    })
+}
+
+fn map_view_dimension_or_2d(value: WGPUTextureViewDimension) -> Option<wgt::TextureViewDimension> {
+    match value {
+        WGPUTextureViewDimension_Undefined => Some(wgt::TextureViewDimension::D2),
+        other => map_texture_view_dimension(other),
+    }
+}
+
+pub fn map_vertex_format(value: WGPUVertexFormat) -> Option<wgt::VertexFormat> {
+    use wgt::VertexFormat as Vf;
+    Some(match value {
+        WGPUVertexFormat_Uint8x2 => Vf::Uint8x2,
+        WGPUVertexFormat_Uint8x4 => Vf::Uint8x4,
+        WGPUVertexFormat_Sint8x2 => Vf::Sint8x2,
+        WGPUVertexFormat_Sint8x4 => Vf::Sint8x4,
+        WGPUVertexFormat_Unorm8x2 => Vf::Unorm8x2,
+        WGPUVertexFormat_Unorm8x4 => Vf::Unorm8x4,
+        WGPUVertexFormat_Snorm8x2 => Vf::Snorm8x2,
+        WGPUVertexFormat_Snorm8x4 => Vf::Snorm8x4,
+        WGPUVertexFormat_Uint16x2 => Vf::Uint16x2,
+        WGPUVertexFormat_Uint16x4 => Vf::Uint16x4,
+        WGPUVertexFormat_Sint16x2 => Vf::Sint16x2,
+        WGPUVertexFormat_Sint16x4 => Vf::Sint16x4,
+        WGPUVertexFormat_Unorm16x2 => Vf::Unorm16x2,
+        WGPUVertexFormat_Unorm16x4 => Vf::Unorm16x4,
+        WGPUVertexFormat_Snorm16x2 => Vf::Snorm16x2,
+        WGPUVertexFormat_Snorm16x4 => Vf::Snorm16x4,
+        WGPUVertexFormat_Float16x2 => Vf::Float16x2,
+        WGPUVertexFormat_Float16x4 => Vf::Float16x4,
+        WGPUVertexFormat_Float32 => Vf::Float32,
+        WGPUVertexFormat_Float32x2 => Vf::Float32x2,
+        WGPUVertexFormat_Float32x3 => Vf::Float32x3,
+        WGPUVertexFormat_Float32x4 => Vf::Float32x4,
+        WGPUVertexFormat_Uint32 => Vf::Uint32,
+        WGPUVertexFormat_Uint32x2 => Vf::Uint32x2,
+        WGPUVertexFormat_Uint32x3 => Vf::Uint32x3,
+        WGPUVertexFormat_Uint32x4 => Vf::Uint32x4,
+        WGPUVertexFormat_Sint32 => Vf::Sint32,
+        WGPUVertexFormat_Sint32x2 => Vf::Sint32x2,
+        WGPUVertexFormat_Sint32x3 => Vf::Sint32x3,
+        WGPUVertexFormat_Sint32x4 => Vf::Sint32x4,
+        _ => return None,
+    })
+}
+
+pub fn map_input_step_mode(value: WGPUInputStepMode) -> Option<wgt::InputStepMode> {
+    Some(match value {
+        WGPUInputStepMode_Vertex => wgt::InputStepMode::Vertex,
+        WGPUInputStepMode_Instance => wgt::InputStepMode::Instance,
+        _ => return None,
+    })
+}
+
+pub fn map_primitive_state(state: &WGPUPrimitiveState) -> Option<wgt::PrimitiveState> {
+    use wgt::PrimitiveTopology as Pt;
+    Some(wgt::PrimitiveState {
+        topology: match state.topology {
+            WGPUPrimitiveTopology_PointList => Pt::PointList,
+            WGPUPrimitiveTopology_LineList => Pt::LineList,
+            WGPUPrimitiveTopology_LineStrip => Pt::LineStrip,
+            WGPUPrimitiveTopology_TriangleList => Pt::TriangleList,
+            WGPUPrimitiveTopology_TriangleStrip => Pt::TriangleStrip,
+            _ => return None,
+        },
+        strip_index_format: match state.stripIndexFormat {
+            WGPUIndexFormat_Undefined => None,
+            other => Some(map_index_format(other)?),
+        },
+        front_face: match state.frontFace {
+            WGPUFrontFace_CCW => wgt::FrontFace::Ccw,
+            WGPUFrontFace_CW => wgt::FrontFace::Cw,
+            _ => return None,
+        },
+        cull_mode: match state.cullMode {
+            WGPUCullMode_None => None,
+            WGPUCullMode_Front => Some(wgt::Face::Front),
+            WGPUCullMode_Back => Some(wgt::Face::Back),
+            _ => return None,
+        },
+        ..Default::default()
+    })
+}
+
+pub fn map_index_format(value: WGPUIndexFormat) -> Option<wgt::IndexFormat> {
+    Some(match value {
+        WGPUIndexFormat_Uint16 => wgt::IndexFormat::Uint16,
+        WGPUIndexFormat_Uint32 => wgt::IndexFormat::Uint32,
+        _ => return None,
+    })
+}
+
+fn map_stencil_operation(value: WGPUStencilOperation) -> Option<wgt::StencilOperation> {
+    use wgt::StencilOperation as So;
+    Some(match value {
+        WGPUStencilOperation_Keep => So::Keep,
+        WGPUStencilOperation_Zero => So::Zero,
+        WGPUStencilOperation_Replace => So::Replace,
+        WGPUStencilOperation_Invert => So::Invert,
+        WGPUStencilOperation_IncrementClamp => So::IncrementClamp,
+        WGPUStencilOperation_DecrementClamp => So::DecrementClamp,
+        WGPUStencilOperation_IncrementWrap => So::IncrementWrap,
+        WGPUStencilOperation_DecrementWrap => So::DecrementWrap,
+        _ => return None,
+    })
+}
+
+fn map_stencil_face(state: &WGPUStencilFaceState) -> Option<wgt::StencilFaceState> {
+    Some(wgt::StencilFaceState {
+        compare: map_compare_function(state.compare)?.unwrap_or(wgt::CompareFunction::Always),
+        fail_op: map_stencil_operation(state.failOp)?,
+        depth_fail_op: map_stencil_operation(state.depthFailOp)?,
+        pass_op: map_stencil_operation(state.passOp)?,
+    })
+}
+
+pub fn map_depth_stencil_state(state: &WGPUDepthStencilState) -> Option<wgt::DepthStencilState> {
+    Some(wgt::DepthStencilState {
+        format: map_texture_format(state.format)?,
+        depth_write_enabled: state.depthWriteEnabled,
+        depth_compare: map_compare_function(state.depthCompare)?
+            .unwrap_or(wgt::CompareFunction::Always),
+        stencil: wgt::StencilState {
+            front: map_stencil_face(&state.stencilFront)?,
+            back: map_stencil_face(&state.stencilBack)?,
+            read_mask: state.stencilReadMask,
+            write_mask: state.stencilWriteMask,
+        },
+        bias: wgt::DepthBiasState {
+            constant: state.depthBias,
+            slope_scale: state.depthBiasSlopeScale,
+            clamp: state.depthBiasClamp,
+        },
+        clamp_depth: false,
+    })
+}
+
+fn map_blend_component(component: &WGPUBlendComponent) -> Option<wgt::BlendComponent> {
+    use wgt::{BlendFactor as Bf, BlendOperation as Bo};
+    let map_factor = |value| {
+        Some(match value {
+            WGPUBlendFactor_Zero => Bf::Zero,
+            WGPUBlendFactor_One => Bf::One,
+            WGPUBlendFactor_Src => Bf::SrcColor,
+            WGPUBlendFactor_OneMinusSrc => Bf::OneMinusSrcColor,
+            WGPUBlendFactor_SrcAlpha => Bf::SrcAlpha,
+            WGPUBlendFactor_OneMinusSrcAlpha => Bf::OneMinusSrcAlpha,
+            WGPUBlendFactor_Dst => Bf::DstColor,
+            WGPUBlendFactor_OneMinusDst => Bf::OneMinusDstColor,
+            WGPUBlendFactor_DstAlpha => Bf::DstAlpha,
+            WGPUBlendFactor_OneMinusDstAlpha => Bf::OneMinusDstAlpha,
+            WGPUBlendFactor_SrcAlphaSaturated => Bf::SrcAlphaSaturated,
+            WGPUBlendFactor_Constant => Bf::BlendColor,
+            WGPUBlendFactor_OneMinusConstant => Bf::OneMinusBlendColor,
+            _ => return None,
+        })
+    };
+    Some(wgt::BlendComponent {
+        src_factor: map_factor(component.srcFactor)?,
+        dst_factor: map_factor(component.dstFactor)?,
+        operation: match component.operation {
+            WGPUBlendOperation_Add => Bo::Add,
+            WGPUBlendOperation_Subtract => Bo::Subtract,
+            WGPUBlendOperation_ReverseSubtract => Bo::ReverseSubtract,
+            WGPUBlendOperation_Min => Bo::Min,
+            WGPUBlendOperation_Max => Bo::Max,
+            _ => return None,
+        },
+    })
+}
+
+/// # Safety
+///
+/// The `blend` pointer has to be either null or valid.
+pub unsafe fn map_color_target_state(
+    state: &WGPUColorTargetState,
+) -> Option<wgt::ColorTargetState> {
+    Some(wgt::ColorTargetState {
+        format: map_texture_format(state.format)?,
+        blend: match state.blend.as_ref() {
+            Some(blend) => Some(wgt::BlendState {
+                color: map_blend_component(&blend.color)?,
+                alpha: map_blend_component(&blend.alpha)?,
+            }),
+            None => None,
+        },
+        write_mask: wgt::ColorWrite::from_bits(state.writeMask)?,
+    })
+}
+
+pub fn map_multisample_state(state: &WGPUMultisampleState) -> wgt::MultisampleState {
+    wgt::MultisampleState {
+        count: state.count,
+        mask: state.mask as u64,
+        alpha_to_coverage_enabled: state.alphaToCoverageEnabled,
+    }
+}
+
+pub fn map_load_op(value: WGPULoadOp) -> Option<LoadOp> {
+    Some(match value {
+        WGPULoadOp_Clear => LoadOp::Clear,
+        WGPULoadOp_Load => LoadOp::Load,
+        _ => return None,
+    })
+}
+
+pub fn map_store_op(value: WGPUStoreOp) -> Option<StoreOp> {
+    Some(match value {
+        WGPUStoreOp_Store => StoreOp::Store,
+        WGPUStoreOp_Clear => StoreOp::Clear,
+        _ => return None,
+    })
+}
+
+pub fn map_extent(extent: &WGPUExtent3D) -> wgt::Extent3d {
+    wgt::Extent3d {
+        width: extent.width,
+        height: extent.height,
+        depth_or_array_layers: extent.depthOrArrayLayers,
+    }
+}
+
+pub fn map_origin(origin: &WGPUOrigin3D) -> wgt::Origin3d {
+    wgt::Origin3d {
+        x: origin.x,
+        y: origin.y,
+        z: origin.z,
+    }
+}
+
+pub fn map_texture_data_layout(layout: &WGPUTextureDataLayout) -> wgt::TextureDataLayout {
+    wgt::TextureDataLayout {
+        offset: layout.offset,
+        bytes_per_row: layout.bytesPerRow,
+        rows_per_image: layout.rowsPerImage,
+    }
+}
+
+pub fn map_adapter_type(value: &wgt::DeviceType) -> WGPUAdapterType {
+    match *value {
+        wgt::DeviceType::DiscreteGpu => WGPUAdapterType_DiscreteGPU,
+        wgt::DeviceType::IntegratedGpu => WGPUAdapterType_IntegratedGPU,
+        wgt::DeviceType::Cpu => WGPUAdapterType_CPU,
+        wgt::DeviceType::VirtualGpu | wgt::DeviceType::Other => WGPUAdapterType_Unknown,
+    }
+}
+
+pub fn map_backend_type(value: wgt::Backend) -> WGPUBackendType {
+    match value {
+        wgt::Backend::Empty => WGPUBackendType_Null,
+        wgt::Backend::Vulkan => WGPUBackendType_Vulkan,
+        wgt::Backend::Metal => WGPUBackendType_Metal,
+        wgt::Backend::Dx12 => WGPUBackendType_D3D12,
+        wgt::Backend::Dx11 => WGPUBackendType_D3D11,
+        wgt::Backend::Gl => WGPUBackendType_OpenGL,
+        wgt::Backend::BrowserWebGpu => WGPUBackendType_WebGPU,
+    }
+}
+
+pub fn map_buffer_map_status(
+    value: crate::resource::BufferMapAsyncStatus,
+) -> WGPUBufferMapAsyncStatus {
+    use crate::resource::BufferMapAsyncStatus as Bmas;
+    match value {
+        Bmas::Success => WGPUBufferMapAsyncStatus_Success,
+        Bmas::Error => WGPUBufferMapAsyncStatus_Error,
+        Bmas::Aborted => WGPUBufferMapAsyncStatus_UnmappedBeforeCallback,
+        Bmas::Unknown => WGPUBufferMapAsyncStatus_Unknown,
+        Bmas::ContextLost => WGPUBufferMapAsyncStatus_DeviceLost,
+    }
+}
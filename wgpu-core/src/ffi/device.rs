@@ -0,0 +1,689 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::{
+    borrow, clone_arc, conv, make_handle, make_slice, map_label, map_string, types::*, WGPUDevice,
+    WGPUDeviceImpl,
+};
+use crate::{binding_model, device::HostMap, gfx_select, id, pipeline, resource, MAX_BIND_GROUPS};
+
+use std::{
+    borrow::Cow,
+    num::{NonZeroU32, NonZeroU8},
+    os::raw::c_void,
+    ptr,
+    sync::Arc,
+};
+
+macro_rules! device_child {
+    ($($impl:ident: $handle:ident($id:ty) => $drop:ident($($arg:expr),*);)*) => {$(
+        pub struct $impl {
+            pub(super) device: Arc<WGPUDeviceImpl>,
+            pub(super) id: $id,
+        }
+
+        pub type $handle = *const $impl;
+
+        impl Drop for $impl {
+            fn drop(&mut self) {
+                let global = &*self.device.context;
+                gfx_select!(self.id => global.$drop(self.id $(, $arg)*));
+            }
+        }
+    )*};
+}
+
+device_child! {
+    WGPUTextureImpl: WGPUTexture(id::TextureId) => texture_drop(false);
+    WGPUSamplerImpl: WGPUSampler(id::SamplerId) => sampler_drop();
+    WGPUShaderModuleImpl: WGPUShaderModule(id::ShaderModuleId) => shader_module_drop();
+    WGPUBindGroupLayoutImpl: WGPUBindGroupLayout(id::BindGroupLayoutId) => bind_group_layout_drop();
+    WGPUPipelineLayoutImpl: WGPUPipelineLayout(id::PipelineLayoutId) => pipeline_layout_drop();
+    WGPUBindGroupImpl: WGPUBindGroup(id::BindGroupId) => bind_group_drop();
+    WGPUComputePipelineImpl: WGPUComputePipeline(id::ComputePipelineId) => compute_pipeline_drop();
+    WGPURenderPipelineImpl: WGPURenderPipeline(id::RenderPipelineId) => render_pipeline_drop();
+}
+
+pub struct WGPUBufferImpl {
+    pub(super) device: Arc<WGPUDeviceImpl>,
+    pub(super) id: id::BufferId,
+    size: u64,
+}
+
+pub type WGPUBuffer = *const WGPUBufferImpl;
+
+impl Drop for WGPUBufferImpl {
+    fn drop(&mut self) {
+        let global = &*self.device.context;
+        gfx_select!(self.id => global.buffer_drop(self.id, false));
+    }
+}
+
+pub struct WGPUTextureViewImpl {
+    pub(super) device: Arc<WGPUDeviceImpl>,
+    pub(super) id: id::TextureViewId,
+}
+
+pub type WGPUTextureView = *const WGPUTextureViewImpl;
+
+impl Drop for WGPUTextureViewImpl {
+    fn drop(&mut self) {
+        let global = &*self.device.context;
+        if let Err(error) = gfx_select!(self.id => global.texture_view_drop(self.id, false)) {
+            self.device.handle_error(error);
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateBuffer(
+    device: WGPUDevice,
+    descriptor: &WGPUBufferDescriptor,
+) -> WGPUBuffer {
+    let device = non_null!(clone_arc(device));
+    let usage = match wgt::BufferUsage::from_bits(descriptor.usage) {
+        Some(usage) => usage,
+        None => {
+            device.handle_invalid_descriptor("wgpuDeviceCreateBuffer");
+            return ptr::null();
+        }
+    };
+    let desc = wgt::BufferDescriptor {
+        label: map_label(descriptor.label),
+        size: descriptor.size,
+        usage,
+        mapped_at_creation: descriptor.mappedAtCreation,
+    };
+    let global = &*device.context;
+    let (id, error) = gfx_select!(device.id => global.device_create_buffer(device.id, &desc, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUBufferImpl {
+        device,
+        id,
+        size: descriptor.size,
+    })
+}
+
+struct MapCallback {
+    callback: WGPUBufferMapCallback,
+    userdata: *mut c_void,
+}
+
+unsafe extern "C" fn map_callback(status: resource::BufferMapAsyncStatus, userdata: *mut u8) {
+    let closure = Box::from_raw(userdata as *mut MapCallback);
+    if let Some(callback) = closure.callback {
+        callback(conv::map_buffer_map_status(status), closure.userdata);
+    }
+}
+
+/// The callback is called by `wgpuDevicePoll` or `wgpuQueueSubmit` once the buffer is mapped.
+/// A `size` of `SIZE_MAX` maps the rest of the buffer.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuBufferMapAsync(
+    buffer: WGPUBuffer,
+    mode: WGPUMapModeFlags,
+    offset: usize,
+    size: usize,
+    callback: WGPUBufferMapCallback,
+    userdata: *mut c_void,
+) {
+    let buffer = non_null!(borrow(buffer));
+    let offset = offset as wgt::BufferAddress;
+    let end = if size == usize::MAX {
+        buffer.size
+    } else {
+        offset + size as wgt::BufferAddress
+    };
+    let host = match mode {
+        WGPUMapMode_Read => HostMap::Read,
+        WGPUMapMode_Write => HostMap::Write,
+        _ => {
+            buffer
+                .device
+                .handle_invalid_descriptor("wgpuBufferMapAsync");
+            if let Some(callback) = callback {
+                callback(WGPUBufferMapAsyncStatus_Error, userdata);
+            }
+            return;
+        }
+    };
+    let user_data = Box::into_raw(Box::new(MapCallback { callback, userdata })) as *mut u8;
+    let op = resource::BufferMapOperation {
+        host,
+        callback: map_callback,
+        user_data,
+    };
+    let global = &*buffer.device.context;
+    if let Err(error) =
+        gfx_select!(buffer.id => global.buffer_map_async(buffer.id, offset..end, op))
+    {
+        buffer.device.handle_error(error);
+        map_callback(resource::BufferMapAsyncStatus::Error, user_data);
+    }
+}
+
+/// Returns null if the range is not mapped.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuBufferGetMappedRange(
+    buffer: WGPUBuffer,
+    offset: usize,
+    size: usize,
+) -> *mut c_void {
+    let buffer = non_null!(borrow(buffer));
+    let size = wgt::BufferSize::new(size as wgt::BufferAddress);
+    let global = &*buffer.device.context;
+    match gfx_select!(buffer.id => global.buffer_get_mapped_range(buffer.id, offset as wgt::BufferAddress, size))
+    {
+        Ok(pointer) => pointer as *mut c_void,
+        Err(error) => {
+            buffer.device.handle_error(error);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns null if the range is not mapped.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuBufferGetConstMappedRange(
+    buffer: WGPUBuffer,
+    offset: usize,
+    size: usize,
+) -> *const c_void {
+    wgpuBufferGetMappedRange(buffer, offset, size)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuBufferUnmap(buffer: WGPUBuffer) {
+    let buffer = non_null!(borrow(buffer));
+    let global = &*buffer.device.context;
+    if let Err(error) = gfx_select!(buffer.id => global.buffer_unmap(buffer.id)) {
+        buffer.device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuBufferDestroy(buffer: WGPUBuffer) {
+    let buffer = non_null!(borrow(buffer));
+    let global = &*buffer.device.context;
+    if let Err(error) = gfx_select!(buffer.id => global.buffer_destroy(buffer.id)) {
+        buffer.device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateTexture(
+    device: WGPUDevice,
+    descriptor: &WGPUTextureDescriptor,
+) -> WGPUTexture {
+    let device = non_null!(clone_arc(device));
+    let desc = match (
+        wgt::TextureUsage::from_bits(descriptor.usage),
+        conv::map_texture_dimension(descriptor.dimension),
+        conv::map_texture_format(descriptor.format),
+    ) {
+        (Some(usage), Some(dimension), Some(format)) => wgt::TextureDescriptor {
+            label: map_label(descriptor.label),
+            size: conv::map_extent(&descriptor.size),
+            mip_level_count: descriptor.mipLevelCount,
+            sample_count: descriptor.sampleCount,
+            dimension,
+            format,
+            usage,
+        },
+        _ => {
+            device.handle_invalid_descriptor("wgpuDeviceCreateTexture");
+            return ptr::null();
+        }
+    };
+    let global = &*device.context;
+    let (id, error) = gfx_select!(device.id => global.device_create_texture(device.id, &desc, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUTextureImpl { device, id })
+}
+
+/// The descriptor may be null, to create a view of the whole texture.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuTextureCreateView(
+    texture: WGPUTexture,
+    descriptor: *const WGPUTextureViewDescriptor,
+) -> WGPUTextureView {
+    let texture = non_null!(borrow(texture));
+    let device = Arc::clone(&texture.device);
+    let desc = match descriptor.as_ref() {
+        None => resource::TextureViewDescriptor::default(),
+        Some(descriptor) => {
+            let format = match descriptor.format {
+                WGPUTextureFormat_Undefined => Some(None),
+                other => conv::map_texture_format(other).map(Some),
+            };
+            let dimension = match descriptor.dimension {
+                WGPUTextureViewDimension_Undefined => Some(None),
+                other => conv::map_texture_view_dimension(other).map(Some),
+            };
+            match (
+                format,
+                dimension,
+                conv::map_texture_aspect(descriptor.aspect),
+            ) {
+                (Some(format), Some(dimension), Some(aspect)) => resource::TextureViewDescriptor {
+                    label: map_label(descriptor.label),
+                    format,
+                    dimension,
+                    aspect,
+                    base_mip_level: descriptor.baseMipLevel,
+                    level_count: NonZeroU32::new(descriptor.mipLevelCount),
+                    base_array_layer: descriptor.baseArrayLayer,
+                    array_layer_count: NonZeroU32::new(descriptor.arrayLayerCount),
+                },
+                _ => {
+                    device.handle_invalid_descriptor("wgpuTextureCreateView");
+                    return ptr::null();
+                }
+            }
+        }
+    };
+    let global = &*device.context;
+    let (id, error) = gfx_select!(texture.id => global.texture_create_view(texture.id, &desc, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUTextureViewImpl { device, id })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuTextureDestroy(texture: WGPUTexture) {
+    let texture = non_null!(borrow(texture));
+    let global = &*texture.device.context;
+    if let Err(error) = gfx_select!(texture.id => global.texture_destroy(texture.id)) {
+        texture.device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateSampler(
+    device: WGPUDevice,
+    descriptor: &WGPUSamplerDescriptor,
+) -> WGPUSampler {
+    let device = non_null!(clone_arc(device));
+    let desc = match (
+        conv::map_address_mode(descriptor.addressModeU),
+        conv::map_address_mode(descriptor.addressModeV),
+        conv::map_address_mode(descriptor.addressModeW),
+        conv::map_filter_mode(descriptor.magFilter),
+        conv::map_filter_mode(descriptor.minFilter),
+        conv::map_filter_mode(descriptor.mipmapFilter),
+        conv::map_compare_function(descriptor.compare),
+    ) {
+        (
+            Some(u),
+            Some(v),
+            Some(w),
+            Some(mag_filter),
+            Some(min_filter),
+            Some(mipmap_filter),
+            Some(compare),
+        ) => resource::SamplerDescriptor {
+            label: map_label(descriptor.label),
+            address_modes: [u, v, w],
+            mag_filter,
+            min_filter,
+            mipmap_filter,
+            lod_min_clamp: descriptor.lodMinClamp,
+            lod_max_clamp: descriptor.lodMaxClamp,
+            compare,
+            anisotropy_clamp: match descriptor.maxAnisotropy {
+                0 | 1 => None,
+                other => NonZeroU8::new(other.min(u8::MAX as u16) as u8),
+            },
+            border_color: None,
+        },
+        _ => {
+            device.handle_invalid_descriptor("wgpuDeviceCreateSampler");
+            return ptr::null();
+        }
+    };
+    let global = &*device.context;
+    let (id, error) = gfx_select!(device.id => global.device_create_sampler(device.id, &desc, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUSamplerImpl { device, id })
+}
+
+/// The source is given by a `WGPUShaderModuleSPIRVDescriptor`
+/// or a `WGPUShaderModuleWGSLDescriptor` chained to the descriptor.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateShaderModule(
+    device: WGPUDevice,
+    descriptor: &WGPUShaderModuleDescriptor,
+) -> WGPUShaderModule {
+    let device = non_null!(clone_arc(device));
+    let source = match descriptor.nextInChain.as_ref().map(|chain| chain.sType) {
+        Some(WGPUSType_ShaderModuleSPIRVDescriptor) => {
+            let spirv = &*(descriptor.nextInChain as *const WGPUShaderModuleSPIRVDescriptor);
+            pipeline::ShaderModuleSource::SpirV(Cow::Borrowed(make_slice(
+                spirv.code,
+                spirv.codeSize as usize,
+            )))
+        }
+        Some(WGPUSType_ShaderModuleWGSLDescriptor) => {
+            let wgsl = &*(descriptor.nextInChain as *const WGPUShaderModuleWGSLDescriptor);
+            pipeline::ShaderModuleSource::Wgsl(map_string(wgsl.source))
+        }
+        _ => {
+            device.handle_invalid_descriptor("wgpuDeviceCreateShaderModule");
+            return ptr::null();
+        }
+    };
+    let desc = pipeline::ShaderModuleDescriptor {
+        label: map_label(descriptor.label),
+        flags: wgt::ShaderFlags::VALIDATION,
+        interface: None,
+    };
+    let global = &*device.context;
+    let (id, error) =
+        gfx_select!(device.id => global.device_create_shader_module(device.id, &desc, source, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUShaderModuleImpl { device, id })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateBindGroupLayout(
+    device: WGPUDevice,
+    descriptor: &WGPUBindGroupLayoutDescriptor,
+) -> WGPUBindGroupLayout {
+    let device = non_null!(clone_arc(device));
+    let entries = make_slice(descriptor.entries, descriptor.entryCount as usize)
+        .iter()
+        .map(|entry| {
+            Some(wgt::BindGroupLayoutEntry {
+                binding: entry.binding,
+                visibility: wgt::ShaderStage::from_bits(entry.visibility)?,
+                ty: conv::map_binding_type(entry)?,
+                count: None,
+            })
+        })
+        .collect::<Option<Vec<_>>>();
+    let entries = match entries {
+        Some(entries) => entries,
+        None => {
+            device.handle_invalid_descriptor("wgpuDeviceCreateBindGroupLayout");
+            return ptr::null();
+        }
+    };
+    let desc = binding_model::BindGroupLayoutDescriptor {
+        label: map_label(descriptor.label),
+        entries: Cow::Owned(entries),
+    };
+    let global = &*device.context;
+    let (id, error) =
+        gfx_select!(device.id => global.device_create_bind_group_layout(device.id, &desc, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUBindGroupLayoutImpl { device, id })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreatePipelineLayout(
+    device: WGPUDevice,
+    descriptor: &WGPUPipelineLayoutDescriptor,
+) -> WGPUPipelineLayout {
+    let device = non_null!(clone_arc(device));
+    let bind_group_layouts = make_slice(
+        descriptor.bindGroupLayouts,
+        descriptor.bindGroupLayoutCount as usize,
+    )
+    .iter()
+    .map(|&layout| borrow(layout).map(|layout| layout.id))
+    .collect::<Option<Vec<_>>>();
+    let bind_group_layouts = match bind_group_layouts {
+        Some(bind_group_layouts) => bind_group_layouts,
+        None => {
+            device.handle_invalid_descriptor("wgpuDeviceCreatePipelineLayout");
+            return ptr::null();
+        }
+    };
+    let desc = binding_model::PipelineLayoutDescriptor {
+        label: map_label(descriptor.label),
+        bind_group_layouts: Cow::Owned(bind_group_layouts),
+        push_constant_ranges: Cow::Borrowed(&[]),
+    };
+    let global = &*device.context;
+    let (id, error) =
+        gfx_select!(device.id => global.device_create_pipeline_layout(device.id, &desc, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUPipelineLayoutImpl { device, id })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateBindGroup(
+    device: WGPUDevice,
+    descriptor: &WGPUBindGroupDescriptor,
+) -> WGPUBindGroup {
+    let device = non_null!(clone_arc(device));
+    let entries = make_slice(descriptor.entries, descriptor.entryCount as usize)
+        .iter()
+        .map(|entry| {
+            let resource = if let Some(buffer) = entry.buffer.as_ref() {
+                binding_model::BindingResource::Buffer(binding_model::BufferBinding {
+                    buffer_id: buffer.id,
+                    offset: entry.offset,
+                    size: match entry.size {
+                        WGPU_WHOLE_SIZE => None,
+                        size => Some(wgt::BufferSize::new(size)?),
+                    },
+                })
+            } else if let Some(sampler) = entry.sampler.as_ref() {
+                binding_model::BindingResource::Sampler(sampler.id)
+            } else {
+                binding_model::BindingResource::TextureView(entry.textureView.as_ref()?.id)
+            };
+            Some(binding_model::BindGroupEntry {
+                binding: entry.binding,
+                resource,
+            })
+        })
+        .collect::<Option<Vec<_>>>();
+    let (entries, layout) = match (entries, borrow(descriptor.layout)) {
+        (Some(entries), Some(layout)) => (entries, layout),
+        _ => {
+            device.handle_invalid_descriptor("wgpuDeviceCreateBindGroup");
+            return ptr::null();
+        }
+    };
+    let desc = binding_model::BindGroupDescriptor {
+        label: map_label(descriptor.label),
+        layout: layout.id,
+        entries: Cow::Owned(entries),
+    };
+    let global = &*device.context;
+    let (id, error) =
+        gfx_select!(device.id => global.device_create_bind_group(device.id, &desc, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUBindGroupImpl { device, id })
+}
+
+unsafe fn map_stage<'a>(
+    module: WGPUShaderModule,
+    entry_point: *const std::os::raw::c_char,
+) -> Option<pipeline::ProgrammableStageDescriptor<'a>> {
+    Some(pipeline::ProgrammableStageDescriptor {
+        module: borrow(module)?.id,
+        entry_point: map_string(entry_point),
+    })
+}
+
+const IMPLICIT_GROUP_IDS: [(); MAX_BIND_GROUPS] = [(); MAX_BIND_GROUPS];
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateComputePipeline(
+    device: WGPUDevice,
+    descriptor: &WGPUComputePipelineDescriptor,
+) -> WGPUComputePipeline {
+    let device = non_null!(clone_arc(device));
+    let layout = descriptor.layout.as_ref().map(|layout| layout.id);
+    let stage = match map_stage(descriptor.compute.module, descriptor.compute.entryPoint) {
+        Some(stage) => stage,
+        None => {
+            device.handle_invalid_descriptor("wgpuDeviceCreateComputePipeline");
+            return ptr::null();
+        }
+    };
+    let desc = pipeline::ComputePipelineDescriptor {
+        label: map_label(descriptor.label),
+        layout,
+        stage,
+    };
+    let implicit_pipeline_ids = match layout {
+        Some(_) => None,
+        None => Some(crate::device::ImplicitPipelineIds {
+            root_id: (),
+            group_ids: &IMPLICIT_GROUP_IDS,
+        }),
+    };
+    let global = &*device.context;
+    let (id, _, error) = gfx_select!(device.id => global.device_create_compute_pipeline(device.id, &desc, (), implicit_pipeline_ids));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUComputePipelineImpl { device, id })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuComputePipelineGetBindGroupLayout(
+    pipeline: WGPUComputePipeline,
+    group_index: u32,
+) -> WGPUBindGroupLayout {
+    let pipeline = non_null!(borrow(pipeline));
+    let device = Arc::clone(&pipeline.device);
+    let global = &*device.context;
+    let (id, error) = gfx_select!(pipeline.id => global.compute_pipeline_get_bind_group_layout(pipeline.id, group_index, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUBindGroupLayoutImpl { device, id })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceCreateRenderPipeline(
+    device: WGPUDevice,
+    descriptor: &WGPURenderPipelineDescriptor,
+) -> WGPURenderPipeline {
+    let device = non_null!(clone_arc(device));
+    let vertex_buffers = make_slice(
+        descriptor.vertex.buffers,
+        descriptor.vertex.bufferCount as usize,
+    )
+    .iter()
+    .map(|layout| {
+        let attributes = make_slice(layout.attributes, layout.attributeCount as usize)
+            .iter()
+            .map(|attribute| {
+                Some(wgt::VertexAttribute {
+                    format: conv::map_vertex_format(attribute.format)?,
+                    offset: attribute.offset,
+                    shader_location: attribute.shaderLocation,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(pipeline::VertexBufferLayout {
+            array_stride: layout.arrayStride,
+            step_mode: conv::map_input_step_mode(layout.stepMode)?,
+            attributes: Cow::Owned(attributes),
+        })
+    })
+    .collect::<Option<Vec<_>>>();
+    let fragment = descriptor.fragment.as_ref().map(|fragment| {
+        let targets = make_slice(fragment.targets, fragment.targetCount as usize)
+            .iter()
+            .map(|target| conv::map_color_target_state(target))
+            .collect::<Option<Vec<_>>>()?;
+        Some(pipeline::FragmentState {
+            stage: map_stage(fragment.module, fragment.entryPoint)?,
+            targets: Cow::Owned(targets),
+        })
+    });
+    let depth_stencil = descriptor
+        .depthStencil
+        .as_ref()
+        .map(conv::map_depth_stencil_state);
+
+    let (vertex_stage, vertex_buffers, fragment, depth_stencil, primitive) = match (
+        map_stage(descriptor.vertex.module, descriptor.vertex.entryPoint),
+        vertex_buffers,
+        fragment.map_or(Some(None), |fragment| fragment.map(Some)),
+        depth_stencil.map_or(Some(None), |depth_stencil| depth_stencil.map(Some)),
+        conv::map_primitive_state(&descriptor.primitive),
+    ) {
+        (
+            Some(vertex_stage),
+            Some(vertex_buffers),
+            Some(fragment),
+            Some(depth_stencil),
+            Some(primitive),
+        ) => (
+            vertex_stage,
+            vertex_buffers,
+            fragment,
+            depth_stencil,
+            primitive,
+        ),
+        _ => {
+            device.handle_invalid_descriptor("wgpuDeviceCreateRenderPipeline");
+            return ptr::null();
+        }
+    };
+    let layout = descriptor.layout.as_ref().map(|layout| layout.id);
+    let desc = pipeline::RenderPipelineDescriptor {
+        label: map_label(descriptor.label),
+        layout,
+        vertex: pipeline::VertexState {
+            stage: vertex_stage,
+            buffers: Cow::Owned(vertex_buffers),
+        },
+        primitive,
+        depth_stencil,
+        multisample: conv::map_multisample_state(&descriptor.multisample),
+        fragment,
+    };
+    let implicit_pipeline_ids = match layout {
+        Some(_) => None,
+        None => Some(crate::device::ImplicitPipelineIds {
+            root_id: (),
+            group_ids: &IMPLICIT_GROUP_IDS,
+        }),
+    };
+    let global = &*device.context;
+    let (id, _, error) = gfx_select!(device.id => global.device_create_render_pipeline(device.id, &desc, (), implicit_pipeline_ids));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPURenderPipelineImpl { device, id })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuRenderPipelineGetBindGroupLayout(
+    pipeline: WGPURenderPipeline,
+    group_index: u32,
+) -> WGPUBindGroupLayout {
+    let pipeline = non_null!(borrow(pipeline));
+    let device = Arc::clone(&pipeline.device);
+    let global = &*device.context;
+    let (id, error) = gfx_select!(pipeline.id => global.render_pipeline_get_bind_group_layout(pipeline.id, group_index, ()));
+    if let Some(error) = error {
+        device.handle_error(error);
+    }
+    make_handle(WGPUBindGroupLayoutImpl { device, id })
+}
@@ -0,0 +1,549 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! C API following `webgpu.h`, implemented on top of `Global<IdentityManagerFactory>`.
+//!
+//! Every object is handed out as a reference-counted pointer, managed with
+//! the `wgpu*Reference` and `wgpu*Release` functions. Child objects keep their
+//! device alive, and devices and adapters keep the instance alive.
+//!
+//! Errors are not returned from the entry points. Instead, they are reported to
+//! the callback set by `wgpuDeviceSetUncapturedErrorCallback`, or logged if
+//! there is none. Descriptors with values outside of the known enumerations
+//! are reported the same way, and the corresponding function returns null.
+//! Null handles are reported too, or logged if there is no device to report
+//! them to, and the function returns early.
+//!
+//! # Safety
+//!
+//! All the functions expect the non-null handles to be valid, and the
+//! pointers to be valid for the given counts.
+//!
+//! The header is generated with `cbindgen` from this module, see `cbindgen.toml`.
+
+#![allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+#![allow(clippy::missing_safety_doc)]
+
+/// Unwraps the object behind a handle, returning early from the entry point
+/// if the handle is null, since unwinding out of it is undefined behavior.
+///
+/// The null handle is reported to `$device` if given, or logged otherwise.
+macro_rules! non_null {
+    ($object:expr) => {
+        match $object {
+            Some(object) => object,
+            None => {
+                tracing::error!("Null handle in `{}`", stringify!($object));
+                return $crate::ffi::NullReturn::null();
+            }
+        }
+    };
+    ($object:expr, $device:expr, $function:expr) => {
+        match $object {
+            Some(object) => object,
+            None => return $device.handle_null_handle($function),
+        }
+    };
+}
+
+mod command;
+mod conv;
+mod device;
+pub mod types;
+
+pub use self::{command::*, device::*, types::*};
+
+use crate::{
+    device::DeviceError,
+    gfx_select,
+    hub::{Global, IdentityManagerFactory},
+    id,
+    instance::AdapterInputs,
+    Label,
+};
+
+use parking_lot::Mutex;
+use std::{
+    borrow::Cow,
+    error::Error,
+    ffi::{CStr, CString},
+    mem,
+    os::raw::{c_char, c_void},
+    ptr, slice,
+    sync::Arc,
+};
+
+type Context = Global<IdentityManagerFactory>;
+
+pub struct WGPUInstanceImpl {
+    context: Arc<Context>,
+}
+
+pub struct WGPUAdapterImpl {
+    context: Arc<Context>,
+    id: id::AdapterId,
+    name: CString,
+}
+
+pub struct WGPUDeviceImpl {
+    context: Arc<Context>,
+    id: id::DeviceId,
+    error_sink: Mutex<ErrorSink>,
+}
+
+pub struct WGPUQueueImpl {
+    device: Arc<WGPUDeviceImpl>,
+}
+
+pub type WGPUInstance = *const WGPUInstanceImpl;
+pub type WGPUAdapter = *const WGPUAdapterImpl;
+pub type WGPUDevice = *const WGPUDeviceImpl;
+pub type WGPUQueue = *const WGPUQueueImpl;
+
+struct ErrorSink {
+    callback: WGPUErrorCallback,
+    userdata: *mut c_void,
+}
+
+unsafe impl Send for ErrorSink {}
+
+impl Drop for WGPUAdapterImpl {
+    fn drop(&mut self) {
+        let global = &*self.context;
+        gfx_select!(self.id => global.adapter_drop(self.id));
+    }
+}
+
+impl Drop for WGPUDeviceImpl {
+    fn drop(&mut self) {
+        let global = &*self.context;
+        gfx_select!(self.id => global.device_drop(self.id));
+    }
+}
+
+impl WGPUDeviceImpl {
+    /// Reports an error to the uncaptured error callback.
+    fn handle_error(&self, error: impl Error + 'static) {
+        let mut message = error.to_string();
+        let mut error_type = WGPUErrorType_Validation;
+        let mut source: Option<&(dyn Error + 'static)> = Some(&error);
+        while let Some(error) = source {
+            match error.downcast_ref::<DeviceError>() {
+                Some(&DeviceError::OutOfMemory) => error_type = WGPUErrorType_OutOfMemory,
+                Some(&DeviceError::Lost) => error_type = WGPUErrorType_DeviceLost,
+                _ => {}
+            }
+            source = error.source();
+            if let Some(error) = source {
+                message = format!("{}: {}", message, error);
+            }
+        }
+        self.report(error_type, message);
+    }
+
+    /// Reports a descriptor that can't be converted.
+    fn handle_invalid_descriptor(&self, function: &str) {
+        self.report(
+            WGPUErrorType_Validation,
+            format!(
+                "{}: descriptor contains unknown enumeration values or null handles",
+                function
+            ),
+        );
+    }
+
+    /// Reports a null handle passed as an argument.
+    fn handle_null_handle(&self, function: &str) {
+        self.report(
+            WGPUErrorType_Validation,
+            format!("{}: argument is a null handle", function),
+        );
+    }
+
+    fn report(&self, error_type: WGPUErrorType, message: String) {
+        // The lock is not held during the callback, so that it can call back into the API.
+        let (callback, userdata) = {
+            let sink = self.error_sink.lock();
+            (sink.callback, sink.userdata)
+        };
+        match callback {
+            Some(callback) => {
+                let message = CString::new(message.replace('\0', "")).unwrap();
+                unsafe { callback(error_type, message.as_ptr(), userdata) }
+            }
+            None => tracing::error!("Uncaptured error: {}", message),
+        }
+    }
+}
+
+fn make_handle<T>(value: T) -> *const T {
+    Arc::into_raw(Arc::new(value))
+}
+
+/// Value returned by an entry point called with a null handle.
+pub(crate) trait NullReturn {
+    fn null() -> Self;
+}
+
+impl NullReturn for () {
+    fn null() -> Self {}
+}
+
+impl<T> NullReturn for *const T {
+    fn null() -> Self {
+        ptr::null()
+    }
+}
+
+impl<T> NullReturn for *mut T {
+    fn null() -> Self {
+        ptr::null_mut()
+    }
+}
+
+/// Borrows the object behind a handle, unless it's null.
+unsafe fn borrow<'a, T>(handle: *const T) -> Option<&'a T> {
+    handle.as_ref()
+}
+
+/// Clones the reference to the object behind a handle, unless it's null.
+unsafe fn clone_arc<T>(handle: *const T) -> Option<Arc<T>> {
+    if handle.is_null() {
+        return None;
+    }
+    let arc = Arc::from_raw(handle);
+    let clone = Arc::clone(&arc);
+    mem::forget(arc);
+    Some(clone)
+}
+
+unsafe fn map_label<'a>(label: *const c_char) -> Label<'a> {
+    if label.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(label).to_string_lossy())
+    }
+}
+
+unsafe fn map_string<'a>(string: *const c_char) -> Cow<'a, str> {
+    if string.is_null() {
+        Cow::Borrowed("")
+    } else {
+        CStr::from_ptr(string).to_string_lossy()
+    }
+}
+
+unsafe fn make_slice<'a, T>(pointer: *const T, count: usize) -> &'a [T] {
+    if count == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(pointer, count)
+    }
+}
+
+macro_rules! reference_release {
+    ($($handle:ident: $reference:ident, $release:ident;)*) => {$(
+        #[no_mangle]
+        pub unsafe extern "C" fn $reference(handle: $handle) {
+            mem::forget(non_null!(clone_arc(handle)));
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn $release(handle: $handle) {
+            drop(Arc::from_raw(non_null!(borrow(handle))));
+        }
+    )*};
+}
+
+reference_release! {
+    WGPUInstance: wgpuInstanceReference, wgpuInstanceRelease;
+    WGPUAdapter: wgpuAdapterReference, wgpuAdapterRelease;
+    WGPUDevice: wgpuDeviceReference, wgpuDeviceRelease;
+    WGPUQueue: wgpuQueueReference, wgpuQueueRelease;
+    WGPUBuffer: wgpuBufferReference, wgpuBufferRelease;
+    WGPUTexture: wgpuTextureReference, wgpuTextureRelease;
+    WGPUTextureView: wgpuTextureViewReference, wgpuTextureViewRelease;
+    WGPUSampler: wgpuSamplerReference, wgpuSamplerRelease;
+    WGPUShaderModule: wgpuShaderModuleReference, wgpuShaderModuleRelease;
+    WGPUBindGroupLayout: wgpuBindGroupLayoutReference, wgpuBindGroupLayoutRelease;
+    WGPUPipelineLayout: wgpuPipelineLayoutReference, wgpuPipelineLayoutRelease;
+    WGPUBindGroup: wgpuBindGroupReference, wgpuBindGroupRelease;
+    WGPUComputePipeline: wgpuComputePipelineReference, wgpuComputePipelineRelease;
+    WGPURenderPipeline: wgpuRenderPipelineReference, wgpuRenderPipelineRelease;
+    WGPUCommandEncoder: wgpuCommandEncoderReference, wgpuCommandEncoderRelease;
+    WGPUCommandBuffer: wgpuCommandBufferReference, wgpuCommandBufferRelease;
+    WGPUComputePassEncoder: wgpuComputePassEncoderReference, wgpuComputePassEncoderRelease;
+    WGPURenderPassEncoder: wgpuRenderPassEncoderReference, wgpuRenderPassEncoderRelease;
+}
+
+/// The descriptor may be null.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuCreateInstance(
+    _descriptor: *const WGPUInstanceDescriptor,
+) -> WGPUInstance {
+    make_handle(WGPUInstanceImpl {
+        context: Arc::new(Global::new(
+            "wgpu",
+            IdentityManagerFactory,
+            wgt::BackendBit::PRIMARY,
+        )),
+    })
+}
+
+/// The callback is called before this function returns. The options may be null.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuInstanceRequestAdapter(
+    instance: WGPUInstance,
+    options: *const WGPURequestAdapterOptions,
+    callback: WGPURequestAdapterCallback,
+    userdata: *mut c_void,
+) {
+    let instance = non_null!(borrow(instance));
+    let power_preference = match options.as_ref().map(|options| options.powerPreference) {
+        None | Some(WGPUPowerPreference_LowPower) => wgt::PowerPreference::LowPower,
+        Some(WGPUPowerPreference_HighPerformance) => wgt::PowerPreference::HighPerformance,
+        Some(other) => {
+            let message = CString::new(format!("unknown power preference {}", other)).unwrap();
+            if let Some(callback) = callback {
+                callback(
+                    WGPURequestAdapterStatus_Error,
+                    ptr::null(),
+                    message.as_ptr(),
+                    userdata,
+                );
+            }
+            return;
+        }
+    };
+    let desc = wgt::RequestAdapterOptions {
+        power_preference,
+        compatible_surface: None,
+    };
+    let global = &*instance.context;
+    let (status, adapter, message) = match global
+        .request_adapter(&desc, AdapterInputs::Mask(wgt::BackendBit::PRIMARY, |_| ()))
+    {
+        Ok(id) => match gfx_select!(id => global.adapter_get_info(id)) {
+            Ok(info) => {
+                let adapter = make_handle(WGPUAdapterImpl {
+                    context: Arc::clone(&instance.context),
+                    id,
+                    name: CString::new(info.name.replace('\0', "")).unwrap(),
+                });
+                (WGPURequestAdapterStatus_Success, adapter, None)
+            }
+            Err(error) => (
+                WGPURequestAdapterStatus_Error,
+                ptr::null(),
+                Some(error.to_string()),
+            ),
+        },
+        Err(error) => (
+            WGPURequestAdapterStatus_Unavailable,
+            ptr::null(),
+            Some(error.to_string()),
+        ),
+    };
+    let message = message.map(|message| CString::new(message.replace('\0', "")).unwrap());
+    match callback {
+        Some(callback) => callback(
+            status,
+            adapter,
+            message
+                .as_ref()
+                .map_or(ptr::null(), |message| message.as_ptr()),
+            userdata,
+        ),
+        None if !adapter.is_null() => wgpuAdapterRelease(adapter),
+        None => {}
+    }
+}
+
+/// The strings in the properties are valid for the lifetime of the adapter.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuAdapterGetProperties(
+    adapter: WGPUAdapter,
+    properties: &mut WGPUAdapterProperties,
+) {
+    let adapter = non_null!(borrow(adapter));
+    let global = &*adapter.context;
+    let info = match gfx_select!(adapter.id => global.adapter_get_info(adapter.id)) {
+        Ok(info) => info,
+        Err(error) => {
+            tracing::error!("wgpuAdapterGetProperties: {}", error);
+            return;
+        }
+    };
+    properties.vendorID = info.vendor as u32;
+    properties.deviceID = info.device as u32;
+    properties.name = adapter.name.as_ptr();
+    properties.adapterType = conv::map_adapter_type(&info.device_type);
+    properties.backendType = conv::map_backend_type(info.backend);
+}
+
+/// The callback is called before this function returns. The descriptor may be null.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuAdapterRequestDevice(
+    adapter: WGPUAdapter,
+    descriptor: *const WGPUDeviceDescriptor,
+    callback: WGPURequestDeviceCallback,
+    userdata: *mut c_void,
+) {
+    let adapter = non_null!(borrow(adapter));
+    let desc = wgt::DeviceDescriptor {
+        label: descriptor
+            .as_ref()
+            .and_then(|descriptor| map_label(descriptor.label)),
+        features: wgt::Features::empty(),
+        limits: wgt::Limits::default(),
+    };
+    let global = &*adapter.context;
+    let (id, error) =
+        gfx_select!(adapter.id => global.adapter_request_device(adapter.id, &desc, None, ()));
+    let device = make_handle(WGPUDeviceImpl {
+        context: Arc::clone(&adapter.context),
+        id,
+        error_sink: Mutex::new(ErrorSink {
+            callback: None,
+            userdata: ptr::null_mut(),
+        }),
+    });
+    let (status, device, message) = match error {
+        None => (WGPURequestDeviceStatus_Success, device, None),
+        Some(error) => {
+            wgpuDeviceRelease(device);
+            let message = CString::new(error.to_string().replace('\0', "")).unwrap();
+            (WGPURequestDeviceStatus_Error, ptr::null(), Some(message))
+        }
+    };
+    match callback {
+        Some(callback) => callback(
+            status,
+            device,
+            message
+                .as_ref()
+                .map_or(ptr::null(), |message| message.as_ptr()),
+            userdata,
+        ),
+        None if !device.is_null() => wgpuDeviceRelease(device),
+        None => {}
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceSetUncapturedErrorCallback(
+    device: WGPUDevice,
+    callback: WGPUErrorCallback,
+    userdata: *mut c_void,
+) {
+    *non_null!(borrow(device)).error_sink.lock() = ErrorSink { callback, userdata };
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDeviceGetQueue(device: WGPUDevice) -> WGPUQueue {
+    make_handle(WGPUQueueImpl {
+        device: non_null!(clone_arc(device)),
+    })
+}
+
+/// Native extension: maintains the device, calling the ready buffer mapping
+/// callbacks. If `wait` is true, blocks until all the submitted work is done.
+#[no_mangle]
+pub unsafe extern "C" fn wgpuDevicePoll(device: WGPUDevice, wait: bool) {
+    let device = non_null!(borrow(device));
+    let global = &*device.context;
+    if let Err(error) = gfx_select!(device.id => global.device_poll(device.id, wait)) {
+        device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuQueueSubmit(
+    queue: WGPUQueue,
+    command_count: u32,
+    commands: *const WGPUCommandBuffer,
+) {
+    let device = &*non_null!(borrow(queue)).device;
+    let command_buffers = non_null!(
+        make_slice(commands, command_count as usize)
+            .iter()
+            .map(|&command_buffer| borrow(command_buffer))
+            .collect::<Option<Vec<_>>>(),
+        device,
+        "wgpuQueueSubmit"
+    );
+    let ids = command_buffers
+        .iter()
+        .map(|command_buffer| command_buffer.consume())
+        .collect::<Vec<_>>();
+    let global = &*device.context;
+    if let Err(error) = gfx_select!(device.id => global.queue_submit(device.id, &ids)) {
+        device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuQueueWriteBuffer(
+    queue: WGPUQueue,
+    buffer: WGPUBuffer,
+    buffer_offset: u64,
+    data: *const c_void,
+    size: usize,
+) {
+    let device = &*non_null!(borrow(queue)).device;
+    let buffer_id = non_null!(borrow(buffer), device, "wgpuQueueWriteBuffer").id;
+    let data = make_slice(data as *const u8, size);
+    let global = &*device.context;
+    if let Err(error) = gfx_select!(device.id => global.queue_write_buffer(device.id, buffer_id, buffer_offset, data))
+    {
+        device.handle_error(error);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wgpuQueueWriteTexture(
+    queue: WGPUQueue,
+    destination: &WGPUImageCopyTexture,
+    data: *const c_void,
+    data_size: usize,
+    data_layout: &WGPUTextureDataLayout,
+    write_size: &WGPUExtent3D,
+) {
+    let device = &*non_null!(borrow(queue)).device;
+    let destination = match map_image_copy_texture(destination) {
+        Some(destination) => destination,
+        None => return device.handle_invalid_descriptor("wgpuQueueWriteTexture"),
+    };
+    let data = make_slice(data as *const u8, data_size);
+    let global = &*device.context;
+    if let Err(error) = gfx_select!(device.id => global.queue_write_texture(
+        device.id,
+        &destination,
+        data,
+        &conv::map_texture_data_layout(data_layout),
+        &conv::map_extent(write_size)
+    )) {
+        device.handle_error(error);
+    }
+}
+
+unsafe fn map_image_copy_texture(
+    copy: &WGPUImageCopyTexture,
+) -> Option<crate::command::TextureCopyView> {
+    Some(crate::command::TextureCopyView {
+        texture: borrow(copy.texture)?.id,
+        mip_level: copy.mipLevel,
+        origin: conv::map_origin(&copy.origin),
+        aspect: conv::map_texture_aspect(copy.aspect)?,
+    })
+}
+
+unsafe fn map_image_copy_buffer(
+    copy: &WGPUImageCopyBuffer,
+) -> Option<crate::command::BufferCopyView> {
+    Some(crate::command::BufferCopyView {
+        buffer: borrow(copy.buffer)?.id,
+        layout: conv::map_texture_data_layout(&copy.layout),
+    })
+}
@@ -0,0 +1,765 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Plain data types of `webgpu.h`.
+//!
+//! Enumerations are exposed as integer typedefs with one constant per value,
+//! so that unknown values coming from C can be rejected instead of being
+//! undefined behavior on the Rust side. The values follow `webgpu.h`.
+
+use super::{
+    WGPUBindGroupLayout, WGPUBuffer, WGPUPipelineLayout, WGPUSampler, WGPUShaderModule,
+    WGPUTexture, WGPUTextureView,
+};
+use std::os::raw::{c_char, c_void};
+
+pub type WGPUFlags = u32;
+pub type WGPUBufferUsageFlags = WGPUFlags;
+pub type WGPUTextureUsageFlags = WGPUFlags;
+pub type WGPUShaderStageFlags = WGPUFlags;
+pub type WGPUColorWriteMaskFlags = WGPUFlags;
+pub type WGPUMapModeFlags = WGPUFlags;
+
+pub const WGPUBufferUsage_MapRead: WGPUBufferUsageFlags = 0x1;
+pub const WGPUBufferUsage_MapWrite: WGPUBufferUsageFlags = 0x2;
+pub const WGPUBufferUsage_CopySrc: WGPUBufferUsageFlags = 0x4;
+pub const WGPUBufferUsage_CopyDst: WGPUBufferUsageFlags = 0x8;
+pub const WGPUBufferUsage_Index: WGPUBufferUsageFlags = 0x10;
+pub const WGPUBufferUsage_Vertex: WGPUBufferUsageFlags = 0x20;
+pub const WGPUBufferUsage_Uniform: WGPUBufferUsageFlags = 0x40;
+pub const WGPUBufferUsage_Storage: WGPUBufferUsageFlags = 0x80;
+pub const WGPUBufferUsage_Indirect: WGPUBufferUsageFlags = 0x100;
+
+pub const WGPUTextureUsage_CopySrc: WGPUTextureUsageFlags = 0x1;
+pub const WGPUTextureUsage_CopyDst: WGPUTextureUsageFlags = 0x2;
+pub const WGPUTextureUsage_Sampled: WGPUTextureUsageFlags = 0x4;
+pub const WGPUTextureUsage_Storage: WGPUTextureUsageFlags = 0x8;
+pub const WGPUTextureUsage_RenderAttachment: WGPUTextureUsageFlags = 0x10;
+
+pub const WGPUShaderStage_Vertex: WGPUShaderStageFlags = 0x1;
+pub const WGPUShaderStage_Fragment: WGPUShaderStageFlags = 0x2;
+pub const WGPUShaderStage_Compute: WGPUShaderStageFlags = 0x4;
+
+pub const WGPUColorWriteMask_Red: WGPUColorWriteMaskFlags = 0x1;
+pub const WGPUColorWriteMask_Green: WGPUColorWriteMaskFlags = 0x2;
+pub const WGPUColorWriteMask_Blue: WGPUColorWriteMaskFlags = 0x4;
+pub const WGPUColorWriteMask_Alpha: WGPUColorWriteMaskFlags = 0x8;
+pub const WGPUColorWriteMask_All: WGPUColorWriteMaskFlags = 0xF;
+
+pub const WGPU_WHOLE_SIZE: u64 = !0;
+
+pub type WGPUSType = u32;
+pub const WGPUSType_Invalid: WGPUSType = 0;
+pub const WGPUSType_ShaderModuleSPIRVDescriptor: WGPUSType = 5;
+pub const WGPUSType_ShaderModuleWGSLDescriptor: WGPUSType = 6;
+
+pub type WGPUAdapterType = u32;
+pub const WGPUAdapterType_DiscreteGPU: WGPUAdapterType = 0;
+pub const WGPUAdapterType_IntegratedGPU: WGPUAdapterType = 1;
+pub const WGPUAdapterType_CPU: WGPUAdapterType = 2;
+pub const WGPUAdapterType_Unknown: WGPUAdapterType = 3;
+
+pub type WGPUBackendType = u32;
+pub const WGPUBackendType_Null: WGPUBackendType = 0;
+pub const WGPUBackendType_WebGPU: WGPUBackendType = 1;
+pub const WGPUBackendType_D3D11: WGPUBackendType = 2;
+pub const WGPUBackendType_D3D12: WGPUBackendType = 3;
+pub const WGPUBackendType_Metal: WGPUBackendType = 4;
+pub const WGPUBackendType_Vulkan: WGPUBackendType = 5;
+pub const WGPUBackendType_OpenGL: WGPUBackendType = 6;
+
+pub type WGPUPowerPreference = u32;
+pub const WGPUPowerPreference_LowPower: WGPUPowerPreference = 0;
+pub const WGPUPowerPreference_HighPerformance: WGPUPowerPreference = 1;
+
+pub type WGPURequestAdapterStatus = u32;
+pub const WGPURequestAdapterStatus_Success: WGPURequestAdapterStatus = 0;
+pub const WGPURequestAdapterStatus_Unavailable: WGPURequestAdapterStatus = 1;
+pub const WGPURequestAdapterStatus_Error: WGPURequestAdapterStatus = 2;
+
+pub type WGPURequestDeviceStatus = u32;
+pub const WGPURequestDeviceStatus_Success: WGPURequestDeviceStatus = 0;
+pub const WGPURequestDeviceStatus_Error: WGPURequestDeviceStatus = 1;
+
+pub type WGPUErrorType = u32;
+pub const WGPUErrorType_NoError: WGPUErrorType = 0;
+pub const WGPUErrorType_Validation: WGPUErrorType = 1;
+pub const WGPUErrorType_OutOfMemory: WGPUErrorType = 2;
+pub const WGPUErrorType_Unknown: WGPUErrorType = 3;
+pub const WGPUErrorType_DeviceLost: WGPUErrorType = 4;
+
+pub type WGPUBufferMapAsyncStatus = u32;
+pub const WGPUBufferMapAsyncStatus_Success: WGPUBufferMapAsyncStatus = 0;
+pub const WGPUBufferMapAsyncStatus_Error: WGPUBufferMapAsyncStatus = 1;
+pub const WGPUBufferMapAsyncStatus_Unknown: WGPUBufferMapAsyncStatus = 2;
+pub const WGPUBufferMapAsyncStatus_DeviceLost: WGPUBufferMapAsyncStatus = 3;
+pub const WGPUBufferMapAsyncStatus_DestroyedBeforeCallback: WGPUBufferMapAsyncStatus = 4;
+pub const WGPUBufferMapAsyncStatus_UnmappedBeforeCallback: WGPUBufferMapAsyncStatus = 5;
+
+pub const WGPUMapMode_Read: WGPUMapModeFlags = 1;
+pub const WGPUMapMode_Write: WGPUMapModeFlags = 2;
+
+pub type WGPUTextureFormat = u32;
+pub const WGPUTextureFormat_Undefined: WGPUTextureFormat = 0;
+pub const WGPUTextureFormat_R8Unorm: WGPUTextureFormat = 1;
+pub const WGPUTextureFormat_R8Snorm: WGPUTextureFormat = 2;
+pub const WGPUTextureFormat_R8Uint: WGPUTextureFormat = 3;
+pub const WGPUTextureFormat_R8Sint: WGPUTextureFormat = 4;
+pub const WGPUTextureFormat_R16Uint: WGPUTextureFormat = 5;
+pub const WGPUTextureFormat_R16Sint: WGPUTextureFormat = 6;
+pub const WGPUTextureFormat_R16Float: WGPUTextureFormat = 7;
+pub const WGPUTextureFormat_RG8Unorm: WGPUTextureFormat = 8;
+pub const WGPUTextureFormat_RG8Snorm: WGPUTextureFormat = 9;
+pub const WGPUTextureFormat_RG8Uint: WGPUTextureFormat = 10;
+pub const WGPUTextureFormat_RG8Sint: WGPUTextureFormat = 11;
+pub const WGPUTextureFormat_R32Float: WGPUTextureFormat = 12;
+pub const WGPUTextureFormat_R32Uint: WGPUTextureFormat = 13;
+pub const WGPUTextureFormat_R32Sint: WGPUTextureFormat = 14;
+pub const WGPUTextureFormat_RG16Uint: WGPUTextureFormat = 15;
+pub const WGPUTextureFormat_RG16Sint: WGPUTextureFormat = 16;
+pub const WGPUTextureFormat_RG16Float: WGPUTextureFormat = 17;
+pub const WGPUTextureFormat_RGBA8Unorm: WGPUTextureFormat = 18;
+pub const WGPUTextureFormat_RGBA8UnormSrgb: WGPUTextureFormat = 19;
+pub const WGPUTextureFormat_RGBA8Snorm: WGPUTextureFormat = 20;
+pub const WGPUTextureFormat_RGBA8Uint: WGPUTextureFormat = 21;
+pub const WGPUTextureFormat_RGBA8Sint: WGPUTextureFormat = 22;
+pub const WGPUTextureFormat_BGRA8Unorm: WGPUTextureFormat = 23;
+pub const WGPUTextureFormat_BGRA8UnormSrgb: WGPUTextureFormat = 24;
+pub const WGPUTextureFormat_RGB10A2Unorm: WGPUTextureFormat = 25;
+pub const WGPUTextureFormat_RG11B10Ufloat: WGPUTextureFormat = 26;
+pub const WGPUTextureFormat_RGB9E5Ufloat: WGPUTextureFormat = 27;
+pub const WGPUTextureFormat_RG32Float: WGPUTextureFormat = 28;
+pub const WGPUTextureFormat_RG32Uint: WGPUTextureFormat = 29;
+pub const WGPUTextureFormat_RG32Sint: WGPUTextureFormat = 30;
+pub const WGPUTextureFormat_RGBA16Uint: WGPUTextureFormat = 31;
+pub const WGPUTextureFormat_RGBA16Sint: WGPUTextureFormat = 32;
+pub const WGPUTextureFormat_RGBA16Float: WGPUTextureFormat = 33;
+pub const WGPUTextureFormat_RGBA32Float: WGPUTextureFormat = 34;
+pub const WGPUTextureFormat_RGBA32Uint: WGPUTextureFormat = 35;
+pub const WGPUTextureFormat_RGBA32Sint: WGPUTextureFormat = 36;
+pub const WGPUTextureFormat_Stencil8: WGPUTextureFormat = 37;
+pub const WGPUTextureFormat_Depth16Unorm: WGPUTextureFormat = 38;
+pub const WGPUTextureFormat_Depth24Plus: WGPUTextureFormat = 39;
+pub const WGPUTextureFormat_Depth24PlusStencil8: WGPUTextureFormat = 40;
+pub const WGPUTextureFormat_Depth32Float: WGPUTextureFormat = 41;
+/// Native extension, requires `Features::DEPTH32FLOAT_STENCIL8`.
+pub const WGPUTextureFormat_Depth32FloatStencil8: WGPUTextureFormat = 0x6000_0000;
+/// Native extension, requires `Features::TEXTURE_FORMAT_16BIT_NORM`.
+pub const WGPUTextureFormat_R16Unorm: WGPUTextureFormat = 0x6000_0001;
+/// Native extension, requires `Features::TEXTURE_FORMAT_16BIT_NORM`.
+pub const WGPUTextureFormat_R16Snorm: WGPUTextureFormat = 0x6000_0002;
+/// Native extension, requires `Features::TEXTURE_FORMAT_16BIT_NORM`.
+pub const WGPUTextureFormat_RG16Unorm: WGPUTextureFormat = 0x6000_0003;
+/// Native extension, requires `Features::TEXTURE_FORMAT_16BIT_NORM`.
+pub const WGPUTextureFormat_RG16Snorm: WGPUTextureFormat = 0x6000_0004;
+/// Native extension, requires `Features::TEXTURE_FORMAT_16BIT_NORM`.
+pub const WGPUTextureFormat_RGBA16Unorm: WGPUTextureFormat = 0x6000_0005;
+/// Native extension, requires `Features::TEXTURE_FORMAT_16BIT_NORM`.
+pub const WGPUTextureFormat_RGBA16Snorm: WGPUTextureFormat = 0x6000_0006;
+pub const WGPUTextureFormat_BC1RGBAUnorm: WGPUTextureFormat = 42;
+pub const WGPUTextureFormat_BC1RGBAUnormSrgb: WGPUTextureFormat = 43;
+pub const WGPUTextureFormat_BC2RGBAUnorm: WGPUTextureFormat = 44;
+pub const WGPUTextureFormat_BC2RGBAUnormSrgb: WGPUTextureFormat = 45;
+pub const WGPUTextureFormat_BC3RGBAUnorm: WGPUTextureFormat = 46;
+pub const WGPUTextureFormat_BC3RGBAUnormSrgb: WGPUTextureFormat = 47;
+pub const WGPUTextureFormat_BC4RUnorm: WGPUTextureFormat = 48;
+pub const WGPUTextureFormat_BC4RSnorm: WGPUTextureFormat = 49;
+pub const WGPUTextureFormat_BC5RGUnorm: WGPUTextureFormat = 50;
+pub const WGPUTextureFormat_BC5RGSnorm: WGPUTextureFormat = 51;
+pub const WGPUTextureFormat_BC6HRGBUfloat: WGPUTextureFormat = 52;
+pub const WGPUTextureFormat_BC6HRGBFloat: WGPUTextureFormat = 53;
+pub const WGPUTextureFormat_BC7RGBAUnorm: WGPUTextureFormat = 54;
+pub const WGPUTextureFormat_BC7RGBAUnormSrgb: WGPUTextureFormat = 55;
+
+pub type WGPUTextureDimension = u32;
+pub const WGPUTextureDimension_1D: WGPUTextureDimension = 0;
+pub const WGPUTextureDimension_2D: WGPUTextureDimension = 1;
+pub const WGPUTextureDimension_3D: WGPUTextureDimension = 2;
+
+pub type WGPUTextureViewDimension = u32;
+pub const WGPUTextureViewDimension_Undefined: WGPUTextureViewDimension = 0;
+pub const WGPUTextureViewDimension_1D: WGPUTextureViewDimension = 1;
+pub const WGPUTextureViewDimension_2D: WGPUTextureViewDimension = 2;
+pub const WGPUTextureViewDimension_2DArray: WGPUTextureViewDimension = 3;
+pub const WGPUTextureViewDimension_Cube: WGPUTextureViewDimension = 4;
+pub const WGPUTextureViewDimension_CubeArray: WGPUTextureViewDimension = 5;
+pub const WGPUTextureViewDimension_3D: WGPUTextureViewDimension = 6;
+
+pub type WGPUTextureAspect = u32;
+pub const WGPUTextureAspect_All: WGPUTextureAspect = 0;
+pub const WGPUTextureAspect_StencilOnly: WGPUTextureAspect = 1;
+pub const WGPUTextureAspect_DepthOnly: WGPUTextureAspect = 2;
+
+pub type WGPUAddressMode = u32;
+pub const WGPUAddressMode_Repeat: WGPUAddressMode = 0;
+pub const WGPUAddressMode_MirrorRepeat: WGPUAddressMode = 1;
+pub const WGPUAddressMode_ClampToEdge: WGPUAddressMode = 2;
+
+pub type WGPUFilterMode = u32;
+pub const WGPUFilterMode_Nearest: WGPUFilterMode = 0;
+pub const WGPUFilterMode_Linear: WGPUFilterMode = 1;
+
+pub type WGPUCompareFunction = u32;
+pub const WGPUCompareFunction_Undefined: WGPUCompareFunction = 0;
+pub const WGPUCompareFunction_Never: WGPUCompareFunction = 1;
+pub const WGPUCompareFunction_Less: WGPUCompareFunction = 2;
+pub const WGPUCompareFunction_LessEqual: WGPUCompareFunction = 3;
+pub const WGPUCompareFunction_Greater: WGPUCompareFunction = 4;
+pub const WGPUCompareFunction_GreaterEqual: WGPUCompareFunction = 5;
+pub const WGPUCompareFunction_Equal: WGPUCompareFunction = 6;
+pub const WGPUCompareFunction_NotEqual: WGPUCompareFunction = 7;
+pub const WGPUCompareFunction_Always: WGPUCompareFunction = 8;
+
+pub type WGPUBufferBindingType = u32;
+pub const WGPUBufferBindingType_Undefined: WGPUBufferBindingType = 0;
+pub const WGPUBufferBindingType_Uniform: WGPUBufferBindingType = 1;
+pub const WGPUBufferBindingType_Storage: WGPUBufferBindingType = 2;
+pub const WGPUBufferBindingType_ReadOnlyStorage: WGPUBufferBindingType = 3;
+
+pub type WGPUSamplerBindingType = u32;
+pub const WGPUSamplerBindingType_Undefined: WGPUSamplerBindingType = 0;
+pub const WGPUSamplerBindingType_Filtering: WGPUSamplerBindingType = 1;
+pub const WGPUSamplerBindingType_NonFiltering: WGPUSamplerBindingType = 2;
+pub const WGPUSamplerBindingType_Comparison: WGPUSamplerBindingType = 3;
+
+pub type WGPUTextureSampleType = u32;
+pub const WGPUTextureSampleType_Undefined: WGPUTextureSampleType = 0;
+pub const WGPUTextureSampleType_Float: WGPUTextureSampleType = 1;
+pub const WGPUTextureSampleType_UnfilterableFloat: WGPUTextureSampleType = 2;
+pub const WGPUTextureSampleType_Depth: WGPUTextureSampleType = 3;
+pub const WGPUTextureSampleType_Sint: WGPUTextureSampleType = 4;
+pub const WGPUTextureSampleType_Uint: WGPUTextureSampleType = 5;
+
+pub type WGPUStorageTextureAccess = u32;
+pub const WGPUStorageTextureAccess_Undefined: WGPUStorageTextureAccess = 0;
+pub const WGPUStorageTextureAccess_ReadOnly: WGPUStorageTextureAccess = 1;
+pub const WGPUStorageTextureAccess_WriteOnly: WGPUStorageTextureAccess = 2;
+/// Native extension, requires `Features::STORAGE_TEXTURE_ACCESS_READ_WRITE`.
+pub const WGPUStorageTextureAccess_ReadWrite: WGPUStorageTextureAccess = 3;
+
+pub type WGPUVertexFormat = u32;
+pub const WGPUVertexFormat_Undefined: WGPUVertexFormat = 0;
+pub const WGPUVertexFormat_Uint8x2: WGPUVertexFormat = 1;
+pub const WGPUVertexFormat_Uint8x4: WGPUVertexFormat = 2;
+pub const WGPUVertexFormat_Sint8x2: WGPUVertexFormat = 3;
+pub const WGPUVertexFormat_Sint8x4: WGPUVertexFormat = 4;
+pub const WGPUVertexFormat_Unorm8x2: WGPUVertexFormat = 5;
+pub const WGPUVertexFormat_Unorm8x4: WGPUVertexFormat = 6;
+pub const WGPUVertexFormat_Snorm8x2: WGPUVertexFormat = 7;
+pub const WGPUVertexFormat_Snorm8x4: WGPUVertexFormat = 8;
+pub const WGPUVertexFormat_Uint16x2: WGPUVertexFormat = 9;
+pub const WGPUVertexFormat_Uint16x4: WGPUVertexFormat = 10;
+pub const WGPUVertexFormat_Sint16x2: WGPUVertexFormat = 11;
+pub const WGPUVertexFormat_Sint16x4: WGPUVertexFormat = 12;
+pub const WGPUVertexFormat_Unorm16x2: WGPUVertexFormat = 13;
+pub const WGPUVertexFormat_Unorm16x4: WGPUVertexFormat = 14;
+pub const WGPUVertexFormat_Snorm16x2: WGPUVertexFormat = 15;
+pub const WGPUVertexFormat_Snorm16x4: WGPUVertexFormat = 16;
+pub const WGPUVertexFormat_Float16x2: WGPUVertexFormat = 17;
+pub const WGPUVertexFormat_Float16x4: WGPUVertexFormat = 18;
+pub const WGPUVertexFormat_Float32: WGPUVertexFormat = 19;
+pub const WGPUVertexFormat_Float32x2: WGPUVertexFormat = 20;
+pub const WGPUVertexFormat_Float32x3: WGPUVertexFormat = 21;
+pub const WGPUVertexFormat_Float32x4: WGPUVertexFormat = 22;
+pub const WGPUVertexFormat_Uint32: WGPUVertexFormat = 23;
+pub const WGPUVertexFormat_Uint32x2: WGPUVertexFormat = 24;
+pub const WGPUVertexFormat_Uint32x3: WGPUVertexFormat = 25;
+pub const WGPUVertexFormat_Uint32x4: WGPUVertexFormat = 26;
+pub const WGPUVertexFormat_Sint32: WGPUVertexFormat = 27;
+pub const WGPUVertexFormat_Sint32x2: WGPUVertexFormat = 28;
+pub const WGPUVertexFormat_Sint32x3: WGPUVertexFormat = 29;
+pub const WGPUVertexFormat_Sint32x4: WGPUVertexFormat = 30;
+
+pub type WGPUInputStepMode = u32;
+pub const WGPUInputStepMode_Vertex: WGPUInputStepMode = 0;
+pub const WGPUInputStepMode_Instance: WGPUInputStepMode = 1;
+
+pub type WGPUPrimitiveTopology = u32;
+pub const WGPUPrimitiveTopology_PointList: WGPUPrimitiveTopology = 0;
+pub const WGPUPrimitiveTopology_LineList: WGPUPrimitiveTopology = 1;
+pub const WGPUPrimitiveTopology_LineStrip: WGPUPrimitiveTopology = 2;
+pub const WGPUPrimitiveTopology_TriangleList: WGPUPrimitiveTopology = 3;
+pub const WGPUPrimitiveTopology_TriangleStrip: WGPUPrimitiveTopology = 4;
+
+pub type WGPUIndexFormat = u32;
+pub const WGPUIndexFormat_Undefined: WGPUIndexFormat = 0;
+pub const WGPUIndexFormat_Uint16: WGPUIndexFormat = 1;
+pub const WGPUIndexFormat_Uint32: WGPUIndexFormat = 2;
+
+pub type WGPUFrontFace = u32;
+pub const WGPUFrontFace_CCW: WGPUFrontFace = 0;
+pub const WGPUFrontFace_CW: WGPUFrontFace = 1;
+
+pub type WGPUCullMode = u32;
+pub const WGPUCullMode_None: WGPUCullMode = 0;
+pub const WGPUCullMode_Front: WGPUCullMode = 1;
+pub const WGPUCullMode_Back: WGPUCullMode = 2;
+
+pub type WGPUBlendFactor = u32;
+pub const WGPUBlendFactor_Zero: WGPUBlendFactor = 0;
+pub const WGPUBlendFactor_One: WGPUBlendFactor = 1;
+pub const WGPUBlendFactor_Src: WGPUBlendFactor = 2;
+pub const WGPUBlendFactor_OneMinusSrc: WGPUBlendFactor = 3;
+pub const WGPUBlendFactor_SrcAlpha: WGPUBlendFactor = 4;
+pub const WGPUBlendFactor_OneMinusSrcAlpha: WGPUBlendFactor = 5;
+pub const WGPUBlendFactor_Dst: WGPUBlendFactor = 6;
+pub const WGPUBlendFactor_OneMinusDst: WGPUBlendFactor = 7;
+pub const WGPUBlendFactor_DstAlpha: WGPUBlendFactor = 8;
+pub const WGPUBlendFactor_OneMinusDstAlpha: WGPUBlendFactor = 9;
+pub const WGPUBlendFactor_SrcAlphaSaturated: WGPUBlendFactor = 10;
+pub const WGPUBlendFactor_Constant: WGPUBlendFactor = 11;
+pub const WGPUBlendFactor_OneMinusConstant: WGPUBlendFactor = 12;
+
+pub type WGPUBlendOperation = u32;
+pub const WGPUBlendOperation_Add: WGPUBlendOperation = 0;
+pub const WGPUBlendOperation_Subtract: WGPUBlendOperation = 1;
+pub const WGPUBlendOperation_ReverseSubtract: WGPUBlendOperation = 2;
+pub const WGPUBlendOperation_Min: WGPUBlendOperation = 3;
+pub const WGPUBlendOperation_Max: WGPUBlendOperation = 4;
+
+pub type WGPUStencilOperation = u32;
+pub const WGPUStencilOperation_Keep: WGPUStencilOperation = 0;
+pub const WGPUStencilOperation_Zero: WGPUStencilOperation = 1;
+pub const WGPUStencilOperation_Replace: WGPUStencilOperation = 2;
+pub const WGPUStencilOperation_Invert: WGPUStencilOperation = 3;
+pub const WGPUStencilOperation_IncrementClamp: WGPUStencilOperation = 4;
+pub const WGPUStencilOperation_DecrementClamp: WGPUStencilOperation = 5;
+pub const WGPUStencilOperation_IncrementWrap: WGPUStencilOperation = 6;
+pub const WGPUStencilOperation_DecrementWrap: WGPUStencilOperation = 7;
+
+pub type WGPULoadOp = u32;
+pub const WGPULoadOp_Clear: WGPULoadOp = 0;
+pub const WGPULoadOp_Load: WGPULoadOp = 1;
+
+pub type WGPUStoreOp = u32;
+pub const WGPUStoreOp_Store: WGPUStoreOp = 0;
+pub const WGPUStoreOp_Clear: WGPUStoreOp = 1;
+
+#[repr(C)]
+pub struct WGPUChainedStruct {
+    pub next: *const WGPUChainedStruct,
+    pub sType: WGPUSType,
+}
+
+#[repr(C)]
+pub struct WGPUInstanceDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+}
+
+#[repr(C)]
+pub struct WGPURequestAdapterOptions {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub powerPreference: WGPUPowerPreference,
+}
+
+#[repr(C)]
+pub struct WGPUAdapterProperties {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub vendorID: u32,
+    pub deviceID: u32,
+    pub name: *const c_char,
+    pub adapterType: WGPUAdapterType,
+    pub backendType: WGPUBackendType,
+}
+
+#[repr(C)]
+pub struct WGPUDeviceDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+}
+
+#[repr(C)]
+pub struct WGPUBufferDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub usage: WGPUBufferUsageFlags,
+    pub size: u64,
+    pub mappedAtCreation: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct WGPUExtent3D {
+    pub width: u32,
+    pub height: u32,
+    pub depthOrArrayLayers: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct WGPUOrigin3D {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+#[repr(C)]
+pub struct WGPUTextureDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub usage: WGPUTextureUsageFlags,
+    pub dimension: WGPUTextureDimension,
+    pub size: WGPUExtent3D,
+    pub format: WGPUTextureFormat,
+    pub mipLevelCount: u32,
+    pub sampleCount: u32,
+}
+
+#[repr(C)]
+pub struct WGPUTextureViewDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub format: WGPUTextureFormat,
+    pub dimension: WGPUTextureViewDimension,
+    pub baseMipLevel: u32,
+    pub mipLevelCount: u32,
+    pub baseArrayLayer: u32,
+    pub arrayLayerCount: u32,
+    pub aspect: WGPUTextureAspect,
+}
+
+#[repr(C)]
+pub struct WGPUSamplerDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub addressModeU: WGPUAddressMode,
+    pub addressModeV: WGPUAddressMode,
+    pub addressModeW: WGPUAddressMode,
+    pub magFilter: WGPUFilterMode,
+    pub minFilter: WGPUFilterMode,
+    pub mipmapFilter: WGPUFilterMode,
+    pub lodMinClamp: f32,
+    pub lodMaxClamp: f32,
+    pub compare: WGPUCompareFunction,
+    pub maxAnisotropy: u16,
+}
+
+#[repr(C)]
+pub struct WGPUShaderModuleDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+}
+
+#[repr(C)]
+pub struct WGPUShaderModuleSPIRVDescriptor {
+    pub chain: WGPUChainedStruct,
+    pub codeSize: u32,
+    pub code: *const u32,
+}
+
+#[repr(C)]
+pub struct WGPUShaderModuleWGSLDescriptor {
+    pub chain: WGPUChainedStruct,
+    pub source: *const c_char,
+}
+
+#[repr(C)]
+pub struct WGPUBufferBindingLayout {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub r#type: WGPUBufferBindingType,
+    pub hasDynamicOffset: bool,
+    pub minBindingSize: u64,
+}
+
+#[repr(C)]
+pub struct WGPUSamplerBindingLayout {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub r#type: WGPUSamplerBindingType,
+}
+
+#[repr(C)]
+pub struct WGPUTextureBindingLayout {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub sampleType: WGPUTextureSampleType,
+    pub viewDimension: WGPUTextureViewDimension,
+    pub multisampled: bool,
+}
+
+#[repr(C)]
+pub struct WGPUStorageTextureBindingLayout {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub access: WGPUStorageTextureAccess,
+    pub format: WGPUTextureFormat,
+    pub viewDimension: WGPUTextureViewDimension,
+}
+
+/// Exactly one of the binding layouts has to be defined,
+/// the others are left zero-initialized.
+#[repr(C)]
+pub struct WGPUBindGroupLayoutEntry {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub binding: u32,
+    pub visibility: WGPUShaderStageFlags,
+    pub buffer: WGPUBufferBindingLayout,
+    pub sampler: WGPUSamplerBindingLayout,
+    pub texture: WGPUTextureBindingLayout,
+    pub storageTexture: WGPUStorageTextureBindingLayout,
+}
+
+#[repr(C)]
+pub struct WGPUBindGroupLayoutDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub entryCount: u32,
+    pub entries: *const WGPUBindGroupLayoutEntry,
+}
+
+#[repr(C)]
+pub struct WGPUPipelineLayoutDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub bindGroupLayoutCount: u32,
+    pub bindGroupLayouts: *const WGPUBindGroupLayout,
+}
+
+/// Exactly one of `buffer`, `sampler` and `textureView` has to be non-null.
+#[repr(C)]
+pub struct WGPUBindGroupEntry {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub binding: u32,
+    pub buffer: WGPUBuffer,
+    pub offset: u64,
+    pub size: u64,
+    pub sampler: WGPUSampler,
+    pub textureView: WGPUTextureView,
+}
+
+#[repr(C)]
+pub struct WGPUBindGroupDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub layout: WGPUBindGroupLayout,
+    pub entryCount: u32,
+    pub entries: *const WGPUBindGroupEntry,
+}
+
+#[repr(C)]
+pub struct WGPUProgrammableStageDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub module: WGPUShaderModule,
+    pub entryPoint: *const c_char,
+}
+
+/// A null `layout` requests a layout derived from the shader.
+#[repr(C)]
+pub struct WGPUComputePipelineDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub layout: WGPUPipelineLayout,
+    pub compute: WGPUProgrammableStageDescriptor,
+}
+
+#[repr(C)]
+pub struct WGPUVertexAttribute {
+    pub format: WGPUVertexFormat,
+    pub offset: u64,
+    pub shaderLocation: u32,
+}
+
+#[repr(C)]
+pub struct WGPUVertexBufferLayout {
+    pub arrayStride: u64,
+    pub stepMode: WGPUInputStepMode,
+    pub attributeCount: u32,
+    pub attributes: *const WGPUVertexAttribute,
+}
+
+#[repr(C)]
+pub struct WGPUVertexState {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub module: WGPUShaderModule,
+    pub entryPoint: *const c_char,
+    pub bufferCount: u32,
+    pub buffers: *const WGPUVertexBufferLayout,
+}
+
+#[repr(C)]
+pub struct WGPUPrimitiveState {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub topology: WGPUPrimitiveTopology,
+    pub stripIndexFormat: WGPUIndexFormat,
+    pub frontFace: WGPUFrontFace,
+    pub cullMode: WGPUCullMode,
+}
+
+#[repr(C)]
+pub struct WGPUStencilFaceState {
+    pub compare: WGPUCompareFunction,
+    pub failOp: WGPUStencilOperation,
+    pub depthFailOp: WGPUStencilOperation,
+    pub passOp: WGPUStencilOperation,
+}
+
+#[repr(C)]
+pub struct WGPUDepthStencilState {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub format: WGPUTextureFormat,
+    pub depthWriteEnabled: bool,
+    pub depthCompare: WGPUCompareFunction,
+    pub stencilFront: WGPUStencilFaceState,
+    pub stencilBack: WGPUStencilFaceState,
+    pub stencilReadMask: u32,
+    pub stencilWriteMask: u32,
+    pub depthBias: i32,
+    pub depthBiasSlopeScale: f32,
+    pub depthBiasClamp: f32,
+}
+
+#[repr(C)]
+pub struct WGPUMultisampleState {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub count: u32,
+    pub mask: u32,
+    pub alphaToCoverageEnabled: bool,
+}
+
+#[repr(C)]
+pub struct WGPUBlendComponent {
+    pub srcFactor: WGPUBlendFactor,
+    pub dstFactor: WGPUBlendFactor,
+    pub operation: WGPUBlendOperation,
+}
+
+#[repr(C)]
+pub struct WGPUBlendState {
+    pub color: WGPUBlendComponent,
+    pub alpha: WGPUBlendComponent,
+}
+
+#[repr(C)]
+pub struct WGPUColorTargetState {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub format: WGPUTextureFormat,
+    pub blend: *const WGPUBlendState,
+    pub writeMask: WGPUColorWriteMaskFlags,
+}
+
+#[repr(C)]
+pub struct WGPUFragmentState {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub module: WGPUShaderModule,
+    pub entryPoint: *const c_char,
+    pub targetCount: u32,
+    pub targets: *const WGPUColorTargetState,
+}
+
+/// A null `layout` requests a layout derived from the shaders.
+#[repr(C)]
+pub struct WGPURenderPipelineDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub layout: WGPUPipelineLayout,
+    pub vertex: WGPUVertexState,
+    pub primitive: WGPUPrimitiveState,
+    pub depthStencil: *const WGPUDepthStencilState,
+    pub multisample: WGPUMultisampleState,
+    pub fragment: *const WGPUFragmentState,
+}
+
+#[repr(C)]
+pub struct WGPUCommandEncoderDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+}
+
+#[repr(C)]
+pub struct WGPUCommandBufferDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+}
+
+#[repr(C)]
+pub struct WGPUComputePassDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct WGPUColor {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+#[repr(C)]
+pub struct WGPURenderPassColorAttachment {
+    pub view: WGPUTextureView,
+    pub resolveTarget: WGPUTextureView,
+    pub loadOp: WGPULoadOp,
+    pub storeOp: WGPUStoreOp,
+    pub clearColor: WGPUColor,
+}
+
+#[repr(C)]
+pub struct WGPURenderPassDepthStencilAttachment {
+    pub view: WGPUTextureView,
+    pub depthLoadOp: WGPULoadOp,
+    pub depthStoreOp: WGPUStoreOp,
+    pub clearDepth: f32,
+    pub depthReadOnly: bool,
+    pub stencilLoadOp: WGPULoadOp,
+    pub stencilStoreOp: WGPUStoreOp,
+    pub clearStencil: u32,
+    pub stencilReadOnly: bool,
+}
+
+#[repr(C)]
+pub struct WGPURenderPassDescriptor {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub label: *const c_char,
+    pub colorAttachmentCount: u32,
+    pub colorAttachments: *const WGPURenderPassColorAttachment,
+    pub depthStencilAttachment: *const WGPURenderPassDepthStencilAttachment,
+}
+
+#[repr(C)]
+pub struct WGPUTextureDataLayout {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub offset: u64,
+    pub bytesPerRow: u32,
+    pub rowsPerImage: u32,
+}
+
+#[repr(C)]
+pub struct WGPUImageCopyBuffer {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub layout: WGPUTextureDataLayout,
+    pub buffer: WGPUBuffer,
+}
+
+#[repr(C)]
+pub struct WGPUImageCopyTexture {
+    pub nextInChain: *const WGPUChainedStruct,
+    pub texture: WGPUTexture,
+    pub mipLevel: u32,
+    pub origin: WGPUOrigin3D,
+    pub aspect: WGPUTextureAspect,
+}
+
+pub type WGPURequestAdapterCallback = Option<
+    unsafe extern "C" fn(
+        status: WGPURequestAdapterStatus,
+        adapter: super::WGPUAdapter,
+        message: *const c_char,
+        userdata: *mut c_void,
+    ),
+>;
+
+pub type WGPURequestDeviceCallback = Option<
+    unsafe extern "C" fn(
+        status: WGPURequestDeviceStatus,
+        device: super::WGPUDevice,
+        message: *const c_char,
+        userdata: *mut c_void,
+    ),
+>;
+
+pub type WGPUErrorCallback = Option<
+    unsafe extern "C" fn(error_type: WGPUErrorType, message: *const c_char, userdata: *mut c_void),
+>;
+
+pub type WGPUBufferMapCallback =
+    Option<unsafe extern "C" fn(status: WGPUBufferMapAsyncStatus, userdata: *mut c_void)>;
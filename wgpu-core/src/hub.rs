@@ -21,13 +21,14 @@ use crate::{
 };
 
 use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use thiserror::Error;
 use wgt::Backend;
 
 use crate::id::QuerySetId;
 use crate::resource::QuerySet;
 #[cfg(debug_assertions)]
 use std::cell::Cell;
-use std::{fmt::Debug, marker::PhantomData, ops, thread};
+use std::{fmt::Debug, marker::PhantomData, ops, sync::Arc, thread};
 
 /// A simple structure to manage identities of objects.
 #[derive(Debug)]
@@ -85,8 +86,23 @@ enum Element<T> {
     Error(Epoch, String),
 }
 
-#[derive(Clone, Debug)]
-pub(crate) struct InvalidId;
+/// Error of accessing a resource by an id that doesn't refer to a valid resource.
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum InvalidId {
+    #[error("{kind}[{index}] failed to be created")]
+    Failed { kind: &'static str, index: Index },
+    #[error("{kind}[{index}] does not exist")]
+    Vacant { kind: &'static str, index: Index },
+    #[error(
+        "{kind}[{index}] is no longer alive: epoch {epoch} is used instead of {storage_epoch}"
+    )]
+    Stale {
+        kind: &'static str,
+        index: Index,
+        epoch: Epoch,
+        storage_epoch: Epoch,
+    },
+}
 
 #[derive(Debug)]
 pub struct Storage<T, I: TypedId> {
@@ -111,52 +127,63 @@ impl<T, I: TypedId> ops::IndexMut<Valid<I>> for Storage<T, I> {
 impl<T, I: TypedId> Storage<T, I> {
     pub(crate) fn contains(&self, id: I) -> bool {
         let (index, epoch, _) = id.unzip();
-        match self.map[index as usize] {
-            Element::Vacant => false,
-            Element::Occupied(_, storage_epoch) | Element::Error(storage_epoch, ..) => {
-                epoch == storage_epoch
-            }
+        match self.map.get(index as usize) {
+            None | Some(&Element::Vacant) => false,
+            Some(&Element::Occupied(_, storage_epoch))
+            | Some(&Element::Error(storage_epoch, ..)) => epoch == storage_epoch,
         }
     }
 
-    /// Get a reference to an item behind a potentially invalid ID.
-    /// Panics if there is an epoch mismatch, or the entry is empty.
-    pub(crate) fn get(&self, id: I) -> Result<&T, InvalidId> {
+    /// Checks that the entry behind an ID is occupied by the same epoch,
+    /// returning the index of the entry.
+    ///
+    /// Vacant entries and epoch mismatches mean that the ID is dangling,
+    /// which is a bug on the user side, so they are also logged.
+    fn check(&self, id: I) -> Result<usize, InvalidId> {
         let (index, epoch, _) = id.unzip();
-        let (result, storage_epoch) = match self.map[index as usize] {
-            Element::Occupied(ref v, epoch) => (Ok(v), epoch),
-            Element::Vacant => panic!("{}[{}] does not exist", self.kind, index),
-            Element::Error(epoch, ..) => (Err(InvalidId), epoch),
+        let kind = self.kind;
+        let error = match self.map.get(index as usize) {
+            None | Some(&Element::Vacant) => InvalidId::Vacant { kind, index },
+            Some(&Element::Occupied(_, storage_epoch))
+            | Some(&Element::Error(storage_epoch, ..))
+                if epoch != storage_epoch =>
+            {
+                InvalidId::Stale {
+                    kind,
+                    index,
+                    epoch,
+                    storage_epoch,
+                }
+            }
+            Some(&Element::Error(..)) => return Err(InvalidId::Failed { kind, index }),
+            Some(&Element::Occupied(..)) => return Ok(index as usize),
         };
-        assert_eq!(
-            epoch, storage_epoch,
-            "{}[{}] is no longer alive",
-            self.kind, index
-        );
-        result
+        tracing::error!("{}", error);
+        Err(error)
+    }
+
+    /// Get a reference to an item behind a potentially invalid ID.
+    pub(crate) fn get(&self, id: I) -> Result<&T, InvalidId> {
+        let index = self.check(id)?;
+        match self.map[index] {
+            Element::Occupied(ref v, _) => Ok(v),
+            _ => unreachable!(),
+        }
     }
 
     /// Get a mutable reference to an item behind a potentially invalid ID.
-    /// Panics if there is an epoch mismatch, or the entry is empty.
     pub(crate) fn get_mut(&mut self, id: I) -> Result<&mut T, InvalidId> {
-        let (index, epoch, _) = id.unzip();
-        let (result, storage_epoch) = match self.map[index as usize] {
-            Element::Occupied(ref mut v, epoch) => (Ok(v), epoch),
-            Element::Vacant => panic!("{}[{}] does not exist", self.kind, index),
-            Element::Error(epoch, ..) => (Err(InvalidId), epoch),
-        };
-        assert_eq!(
-            epoch, storage_epoch,
-            "{}[{}] is no longer alive",
-            self.kind, index
-        );
-        result
+        let index = self.check(id)?;
+        match self.map[index] {
+            Element::Occupied(ref mut v, _) => Ok(v),
+            _ => unreachable!(),
+        }
     }
 
     pub(crate) fn label_for_invalid_id(&self, id: I) -> &str {
         let (index, _, _) = id.unzip();
-        match self.map[index as usize] {
-            Element::Error(_, ref label) => label,
+        match self.map.get(index as usize) {
+            Some(&Element::Error(_, ref label)) => label,
             _ => "",
         }
     }
@@ -181,30 +208,29 @@ impl<T, I: TypedId> Storage<T, I> {
         self.insert_impl(index as usize, Element::Error(epoch, label.to_string()))
     }
 
-    pub(crate) fn remove(&mut self, id: I) -> Option<T> {
-        let (index, epoch, _) = id.unzip();
-        match std::mem::replace(&mut self.map[index as usize], Element::Vacant) {
-            Element::Occupied(value, storage_epoch) => {
-                assert_eq!(epoch, storage_epoch);
-                Some(value)
-            }
-            Element::Error(..) => None,
-            Element::Vacant => panic!("Cannot remove a vacant resource"),
+    /// Removes the entry behind an ID, which is either a valid resource or an error.
+    pub(crate) fn remove(&mut self, id: I) -> Result<Option<T>, InvalidId> {
+        let index = match self.check(id) {
+            Ok(index) => index,
+            Err(InvalidId::Failed { index, .. }) => index as usize,
+            Err(e) => return Err(e),
+        };
+        match std::mem::replace(&mut self.map[index], Element::Vacant) {
+            Element::Occupied(value, _) => Ok(Some(value)),
+            _ => Ok(None),
         }
     }
 
     // Prevents panic on out of range access, allows Vacant elements.
     pub(crate) fn try_remove(&mut self, id: I) -> Option<T> {
         let (index, epoch, _) = id.unzip();
-        if index as usize >= self.map.len() {
-            None
-        } else if let Element::Occupied(value, storage_epoch) =
-            std::mem::replace(&mut self.map[index as usize], Element::Vacant)
-        {
-            assert_eq!(epoch, storage_epoch);
-            Some(value)
-        } else {
-            None
+        match self.map.get(index as usize) {
+            Some(&Element::Occupied(_, storage_epoch)) if epoch == storage_epoch => {}
+            _ => return None,
+        }
+        match std::mem::replace(&mut self.map[index as usize], Element::Vacant) {
+            Element::Occupied(value, _) => Some(value),
+            _ => unreachable!(),
         }
     }
 
@@ -219,6 +245,18 @@ impl<T, I: TypedId> Storage<T, I> {
                 _ => None,
             })
     }
+
+    pub(crate) fn iter_mut(&mut self, backend: Backend) -> impl Iterator<Item = (I, &mut T)> {
+        self.map
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(index, x)| match *x {
+                Element::Occupied(ref mut value, storage_epoch) => {
+                    Some((I::zip(index as Index, storage_epoch, backend), value))
+                }
+                _ => None,
+            })
+    }
 }
 
 /// Type system for enforcing the lock order on shared HUB structures.
@@ -491,7 +529,8 @@ impl<T: Resource, I: TypedId + Copy, F: IdentityHandlerFactory<I>> Registry<T, I
     }
 
     pub fn unregister_locked(&self, id: I, guard: &mut Storage<T, I>) -> Option<T> {
-        let value = guard.remove(id);
+        // Dangling IDs are not freed again, they don't belong to the storage any more.
+        let value = guard.remove(id).ok()?;
         //Note: careful about the order here!
         self.identity.free(id);
         //Returning None is legal if it's an error ID
@@ -503,10 +542,7 @@ impl<T: Resource, I: TypedId + Copy, F: IdentityHandlerFactory<I>> Registry<T, I
         id: I,
         _token: &'a mut Token<A>,
     ) -> (Option<T>, Token<'a, T>) {
-        let value = self.data.write().remove(id);
-        //Note: careful about the order here!
-        self.identity.free(id);
-        //Returning None is legal if it's an error ID
+        let value = self.unregister_locked(id, &mut *self.data.write());
         (value, Token::new())
     }
 
@@ -592,10 +628,10 @@ impl<B: GfxBackend, F: GlobalIdentityHandlerFactory> Hub<B, F> {
 
         for element in self.samplers.data.write().map.drain(..) {
             if let Element::Occupied(sampler, _) = element {
-                unsafe {
-                    devices[sampler.device_id.value]
-                        .raw
-                        .destroy_sampler(sampler.raw);
+                if let Ok(raw) = Arc::try_unwrap(sampler.raw) {
+                    unsafe {
+                        devices[sampler.device_id.value].raw.destroy_sampler(raw);
+                    }
                 }
             }
         }
@@ -629,7 +665,7 @@ impl<B: GfxBackend, F: GlobalIdentityHandlerFactory> Hub<B, F> {
         }
         for element in self.command_buffers.data.write().map.drain(..) {
             if let Element::Occupied(command_buffer, _) = element {
-                let device = &devices[command_buffer.device_id.value];
+                let device = &devices[command_buffer.data.get_mut().device_id.value];
                 device
                     .cmd_allocator
                     .after_submit(command_buffer, &device.raw, 0);
@@ -661,8 +697,10 @@ impl<B: GfxBackend, F: GlobalIdentityHandlerFactory> Hub<B, F> {
         for element in self.pipeline_layouts.data.write().map.drain(..) {
             if let Element::Occupied(pipeline_layout, _) = element {
                 let device = &devices[pipeline_layout.device_id.value];
-                unsafe {
-                    device.raw.destroy_pipeline_layout(pipeline_layout.raw);
+                if let Ok(raw) = Arc::try_unwrap(pipeline_layout.raw) {
+                    unsafe {
+                        device.raw.destroy_pipeline_layout(raw);
+                    }
                 }
             }
         }
@@ -703,9 +741,11 @@ impl<B: GfxBackend, F: GlobalIdentityHandlerFactory> Hub<B, F> {
 
         for element in self.query_sets.data.write().map.drain(..) {
             if let Element::Occupied(query_set, _) = element {
-                let device = &devices[query_set.device_id.value];
-                unsafe {
-                    device.raw.destroy_query_pool(query_set.raw);
+                if let Some(raw) = query_set.raw {
+                    let device = &devices[query_set.device_id.value];
+                    unsafe {
+                        device.raw.destroy_query_pool(raw);
+                    }
                 }
             }
         }
@@ -881,3 +921,89 @@ fn _test_send_sync(global: &Global<IdentityManagerFactory>) {
     fn test_internal<T: Send + Sync>(_: T) {}
     test_internal(global)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{InvalidId, Storage};
+    use crate::id::{BufferId, TypedId as _};
+    use std::marker::PhantomData;
+    use wgt::Backend;
+
+    fn storage() -> Storage<u32, BufferId> {
+        Storage {
+            map: Vec::new(),
+            kind: "Buffer",
+            _phantom: PhantomData,
+        }
+    }
+
+    #[test]
+    fn use_after_drop() {
+        let mut storage = storage();
+        let id = BufferId::zip(1, 1, Backend::Vulkan);
+        storage.insert(id, 5);
+        assert_eq!(storage.get(id), Ok(&5));
+        assert_eq!(storage.remove(id), Ok(Some(5)));
+
+        let vacant = InvalidId::Vacant {
+            kind: "Buffer",
+            index: 1,
+        };
+        assert_eq!(storage.get(id), Err(vacant.clone()));
+        assert_eq!(storage.get_mut(id), Err(vacant.clone()));
+        assert_eq!(storage.remove(id), Err(vacant));
+        assert!(!storage.contains(id));
+        assert_eq!(storage.try_remove(id), None);
+    }
+
+    #[test]
+    fn stale_epoch() {
+        let mut storage = storage();
+        let old = BufferId::zip(0, 1, Backend::Vulkan);
+        let new = BufferId::zip(0, 2, Backend::Vulkan);
+        storage.insert(new, 7);
+
+        let error = storage.get(old).unwrap_err();
+        assert_eq!(
+            error,
+            InvalidId::Stale {
+                kind: "Buffer",
+                index: 0,
+                epoch: 1,
+                storage_epoch: 2,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Buffer[0] is no longer alive: epoch 1 is used instead of 2"
+        );
+        assert!(storage.remove(old).is_err());
+        assert_eq!(storage.try_remove(old), None);
+        // the live resource is untouched
+        assert_eq!(storage.get(new), Ok(&7));
+    }
+
+    #[test]
+    fn out_of_range_and_failed() {
+        let mut storage = storage();
+        let missing = BufferId::zip(10, 1, Backend::Vulkan);
+        assert_eq!(
+            storage.get(missing).unwrap_err().to_string(),
+            "Buffer[10] does not exist"
+        );
+        assert_eq!(storage.label_for_invalid_id(missing), "");
+
+        let failed = BufferId::zip(2, 1, Backend::Vulkan);
+        storage.insert_error(failed, "broken");
+        assert_eq!(
+            storage.get(failed),
+            Err(InvalidId::Failed {
+                kind: "Buffer",
+                index: 2,
+            })
+        );
+        assert_eq!(storage.label_for_invalid_id(failed), "broken");
+        assert_eq!(storage.remove(failed), Ok(None));
+        assert!(!storage.contains(failed));
+    }
+}
@@ -0,0 +1,254 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! CPU-side knowledge of index buffer contents, used by
+//! `Features::ROBUST_INDEX_VALIDATION`.
+//!
+//! Index buffers created on a device with the feature keep a copy of every
+//! byte the CPU writes into them (through `queue_write_buffer`, a mapping
+//! at creation, or a write mapping). Writes done by the GPU (copies and
+//! query resolves) only mark the written range as unknown. Draws reading
+//! unknown indices are checked against the largest index representable by
+//! the format, so they need vertex buffers covering all of them.
+//!
+//! Indexed draws don't check anything while recording. They push an
+//! `IndexTrackerAction::Validate` that is resolved at submission time, so
+//! that the checked contents are the ones the GPU will actually read.
+
+use crate::{id::BufferId, FastHashMap};
+use std::ops::Range;
+
+#[derive(Debug, Clone)]
+pub(crate) struct IndexDrawValidation {
+    pub(crate) buffer: BufferId,
+    /// Byte offset of the first index read by the draw.
+    pub(crate) offset: wgt::BufferAddress,
+    pub(crate) count: u32,
+    pub(crate) format: wgt::IndexFormat,
+    /// The pipeline uses a strip topology, so the all-ones index restarts
+    /// the primitive instead of referencing a vertex.
+    pub(crate) primitive_restart: bool,
+    pub(crate) base_vertex: i32,
+    /// Length of the shortest vertex rate vertex buffer.
+    pub(crate) vertex_limit: u32,
+    /// Buffer slot which the shortest vertex rate vertex buffer is bound to.
+    pub(crate) vertex_limit_slot: u32,
+}
+
+impl IndexDrawValidation {
+    /// Checks that the draw doesn't reference a vertex beyond `vertex_limit`,
+    /// returning the largest index it may read otherwise.
+    pub(crate) fn check(&self, tracker: Option<&mut IndexTracker>) -> Result<(), u32> {
+        if self.count == 0 {
+            return Ok(());
+        }
+        let range = IndexRange {
+            offset: self.offset,
+            count: self.count,
+            format: self.format,
+            primitive_restart: self.primitive_restart,
+        };
+        let max_index = match tracker {
+            Some(tracker) => tracker.max_index(range),
+            None => range.max_representable(),
+        };
+        if self.base_vertex as i64 + max_index as i64 >= self.vertex_limit as i64 {
+            Err(max_index)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum IndexTrackerAction {
+    /// An indexed draw reads a range of an index buffer.
+    Validate(IndexDrawValidation),
+    /// The GPU writes a range of an index buffer.
+    Invalidate {
+        buffer: BufferId,
+        range: Range<wgt::BufferAddress>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+struct IndexRange {
+    offset: wgt::BufferAddress,
+    count: u32,
+    format: wgt::IndexFormat,
+    primitive_restart: bool,
+}
+
+impl IndexRange {
+    fn byte_range(&self) -> Range<wgt::BufferAddress> {
+        let size = match self.format {
+            wgt::IndexFormat::Uint16 => 2,
+            wgt::IndexFormat::Uint32 => 4,
+        };
+        self.offset..self.offset + self.count as wgt::BufferAddress * size
+    }
+
+    /// Conservative bound for indices whose value is unknown.
+    fn max_representable(&self) -> u32 {
+        let max = match self.format {
+            wgt::IndexFormat::Uint16 => u16::MAX as u32,
+            wgt::IndexFormat::Uint32 => u32::MAX,
+        };
+        if self.primitive_restart {
+            max - 1
+        } else {
+            max
+        }
+    }
+}
+
+/// Tracks the contents of an index buffer from 0..size.
+#[derive(Debug)]
+pub(crate) struct IndexTracker {
+    data: Vec<u8>,
+    // Ordered, non overlapping list of ranges written by the GPU.
+    unknown_ranges: Vec<Range<wgt::BufferAddress>>,
+    // Largest index of every range validated since the last write.
+    max_indices: FastHashMap<IndexRange, u32>,
+}
+
+impl IndexTracker {
+    /// Buffers are zero-initialized, so the whole content starts as known.
+    pub(crate) fn new(size: wgt::BufferAddress) -> Self {
+        Self {
+            data: vec![0; size as usize],
+            unknown_ranges: Vec::new(),
+            max_indices: FastHashMap::default(),
+        }
+    }
+
+    pub(crate) fn write(&mut self, offset: wgt::BufferAddress, data: &[u8]) {
+        let end = offset + data.len() as wgt::BufferAddress;
+        self.data[offset as usize..end as usize].copy_from_slice(data);
+        self.remove_unknown(offset..end);
+        self.max_indices.clear();
+    }
+
+    pub(crate) fn invalidate(&mut self, range: Range<wgt::BufferAddress>) {
+        if range.start >= range.end {
+            return;
+        }
+        self.remove_unknown(range.clone());
+        let index = self
+            .unknown_ranges
+            .iter()
+            .position(|r| r.start > range.start)
+            .unwrap_or(self.unknown_ranges.len());
+        self.unknown_ranges.insert(index, range);
+        self.max_indices.clear();
+    }
+
+    fn remove_unknown(&mut self, range: Range<wgt::BufferAddress>) {
+        let mut remaining = Vec::with_capacity(self.unknown_ranges.len() + 1);
+        for r in self.unknown_ranges.drain(..) {
+            if r.end <= range.start || r.start >= range.end {
+                remaining.push(r);
+                continue;
+            }
+            if r.start < range.start {
+                remaining.push(r.start..range.start);
+            }
+            if r.end > range.end {
+                remaining.push(range.end..r.end);
+            }
+        }
+        self.unknown_ranges = remaining;
+    }
+
+    fn is_known(&self, range: &Range<wgt::BufferAddress>) -> bool {
+        self.unknown_ranges
+            .iter()
+            .all(|r| r.end <= range.start || r.start >= range.end)
+    }
+
+    fn max_index(&mut self, range: IndexRange) -> u32 {
+        if let Some(&max) = self.max_indices.get(&range) {
+            return max;
+        }
+        let bytes = range.byte_range();
+        if bytes.end > self.data.len() as wgt::BufferAddress || !self.is_known(&bytes) {
+            return range.max_representable();
+        }
+        let data = &self.data[bytes.start as usize..bytes.end as usize];
+        let max = match range.format {
+            wgt::IndexFormat::Uint16 => data
+                .chunks_exact(2)
+                .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+                .filter(|&index| !(range.primitive_restart && index == u16::MAX))
+                .max()
+                .map_or(0, |index| index as u32),
+            wgt::IndexFormat::Uint32 => data
+                .chunks_exact(4)
+                .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                .filter(|&index| !(range.primitive_restart && index == u32::MAX))
+                .max()
+                .unwrap_or(0),
+        };
+        self.max_indices.insert(range, max);
+        max
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IndexRange, IndexTracker};
+
+    fn range(offset: u64, count: u32, primitive_restart: bool) -> IndexRange {
+        IndexRange {
+            offset,
+            count,
+            format: wgt::IndexFormat::Uint16,
+            primitive_restart,
+        }
+    }
+
+    fn write_u16(tracker: &mut IndexTracker, offset: u64, indices: &[u16]) {
+        let data = indices
+            .iter()
+            .flat_map(|index| index.to_ne_bytes().to_vec())
+            .collect::<Vec<_>>();
+        tracker.write(offset, &data);
+    }
+
+    #[test]
+    fn max_of_written_ranges() {
+        let mut tracker = IndexTracker::new(16);
+        assert_eq!(tracker.max_index(range(0, 8, false)), 0);
+        write_u16(&mut tracker, 0, &[0, 1, 2, 7, 3, 4, 5, 6]);
+        assert_eq!(tracker.max_index(range(0, 8, false)), 7);
+        assert_eq!(tracker.max_index(range(8, 4, false)), 6);
+        write_u16(&mut tracker, 6, &[9]);
+        assert_eq!(tracker.max_index(range(0, 8, false)), 9);
+    }
+
+    #[test]
+    fn primitive_restart_is_ignored() {
+        let mut tracker = IndexTracker::new(8);
+        write_u16(&mut tracker, 0, &[0, 1, u16::MAX, 2]);
+        assert_eq!(tracker.max_index(range(0, 4, false)), u16::MAX as u32);
+        assert_eq!(tracker.max_index(range(0, 4, true)), 2);
+    }
+
+    #[test]
+    fn unknown_ranges_are_conservative() {
+        let mut tracker = IndexTracker::new(16);
+        write_u16(&mut tracker, 0, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        tracker.invalidate(4..12);
+        assert_eq!(tracker.max_index(range(0, 2, false)), 2);
+        assert_eq!(tracker.max_index(range(0, 4, false)), u16::MAX as u32);
+        assert_eq!(tracker.max_index(range(6, 2, true)), u16::MAX as u32 - 1);
+        assert_eq!(tracker.max_index(range(12, 2, false)), 8);
+
+        write_u16(&mut tracker, 4, &[1, 1]);
+        assert_eq!(tracker.max_index(range(0, 4, false)), 2);
+        assert_eq!(tracker.max_index(range(0, 6, false)), u16::MAX as u32);
+        write_u16(&mut tracker, 8, &[1, 1]);
+        assert_eq!(tracker.max_index(range(0, 8, false)), 8);
+    }
+}
@@ -135,7 +135,8 @@ impl<B: GfxBackend> Adapter<B> {
         let mut features = wgt::Features::default()
             | wgt::Features::MAPPABLE_PRIMARY_BUFFERS
             | wgt::Features::PUSH_CONSTANTS
-            | wgt::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+            | wgt::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            | wgt::Features::ROBUST_INDEX_VALIDATION;
         features.set(
             wgt::Features::DEPTH_CLAMPING,
             adapter_features.contains(hal::Features::DEPTH_CLAMP),
@@ -192,10 +193,37 @@ impl<B: GfxBackend> Adapter<B> {
             wgt::Features::SHADER_FLOAT64,
             adapter_features.contains(hal::Features::SHADER_FLOAT64),
         );
+        features.set(
+            wgt::Features::SPIRV_SHADER_PASSTHROUGH,
+            B::VARIANT == wgt::Backend::Vulkan,
+        );
         features.set(
             wgt::Features::CONSERVATIVE_RASTERIZATION,
             adapter_features.contains(hal::Features::CONSERVATIVE_RASTERIZATION),
         );
+        features.set(
+            wgt::Features::DEPTH32FLOAT_STENCIL8,
+            raw.physical_device
+                .format_properties(Some(hal::format::Format::D32SfloatS8Uint))
+                .optimal_tiling
+                .contains(hal::format::ImageFeature::DEPTH_STENCIL_ATTACHMENT),
+        );
+        features.set(wgt::Features::TEXTURE_FORMAT_16BIT_NORM, {
+            use hal::format::{Format as H, ImageFeature as If};
+            let supports = |format: H, features: If| {
+                raw.physical_device
+                    .format_properties(Some(format))
+                    .optimal_tiling
+                    .contains(features)
+            };
+            let sampled = If::SAMPLED | If::SAMPLED_LINEAR;
+            [H::R16Unorm, H::Rg16Unorm, H::Rgba16Unorm]
+                .iter()
+                .all(|&format| supports(format, sampled | If::COLOR_ATTACHMENT))
+                && [H::R16Snorm, H::Rg16Snorm, H::Rgba16Snorm]
+                    .iter()
+                    .all(|&format| supports(format, sampled))
+        });
         #[cfg(not(target_os = "ios"))]
         //TODO: https://github.com/gfx-rs/gfx/issues/3346
         features.set(wgt::Features::ADDRESS_MODE_CLAMP_TO_BORDER, true);
@@ -212,6 +240,21 @@ impl<B: GfxBackend> Adapter<B> {
                 .format_properties(Some(hal::format::Format::D24UnormS8Uint))
                 .optimal_tiling
                 .contains(hal::format::ImageFeature::DEPTH_STENCIL_ATTACHMENT),
+            texture_s8: raw
+                .physical_device
+                .format_properties(Some(hal::format::Format::S8Uint))
+                .optimal_tiling
+                .contains(hal::format::ImageFeature::DEPTH_STENCIL_ATTACHMENT),
+            unified_memory: {
+                use hal::memory::Properties as P;
+                let memory_types = raw.physical_device.memory_properties().memory_types;
+                memory_types
+                    .iter()
+                    .all(|ty| ty.properties.contains(P::DEVICE_LOCAL))
+                    && memory_types
+                        .iter()
+                        .any(|ty| ty.properties.contains(P::CPU_VISIBLE))
+            },
         };
 
         let default_limits = wgt::Limits::default();
@@ -371,6 +414,7 @@ impl<B: GfxBackend> Adapter<B> {
         self_id: AdapterId,
         desc: &DeviceDescriptor,
         trace_path: Option<&std::path::Path>,
+        traceable: bool,
     ) -> Result<Device<B>, RequestDeviceError> {
         // Verify all features were exposed by the adapter
         if !self.features.contains(desc.features) {
@@ -525,6 +569,7 @@ impl<B: GfxBackend> Adapter<B> {
             self.private_features,
             desc,
             trace_path,
+            traceable,
         )
         .or(Err(RequestDeviceError::OutOfMemory))
     }
@@ -667,7 +712,9 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         span!(_guard, INFO, "Surface::drop");
         let mut token = Token::root();
         let (surface, _) = self.surfaces.unregister(id, &mut token);
-        self.instance.destroy_surface(surface.unwrap());
+        if let Some(surface) = surface {
+            self.instance.destroy_surface(surface);
+        }
     }
 
     pub fn enumerate_adapters(&self, inputs: AdapterInputs<Input<G, AdapterId>>) -> Vec<AdapterId> {
@@ -953,7 +1000,30 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         id_in: Input<G, DeviceId>,
     ) -> (DeviceId, Option<RequestDeviceError>) {
         span!(_guard, INFO, "Adapter::request_device");
+        self.adapter_request_device_impl::<B>(adapter_id, desc, trace_path, false, id_in)
+    }
 
+    /// Requests a device that keeps track of its live resources, so that
+    /// a trace can be started and stopped at any point of its lifetime.
+    #[cfg(feature = "trace")]
+    pub fn adapter_request_traceable_device<B: GfxBackend>(
+        &self,
+        adapter_id: AdapterId,
+        desc: &DeviceDescriptor,
+        id_in: Input<G, DeviceId>,
+    ) -> (DeviceId, Option<RequestDeviceError>) {
+        span!(_guard, INFO, "Adapter::request_traceable_device");
+        self.adapter_request_device_impl::<B>(adapter_id, desc, None, true, id_in)
+    }
+
+    fn adapter_request_device_impl<B: GfxBackend>(
+        &self,
+        adapter_id: AdapterId,
+        desc: &DeviceDescriptor,
+        trace_path: Option<&std::path::Path>,
+        traceable: bool,
+        id_in: Input<G, DeviceId>,
+    ) -> (DeviceId, Option<RequestDeviceError>) {
         let hub = B::hub(self);
         let mut token = Token::root();
         let fid = hub.devices.prepare(id_in);
@@ -964,7 +1034,7 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
                 Ok(adapter) => adapter,
                 Err(_) => break RequestDeviceError::InvalidAdapter,
             };
-            let device = match adapter.create_device(adapter_id, desc, trace_path) {
+            let device = match adapter.create_device(adapter_id, desc, trace_path, traceable) {
                 Ok(device) => device,
                 Err(e) => break e,
             };
@@ -45,8 +45,12 @@ pub mod binding_model;
 pub mod command;
 mod conv;
 pub mod device;
+mod dispatch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod hub;
 pub mod id;
+mod index_tracker;
 pub mod instance;
 mod memory_init_tracker;
 pub mod pipeline;
@@ -235,6 +239,9 @@ struct PrivateFeatures {
     anisotropic_filtering: bool,
     texture_d24: bool,
     texture_d24_s8: bool,
+    texture_s8: bool,
+    /// All the memory is device local, and some of it is host visible.
+    unified_memory: bool,
 }
 
 #[macro_export]
@@ -9,16 +9,45 @@ use crate::{
     id::{DeviceId, PipelineLayoutId, ShaderModuleId},
     validation, Label, LifeGuard, Stored,
 };
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Arc};
 use thiserror::Error;
 
 #[derive(Debug)]
 pub enum ShaderModuleSource<'a> {
     SpirV(Cow<'a, [u32]>),
     Wgsl(Cow<'a, str>),
+    /// GLSL source of a single shader stage, with `main` as its entry point.
+    Glsl {
+        code: Cow<'a, str>,
+        stage: naga::ShaderStage,
+        defines: naga::FastHashMap<String, String>,
+    },
     Naga(naga::Module),
 }
 
+/// Resource binding used by an entry point of a shader module.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "trace", derive(serde::Serialize))]
+#[cfg_attr(feature = "replay", derive(serde::Deserialize))]
+pub struct ShaderBinding {
+    pub group: u32,
+    pub binding: u32,
+    /// Type of the resource, as the shader accesses it. Read-only access is assumed for
+    /// uniform buffers, samplers and sampled textures.
+    pub ty: wgt::BindingType,
+}
+
+/// Declared interface of an entry point of a shader module that isn't reflected by `Naga`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "trace", derive(serde::Serialize))]
+#[cfg_attr(feature = "replay", derive(serde::Deserialize))]
+pub struct ShaderEntryPoint<'a> {
+    pub name: Cow<'a, str>,
+    /// A single stage of `VERTEX`, `FRAGMENT` or `COMPUTE`.
+    pub stage: wgt::ShaderStage,
+    pub bindings: Cow<'a, [ShaderBinding]>,
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "trace", derive(serde::Serialize))]
 #[cfg_attr(feature = "replay", derive(serde::Deserialize))]
@@ -26,13 +55,19 @@ pub struct ShaderModuleDescriptor<'a> {
     pub label: Label<'a>,
     #[cfg_attr(any(feature = "replay", feature = "trace"), serde(default))]
     pub flags: wgt::ShaderFlags,
+    /// Interface that pipelines are validated against, for modules created with
+    /// `ShaderFlags::SPIRV_PASSTHROUGH`. Ignored otherwise.
+    ///
+    /// Stage inputs and outputs of these modules are never validated.
+    #[cfg_attr(any(feature = "replay", feature = "trace"), serde(default))]
+    pub interface: Option<Cow<'a, [ShaderEntryPoint<'a>]>>,
 }
 
 #[derive(Debug)]
 pub struct ShaderModule<B: hal::Backend> {
     pub(crate) raw: B::ShaderModule,
     pub(crate) device_id: Stored<DeviceId>,
-    pub(crate) interface: Option<validation::Interface>,
+    pub(crate) interface: Option<Arc<validation::Interface>>,
     #[cfg(debug_assertions)]
     pub(crate) label: String,
 }
@@ -52,14 +87,18 @@ impl<B: hal::Backend> Resource for ShaderModule<B> {
     }
 }
 
-#[derive(Clone, Debug, Error)]
+#[derive(Debug, Error)]
 pub enum CreateShaderModuleError {
     #[error("Failed to parse WGSL")]
     Parsing,
+    #[error("failed to parse GLSL")]
+    ParsingGlsl(#[source] naga::front::glsl::ParseError),
     #[error(transparent)]
     Device(#[from] DeviceError),
     #[error(transparent)]
     Validation(#[from] naga::proc::ValidationError),
+    #[error(transparent)]
+    Interface(#[from] validation::InterfaceError),
     #[error("missing required device features {0:?}")]
     MissingFeature(wgt::Features),
 }
@@ -241,7 +280,8 @@ bitflags::bitflags! {
     pub struct PipelineFlags: u32 {
         const BLEND_COLOR = 1;
         const STENCIL_REFERENCE = 2;
-        const WRITES_DEPTH_STENCIL = 4;
+        const WRITES_DEPTH = 4;
+        const WRITES_STENCIL = 8;
     }
 }
 
@@ -4,8 +4,9 @@
 
 use crate::{
     device::{alloc::MemoryBlock, DeviceError, HostMap},
-    hub::Resource,
+    hub::{InvalidId, Resource},
     id::{DeviceId, SwapChainId, TextureId},
+    index_tracker::IndexTracker,
     memory_init_tracker::MemoryInitTracker,
     track::{TextureSelector, DUMMY_SELECTOR},
     validation::MissingBufferUsageError,
@@ -19,6 +20,7 @@ use std::{
     num::{NonZeroU32, NonZeroU8},
     ops::Range,
     ptr::NonNull,
+    sync::Arc,
 };
 
 bitflags::bitflags! {
@@ -58,7 +60,7 @@ bitflags::bitflags! {
         const ATTACHMENT_READ = 8;
         const ATTACHMENT_WRITE = 16;
         const STORAGE_LOAD = 32;
-        const STORAGE_STORE = 48;
+        const STORAGE_STORE = 64;
         /// The combination of all read-only usages.
         const READ_ALL = Self::COPY_SRC.bits | Self::SAMPLED.bits | Self::ATTACHMENT_READ.bits | Self::STORAGE_LOAD.bits;
         /// The combination of all write-only and read-write usages.
@@ -133,7 +135,7 @@ pub enum BufferAccessError {
     #[error(transparent)]
     Device(#[from] DeviceError),
     #[error("buffer is invalid")]
-    Invalid,
+    Invalid(#[source] InvalidId),
     #[error("buffer is destroyed")]
     Destroyed,
     #[error("buffer is already mapped")]
@@ -163,6 +165,8 @@ pub struct Buffer<B: hal::Backend> {
     pub(crate) usage: wgt::BufferUsage,
     pub(crate) size: wgt::BufferAddress,
     pub(crate) initialization_status: MemoryInitTracker,
+    /// Contents of an index buffer, when `Features::ROBUST_INDEX_VALIDATION` is enabled.
+    pub(crate) index_tracker: Option<IndexTracker>,
     pub(crate) sync_mapped_writes: Option<hal::memory::Segment>,
     pub(crate) life_guard: LifeGuard,
     pub(crate) map_state: BufferMapState<B>,
@@ -180,6 +184,8 @@ pub enum CreateBufferError {
     EmptyUsage,
     #[error("`MAP` usage can only be combined with the opposite `COPY`, requested {0:?}")]
     UsageMismatch(wgt::BufferUsage),
+    #[error("buffers created from hal objects can't be mapped")]
+    HalBufferMapping,
 }
 
 impl<B: hal::Backend> Resource for Buffer<B> {
@@ -213,6 +219,21 @@ pub struct Texture<B: hal::Backend> {
     pub(crate) life_guard: LifeGuard,
 }
 
+impl<B: hal::Backend> Texture<B> {
+    /// Hal aspects selected by `aspect`, which may not all be in `self.aspects`.
+    pub(crate) fn hal_aspects(&self, aspect: wgt::TextureAspect) -> hal::format::Aspects {
+        match aspect {
+            // `Stencil8` may be backed by a depth/stencil format.
+            wgt::TextureAspect::All if self.format == wgt::TextureFormat::Stencil8 => {
+                hal::format::Aspects::STENCIL
+            }
+            wgt::TextureAspect::All => self.aspects,
+            wgt::TextureAspect::DepthOnly => hal::format::Aspects::DEPTH,
+            wgt::TextureAspect::StencilOnly => hal::format::Aspects::STENCIL,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum TextureErrorDimension {
     X,
@@ -250,6 +271,8 @@ pub enum CreateTextureError {
     InvalidUsages(wgt::TextureUsage, wgt::TextureFormat),
     #[error("Feature {0:?} must be enabled to create a texture of type {1:?}")]
     MissingFeature(wgt::Features, wgt::TextureFormat),
+    #[error("hal textures can't be created in use {0:?}, which isn't a single ordered use")]
+    InvalidInitialUse(TextureUse),
 }
 
 impl<B: hal::Backend> Resource for Texture<B> {
@@ -326,8 +349,12 @@ pub struct TextureView<B: hal::Backend> {
 
 #[derive(Clone, Debug, Error)]
 pub enum CreateTextureViewError {
-    #[error("parent texture is invalid or destroyed")]
-    InvalidTexture,
+    #[error(transparent)]
+    Device(#[from] DeviceError),
+    #[error("parent texture is invalid")]
+    InvalidTexture(#[source] InvalidId),
+    #[error("parent texture is destroyed")]
+    DestroyedTexture,
     #[error("not enough memory left")]
     OutOfMemory,
     #[error("Invalid texture view dimension `{view:?}` with texture of dimension `{image:?}`")]
@@ -423,7 +450,8 @@ impl Default for SamplerDescriptor<'_> {
 
 #[derive(Debug)]
 pub struct Sampler<B: hal::Backend> {
-    pub(crate) raw: B::Sampler,
+    /// Shared with the samplers created with the same description.
+    pub(crate) raw: Arc<B::Sampler>,
     pub(crate) device_id: Stored<DeviceId>,
     pub(crate) life_guard: LifeGuard,
     /// `true` if this is a comparison sampler
@@ -470,7 +498,7 @@ pub enum CreateQuerySetError {
 
 #[derive(Debug)]
 pub struct QuerySet<B: hal::Backend> {
-    pub(crate) raw: B::QueryPool,
+    pub(crate) raw: Option<B::QueryPool>,
     pub(crate) device_id: Stored<DeviceId>,
     pub(crate) life_guard: LifeGuard,
     /// Amount of queries in the query set.
@@ -496,7 +524,7 @@ impl<B: hal::Backend> Borrow<()> for QuerySet<B> {
 #[derive(Clone, Debug, Error)]
 pub enum DestroyError {
     #[error("resource is invalid")]
-    Invalid,
+    Invalid(#[source] InvalidId),
     #[error("resource is already destroyed")]
     AlreadyDestroyed,
 }
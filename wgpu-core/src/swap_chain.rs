@@ -158,8 +158,8 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         #[allow(unused_variables)]
         let device = &device_guard[sc.device_id.value];
         #[cfg(feature = "trace")]
-        if let Some(ref trace) = device.trace {
-            trace.lock().add(Action::GetSwapChainTexture {
+        if let Some(mut trace) = device.active_trace() {
+            trace.add(Action::GetSwapChainTexture {
                 id: fid.id(),
                 parent_id: swap_chain_id,
             });
@@ -259,8 +259,8 @@ impl<G: GlobalIdentityHandlerFactory> Global<G> {
         let device = &mut device_guard[sc.device_id.value];
 
         #[cfg(feature = "trace")]
-        if let Some(ref trace) = device.trace {
-            trace.lock().add(Action::PresentSwapChain(swap_chain_id));
+        if let Some(mut trace) = device.active_trace() {
+            trace.add(Action::PresentSwapChain(swap_chain_id));
         }
 
         let view = {
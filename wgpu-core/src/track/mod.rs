@@ -476,8 +476,8 @@ impl<S: ResourceState> ResourceTracker<S> {
         id: S::Id,
         selector: S::Selector,
         usage: S::Usage,
-    ) -> Result<(&'a T, Drain<PendingTransition<S>>), S::Id> {
-        let item = storage.get(id).map_err(|_| id)?;
+    ) -> Result<(&'a T, Drain<PendingTransition<S>>), hub::InvalidId> {
+        let item = storage.get(id)?;
         let drain = self.change_replace(
             Valid(id),
             item.life_guard().ref_count.as_ref().unwrap(),
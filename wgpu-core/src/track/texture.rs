@@ -45,9 +45,22 @@ impl PendingTransition<TextureState> {
 
 impl TextureState {
     pub fn new(mip_level_count: hal::image::Level, array_layer_count: hal::image::Layer) -> Self {
+        Self::with_usage(
+            mip_level_count,
+            array_layer_count,
+            TextureUse::UNINITIALIZED,
+        )
+    }
+
+    /// Creates the state of a texture that is known to be in `usage` already.
+    pub fn with_usage(
+        mip_level_count: hal::image::Level,
+        array_layer_count: hal::image::Layer,
+        usage: TextureUse,
+    ) -> Self {
         Self {
             mips: iter::repeat_with(|| {
-                PlaneStates::from_range(0..array_layer_count, Unit::new(TextureUse::UNINITIALIZED))
+                PlaneStates::from_range(0..array_layer_count, Unit::new(usage))
             })
             .take(mip_level_count as usize)
             .collect(),
@@ -277,6 +290,40 @@ mod test {
     use super::*;
     use crate::id::Id;
 
+    #[test]
+    fn change_storage() {
+        let id = Id::dummy();
+        let selector = TextureSelector {
+            levels: 0..1,
+            layers: 0..1,
+        };
+        let read_write = TextureUse::STORAGE_LOAD | TextureUse::STORAGE_STORE;
+        let mut ts = TextureState::default();
+        ts.mips
+            .push(PlaneStates::from_slice(&[(0..1, Unit::new(read_write))]));
+
+        let mut list = Vec::new();
+        ts.change(id, selector.clone(), read_write, Some(&mut list))
+            .unwrap();
+        assert_eq!(
+            &list,
+            &[PendingTransition {
+                id,
+                selector: selector.clone(),
+                usage: read_write..read_write,
+            }],
+            "storage writes need a barrier between consecutive uses"
+        );
+
+        list.clear();
+        ts.change(id, selector.clone(), TextureUse::SAMPLED, Some(&mut list))
+            .unwrap();
+        ts.change(id, selector, TextureUse::SAMPLED, Some(&mut list))
+            .unwrap();
+        assert_eq!(list.len(), 1, "sampled uses are ordered");
+        assert!(!TextureUse::ORDERED.intersects(TextureUse::STORAGE_STORE));
+    }
+
     #[test]
     fn query() {
         let mut ts = TextureState::default();
@@ -321,6 +368,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn imported_state() {
+        let id = Id::dummy();
+        let selector = TextureSelector {
+            levels: 0..1,
+            layers: 0..3,
+        };
+        let mut ts = TextureState::with_usage(1, 3, TextureUse::COPY_DST);
+        assert_eq!(ts.query(selector.clone()), Some(TextureUse::COPY_DST));
+
+        let mut list = Vec::new();
+        ts.change(id, selector.clone(), TextureUse::SAMPLED, Some(&mut list))
+            .unwrap();
+        assert_eq!(
+            &list,
+            &[PendingTransition {
+                id,
+                selector,
+                usage: TextureUse::COPY_DST..TextureUse::SAMPLED,
+            }],
+            "the first use transitions from the imported state"
+        );
+    }
+
     #[test]
     fn merge() {
         let id = Id::dummy();
@@ -11,7 +11,8 @@ use wgt::{BindGroupLayoutEntry, BindingType};
 #[derive(Debug)]
 enum ResourceType {
     Buffer {
-        size: wgt::BufferSize,
+        /// Unknown for declared interfaces without a minimum binding size.
+        size: Option<wgt::BufferSize>,
     },
     Texture {
         dim: naga::ImageDimension,
@@ -63,6 +64,20 @@ struct EntryPoint {
     outputs: Vec<Varying>,
     resources: Vec<(naga::Handle<Resource>, GlobalUse)>,
     spec_constants: Vec<SpecializationConstant>,
+    /// The entry point comes from a declared interface, so its inputs and outputs are unknown.
+    declared: bool,
+}
+
+impl EntryPoint {
+    fn outputs(&self) -> StageIo {
+        self.outputs
+            .iter()
+            .filter_map(|output| match *output {
+                Varying::Local { location, ty } => Some((location, ty)),
+                Varying::BuiltIn(_) => None,
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -150,6 +165,20 @@ pub enum InputError {
     WrongType,
 }
 
+/// Errors produced when creating an `Interface` from a declared one.
+#[derive(Clone, Debug, Error)]
+pub enum InterfaceError {
+    #[error("entry point {0:?} has invalid stage {1:?}")]
+    InvalidStage(String, wgt::ShaderStage),
+    #[error("entry point {entry_point:?} declares an invalid binding at index {binding} in group {group}: {error}")]
+    Binding {
+        entry_point: String,
+        group: u32,
+        binding: u32,
+        error: BindingError,
+    },
+}
+
 /// Errors produced when validating a programmable stage of a pipeline.
 #[derive(Clone, Debug, Error)]
 pub enum StageError {
@@ -262,6 +291,21 @@ fn map_storage_format_to_naga(format: wgt::TextureFormat) -> Option<naga::Storag
     })
 }
 
+fn map_view_dimension_to_naga(
+    dimension: wgt::TextureViewDimension,
+) -> (naga::ImageDimension, bool) {
+    use naga::ImageDimension as Id;
+    use wgt::TextureViewDimension as Tvd;
+    match dimension {
+        Tvd::D1 => (Id::D1, false),
+        Tvd::D2 => (Id::D2, false),
+        Tvd::D2Array => (Id::D2, true),
+        Tvd::Cube => (Id::Cube, false),
+        Tvd::CubeArray => (Id::Cube, true),
+        Tvd::D3 => (Id::D3, false),
+    }
+}
+
 fn map_storage_format_from_naga(format: naga::StorageFormat) -> wgt::TextureFormat {
     use naga::StorageFormat as Sf;
     use wgt::TextureFormat as Tf;
@@ -326,8 +370,8 @@ impl Resource {
                     }
                     _ => return Err(BindingError::WrongType),
                 };
-                match min_size {
-                    Some(non_zero) if non_zero < size => {
+                match (min_size, size) {
+                    (Some(non_zero), Some(size)) if non_zero < size => {
                         return Err(BindingError::WrongBufferSize(size))
                     }
                     _ => (),
@@ -423,7 +467,8 @@ impl Resource {
                         let usage = match access {
                             wgt::StorageTextureAccess::ReadOnly => GlobalUse::READ,
                             wgt::StorageTextureAccess::WriteOnly => GlobalUse::WRITE,
-                            wgt::StorageTextureAccess::ReadWrite => GlobalUse::all(),
+                            wgt::StorageTextureAccess::ReadWrite
+                            | wgt::StorageTextureAccess::Atomic => GlobalUse::all(),
                         };
                         (naga::ImageClass::Storage(naga_format), usage)
                     }
@@ -446,6 +491,102 @@ impl Resource {
         }
     }
 
+    /// Creates a resource from its declared binding type, along with the usage the type implies.
+    fn from_binding_type(
+        group: u32,
+        binding: u32,
+        ty: BindingType,
+    ) -> Result<(Self, GlobalUse), BindingError> {
+        let (ty, class, usage) = match ty {
+            BindingType::Buffer {
+                ty,
+                has_dynamic_offset: _,
+                min_binding_size,
+            } => {
+                let (class, usage) = match ty {
+                    wgt::BufferBindingType::Uniform => {
+                        (naga::StorageClass::Uniform, GlobalUse::READ)
+                    }
+                    wgt::BufferBindingType::Storage { read_only: true } => {
+                        (naga::StorageClass::Storage, GlobalUse::READ)
+                    }
+                    wgt::BufferBindingType::Storage { read_only: false } => {
+                        (naga::StorageClass::Storage, GlobalUse::all())
+                    }
+                };
+                let ty = ResourceType::Buffer {
+                    size: min_binding_size,
+                };
+                (ty, class, usage)
+            }
+            BindingType::Sampler {
+                filtering: _,
+                comparison,
+            } => (
+                ResourceType::Sampler { comparison },
+                naga::StorageClass::Handle,
+                GlobalUse::READ,
+            ),
+            BindingType::Texture {
+                sample_type,
+                view_dimension,
+                multisampled: multi,
+            } => {
+                let (dim, arrayed) = map_view_dimension_to_naga(view_dimension);
+                let class = match sample_type {
+                    wgt::TextureSampleType::Float { .. } => naga::ImageClass::Sampled {
+                        kind: naga::ScalarKind::Float,
+                        multi,
+                    },
+                    wgt::TextureSampleType::Sint => naga::ImageClass::Sampled {
+                        kind: naga::ScalarKind::Sint,
+                        multi,
+                    },
+                    wgt::TextureSampleType::Uint => naga::ImageClass::Sampled {
+                        kind: naga::ScalarKind::Uint,
+                        multi,
+                    },
+                    wgt::TextureSampleType::Depth => naga::ImageClass::Depth,
+                };
+                let ty = ResourceType::Texture {
+                    dim,
+                    arrayed,
+                    class,
+                };
+                (ty, naga::StorageClass::Handle, GlobalUse::READ)
+            }
+            BindingType::StorageTexture {
+                access,
+                format,
+                view_dimension,
+            } => {
+                let (dim, arrayed) = map_view_dimension_to_naga(view_dimension);
+                let naga_format = map_storage_format_to_naga(format)
+                    .ok_or(BindingError::BadStorageFormat(format))?;
+                let usage = match access {
+                    wgt::StorageTextureAccess::ReadOnly => GlobalUse::READ,
+                    wgt::StorageTextureAccess::WriteOnly => GlobalUse::WRITE,
+                    wgt::StorageTextureAccess::ReadWrite | wgt::StorageTextureAccess::Atomic => {
+                        GlobalUse::all()
+                    }
+                };
+                let ty = ResourceType::Texture {
+                    dim,
+                    arrayed,
+                    class: naga::ImageClass::Storage(naga_format),
+                };
+                (ty, naga::StorageClass::Handle, usage)
+            }
+        };
+        let resource = Resource {
+            group,
+            binding,
+            ty,
+            class,
+        };
+        Ok((resource, usage))
+    }
+
     fn derive_binding_type(&self, shader_usage: GlobalUse) -> Result<BindingType, BindingError> {
         Ok(match self.ty {
             ResourceType::Buffer { size } => BindingType::Buffer {
@@ -457,7 +598,7 @@ impl Resource {
                     _ => return Err(BindingError::WrongType),
                 },
                 has_dynamic_offset: false,
-                min_binding_size: Some(size),
+                min_binding_size: size,
             },
             ResourceType::Sampler { comparison } => BindingType::Sampler {
                 filtering: true,
@@ -495,7 +636,9 @@ impl Resource {
                         multisampled: false,
                     },
                     naga::ImageClass::Storage(format) => BindingType::StorageTexture {
-                        access: if shader_usage.contains(GlobalUse::WRITE) {
+                        access: if shader_usage.contains(GlobalUse::READ | GlobalUse::WRITE) {
+                            wgt::StorageTextureAccess::ReadWrite
+                        } else if shader_usage.contains(GlobalUse::WRITE) {
                             wgt::StorageTextureAccess::WriteOnly
                         } else {
                             wgt::StorageTextureAccess::ReadOnly
@@ -571,14 +714,20 @@ impl NumericType {
         use wgt::TextureFormat as Tf;
 
         let (dim, kind) = match format {
-            Tf::R8Unorm | Tf::R8Snorm | Tf::R16Float | Tf::R32Float => {
-                (NumericDimension::Scalar, Sk::Float)
-            }
+            Tf::R8Unorm
+            | Tf::R8Snorm
+            | Tf::R16Float
+            | Tf::R16Unorm
+            | Tf::R16Snorm
+            | Tf::R32Float => (NumericDimension::Scalar, Sk::Float),
             Tf::R8Uint | Tf::R16Uint | Tf::R32Uint => (NumericDimension::Scalar, Sk::Uint),
             Tf::R8Sint | Tf::R16Sint | Tf::R32Sint => (NumericDimension::Scalar, Sk::Sint),
-            Tf::Rg8Unorm | Tf::Rg8Snorm | Tf::Rg16Float | Tf::Rg32Float => {
-                (NumericDimension::Vector(Vs::Bi), Sk::Float)
-            }
+            Tf::Rg8Unorm
+            | Tf::Rg8Snorm
+            | Tf::Rg16Float
+            | Tf::Rg16Unorm
+            | Tf::Rg16Snorm
+            | Tf::Rg32Float => (NumericDimension::Vector(Vs::Bi), Sk::Float),
             Tf::Rg8Uint | Tf::Rg16Uint | Tf::Rg32Uint => {
                 (NumericDimension::Vector(Vs::Bi), Sk::Uint)
             }
@@ -592,6 +741,8 @@ impl NumericType {
             | Tf::Bgra8UnormSrgb
             | Tf::Rgb10a2Unorm
             | Tf::Rgba16Float
+            | Tf::Rgba16Unorm
+            | Tf::Rgba16Snorm
             | Tf::Rgba32Float => (NumericDimension::Vector(Vs::Quad), Sk::Float),
             Tf::Rgba8Uint | Tf::Rgba16Uint | Tf::Rgba32Uint => {
                 (NumericDimension::Vector(Vs::Quad), Sk::Uint)
@@ -599,10 +750,13 @@ impl NumericType {
             Tf::Rgba8Sint | Tf::Rgba16Sint | Tf::Rgba32Sint => {
                 (NumericDimension::Vector(Vs::Quad), Sk::Sint)
             }
-            Tf::Rg11b10Float => (NumericDimension::Vector(Vs::Tri), Sk::Float),
-            Tf::Depth32Float | Tf::Depth24Plus | Tf::Depth24PlusStencil8 => {
-                panic!("Unexpected depth format")
-            }
+            Tf::Rg11b10Float | Tf::Rgb9e5Ufloat => (NumericDimension::Vector(Vs::Tri), Sk::Float),
+            Tf::Depth32Float
+            | Tf::Depth24Plus
+            | Tf::Depth24PlusStencil8
+            | Tf::Depth16Unorm
+            | Tf::Stencil8
+            | Tf::Depth32FloatStencil8 => panic!("Unexpected depth format"),
             Tf::Bc1RgbaUnorm
             | Tf::Bc1RgbaUnormSrgb
             | Tf::Bc2RgbaUnorm
@@ -764,7 +918,7 @@ impl Interface {
                             get_aligned_type_size(module, member.ty, i + 1 == members.len());
                     }
                     ResourceType::Buffer {
-                        size: wgt::BufferSize::new(actual_size).unwrap(),
+                        size: Some(wgt::BufferSize::new(actual_size).unwrap()),
                     }
                 }
                 naga::TypeInner::Image {
@@ -827,14 +981,64 @@ impl Interface {
         }
     }
 
+    /// Creates the interface of a module that isn't parsed, from the one declared by the user.
+    pub fn from_declared(
+        declared: &[crate::pipeline::ShaderEntryPoint],
+    ) -> Result<Self, InterfaceError> {
+        let mut resources = naga::Arena::new();
+        let mut entry_points = FastHashMap::default();
+        for declared_ep in declared {
+            let stage = match declared_ep.stage {
+                wgt::ShaderStage::VERTEX => naga::ShaderStage::Vertex,
+                wgt::ShaderStage::FRAGMENT => naga::ShaderStage::Fragment,
+                wgt::ShaderStage::COMPUTE => naga::ShaderStage::Compute,
+                other => {
+                    return Err(InterfaceError::InvalidStage(
+                        declared_ep.name.to_string(),
+                        other,
+                    ))
+                }
+            };
+            let mut ep = EntryPoint {
+                declared: true,
+                ..EntryPoint::default()
+            };
+            for declared_binding in declared_ep.bindings.iter() {
+                let (resource, usage) = Resource::from_binding_type(
+                    declared_binding.group,
+                    declared_binding.binding,
+                    declared_binding.ty,
+                )
+                .map_err(|error| InterfaceError::Binding {
+                    entry_point: declared_ep.name.to_string(),
+                    group: declared_binding.group,
+                    binding: declared_binding.binding,
+                    error,
+                })?;
+                ep.resources.push((resources.append(resource), usage));
+            }
+            entry_points.insert((stage, declared_ep.name.to_string()), ep);
+        }
+
+        Ok(Interface {
+            resources,
+            entry_points,
+        })
+    }
+
+    /// Validates an entry point against the pipeline layout and the outputs of the
+    /// previous stage, returning its own outputs.
+    ///
+    /// Inputs and outputs are `None` when they are unknown, for entry points of
+    /// declared interfaces.
     pub fn check_stage(
         &self,
         given_layouts: Option<&[&BindEntryMap]>,
         derived_layouts: &mut [BindEntryMap],
         entry_point_name: &str,
         stage_bit: wgt::ShaderStage,
-        inputs: StageIo,
-    ) -> Result<StageIo, StageError> {
+        inputs: Option<StageIo>,
+    ) -> Result<Option<StageIo>, StageError> {
         // Since a shader module can have multiple entry points with the same name,
         // we need to look for one with the right execution model.
         let shader_stage = match stage_bit {
@@ -897,6 +1101,14 @@ impl Interface {
             }
         }
 
+        if entry_point.declared {
+            return Ok(None);
+        }
+        let inputs = match inputs {
+            Some(inputs) => inputs,
+            None => return Ok(Some(entry_point.outputs())),
+        };
+
         for input in entry_point.inputs.iter() {
             match *input {
                 Varying::Local { location, ty } => {
@@ -919,14 +1131,204 @@ impl Interface {
             }
         }
 
-        let outputs = entry_point
-            .outputs
-            .iter()
-            .filter_map(|output| match *output {
-                Varying::Local { location, ty } => Some((location, ty)),
-                Varying::BuiltIn(_) => None,
+        Ok(Some(entry_point.outputs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BindingError, Interface, InterfaceError, Resource, StageError, StageIo};
+    use crate::{
+        binding_model::BindEntryMap,
+        pipeline::{ShaderBinding, ShaderEntryPoint},
+    };
+    use std::borrow::Cow;
+    use wgt::{BindGroupLayoutEntry, BindingType};
+
+    const STORAGE: BindingType = BindingType::Buffer {
+        ty: wgt::BufferBindingType::Storage { read_only: false },
+        has_dynamic_offset: false,
+        min_binding_size: wgt::BufferSize::new(16),
+    };
+
+    fn declared(stage: wgt::ShaderStage) -> Interface {
+        Interface::from_declared(&[ShaderEntryPoint {
+            name: Cow::Borrowed("main"),
+            stage,
+            bindings: Cow::Owned(vec![ShaderBinding {
+                group: 0,
+                binding: 1,
+                ty: STORAGE,
+            }]),
+        }])
+        .unwrap()
+    }
+
+    fn layout(ty: BindingType) -> BindEntryMap {
+        let mut map = BindEntryMap::default();
+        map.insert(
+            1,
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgt::ShaderStage::COMPUTE,
+                ty,
+                count: None,
+            },
+        );
+        map
+    }
+
+    #[test]
+    fn binding_types_round_trip() {
+        let types = [
+            STORAGE,
+            BindingType::Buffer {
+                ty: wgt::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            BindingType::Sampler {
+                filtering: true,
+                comparison: true,
+            },
+            BindingType::Texture {
+                sample_type: wgt::TextureSampleType::Depth,
+                view_dimension: wgt::TextureViewDimension::CubeArray,
+                multisampled: false,
+            },
+            BindingType::StorageTexture {
+                access: wgt::StorageTextureAccess::WriteOnly,
+                format: wgt::TextureFormat::R32Float,
+                view_dimension: wgt::TextureViewDimension::D2Array,
+            },
+        ];
+        for &ty in types.iter() {
+            let (resource, usage) = Resource::from_binding_type(0, 0, ty).unwrap();
+            assert_eq!(resource.derive_binding_type(usage).unwrap(), ty);
+            let entry = BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgt::ShaderStage::COMPUTE,
+                ty,
+                count: None,
+            };
+            resource.check_binding_use(&entry, usage).unwrap();
+        }
+        assert!(matches!(
+            Resource::from_binding_type(
+                0,
+                0,
+                BindingType::StorageTexture {
+                    access: wgt::StorageTextureAccess::ReadOnly,
+                    format: wgt::TextureFormat::Depth32Float,
+                    view_dimension: wgt::TextureViewDimension::D2,
+                },
+            ),
+            Err(BindingError::BadStorageFormat(
+                wgt::TextureFormat::Depth32Float
+            ))
+        ));
+    }
+
+    #[test]
+    fn declared_interface_against_layout() {
+        let interface = declared(wgt::ShaderStage::COMPUTE);
+        let check = |layout: &BindEntryMap| {
+            interface.check_stage(
+                Some(&[layout]),
+                &mut [],
+                "main",
+                wgt::ShaderStage::COMPUTE,
+                Some(StageIo::default()),
+            )
+        };
+
+        // Inputs and outputs of declared entry points are unknown.
+        assert!(check(&layout(STORAGE)).unwrap().is_none());
+        let read_only = BindingType::Buffer {
+            ty: wgt::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        assert!(matches!(
+            check(&layout(read_only)),
+            Err(StageError::Binding {
+                group: 0,
+                binding: 1,
+                error: BindingError::WrongUsage(_),
+            })
+        ));
+        let too_small = BindingType::Buffer {
+            ty: wgt::BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: wgt::BufferSize::new(4),
+        };
+        assert!(matches!(
+            check(&layout(too_small)),
+            Err(StageError::Binding {
+                error: BindingError::WrongBufferSize(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            interface.check_stage(
+                Some(&[&layout(STORAGE)]),
+                &mut [],
+                "main",
+                wgt::ShaderStage::VERTEX,
+                None,
+            ),
+            Err(StageError::MissingEntryPoint(_))
+        ));
+    }
+
+    #[test]
+    fn declared_interface_derives_layout() {
+        let interface = declared(wgt::ShaderStage::FRAGMENT);
+        let mut derived = [BindEntryMap::default(), BindEntryMap::default()];
+        let outputs = interface
+            .check_stage(None, &mut derived, "main", wgt::ShaderStage::FRAGMENT, None)
+            .unwrap();
+        // No outputs, so the color targets of the pipeline aren't validated.
+        assert!(outputs.is_none());
+        assert!(derived[1].is_empty());
+        assert_eq!(derived[0].len(), 1);
+        assert_eq!(derived[0][&1].ty, STORAGE);
+        assert_eq!(derived[0][&1].visibility, wgt::ShaderStage::FRAGMENT);
+    }
+
+    #[test]
+    fn declared_interface_invalid_stage() {
+        let result = Interface::from_declared(&[ShaderEntryPoint {
+            name: Cow::Borrowed("main"),
+            stage: wgt::ShaderStage::VERTEX | wgt::ShaderStage::FRAGMENT,
+            bindings: Cow::Borrowed(&[]),
+        }]);
+        assert!(matches!(
+            result,
+            Err(InterfaceError::InvalidStage(ref name, stage))
+                if name == "main" && stage == wgt::ShaderStage::VERTEX | wgt::ShaderStage::FRAGMENT
+        ));
+        let result = Interface::from_declared(&[ShaderEntryPoint {
+            name: Cow::Borrowed("main"),
+            stage: wgt::ShaderStage::COMPUTE,
+            bindings: Cow::Owned(vec![ShaderBinding {
+                group: 2,
+                binding: 3,
+                ty: BindingType::StorageTexture {
+                    access: wgt::StorageTextureAccess::ReadOnly,
+                    format: wgt::TextureFormat::Depth32Float,
+                    view_dimension: wgt::TextureViewDimension::D2,
+                },
+            }]),
+        }]);
+        assert!(matches!(
+            result,
+            Err(InterfaceError::Binding {
+                group: 2,
+                binding: 3,
+                error: BindingError::BadStorageFormat(_),
+                ..
             })
-            .collect();
-        Ok(outputs)
+        ));
     }
 }
@@ -223,6 +223,15 @@ bitflags::bitflags! {
         ///
         /// This is a web and native feature.
         const PIPELINE_STATISTICS_QUERY = 0x0000_0000_0000_0008;
+        /// Allows for usage of the [`TextureFormat::Depth32FloatStencil8`] format.
+        ///
+        /// Supported platforms:
+        /// - Vulkan (some)
+        /// - DX12
+        /// - Metal (macOS)
+        ///
+        /// This is a web and native feature.
+        const DEPTH32FLOAT_STENCIL8 = 0x0000_0000_0000_0010;
         /// Webgpu only allows the MAP_READ and MAP_WRITE buffer usage to be matched with
         /// COPY_DST and COPY_SRC respectively. This removes this requirement.
         ///
@@ -433,6 +442,41 @@ bitflags::bitflags! {
         ///
         /// This is a native only feature.
         const CONSERVATIVE_RASTERIZATION = 0x0000_0000_8000_0000;
+        /// Validates that indexed draws don't reference vertices beyond the end of the bound
+        /// vertex rate vertex buffers, failing the submission otherwise.
+        ///
+        /// The largest index of a draw is computed on the CPU from a copy of the index data
+        /// written through [`Queue::write_buffer`] or a buffer mapping. This copy doubles
+        /// the memory used by index buffers. Indices written by the GPU (buffer copies, query
+        /// resolves, and any index buffer with [`BufferUsage::STORAGE`]) are unknown, and are
+        /// assumed to be the largest value of the index format. Indirect draws are not validated.
+        ///
+        /// Supported platforms:
+        /// - All
+        ///
+        /// This is a native only feature.
+        const ROBUST_INDEX_VALIDATION = 0x0000_0001_0000_0000;
+        /// Enables normalized 16-bit texture formats.
+        ///
+        /// Support for this feature guarantees availability of [`TextureUsage::COPY_SRC | TextureUsage::COPY_DST | TextureUsage::SAMPLED`]
+        /// with filtering for these formats, as well as [`TextureUsage::RENDER_ATTACHMENT`] for the unsigned ones.
+        /// [`Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`] may enable additional usages.
+        ///
+        /// Supported platforms:
+        /// - Vulkan
+        /// - DX12
+        /// - Metal (macOS)
+        ///
+        /// This is a native only feature.
+        const TEXTURE_FORMAT_16BIT_NORM = 0x0000_0002_0000_0000;
+        /// Allows creating shader modules with [`ShaderFlags::SPIRV_PASSTHROUGH`], which hands
+        /// SPIR-V to the backend without parsing it.
+        ///
+        /// Supported platforms:
+        /// - Vulkan
+        ///
+        /// This is a native only feature.
+        const SPIRV_SHADER_PASSTHROUGH = 0x0000_0004_0000_0000;
         /// Features which are part of the upstream WebGPU standard.
         const ALL_WEBGPU = 0x0000_0000_0000_FFFF;
         /// Features that are only available when targeting native (not web).
@@ -639,6 +683,12 @@ bitflags::bitflags! {
         /// representation of the shader module for both validation and translation
         /// into the backend shader language, on backends where `gfx-hal` supports this.
         const EXPERIMENTAL_TRANSLATION = 2;
+        /// If enabled, SPIR-V sources are given to the backend as they are, without being
+        /// parsed by `Naga`. Pipelines can only be validated against the interface that is
+        /// declared when creating the shader module.
+        ///
+        /// [`Features::SPIRV_SHADER_PASSTHROUGH`] must be enabled to use this flag.
+        const SPIRV_PASSTHROUGH = 4;
     }
 }
 
@@ -979,7 +1029,7 @@ bitflags::bitflags! {
     pub struct TextureFormatFeatureFlags: u32 {
         /// When used as a STORAGE texture, then a texture with this format can be bound with `StorageTextureAccess::ReadWrite`.
         const STORAGE_READ_WRITE = 1;
-        /// When used as a STORAGE texture, then a texture with this format can be written to with atomics. TODO: No access flag exposed as of writing
+        /// When used as a STORAGE texture, then a texture with this format can be bound with `StorageTextureAccess::Atomic`.
         const STORAGE_ATOMICS = 2;
     }
 }
@@ -1040,6 +1090,14 @@ pub enum TextureFormat {
     R16Sint = 5,
     /// Red channel only. 16 bit float per channel. Float in shader.
     R16Float = 6,
+    /// Red channel only. 16 bit integer per channel. [0, 65535] converted to/from float [0, 1] in shader.
+    ///
+    /// [`Features::TEXTURE_FORMAT_16BIT_NORM`] must be enabled to use this texture format.
+    R16Unorm = 93,
+    /// Red channel only. 16 bit integer per channel. [-32767, 32767] converted to/from float [-1, 1] in shader.
+    ///
+    /// [`Features::TEXTURE_FORMAT_16BIT_NORM`] must be enabled to use this texture format.
+    R16Snorm = 94,
     /// Red and green channels. 8 bit integer per channel. [0, 255] converted to/from float [0, 1] in shader.
     Rg8Unorm = 7,
     /// Red and green channels. 8 bit integer per channel. [-127, 127] converted to/from float [-1, 1] in shader.
@@ -1062,6 +1120,14 @@ pub enum TextureFormat {
     Rg16Sint = 15,
     /// Red and green channels. 16 bit float per channel. Float in shader.
     Rg16Float = 16,
+    /// Red and green channels. 16 bit integer per channel. [0, 65535] converted to/from float [0, 1] in shader.
+    ///
+    /// [`Features::TEXTURE_FORMAT_16BIT_NORM`] must be enabled to use this texture format.
+    Rg16Unorm = 95,
+    /// Red and green channels. 16 bit integer per channel. [-32767, 32767] converted to/from float [-1, 1] in shader.
+    ///
+    /// [`Features::TEXTURE_FORMAT_16BIT_NORM`] must be enabled to use this texture format.
+    Rg16Snorm = 96,
     /// Red, green, blue, and alpha channels. 8 bit integer per channel. [0, 255] converted to/from float [0, 1] in shader.
     Rgba8Unorm = 17,
     /// Red, green, blue, and alpha channels. 8 bit integer per channel. Srgb-color [0, 255] converted to/from linear-color float [0, 1] in shader.
@@ -1082,6 +1148,8 @@ pub enum TextureFormat {
     Rgb10a2Unorm = 24,
     /// Red, green, and blue channels. 11 bit float with no sign bit for RG channels. 10 bit float with no sign bit for blue channel. Float in shader.
     Rg11b10Float = 25,
+    /// Red, green, and blue channels. 9 bit integer mantissa for each channel sharing a 5 bit exponent. Float in shader.
+    Rgb9e5Ufloat = 99,
 
     // Normal 64 bit formats
     /// Red and green channels. 32 bit integer per channel. Unsigned in shader.
@@ -1096,6 +1164,14 @@ pub enum TextureFormat {
     Rgba16Sint = 30,
     /// Red, green, blue, and alpha channels. 16 bit float per channel. Float in shader.
     Rgba16Float = 31,
+    /// Red, green, blue, and alpha channels. 16 bit integer per channel. [0, 65535] converted to/from float [0, 1] in shader.
+    ///
+    /// [`Features::TEXTURE_FORMAT_16BIT_NORM`] must be enabled to use this texture format.
+    Rgba16Unorm = 97,
+    /// Red, green, blue, and alpha channels. 16 bit integer per channel. [-32767, 32767] converted to/from float [-1, 1] in shader.
+    ///
+    /// [`Features::TEXTURE_FORMAT_16BIT_NORM`] must be enabled to use this texture format.
+    Rgba16Snorm = 98,
 
     // Normal 128 bit formats
     /// Red, green, blue, and alpha channels. 32 bit integer per channel. Unsigned in shader.
@@ -1112,6 +1188,16 @@ pub enum TextureFormat {
     Depth24Plus = 36,
     /// Special depth/stencil format with at least 24 bit integer depth and 8 bits integer stencil.
     Depth24PlusStencil8 = 37,
+    /// Special depth format with 16 bit integer depth.
+    Depth16Unorm = 90,
+    /// Special stencil format with 8 bit integer stencil.
+    ///
+    /// May be backed by a depth/stencil format on platforms without native support.
+    Stencil8 = 91,
+    /// Special depth/stencil format with 32 bit floating point depth and 8 bits integer stencil.
+    ///
+    /// [`Features::DEPTH32FLOAT_STENCIL8`] must be enabled to use this texture format.
+    Depth32FloatStencil8 = 92,
 
     // Compressed textures usable with `TEXTURE_COMPRESSION_BC` feature.
     /// 4x4 block compressed texture. 8 bytes per block (4 bit/px). 4 color + alpha pallet. 5 bit R + 6 bit G + 5 bit B + 1 bit alpha.
@@ -1410,6 +1496,8 @@ impl TextureFormat {
         let bc = Features::TEXTURE_COMPRESSION_BC;
         let etc2 = Features::TEXTURE_COMPRESSION_ETC2;
         let astc_ldr = Features::TEXTURE_COMPRESSION_ASTC_LDR;
+        let d32_s8 = Features::DEPTH32FLOAT_STENCIL8;
+        let norm16 = Features::TEXTURE_FORMAT_16BIT_NORM;
 
         // Sample Types
         let uint = TextureSampleType::Uint;
@@ -1441,6 +1529,8 @@ impl TextureFormat {
                 Self::R16Uint => (native, uint, linear, (1, 1), 2, attachment),
                 Self::R16Sint => (native, sint, linear, (1, 1), 2, attachment),
                 Self::R16Float => (native, float, linear, (1, 1), 2, attachment),
+                Self::R16Unorm => (norm16, float, linear, (1, 1), 2, attachment),
+                Self::R16Snorm => (norm16, float, linear, (1, 1), 2, basic),
                 Self::Rg8Unorm => (native, float, linear, (1, 1), 2, attachment),
                 Self::Rg8Snorm => (native, float, linear, (1, 1), 2, attachment),
                 Self::Rg8Uint => (native, uint, linear, (1, 1), 2, attachment),
@@ -1453,6 +1543,8 @@ impl TextureFormat {
                 Self::Rg16Uint => (native, uint, linear, (1, 1), 4, attachment),
                 Self::Rg16Sint => (native, sint, linear, (1, 1), 4, attachment),
                 Self::Rg16Float => (native, float, linear, (1, 1), 4, attachment),
+                Self::Rg16Unorm => (norm16, float, linear, (1, 1), 4, attachment),
+                Self::Rg16Snorm => (norm16, float, linear, (1, 1), 4, basic),
                 Self::Rgba8Unorm => (native, float, linear, (1, 1), 4, all_flags),
                 Self::Rgba8UnormSrgb => (native, float, srgb, (1, 1), 4, attachment),
                 Self::Rgba8Snorm => (native, float, linear, (1, 1), 4, storage),
@@ -1464,6 +1556,7 @@ impl TextureFormat {
                 // Packed 32 bit textures
                 Self::Rgb10a2Unorm => (native, float, linear, (1, 1), 4, attachment),
                 Self::Rg11b10Float => (native, float, linear, (1, 1), 4, basic),
+                Self::Rgb9e5Ufloat => (native, float, linear, (1, 1), 4, basic),
 
                 // Packed 32 bit textures
                 Self::Rg32Uint => (native, uint, linear, (1, 1), 8, all_flags),
@@ -1472,6 +1565,8 @@ impl TextureFormat {
                 Self::Rgba16Uint => (native, uint, linear, (1, 1), 8, all_flags),
                 Self::Rgba16Sint => (native, sint, linear, (1, 1), 8, all_flags),
                 Self::Rgba16Float => (native, float, linear, (1, 1), 8, all_flags),
+                Self::Rgba16Unorm => (norm16, float, linear, (1, 1), 8, attachment),
+                Self::Rgba16Snorm => (norm16, float, linear, (1, 1), 8, basic),
 
                 // Packed 32 bit textures
                 Self::Rgba32Uint => (native, uint, linear, (1, 1), 16, all_flags),
@@ -1482,6 +1577,9 @@ impl TextureFormat {
                 Self::Depth32Float => (native, depth, linear, (1, 1), 4, attachment),
                 Self::Depth24Plus => (native, depth, linear, (1, 1), 4, attachment),
                 Self::Depth24PlusStencil8 => (native, depth, linear, (1, 1), 4, attachment),
+                Self::Depth16Unorm => (native, depth, linear, (1, 1), 2, attachment),
+                Self::Stencil8 => (native, uint, linear, (1, 1), 1, attachment),
+                Self::Depth32FloatStencil8 => (d32_s8, depth, linear, (1, 1), 4, attachment),
 
                 // BCn compressed textures
                 Self::Bc1RgbaUnorm => (bc, float, linear, (4, 4), 8, basic),
@@ -2647,6 +2745,16 @@ pub enum StorageTextureAccess {
     /// layout(set=0, binding=0, r32f) uniform image2D myStorageImage;
     /// ```
     ReadWrite,
+    /// The texture can be both read and written in the shader, including atomic operations.
+    /// [`Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`] must be enabled to use this access mode,
+    /// and the format must support [`TextureFormatFeatureFlags::STORAGE_ATOMICS`].
+    ///
+    /// Example GLSL syntax:
+    /// ```cpp,ignore
+    /// layout(set=0, binding=0, r32ui) uniform uimage2D myStorageImage;
+    /// imageAtomicAdd(myStorageImage, coords, 1);
+    /// ```
+    Atomic,
 }
 
 /// Specific type of a binding.
@@ -2785,6 +2893,10 @@ pub struct TextureCopyView<T> {
     /// The base texel of the texture in the selected `mip_level`.
     #[cfg_attr(any(feature = "trace", feature = "replay"), serde(default))]
     pub origin: Origin3d,
+    /// The copied aspect of the texture. Copies between buffers and depth/stencil
+    /// textures have to select a single aspect.
+    #[cfg_attr(any(feature = "trace", feature = "replay"), serde(default))]
+    pub aspect: TextureAspect,
 }
 
 /// Color variation to use when sampler addressing mode is [`AddressMode::ClampToBorder`]